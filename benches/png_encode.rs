@@ -0,0 +1,62 @@
+// PNG 编码基准：`cargo bench --bench png_encode`
+// 以 1200×1800 的合成漫画页（白底、黑色线条、灰度网点）为例，对比 JM_PNG_COMPRESSION / JM_PNG_FILTER 各组合的
+// 编码耗时；开始前打印各组合的输出大小，便于在速度与体积之间取舍
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use image::{Rgb, RgbImage};
+use jm_downloader_rs::png::{encode_png, PngCompression, PngFilter, PngSettings};
+
+const WIDTH: u32 = 1200;
+const HEIGHT: u32 = 1800;
+
+/// 白底上的分格线、斜向线条与网点，接近黑白漫画页的统计特征
+fn comic_page() -> RgbImage {
+    let mut seed = 0x2545_f491_u32;
+    RgbImage::from_fn(WIDTH, HEIGHT, |x, y| {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        let border = x % 400 < 6 || y % 600 < 6;
+        let stroke = (x + 2 * y) % 97 < 3;
+        let tone = (300..700).contains(&y) && (x / 4 + y / 4) % 2 == 0;
+        let value = if border || stroke {
+            20
+        } else if tone {
+            150
+        } else {
+            250 - (seed % 6) as u8
+        };
+        Rgb([value, value, value])
+    })
+}
+
+const SETTINGS: [(&str, PngCompression, PngFilter); 6] = [
+    ("fast+adaptive(默认)", PngCompression::Fast, PngFilter::Adaptive),
+    ("fast+none", PngCompression::Fast, PngFilter::None),
+    ("fast+up", PngCompression::Fast, PngFilter::Up),
+    ("none+none", PngCompression::None, PngFilter::None),
+    ("default+adaptive", PngCompression::Default, PngFilter::Adaptive),
+    ("best+adaptive", PngCompression::Best, PngFilter::Adaptive),
+];
+
+fn bench_encode(c: &mut Criterion) {
+    let page = comic_page();
+    for (name, compression, filter) in SETTINGS {
+        let size = encode_png(&page, PngSettings { compression, filter }).unwrap().len();
+        eprintln!("{:<22} {:>9} 字节", name, size);
+    }
+
+    let mut group = c.benchmark_group("png_encode");
+    group.sample_size(20);
+    group.throughput(Throughput::Bytes(page.as_raw().len() as u64));
+    for (name, compression, filter) in SETTINGS {
+        let settings = PngSettings { compression, filter };
+        group.bench_with_input(BenchmarkId::from_parameter(name), &page, |b, page| {
+            b.iter(|| encode_png(black_box(page), settings).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_encode);
+criterion_main!(benches);
@@ -0,0 +1,50 @@
+// 拼接吞吐基准：`cargo bench --bench stitch`
+// 以 2000×3000 的页面为例，对比按行切片复制与原先逐像素读写的实现
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use image::{Rgb, RgbImage};
+use jm_downloader_rs::stitch::stitch_img;
+
+const WIDTH: u32 = 2000;
+const HEIGHT: u32 = 3000;
+
+/// 原先的逐像素实现，作为对照
+fn stitch_per_pixel(src_img: &RgbImage, block_num: u32) -> RgbImage {
+    let (width, height) = src_img.dimensions();
+    let mut stitched_img = RgbImage::new(width, height);
+    let remainder_height = height % block_num;
+    for i in 0..block_num {
+        let mut block_height = height / block_num;
+        let src_y_start = height - (block_height * (i + 1)) - remainder_height;
+        let mut dst_y_start = block_height * i;
+        if i == 0 {
+            block_height += remainder_height;
+        } else {
+            dst_y_start += remainder_height;
+        }
+        for y in 0..block_height {
+            for x in 0..width {
+                stitched_img.put_pixel(x, dst_y_start + y, *src_img.get_pixel(x, src_y_start + y));
+            }
+        }
+    }
+    stitched_img
+}
+
+fn bench_stitch(c: &mut Criterion) {
+    let page = RgbImage::from_fn(WIDTH, HEIGHT, |x, y| Rgb([x as u8, y as u8, (x ^ y) as u8]));
+    let mut group = c.benchmark_group("stitch_2000x3000");
+    group.throughput(Throughput::Bytes(page.as_raw().len() as u64));
+    for block_num in [10, 20] {
+        group.bench_with_input(BenchmarkId::new("row_copy", block_num), &block_num, |b, &n| {
+            b.iter(|| stitch_img(black_box(&page), n))
+        });
+        group.bench_with_input(BenchmarkId::new("per_pixel", block_num), &block_num, |b, &n| {
+            b.iter(|| stitch_per_pixel(black_box(&page), n))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_stitch);
+criterion_main!(benches);
@@ -0,0 +1,16 @@
+// 启用 grpc 特性时由 proto/jm_downloader.proto 生成 gRPC 服务端代码，
+// 使用 protoc-bin-vendored 自带的 protoc，编译环境无需另外安装
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    #[cfg(feature = "grpc")]
+    {
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("找不到内置的 protoc");
+        std::env::set_var("PROTOC", protoc);
+        tonic_build::configure()
+            .build_client(false)
+            .compile_protos(&["proto/jm_downloader.proto"], &["proto"])
+            .expect("编译 proto/jm_downloader.proto 失败");
+    }
+}
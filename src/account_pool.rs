@@ -0,0 +1,302 @@
+// JM 账号池
+// 配置多个账号（JM_USERNAME 为主账号，JM_ACCOUNTS 为其余账号）时每个账号持有独立的客户端与会话，
+// 获取漫画、章节、scramble_id 等元数据的请求按 JM_ACCOUNT_ROTATION（round_robin 或 lru）轮换账号；
+// 账号触发风控（请求被拦截或登录失败）后冷却 JM_ACCOUNT_COOLDOWN_SECONDS 秒，冷却期间不参与轮换
+
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use jm_downloader_rs::AppError;
+use serde::Deserialize;
+use tokio::sync::{RwLock, RwLockReadGuard};
+
+use crate::jm_api::JmApi;
+
+type Result<T> = std::result::Result<T, AppError>;
+
+/// 账号冷却时长默认值（秒）
+pub const DEFAULT_COOLDOWN_SECONDS: u64 = 1800;
+/// 配置的冷却时长超出 `Instant` 可表示范围时使用的冷却时长（约 100 年）
+const LONGEST_COOLDOWN: Duration = Duration::from_secs(100 * 365 * 24 * 3600);
+
+/// 额外的 JM 账号（JM_ACCOUNTS 中的一项）
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct JmAccount {
+    pub username: String,
+    pub password: String,
+}
+
+/// 元数据请求的账号轮换策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum AccountRotation {
+    /// 依次轮换
+    #[default]
+    RoundRobin,
+    /// 选择最久未使用的账号
+    LeastRecentlyUsed,
+}
+
+impl FromStr for AccountRotation {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "round_robin" | "round-robin" => Ok(Self::RoundRobin),
+            "lru" | "least_recently_used" => Ok(Self::LeastRecentlyUsed),
+            _ => Err(format!("{}，应为 round_robin 或 lru", value)),
+        }
+    }
+}
+
+impl TryFrom<String> for AccountRotation {
+    type Error = String;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// 一个账号的客户端与会话状态；匿名模式下没有账号密码，不登录
+pub struct Session<C> {
+    client: RwLock<C>,
+    credentials: Option<(String, String)>,
+    /// 会话状态标记（用于优化：避免频繁检查）
+    valid: RwLock<bool>,
+    /// 最近一次被轮换选中的时间
+    last_used: Mutex<Option<Instant>>,
+    /// 触发风控后的冷却截止时间
+    cooldown_until: Mutex<Option<Instant>>,
+}
+
+impl<C: JmApi> Session<C> {
+    pub fn new(client: C, credentials: Option<(&str, &str)>) -> Self {
+        Self {
+            client: RwLock::new(client),
+            credentials: credentials.map(|(username, password)| (username.to_string(), password.to_string())),
+            valid: RwLock::new(true),
+            last_used: Mutex::new(None),
+            cooldown_until: Mutex::new(None),
+        }
+    }
+
+    /// 用于日志的账号名
+    pub fn name(&self) -> &str {
+        self.credentials.as_ref().map_or("匿名", |(username, _)| username)
+    }
+
+    /// 账号密码，匿名会话为 None
+    pub fn credentials(&self) -> Option<(&str, &str)> {
+        self.credentials
+            .as_ref()
+            .map(|(username, password)| (username.as_str(), password.as_str()))
+    }
+
+    /// 用账号密码登录，匿名会话不做任何事
+    pub async fn login(&self) -> Result<()> {
+        if let Some((username, password)) = self.credentials() {
+            self.client.read().await.login(username, password).await?;
+        }
+        Ok(())
+    }
+
+    /// 不检查会话的只读引用，用于读取客户端配置
+    pub async fn read(&self) -> RwLockReadGuard<'_, C> {
+        self.client.read().await
+    }
+
+    /// 获取客户端的只读引用（用于读操作）
+    ///
+    /// 在执行操作前会自动检查会话有效性，如果会话失效会自动重新登录
+    pub async fn client(&self) -> Result<RwLockReadGuard<'_, C>> {
+        // 快速路径：如果标记为有效，直接返回
+        if !*self.valid.read().await {
+            self.relogin().await?;
+        }
+        Ok(self.client.read().await)
+    }
+
+    /// 重新登录（当检测到会话失效时调用）
+    pub async fn relogin(&self) -> Result<()> {
+        // 获取写锁以执行重新登录
+        let mut valid = self.valid.write().await;
+
+        // 双重检查：可能其他线程已经完成了重新登录
+        if *valid {
+            return Ok(());
+        }
+
+        // 匿名模式没有会话可以恢复，只让触发的请求失败
+        let Some((username, password)) = self.credentials() else {
+            *valid = true;
+            return Err(login_required());
+        };
+
+        warn!("检测到账号 {} 会话失效，正在重新登录...", username);
+        self.client.read().await.login(username, password).await?;
+        *valid = true;
+
+        info!("账号 {} 重新登录成功", username);
+        Ok(())
+    }
+
+    /// 标记会话为失效（当 API 调用返回认证错误时调用）
+    pub async fn mark_invalid(&self) {
+        *self.valid.write().await = false;
+        warn!("账号 {} 的会话已标记为失效", self.name());
+    }
+
+    /// 替换为已登录的新客户端（切换域名时调用），进行中的请求继续使用原客户端
+    pub async fn replace(&self, client: C) {
+        *self.client.write().await = client;
+        *self.valid.write().await = true;
+    }
+
+    fn cooling(&self, now: Instant) -> bool {
+        self.cooldown_until.lock().unwrap().is_some_and(|until| until > now)
+    }
+}
+
+pub fn login_required() -> AppError {
+    AppError::LoginRequired("该操作需要登录 JM 账号，请配置 JM_USERNAME 与 JM_PASSWORD".to_string())
+}
+
+/// 账号池，第一个会话为主账号
+pub struct AccountPool<C> {
+    sessions: Vec<Arc<Session<C>>>,
+    rotation: Mutex<AccountRotation>,
+    cooldown_seconds: AtomicU64,
+    /// 轮询位置
+    cursor: AtomicUsize,
+}
+
+impl<C: JmApi> AccountPool<C> {
+    /// `sessions` 不能为空
+    pub fn new(sessions: Vec<Session<C>>) -> Self {
+        assert!(!sessions.is_empty(), "账号池至少需要一个会话");
+        Self {
+            sessions: sessions.into_iter().map(Arc::new).collect(),
+            rotation: Mutex::new(AccountRotation::default()),
+            cooldown_seconds: AtomicU64::new(DEFAULT_COOLDOWN_SECONDS),
+            cursor: AtomicUsize::new(0),
+        }
+    }
+
+    /// 更新轮换策略与冷却时长；启动与重新加载配置时调用
+    pub fn configure(&self, rotation: AccountRotation, cooldown: Duration) {
+        *self.rotation.lock().unwrap() = rotation;
+        self.cooldown_seconds.store(cooldown.as_secs(), Ordering::Relaxed);
+    }
+
+    /// 主账号（匿名模式下为唯一的匿名会话）
+    pub fn primary(&self) -> &Arc<Session<C>> {
+        &self.sessions[0]
+    }
+
+    pub fn sessions(&self) -> &[Arc<Session<C>>] {
+        &self.sessions
+    }
+
+    pub fn is_primary(&self, session: &Arc<Session<C>>) -> bool {
+        Arc::ptr_eq(session, self.primary())
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// 按轮换策略选出下一个账号，冷却中的账号不参与；全部在冷却时选冷却最早结束的
+    pub fn pick(&self) -> Arc<Session<C>> {
+        if self.sessions.len() == 1 {
+            return self.primary().clone();
+        }
+        let now = Instant::now();
+        let count = self.sessions.len();
+        let available: Vec<usize> = (0..count).filter(|&index| !self.sessions[index].cooling(now)).collect();
+        let index = if available.is_empty() {
+            (0..count)
+                .min_by_key(|&index| *self.sessions[index].cooldown_until.lock().unwrap())
+                .unwrap_or(0)
+        } else {
+            match *self.rotation.lock().unwrap() {
+                AccountRotation::RoundRobin => {
+                    let start = self.cursor.load(Ordering::Relaxed);
+                    let index = (0..count)
+                        .map(|offset| (start + offset) % count)
+                        .find(|index| available.contains(index))
+                        .unwrap_or(available[0]);
+                    self.cursor.store(index + 1, Ordering::Relaxed);
+                    index
+                }
+                // 从未使用过的账号（None）最先被选中
+                AccountRotation::LeastRecentlyUsed => available
+                    .iter()
+                    .copied()
+                    .min_by_key(|&index| *self.sessions[index].last_used.lock().unwrap())
+                    .unwrap_or(available[0]),
+            }
+        };
+        let session = &self.sessions[index];
+        *session.last_used.lock().unwrap() = Some(now);
+        session.clone()
+    }
+
+    /// 让触发风控的账号进入冷却
+    pub fn cool_down(&self, session: &Session<C>, error: &AppError) {
+        let seconds = self.cooldown_seconds.load(Ordering::Relaxed);
+        let now = Instant::now();
+        // 冷却时长大到无法表示时视为长期冷却
+        let until = now.checked_add(Duration::from_secs(seconds)).unwrap_or(now + LONGEST_COOLDOWN);
+        *session.cooldown_until.lock().unwrap() = Some(until);
+        warn!("账号 {} 触发风控，冷却 {} 秒: {}", session.name(), seconds, error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_client::MockJmClient;
+
+    fn pool(size: usize) -> AccountPool<MockJmClient> {
+        let names = ["a", "b", "c"];
+        AccountPool::new(
+            names[..size]
+                .iter()
+                .map(|name| Session::new(MockJmClient::new(), Some((name, "pass"))))
+                .collect(),
+        )
+    }
+
+    fn picks(pool: &AccountPool<MockJmClient>, count: usize) -> Vec<String> {
+        (0..count).map(|_| pool.pick().name().to_string()).collect()
+    }
+
+    #[test]
+    fn rotates_and_skips_cooling_accounts() {
+        let pool = pool(3);
+        assert_eq!(picks(&pool, 4), ["a", "b", "c", "a"]);
+
+        pool.cool_down(&pool.sessions()[1], &AppError::Blocked("测试".to_string()));
+        assert_eq!(picks(&pool, 3), ["c", "a", "c"]);
+
+        // 最久未使用的优先
+        pool.configure(AccountRotation::LeastRecentlyUsed, Duration::from_secs(60));
+        assert_eq!(picks(&pool, 2), ["a", "c"]);
+
+        // 全部冷却时选冷却最早结束的
+        pool.cool_down(&pool.sessions()[0], &AppError::Blocked("测试".to_string()));
+        pool.cool_down(&pool.sessions()[2], &AppError::Blocked("测试".to_string()));
+        assert_eq!(pool.pick().name(), "a");
+        assert!("LRU".parse::<AccountRotation>().is_ok() && "random".parse::<AccountRotation>().is_err());
+    }
+
+    #[test]
+    fn huge_cooldown_does_not_overflow() {
+        let pool = pool(2);
+        pool.configure(AccountRotation::RoundRobin, Duration::from_secs(u64::MAX));
+        pool.cool_down(&pool.sessions()[0], &AppError::Blocked("测试".to_string()));
+        assert_eq!(picks(&pool, 2), ["b", "b"]);
+    }
+}
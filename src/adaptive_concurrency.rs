@@ -0,0 +1,176 @@
+// 自适应图片下载并发
+// JM_ADAPTIVE_CONCURRENCY 为 true 时，所有任务的图片下载共享一个按 AIMD 调整的并发窗口：窗口从 JM_IMG_CONCURRENCY
+// 开始，CDN 返回 429/503 时减半（冷却期内只减一次，避免同一批请求的限流响应把窗口压到 1），之后每有一个窗口数量的
+// 成功响应加 1，直到回到 JM_IMG_CONCURRENCY。各任务原有的信号量仍然生效；当前窗口可在 /api/admin/domains 查看
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+use crate::models::ImageConcurrency;
+
+/// 两次减半之间的最短间隔，同一批在途请求的限流响应只计一次
+const DECREASE_COOLDOWN: Duration = Duration::from_secs(2);
+
+static WINDOW: Mutex<Window> = Mutex::new(Window {
+    enabled: false,
+    limit: 0,
+    max: 0,
+    in_flight: 0,
+    successes: 0,
+    throttled: 0,
+    last_decrease: None,
+});
+/// 窗口扩大或许可释放时唤醒等待者
+static AVAILABLE: Notify = Notify::const_new();
+
+struct Window {
+    enabled: bool,
+    /// 当前允许的在途下载数
+    limit: usize,
+    /// 窗口上限（JM_IMG_CONCURRENCY）
+    max: usize,
+    in_flight: usize,
+    /// 上次调整以来的成功响应数
+    successes: usize,
+    /// 累计收到的 429/503 响应数
+    throttled: u64,
+    last_decrease: Option<Instant>,
+}
+
+impl Window {
+    /// 成功响应：累计满一个窗口后加 1，返回窗口是否扩大
+    fn on_success(&mut self) -> bool {
+        self.successes += 1;
+        if self.successes < self.limit || self.limit >= self.max {
+            return false;
+        }
+        self.successes = 0;
+        self.limit += 1;
+        true
+    }
+
+    /// 限流响应：距上次减半超过冷却期时把窗口减半（至少为 1），返回减半后的窗口
+    fn on_throttled(&mut self, now: Instant) -> Option<usize> {
+        self.throttled += 1;
+        if self.last_decrease.is_some_and(|last| now.saturating_duration_since(last) < DECREASE_COOLDOWN) {
+            return None;
+        }
+        self.last_decrease = Some(now);
+        self.successes = 0;
+        self.limit = (self.limit / 2).max(1);
+        Some(self.limit)
+    }
+}
+
+/// 设置是否启用及窗口上限，启动与重新加载配置时调用；保持启用时保留已调整的窗口，只按新上限截断
+pub fn configure(enabled: bool, max: usize) {
+    let mut window = WINDOW.lock().unwrap();
+    window.limit = if enabled && window.enabled { window.limit.min(max) } else { max };
+    window.enabled = enabled;
+    window.max = max;
+    window.successes = 0;
+    drop(window);
+    AVAILABLE.notify_waiters();
+}
+
+/// 在途下载的许可，释放时归还窗口
+pub struct Permit(());
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        WINDOW.lock().unwrap().in_flight -= 1;
+        AVAILABLE.notify_waiters();
+    }
+}
+
+/// 等待窗口中的空位；未启用时立即返回 None
+pub async fn acquire() -> Option<Permit> {
+    loop {
+        // 先登记等待再检查，避免检查之后、等待之前的释放被错过
+        let available = AVAILABLE.notified();
+        {
+            let mut window = WINDOW.lock().unwrap();
+            if !window.enabled {
+                return None;
+            }
+            if window.in_flight < window.limit {
+                window.in_flight += 1;
+                return Some(Permit(()));
+            }
+        }
+        available.await;
+    }
+}
+
+/// 记录一次图片请求的 HTTP 状态码：429/503 缩小窗口，成功响应逐步扩大窗口
+pub fn record(status: reqwest::StatusCode) {
+    let mut window = WINDOW.lock().unwrap();
+    if !window.enabled {
+        return;
+    }
+    if matches!(status.as_u16(), 429 | 503) {
+        if let Some(limit) = window.on_throttled(Instant::now()) {
+            warn!("图片 CDN 返回 {}，下载并发降为 {}", status.as_u16(), limit);
+        }
+    } else if status.is_success() && window.on_success() {
+        let limit = window.limit;
+        drop(window);
+        debug!("图片下载并发升至 {}", limit);
+        AVAILABLE.notify_waiters();
+    }
+}
+
+/// 当前窗口状态，未启用时返回 None
+pub fn snapshot() -> Option<ImageConcurrency> {
+    let window = WINDOW.lock().unwrap();
+    window.enabled.then(|| ImageConcurrency {
+        limit: window.limit,
+        max: window.max,
+        in_flight: window.in_flight,
+        throttled_responses: window.throttled,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn halves_on_throttle_and_ramps_back_slowly() {
+        let mut window = Window {
+            enabled: true,
+            limit: 16,
+            max: 16,
+            in_flight: 0,
+            successes: 0,
+            throttled: 0,
+            last_decrease: None,
+        };
+        let now = Instant::now();
+        assert_eq!(window.on_throttled(now), Some(8));
+        // 冷却期内的限流响应只计数
+        assert_eq!(window.on_throttled(now + Duration::from_millis(500)), None);
+        assert_eq!(window.on_throttled(now + DECREASE_COOLDOWN), Some(4));
+        assert_eq!(window.throttled, 3);
+
+        // 每满一个窗口的成功响应加 1
+        assert!(!(0..3).any(|_| window.on_success()));
+        assert!(window.on_success());
+        assert_eq!(window.limit, 5);
+        while window.limit < window.max {
+            window.on_success();
+        }
+        // 达到上限后不再扩大
+        assert!(!(0..100).any(|_| window.on_success()));
+        assert_eq!(window.limit, 16);
+
+        let later = now + DECREASE_COOLDOWN * 10;
+        for i in 0..10 {
+            window.on_throttled(later + DECREASE_COOLDOWN * i);
+        }
+        assert_eq!(window.limit, 1);
+    }
+}
@@ -0,0 +1,404 @@
+// 管理接口模块
+// 需在请求头 X-Admin-Key 中携带 JM_ADMIN_API_KEY，提供下载目录的清理与占用统计、配置热更新、服务模式切换，以及排查用的原始数据接口
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use jm_downloader_rs::{ApiResult, AppError, R};
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::serde::json::Json;
+use rocket::State;
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::okapi::openapi3::{Object, SecurityRequirement, SecurityScheme, SecuritySchemeData};
+use rocket_okapi::openapi;
+use rocket_okapi::request::{OpenApiFromRequest, RequestHeaderInput};
+
+use crate::adaptive_concurrency;
+use crate::config::{load_config, Config, LiveConfig};
+use crate::dir_lease::DirLeases;
+use crate::global_client::GlobalJmClient;
+use crate::history;
+use crate::image_processor::{self, download_root};
+use crate::jobs::Jobs;
+use crate::memory_budget;
+use crate::validation::Validate;
+use crate::metadata;
+use crate::models::{
+    CleanupData, CleanupRequest, ComicStorage, DomainsData, RawDataRequest, ReloadConfigData, ServiceModeData,
+    ServiceModeRequest, StorageData,
+};
+use crate::service_mode;
+use crate::throttle;
+use crate::upscale;
+use crate::url_signer::UrlSigner;
+
+const ADMIN_KEY_HEADER: &str = "X-Admin-Key";
+
+/// 请求头中携带的管理 API Key
+///
+/// 守卫本身总是成功，由 [`AdminKey::verify`] 返回统一格式的业务错误
+pub struct AdminKey(Option<String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AdminKey {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(AdminKey(req.headers().get_one(ADMIN_KEY_HEADER).map(str::to_string)))
+    }
+}
+
+impl<'r> OpenApiFromRequest<'r> for AdminKey {
+    fn from_request_input(
+        _gen: &mut OpenApiGenerator,
+        _name: String,
+        _required: bool,
+    ) -> rocket_okapi::Result<RequestHeaderInput> {
+        let scheme = SecurityScheme {
+            description: Some("管理接口 API Key（JM_ADMIN_API_KEY）".to_string()),
+            data: SecuritySchemeData::ApiKey {
+                name: ADMIN_KEY_HEADER.to_string(),
+                location: "header".to_string(),
+            },
+            extensions: Object::default(),
+        };
+        let mut requirement = SecurityRequirement::new();
+        requirement.insert("AdminKey".to_string(), Vec::new());
+        Ok(RequestHeaderInput::Security("AdminKey".to_string(), scheme, requirement))
+    }
+}
+
+impl AdminKey {
+    /// 校验 API Key；未配置 JM_ADMIN_API_KEY 时管理接口一律拒绝
+    pub fn verify(&self, config: &Config) -> ApiResult<()> {
+        let Some(expected) = config.admin_api_key.as_deref() else {
+            return Err(AppError::Forbidden("未配置 JM_ADMIN_API_KEY，管理接口不可用".to_string()));
+        };
+        match self.0.as_deref() {
+            Some(key) if constant_time_eq(key.as_bytes(), expected.as_bytes()) => Ok(()),
+            Some(_) => Err(AppError::Unauthorized("管理 API Key 无效".to_string())),
+            None => Err(AppError::Unauthorized(format!("缺少 {} 请求头", ADMIN_KEY_HEADER))),
+        }
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// `now` 往前 `hours` 小时的时间点；小时数过大以致溢出时返回参数错误
+fn cleanup_cutoff(now: SystemTime, hours: u64) -> ApiResult<SystemTime> {
+    hours
+        .checked_mul(3600)
+        .and_then(|seconds| now.checked_sub(Duration::from_secs(seconds)))
+        .ok_or_else(|| AppError::BadRequest(format!("older_than_hours 过大: {}", hours)))
+}
+
+/// # 清理下载目录
+/// 按条件删除章节目录：`older_than_hours`、`comic_id` 可组合，均未指定时需传 `all: true`。正在使用的目录会被跳过。
+#[openapi]
+#[post("/api/admin/cleanup", data = "<request>")]
+pub async fn cleanup(
+    config: &State<LiveConfig>,
+    leases: &State<DirLeases>,
+    admin: AdminKey,
+    request: Json<CleanupRequest>,
+) -> ApiResult<R<CleanupData>> {
+    let config = config.load();
+    admin.verify(&config)?;
+    request.validate(&config)?;
+    if request.older_than_hours.is_none() && request.comic_id.is_none() && !request.all {
+        return Err(AppError::BadRequest(
+            "请至少指定 older_than_hours、comic_id 之一，或传入 all: true 清理全部".to_string(),
+        ));
+    }
+
+    let cutoff = request
+        .older_than_hours
+        .map(|hours| cleanup_cutoff(SystemTime::now(), hours))
+        .transpose()?;
+    let comic_filter = request.comic_id.map(|id| id.to_string());
+    let leases = leases.inner().clone();
+
+    let data = tokio::task::spawn_blocking(move || {
+        let mut data = CleanupData {
+            removed_dirs: 0,
+            freed_bytes: 0,
+            skipped_in_use: 0,
+        };
+        for (comic_id, chapter_dirs) in list_download_dirs() {
+            if comic_filter.as_ref().is_some_and(|id| *id != comic_id) {
+                continue;
+            }
+            for chapter_dir in chapter_dirs {
+                let usage = dir_usage(&chapter_dir);
+                if cutoff.is_some_and(|cutoff| usage.modified > cutoff) {
+                    continue;
+                }
+                match leases.remove_if_unused(&chapter_dir) {
+                    Ok(true) => {
+                        data.removed_dirs += 1;
+                        data.freed_bytes += usage.bytes;
+                    }
+                    Ok(false) => data.skipped_in_use += 1,
+                    Err(e) => warn!("删除目录 {} 失败: {}", chapter_dir.display(), e),
+                }
+            }
+            // 漫画目录已空时一并删除
+            let comic_dir = download_root().join(&comic_id);
+            metadata::remove_if_orphaned(&comic_dir);
+            let _ = std::fs::remove_dir(comic_dir);
+        }
+        data
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("清理任务执行失败: {}", e)))?;
+
+    info!(
+        "管理接口清理完成: 删除 {} 个目录，释放 {} 字节，跳过 {} 个正在使用的目录",
+        data.removed_dirs, data.freed_bytes, data.skipped_in_use
+    );
+    Ok(R::success(data))
+}
+
+/// # 下载目录占用
+/// 按漫画统计下载目录的磁盘占用。
+#[openapi]
+#[get("/api/admin/storage")]
+pub async fn storage(config: &State<LiveConfig>, admin: AdminKey) -> ApiResult<R<StorageData>> {
+    admin.verify(&config.load())?;
+
+    let data = tokio::task::spawn_blocking(|| {
+        let mut comics: Vec<ComicStorage> = list_download_dirs()
+            .into_iter()
+            .map(|(comic_id, chapter_dirs)| {
+                let mut comic = ComicStorage {
+                    comic_id,
+                    bytes: 0,
+                    chapters: chapter_dirs.len(),
+                    files: 0,
+                };
+                for chapter_dir in chapter_dirs {
+                    let usage = dir_usage(&chapter_dir);
+                    comic.bytes += usage.bytes;
+                    comic.files += usage.files;
+                }
+                comic
+            })
+            .collect();
+        comics.sort_by_key(|comic| std::cmp::Reverse(comic.bytes));
+        StorageData {
+            total_bytes: comics.iter().map(|comic| comic.bytes).sum(),
+            comics,
+        }
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("统计任务执行失败: {}", e)))?;
+
+    Ok(R::success(data))
+}
+
+/// # 图片域名探测结果
+/// 返回各图片域名最近的探测延迟、失败率与最近一次错误，按下载时的使用顺序排列（健康且最快的在前）。
+#[openapi]
+#[get("/api/admin/domains")]
+pub async fn domains(
+    config: &State<LiveConfig>,
+    global_client: &State<GlobalJmClient>,
+    admin: AdminKey,
+) -> ApiResult<R<DomainsData>> {
+    let config = config.load();
+    admin.verify(&config)?;
+    Ok(R::success(DomainsData {
+        probe_interval_seconds: config.image_probe_seconds,
+        domains: global_client.domain_health().await,
+        concurrency: adaptive_concurrency::snapshot(),
+    }))
+}
+
+/// # 服务模式
+/// 返回当前服务模式与执行中、排队中的任务数。
+#[openapi]
+#[get("/api/admin/mode")]
+pub async fn get_mode(config: &State<LiveConfig>, jobs: &State<Jobs>, admin: AdminKey) -> ApiResult<R<ServiceModeData>> {
+    admin.verify(&config.load())?;
+    let mode = service_mode::current();
+    Ok(R::success(ServiceModeData { mode, previous: mode, running_jobs: jobs.list().len() }))
+}
+
+/// # 切换服务模式
+/// `read_only` 时查询与搜索照常，新的下载请求返回 10014，进行中的任务继续完成；`maintenance` 时除健康检查与管理接口外全部返回 10015。
+/// 升级前先切换为只读，等 `running_jobs` 降为 0 后再停机。
+#[openapi]
+#[post("/api/admin/mode", data = "<request>")]
+pub async fn set_mode(
+    config: &State<LiveConfig>,
+    jobs: &State<Jobs>,
+    admin: AdminKey,
+    request: Json<ServiceModeRequest>,
+) -> ApiResult<R<ServiceModeData>> {
+    admin.verify(&config.load())?;
+    let previous = service_mode::set(request.mode);
+    let running_jobs = jobs.list().len();
+    info!("服务模式已从 {:?} 切换为 {:?}，执行中与排队中的任务 {} 个", previous, request.mode, running_jobs);
+    Ok(R::success(ServiceModeData { mode: request.mode, previous, running_jobs }))
+}
+
+/// # 漫画原始数据
+/// 返回 JM 漫画接口解密后的完整 JSON，不经模型过滤；JM 新增字段或调整结构导致解析失败时用于排查。
+#[openapi]
+#[post("/api/debug/rawAlbum", data = "<request>")]
+pub async fn raw_album(
+    config: &State<LiveConfig>,
+    global_client: &State<GlobalJmClient>,
+    admin: AdminKey,
+    request: Json<RawDataRequest>,
+) -> ApiResult<R<serde_json::Value>> {
+    let config = config.load();
+    admin.verify(&config)?;
+    request.validate(&config)?;
+    global_client.raw_album(request.id).await.map(R::success)
+}
+
+/// # 章节原始数据
+/// 返回 JM 章节接口解密后的完整 JSON，不经模型过滤。
+#[openapi]
+#[post("/api/debug/rawChapter", data = "<request>")]
+pub async fn raw_chapter(
+    config: &State<LiveConfig>,
+    global_client: &State<GlobalJmClient>,
+    admin: AdminKey,
+    request: Json<RawDataRequest>,
+) -> ApiResult<R<serde_json::Value>> {
+    let config = config.load();
+    admin.verify(&config)?;
+    request.validate(&config)?;
+    global_client.raw_chapter(request.id).await.map(R::success)
+}
+
+/// # 重新加载配置
+/// 重新读取环境变量与配置文件，热更新域名、并发数、重试次数、链接有效期等设置；仅在启动时生效的字段会在 `requires_restart` 中列出。
+#[openapi]
+#[post("/api/admin/reloadConfig")]
+pub async fn reload_config(
+    config: &State<LiveConfig>,
+    global_client: &State<GlobalJmClient>,
+    signer: &State<UrlSigner>,
+    admin: AdminKey,
+) -> ApiResult<R<ReloadConfigData>> {
+    admin.verify(&config.load())?;
+    let data = reload(config, global_client, signer).await?;
+    Ok(R::success(data))
+}
+
+/// 重新加载配置并应用到各组件（管理接口与 SIGHUP 共用）
+///
+/// 新配置校验失败或新客户端登录失败时保持原配置不变
+pub async fn reload(
+    live: &LiveConfig,
+    global_client: &GlobalJmClient,
+    signer: &UrlSigner,
+) -> ApiResult<ReloadConfigData> {
+    let running = live.load();
+    let mut config = load_config()?;
+    let requires_restart = config.keep_startup_only(&running);
+    let changed = running.changed_fields(&config);
+
+    global_client.apply_config(&running, &config).await?;
+    signer.set_ttl(config.download_url_ttl);
+    if config.max_download_mbps != running.max_download_mbps {
+        throttle::set_max_download_mbps(config.max_download_mbps);
+    }
+    memory_budget::configure(config.memory_budget_mb, config.spool_threshold_mb);
+    image_processor::set_blocked_image_md5(config.image_blocked_md5.clone());
+    image_processor::set_png_settings(config.png_settings());
+    image_processor::set_max_image_mb(config.max_image_mb);
+    // 重新创建会重置并发许可，只在放大设置变化时更新
+    if changed.iter().any(|field| field.starts_with("upscale_")) {
+        upscale::configure(&config);
+    }
+    adaptive_concurrency::configure(config.adaptive_concurrency, config.img_concurrency);
+    history::configure(config.history_path());
+    live.store(config);
+
+    info!("配置已重新加载，变更字段: {:?}，需重启生效: {:?}", changed, requires_restart);
+    Ok(ReloadConfigData {
+        changed: changed.into_iter().map(str::to_string).collect(),
+        requires_restart: requires_restart.into_iter().map(str::to_string).collect(),
+    })
+}
+
+/// 列出 `{download_root}/{comic_id}/{chapter_id}` 下的全部章节目录
+fn list_download_dirs() -> Vec<(String, Vec<PathBuf>)> {
+    let Ok(comics) = std::fs::read_dir(download_root()) else {
+        return Vec::new();
+    };
+    comics
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| {
+            let comic_id = entry.file_name().to_string_lossy().to_string();
+            let chapter_dirs = std::fs::read_dir(entry.path())
+                .map(|chapters| {
+                    chapters
+                        .flatten()
+                        .map(|chapter| chapter.path())
+                        .filter(|path| path.is_dir())
+                        .collect()
+                })
+                .unwrap_or_default();
+            (comic_id, chapter_dirs)
+        })
+        .collect()
+}
+
+struct DirUsage {
+    bytes: u64,
+    files: usize,
+    /// 目录及其中文件的最晚修改时间
+    modified: SystemTime,
+}
+
+fn dir_usage(dir: &Path) -> DirUsage {
+    let mut usage = DirUsage {
+        bytes: 0,
+        files: 0,
+        modified: std::fs::metadata(dir)
+            .and_then(|meta| meta.modified())
+            .unwrap_or(SystemTime::UNIX_EPOCH),
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return usage;
+    };
+    for entry in entries.flatten() {
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if meta.is_dir() {
+            let child = dir_usage(&entry.path());
+            usage.bytes += child.bytes;
+            usage.files += child.files;
+            usage.modified = usage.modified.max(child.modified);
+        } else {
+            usage.bytes += meta.len();
+            usage.files += 1;
+            if let Ok(modified) = meta.modified() {
+                usage.modified = usage.modified.max(modified);
+            }
+        }
+    }
+    usage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_overflowing_cleanup_hours() {
+        let now = SystemTime::now();
+        assert_eq!(cleanup_cutoff(now, 2).unwrap(), now - Duration::from_secs(7200));
+        assert!(matches!(cleanup_cutoff(now, u64::MAX), Err(AppError::BadRequest(_))));
+        assert!(matches!(cleanup_cutoff(now, u64::MAX / 3600), Err(AppError::BadRequest(_))));
+    }
+}
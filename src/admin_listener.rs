@@ -0,0 +1,93 @@
+// 独立的管理接口监听
+// 设置 JM_ADMIN_ADDR 时，管理、排查与报表接口（/api/admin/*、/api/debug/*、/api/reports/*）以及任务的暂停/恢复/取消
+// 不再挂载到公开端口，改由另一个 Rocket 实例只在该地址提供（含各自的 /openapi.json 与 /ui 仪表盘），
+// 便于把下载接口公开而把管理端口留在内网。仪表盘依赖的任务事件流两边都挂载。
+// 两个实例共享同一份配置、客户端与任务等托管状态，管理接口仍需 X-Admin-Key
+
+use std::net::SocketAddr;
+
+use rocket::{Build, Rocket, Route};
+use rocket_okapi::okapi::openapi3::OpenApi;
+
+/// 移到管理端口的接口路径前缀
+const ADMIN_PREFIXES: [&str; 3] = ["/api/admin/", "/api/debug/", "/api/reports/"];
+
+/// 移到管理端口的任务控制操作（`/api/job/<id>/...`）
+const JOB_CONTROL_ACTIONS: [&str; 3] = ["/pause", "/resume", "/cancel"];
+/// 仪表盘需要、公开端口也保留的接口
+const SHARED_PATHS: [&str; 1] = ["/api/job/events"];
+
+fn is_admin_path(path: &str) -> bool {
+    ADMIN_PREFIXES.iter().any(|prefix| path.starts_with(prefix))
+        || (path.starts_with("/api/job/") && JOB_CONTROL_ACTIONS.iter().any(|action| path.ends_with(action)))
+}
+
+fn is_shared_path(path: &str) -> bool {
+    SHARED_PATHS.contains(&path)
+}
+
+/// 从全部接口与文档中拆出管理接口，返回管理接口的路由与文档；共享接口两边都有，其余组件（schema 等）两份文档都保留
+pub fn split_off(routes: &mut Vec<Route>, spec: &mut OpenApi) -> (Vec<Route>, OpenApi) {
+    let (mut admin_routes, public_routes): (Vec<Route>, Vec<Route>) = std::mem::take(routes)
+        .into_iter()
+        .partition(|route| is_admin_path(route.uri.path()));
+    admin_routes.extend(public_routes.iter().filter(|route| is_shared_path(route.uri.path())).cloned());
+    *routes = public_routes;
+
+    let mut admin_spec = spec.clone();
+    admin_spec.paths.retain(|path, _| is_admin_path(path) || is_shared_path(path));
+    spec.paths.retain(|path, _| !is_admin_path(path));
+    (admin_routes, admin_spec)
+}
+
+/// 在后台启动管理接口实例
+pub fn spawn(rocket: Rocket<Build>, addr: SocketAddr) {
+    tokio::spawn(async move {
+        info!("管理接口监听 {}", addr);
+        if let Err(e) = rocket.launch().await {
+            error!("管理接口服务异常退出: {}", e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::http::Method;
+    use rocket::route::dummy_handler;
+    use rocket_okapi::okapi::openapi3::PathItem;
+
+    #[test]
+    fn moves_admin_routes_and_docs() {
+        let mut routes: Vec<Route> = [
+            "/api/health",
+            "/api/admin/cleanup",
+            "/api/debug/rawAlbum",
+            "/api/job",
+            "/api/job/<id>/pause",
+            "/api/job/<id>/cancel",
+            "/api/job/<id>/result",
+            "/api/job/events",
+        ]
+        .into_iter()
+        .map(|path| Route::new(Method::Get, path, dummy_handler))
+        .collect();
+        let mut spec = OpenApi::default();
+        for path in ["/api/health", "/api/admin/cleanup", "/api/reports/usage", "/api/job/{id}/resume", "/api/job/events"] {
+            spec.paths.insert(path.to_string(), PathItem::default());
+        }
+
+        let (admin_routes, admin_spec) = split_off(&mut routes, &mut spec);
+        let paths = |routes: &[Route]| routes.iter().map(|route| route.uri.path().to_string()).collect::<Vec<_>>();
+        assert_eq!(paths(&routes), ["/api/health", "/api/job", "/api/job/<id>/result", "/api/job/events"]);
+        assert_eq!(
+            paths(&admin_routes),
+            ["/api/admin/cleanup", "/api/debug/rawAlbum", "/api/job/<id>/pause", "/api/job/<id>/cancel", "/api/job/events"]
+        );
+        assert_eq!(spec.paths.keys().collect::<Vec<_>>(), ["/api/health", "/api/job/events"]);
+        assert_eq!(
+            admin_spec.paths.keys().collect::<Vec<_>>(),
+            ["/api/admin/cleanup", "/api/reports/usage", "/api/job/{id}/resume", "/api/job/events"]
+        );
+    }
+}
@@ -0,0 +1,224 @@
+// 下载产物的变体目录与写锁
+// 同一章节以不同处理选项（跨页拆分、电子墨水屏、原始文件名）或不同 PDF 输出选项（压缩档位、DPI、加密）下载时，
+// 产物写入章节目录下的 `variants/{选项哈希}`，默认选项仍直接写入章节目录，各变体的文件互不覆盖；
+// 写入同一变体目录的请求按 `ArtifactKey` 依次持有写锁，不同变体之间互不阻塞
+
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, Weak};
+
+use jm_downloader_rs::AppError;
+use sha2::{Digest, Sha256};
+use tokio::sync::OwnedMutexGuard;
+
+use crate::image_processor::{chapter_dir_path, ProcessOptions};
+use crate::models::{PdfQuality, SpreadOrder};
+
+type Result<T> = std::result::Result<T, AppError>;
+
+/// 章节目录中存放变体的子目录名
+pub const VARIANTS_DIR: &str = "variants";
+
+/// 变体目录名取选项描述 SHA-256 的前 6 字节（12 个十六进制字符）
+const VARIANT_HASH_BYTES: usize = 6;
+
+/// 下载产物的标识：(漫画, 章节, 选项哈希)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ArtifactKey {
+    pub comic_id: i64,
+    pub chapter_id: i64,
+    /// 选项哈希，默认选项为 None
+    variant: Option<String>,
+}
+
+impl ArtifactKey {
+    /// 单页图片的产物，由单页处理选项决定变体
+    pub fn pages(comic_id: i64, chapter_id: i64, process: &ProcessOptions) -> Self {
+        Self::new(comic_id, chapter_id, process_options(process))
+    }
+
+    /// 合并 PDF 的产物，单页处理选项之外再加上 PDF 输出选项；密码只记录是否加密，不参与目录名
+    pub fn pdf(
+        comic_id: i64,
+        chapter_id: i64,
+        process: &ProcessOptions,
+        quality: PdfQuality,
+        dpi: Option<u32>,
+        encrypted: bool,
+        title_page: bool,
+    ) -> Self {
+        let mut options = process_options(process);
+        if quality != PdfQuality::default() {
+            options.push(format!("quality={}", quality.gs_setting().unwrap_or("none")));
+        }
+        if let Some(dpi) = dpi {
+            options.push(format!("dpi={}", dpi));
+        }
+        if encrypted {
+            options.push("encrypted".to_string());
+        }
+        if title_page {
+            options.push("title".to_string());
+        }
+        Self::new(comic_id, chapter_id, options)
+    }
+
+    fn new(comic_id: i64, chapter_id: i64, options: Vec<String>) -> Self {
+        let variant = (!options.is_empty()).then(|| {
+            let hash = Sha256::digest(options.join(";").as_bytes());
+            hex::encode(&hash[..VARIANT_HASH_BYTES])
+        });
+        Self { comic_id, chapter_id, variant }
+    }
+
+    /// 章节目录 `{download_root}/{comic_id}/{chapter_id}`，目录租约与过期删除以它为单位，包含所有变体
+    pub fn chapter_dir(&self) -> PathBuf {
+        chapter_dir_path(self.comic_id, self.chapter_id)
+    }
+
+    /// 产物所在目录：默认选项为章节目录，否则为 `{章节目录}/variants/{选项哈希}`
+    pub fn dir(&self) -> PathBuf {
+        let chapter_dir = self.chapter_dir();
+        match &self.variant {
+            Some(variant) => chapter_dir.join(VARIANTS_DIR).join(variant),
+            None => chapter_dir,
+        }
+    }
+
+    /// 产物目录相对下载服务的路径，如 `download/123/456/variants/0a1b2c3d4e5f`
+    pub fn relative_dir(&self) -> String {
+        let chapter = format!("download/{}/{}", self.comic_id, self.chapter_id);
+        match &self.variant {
+            Some(variant) => format!("{}/{}/{}", chapter, VARIANTS_DIR, variant),
+            None => chapter,
+        }
+    }
+
+    /// 产物目录中文件的相对路径
+    pub fn relative_path(&self, file_name: &str) -> String {
+        format!("{}/{}", self.relative_dir(), file_name)
+    }
+
+    /// 按标题组织目录的远端存储中的目录层级：非默认变体在 `folder` 下再加一层选项哈希，各变体的同名文件互不覆盖
+    pub fn remote_folder(&self, mut folder: Vec<String>) -> Vec<String> {
+        folder.extend(self.variant.clone());
+        folder
+    }
+
+    /// 创建产物目录
+    pub fn create_dir(&self) -> Result<PathBuf> {
+        let dir = self.dir();
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| AppError::Internal(format!("创建目录 {} 失败: {}", dir.display(), e)))?;
+        Ok(dir)
+    }
+}
+
+/// 单页处理选项的规范描述，默认选项为空
+fn process_options(process: &ProcessOptions) -> Vec<String> {
+    let mut options = Vec::new();
+    if let Some(order) = process.split_spreads {
+        options.push(match order {
+            SpreadOrder::Rtl => "spread=rtl".to_string(),
+            SpreadOrder::Ltr => "spread=ltr".to_string(),
+        });
+    }
+    if let Some(long_edge) = process.eink {
+        options.push(format!("eink={}", long_edge));
+    }
+    if process.preserve_filenames {
+        options.push("names=original".to_string());
+    }
+    if process.keep_original {
+        options.push("original".to_string());
+    }
+    if process.trim_borders {
+        options.push("trim".to_string());
+    }
+    if process.upscale {
+        options.push("upscale".to_string());
+    }
+    if !process.post_process.is_empty() {
+        options.push(format!("post={}", process.post_process));
+    }
+    options
+}
+
+/// 下载文件（相对下载根目录的路径）所属章节目录，变体中的文件也归属其章节目录，用于获取目录租约
+pub fn lease_dir(relative_path: &Path) -> Option<PathBuf> {
+    let components: Vec<Component> = relative_path.components().collect();
+    let parent = relative_path.parent()?;
+    if components.len() > 3 && components[2].as_os_str() == VARIANTS_DIR {
+        return Some(components[..2].iter().collect());
+    }
+    Some(parent.to_path_buf())
+}
+
+/// 写入产物持有的锁，释放（drop）后同一变体的下一个请求才能写入
+pub type ArtifactGuard = OwnedMutexGuard<()>;
+
+/// 按 `ArtifactKey` 分配的写锁，克隆后共享同一份状态
+#[derive(Clone, Default)]
+pub struct ArtifactLocks {
+    locks: Arc<Mutex<HashMap<ArtifactKey, Weak<tokio::sync::Mutex<()>>>>>,
+}
+
+impl ArtifactLocks {
+    /// 等待并持有 `key` 的写锁
+    pub async fn lock(&self, key: &ArtifactKey) -> ArtifactGuard {
+        let lock = {
+            let mut locks = self.locks.lock().unwrap();
+            // 没有持有者也没有等待者的锁已释放，顺便清理
+            locks.retain(|_, lock| lock.strong_count() > 0);
+            match locks.get(key).and_then(Weak::upgrade) {
+                Some(lock) => lock,
+                None => {
+                    let lock = Arc::new(tokio::sync::Mutex::new(()));
+                    locks.insert(key.clone(), Arc::downgrade(&lock));
+                    lock
+                }
+            }
+        };
+        lock.lock_owned().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn variants_get_separate_dirs_and_locks() {
+        let plain = ArtifactKey::pages(1, 2, &ProcessOptions::default());
+        assert_eq!(plain.variant, None);
+        assert_eq!(plain.relative_path("0001.png"), "download/1/2/0001.png");
+        assert_eq!(plain.dir(), plain.chapter_dir());
+        assert_eq!(plain.remote_folder(vec!["标题".to_string()]), ["标题"]);
+
+        let eink = ProcessOptions { eink: Some(1600), ..Default::default() };
+        let key = ArtifactKey::pages(1, 2, &eink);
+        let variant = key.variant.clone().unwrap();
+        assert_eq!(variant.len(), VARIANT_HASH_BYTES * 2);
+        assert_eq!(key, ArtifactKey::pdf(1, 2, &eink, PdfQuality::Printer, None, false, false));
+        assert_ne!(key, ArtifactKey::pdf(1, 2, &eink, PdfQuality::Ebook, None, false, false));
+        assert_ne!(key, ArtifactKey::pdf(1, 2, &eink, PdfQuality::Printer, None, true, false));
+        assert_ne!(key, ArtifactKey::pdf(1, 2, &eink, PdfQuality::Printer, None, false, true));
+        assert_eq!(key.dir(), key.chapter_dir().join(VARIANTS_DIR).join(&variant));
+        assert_eq!(key.remote_folder(vec!["标题".to_string()]), ["标题".to_string(), variant.clone()]);
+        let relative = key.relative_path("0001.png");
+        assert_eq!(relative, format!("download/1/2/variants/{}/0001.png", variant));
+        assert_eq!(lease_dir(Path::new(&relative[9..])), Some(PathBuf::from("1/2")));
+        assert_eq!(lease_dir(Path::new("1/2/0001.png")), Some(PathBuf::from("1/2")));
+
+        let locks = ArtifactLocks::default();
+        let guard = locks.lock(&key).await;
+        // 其他变体不受影响，同一变体需等待
+        drop(locks.lock(&plain).await);
+        let same = locks.lock(&key);
+        tokio::pin!(same);
+        assert!(tokio::time::timeout(std::time::Duration::from_millis(20), &mut same).await.is_err());
+        drop(guard);
+        drop(same.await);
+        assert!(locks.locks.lock().unwrap().values().all(|lock| lock.strong_count() == 0));
+    }
+}
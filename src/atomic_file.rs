@@ -0,0 +1,72 @@
+// 原子写入
+// 先写入同目录下的 `<文件名>.part`，成功后再重命名为目标文件。同一文件系统内重命名是原子的，
+// 进程中途崩溃只会留下 `.part` 文件，download/ 下出现的页面、清单与 PDF 总是完整的，跳过逻辑可以放心复用
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// 写入中的临时文件后缀
+pub const PART_SUFFIX: &str = ".part";
+
+/// 目标文件对应的临时文件：`0001.png` -> `0001.png.part`
+pub fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(PART_SUFFIX);
+    path.with_file_name(name)
+}
+
+/// 异步原子写入
+pub async fn write(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let part = part_path(path);
+    let result = match tokio::fs::write(&part, contents).await {
+        Ok(()) => tokio::fs::rename(&part, path).await,
+        Err(e) => Err(e),
+    };
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&part).await;
+    }
+    result
+}
+
+/// 同步原子写入，供 spawn_blocking 中的任务使用
+pub fn write_sync(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    persist_with(path, |part| std::fs::write(part, contents))
+}
+
+/// 由调用方写入临时文件（如流式保存 PDF），成功后重命名为目标文件；失败时删除临时文件
+pub fn persist_with(path: &Path, write: impl FnOnce(&Path) -> io::Result<()>) -> io::Result<()> {
+    let part = part_path(path);
+    let result = write(&part).and_then(|()| std::fs::rename(&part, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&part);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_write_leaves_no_target_or_part_file() {
+        let dir = std::env::temp_dir().join(format!("jm-atomic-file-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let target = dir.join("0001.png");
+        assert_eq!(part_path(&target), dir.join("0001.png.part"));
+
+        write_sync(&target, b"png").unwrap();
+        assert_eq!(std::fs::read(&target).unwrap(), b"png");
+        assert!(!part_path(&target).exists());
+
+        let failed = dir.join("merged.pdf");
+        let result = persist_with(&failed, |part| {
+            std::fs::write(part, b"%PDF-")?;
+            Err(io::Error::other("写入中断"))
+        });
+        assert!(result.is_err());
+        assert!(!failed.exists());
+        assert!(!part_path(&failed).exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,86 @@
+// 校验清单
+// 请求 checksums 为 true 时计算章节目录中本次产出的文件（单页图片、PDF 及分卷）的 SHA-256，
+// 写入与 `sha256sum -c` 兼容的 checksums.sha256，并在响应中返回各文件的哈希，供归档流程校验传输完整性
+
+use std::io::Read;
+use std::path::Path;
+
+use jm_downloader_rs::AppError;
+use sha2::{Digest, Sha256};
+
+use crate::atomic_file;
+use crate::models::FileChecksum;
+
+type Result<T> = std::result::Result<T, AppError>;
+
+/// 章节目录中的清单文件名
+pub const MANIFEST_FILE: &str = "checksums.sha256";
+
+/// 计算 `chapter_dir` 中 `files`（文件名）的 SHA-256 并写入清单，按传入顺序返回，重复的文件名只计一次
+pub async fn write_manifest(chapter_dir: &Path, files: Vec<String>) -> Result<Vec<FileChecksum>> {
+    let chapter_dir = chapter_dir.to_path_buf();
+    tokio::task::spawn_blocking(move || -> Result<Vec<FileChecksum>> {
+        let mut checksums: Vec<FileChecksum> = Vec::with_capacity(files.len());
+        for file in files {
+            if checksums.iter().any(|checksum| checksum.file == file) {
+                continue;
+            }
+            let sha256 = hash_file(&chapter_dir.join(&file))?;
+            checksums.push(FileChecksum { file, sha256 });
+        }
+        let manifest_path = chapter_dir.join(MANIFEST_FILE);
+        atomic_file::write_sync(&manifest_path, manifest(&checksums)).map_err(|e| {
+            AppError::Internal(format!("写入校验清单 {} 失败: {}", manifest_path.display(), e))
+        })?;
+        Ok(checksums)
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("计算校验和任务执行失败: {}", e)))?
+}
+
+/// 流式计算文件的 SHA-256（十六进制），需在阻塞线程中调用
+pub fn hash_file(path: &Path) -> Result<String> {
+    let read_error = |e: std::io::Error| AppError::Internal(format!("读取 {} 计算校验和失败: {}", path.display(), e));
+    let mut file = std::fs::File::open(path).map_err(read_error)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf).map_err(read_error)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// sha256sum 格式：每行 `哈希  文件名`
+fn manifest(checksums: &[FileChecksum]) -> String {
+    checksums
+        .iter()
+        .map(|checksum| format!("{}  {}\n", checksum.sha256, checksum.file))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn writes_sha256sum_manifest() {
+        let dir = std::env::temp_dir().join(format!("jm-checksums-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("0001.png"), b"abc").unwrap();
+        std::fs::write(dir.join("merged.pdf"), b"").unwrap();
+
+        let files = vec!["0001.png".to_string(), "merged.pdf".to_string(), "0001.png".to_string()];
+        let checksums = write_manifest(&dir, files).await.unwrap();
+        assert_eq!(checksums.len(), 2);
+        let expected = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  0001.png\n\
+                        e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  merged.pdf\n";
+        assert_eq!(std::fs::read_to_string(dir.join(MANIFEST_FILE)).unwrap(), expected);
+        assert!(write_manifest(&dir, vec!["missing.png".to_string()]).await.is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
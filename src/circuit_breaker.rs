@@ -0,0 +1,177 @@
+// JM API 熔断器
+// 移动端 API 连续 JM_BREAKER_FAILURE_THRESHOLD 次出现网络、解析或拦截错误时熔断 JM_BREAKER_OPEN_SECONDS 秒，
+// 期间的请求直接返回 `AppError::ServiceUnavailable`（附带建议的重试秒数），不再逐个经历重试与 30 秒超时；
+// 到期后进入半开状态，只放行一个探测请求，成功则恢复，失败则再次熔断
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use jm_downloader_rs::AppError;
+
+/// 连续失败次数阈值默认值
+pub const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+/// 熔断时长默认值（秒）
+pub const DEFAULT_OPEN_SECONDS: u64 = 30;
+/// 配置的熔断时长超出 `Instant` 可表示范围时使用的时长（约 100 年）
+const LONGEST_OPEN: Duration = Duration::from_secs(100 * 365 * 24 * 3600);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// 正常放行，记录连续失败次数
+    Closed { failures: u32 },
+    /// 熔断中，到期前直接失败
+    Open { until: Instant },
+    /// 探测请求进行中；探测请求被取消而没有结果时，超过熔断时长后再放行一个
+    HalfOpen { probe_started: Instant },
+}
+
+/// 熔断器，阈值为 0 时不熔断
+pub struct CircuitBreaker {
+    failure_threshold: AtomicU64,
+    open_seconds: AtomicU64,
+    state: Mutex<State>,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self {
+            failure_threshold: AtomicU64::new(DEFAULT_FAILURE_THRESHOLD.into()),
+            open_seconds: AtomicU64::new(DEFAULT_OPEN_SECONDS),
+            state: Mutex::new(State::Closed { failures: 0 }),
+        }
+    }
+}
+
+impl CircuitBreaker {
+    /// 更新阈值与熔断时长；启动与重新加载配置时调用
+    pub fn configure(&self, failure_threshold: u32, open_seconds: u64) {
+        self.failure_threshold.store(failure_threshold.into(), Ordering::Relaxed);
+        self.open_seconds.store(open_seconds, Ordering::Relaxed);
+        if failure_threshold == 0 {
+            *self.state.lock().unwrap() = State::Closed { failures: 0 };
+        }
+    }
+
+    fn open_duration(&self) -> Duration {
+        Duration::from_secs(self.open_seconds.load(Ordering::Relaxed))
+    }
+
+    /// 请求发出前调用：熔断中返回 `ServiceUnavailable`，熔断到期时放行一个探测请求
+    pub fn check(&self, what: &str) -> Result<(), AppError> {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        match *state {
+            State::Closed { .. } => Ok(()),
+            State::Open { until } if now >= until => {
+                info!("JM API 熔断到期，放行探测请求（{}）", what);
+                *state = State::HalfOpen { probe_started: now };
+                Ok(())
+            }
+            State::HalfOpen { probe_started } if now >= after(probe_started, self.open_duration()) => {
+                *state = State::HalfOpen { probe_started: now };
+                Ok(())
+            }
+            State::Open { until } => Err(unavailable(what, until - now)),
+            State::HalfOpen { probe_started } => {
+                Err(unavailable(what, after(probe_started, self.open_duration()).saturating_duration_since(now)))
+            }
+        }
+    }
+
+    /// 请求结束后调用：只有说明 JM API 不可用的错误计为失败，其余结果（包括漫画不存在等业务错误）计为成功
+    pub fn record<T>(&self, result: &Result<T, AppError>) {
+        let threshold = self.failure_threshold.load(Ordering::Relaxed);
+        if threshold == 0 {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        match result {
+            Err(e) if is_unhealthy(e) => {
+                let failures = match *state {
+                    State::Closed { failures } => failures + 1,
+                    // 探测失败立即重新熔断
+                    State::HalfOpen { .. } => u32::MAX,
+                    // 熔断前已发出的请求
+                    State::Open { .. } => return,
+                };
+                if u64::from(failures) >= threshold {
+                    let open = self.open_duration();
+                    warn!("JM API 连续失败，熔断 {} 秒: {}", open.as_secs(), e);
+                    *state = State::Open { until: after(Instant::now(), open) };
+                } else {
+                    *state = State::Closed { failures };
+                }
+            }
+            _ => {
+                if matches!(*state, State::HalfOpen { .. }) {
+                    info!("JM API 探测请求成功，解除熔断");
+                }
+                *state = State::Closed { failures: 0 };
+            }
+        }
+    }
+}
+
+/// 说明 JM API 本身不可用的错误：网络错误、超时、响应无法解析（Internal）与请求被拦截
+fn is_unhealthy(error: &AppError) -> bool {
+    matches!(error, AppError::Internal(_) | AppError::Blocked(_))
+}
+
+fn unavailable(what: &str, remaining: Duration) -> AppError {
+    // 向上取整，至少 1 秒
+    let retry_after_seconds = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+    AppError::ServiceUnavailable {
+        message: format!("JM API 暂时不可用，已熔断，{}未发出，请在 {} 秒后重试", what, retry_after_seconds.max(1)),
+        retry_after_seconds: retry_after_seconds.max(1),
+    }
+}
+
+/// `start` 之后 `duration` 的时刻，超出可表示范围时取 `LONGEST_OPEN` 之后
+fn after(start: Instant, duration: Duration) -> Instant {
+    start.checked_add(duration).unwrap_or(start + LONGEST_OPEN)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure() -> Result<(), AppError> {
+        Err(AppError::Internal("获取漫画请求失败: timed out".to_string()))
+    }
+
+    #[test]
+    fn opens_after_threshold_and_recovers_through_probe() {
+        let breaker = CircuitBreaker::default();
+        breaker.configure(2, 0);
+        breaker.record(&failure());
+        // 业务错误不计入失败，并清零连续失败次数
+        breaker.record::<()>(&Err(AppError::NotFound("漫画不存在".to_string())));
+        breaker.record(&failure());
+        assert!(breaker.check("获取漫画").is_ok());
+        breaker.record(&failure());
+
+        // 熔断时长为 0，下一个请求立即作为探测请求放行
+        assert!(breaker.check("获取漫画").is_ok());
+        breaker.record(&failure());
+        assert!(matches!(*breaker.state.lock().unwrap(), State::Open { .. }));
+        assert!(breaker.check("获取漫画").is_ok());
+        breaker.record(&Ok(()));
+        assert_eq!(*breaker.state.lock().unwrap(), State::Closed { failures: 0 });
+
+        breaker.configure(1, 60);
+        breaker.record(&failure());
+        match breaker.check("获取章节") {
+            Err(AppError::ServiceUnavailable { retry_after_seconds, .. }) => assert_eq!(retry_after_seconds, 60),
+            other => panic!("应当熔断: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn huge_open_duration_does_not_overflow() {
+        let breaker = CircuitBreaker::default();
+        breaker.configure(1, u64::MAX);
+        breaker.record(&failure());
+        assert!(matches!(breaker.check("获取漫画"), Err(AppError::ServiceUnavailable { .. })));
+    }
+}
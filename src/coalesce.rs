@@ -0,0 +1,71 @@
+// 并发请求合并模块
+// 相同 key 的任务同时只执行一次，后到的请求等待并共享先到请求的结果
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::OnceCell;
+
+/// 按 key 合并进行中的任务
+///
+/// 任务完成后立即移除记录，不缓存结果；之后的同 key 请求会重新执行。
+/// 执行中的请求被取消（如客户端断开）时，由仍在等待的请求接手执行
+pub struct Coalescer<K, V> {
+    inflight: Mutex<HashMap<K, Arc<OnceCell<V>>>>,
+}
+
+impl<K, V> Default for Coalescer<K, V> {
+    fn default() -> Self {
+        Self {
+            inflight: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> Coalescer<K, V> {
+    /// 执行 `task`；若相同 `key` 的任务正在进行，则等待其结果
+    pub async fn run<F, Fut>(&self, key: K, task: F) -> V
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = V>,
+    {
+        let cell = {
+            let mut inflight = self.inflight.lock().unwrap();
+            inflight.entry(key.clone()).or_default().clone()
+        };
+
+        let value = cell.get_or_init(task).await.clone();
+
+        let mut inflight = self.inflight.lock().unwrap();
+        if inflight.get(&key).is_some_and(|current| Arc::ptr_eq(current, &cell)) {
+            inflight.remove(&key);
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn concurrent_runs_share_one_execution() {
+        let coalescer = Coalescer::<i64, usize>::default();
+        let runs = AtomicUsize::new(0);
+        let task = || async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            runs.fetch_add(1, Ordering::SeqCst) + 1
+        };
+
+        let (a, b) = tokio::join!(coalescer.run(1, task), coalescer.run(1, task));
+        assert_eq!((a, b), (1, 1));
+
+        // 完成后不缓存，再次请求会重新执行
+        assert_eq!(coalescer.run(1, task).await, 2);
+    }
+}
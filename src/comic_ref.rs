@@ -0,0 +1,76 @@
+// JM 漫画编号解析
+// 从用户粘贴的文本中提取漫画/章节 ID：`JM123456`、`jm 123456`、`禁漫123456`、纯数字，
+// 以及 `https://18comic.vip/album/123456/...`、`/photo/123456` 等链接；文本可以是包含它们的整条消息
+
+/// 从输入中识别出的编号
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComicRef {
+    /// 漫画（album）ID
+    Album(i64),
+    /// 章节（photo）ID，章节漫画的章节需再查询所属漫画
+    Photo(i64),
+}
+
+/// 按 链接 > JM 前缀 > 唯一数字 的优先级识别，无法确定时返回 None
+pub fn parse(input: &str) -> Option<ComicRef> {
+    let lower = input.to_lowercase();
+
+    // 链接：取最先出现的 /album/ 或 /photo/
+    let link = ["/album/", "/photo/"]
+        .iter()
+        .filter_map(|marker| Some((lower.find(marker)?, *marker)))
+        .min_by_key(|(position, _)| *position);
+    if let Some((position, marker)) = link {
+        if let Some(id) = leading_id(&lower[position + marker.len()..]) {
+            return Some(if marker == "/album/" { ComicRef::Album(id) } else { ComicRef::Photo(id) });
+        }
+    }
+
+    // JM 前缀：`JM123456`、`JM-123456`、`禁漫 123456`
+    for prefix in ["jm", "禁漫"] {
+        for (position, _) in lower.match_indices(prefix) {
+            let rest = lower[position + prefix.len()..]
+                .trim_start_matches(|c: char| c.is_whitespace() || matches!(c, '-' | '_' | ':' | '：' | '#'));
+            if let Some(id) = leading_id(rest) {
+                return Some(ComicRef::Album(id));
+            }
+        }
+    }
+
+    // 消息中只有一串数字时视为漫画 ID
+    let mut numbers = lower
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|part| !part.is_empty());
+    let id = leading_id(numbers.next()?)?;
+    numbers.next().is_none().then_some(ComicRef::Album(id))
+}
+
+/// 文本开头的数字串，必须为正数
+fn leading_id(text: &str) -> Option<i64> {
+    let digits: String = text.chars().take_while(char::is_ascii_digit).collect();
+    digits.parse().ok().filter(|&id| id > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_codes_links_and_messages() {
+        let cases = [
+            ("JM123456", Some(ComicRef::Album(123456))),
+            ("jm-123456", Some(ComicRef::Album(123456))),
+            ("禁漫 123456", Some(ComicRef::Album(123456))),
+            ("123456", Some(ComicRef::Album(123456))),
+            ("https://18comic.vip/album/123456/some-title", Some(ComicRef::Album(123456))),
+            ("看看这个 https://jmcomic.me/photo/654321?page=2", Some(ComicRef::Photo(654321))),
+            ("下载 JM350234 第2话", Some(ComicRef::Album(350234))),
+            ("第2话 第3话", None),
+            ("JM", None),
+            ("0", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse(input), expected, "{}", input);
+        }
+    }
+}
@@ -1,19 +1,463 @@
+use arc_swap::ArcSwap;
 use jm_downloader_rs::AppError;
+use jm_downloader_rs::png::{PngCompression, PngFilter, PngSettings};
+use log::LevelFilter;
 use serde::Deserialize;
 use std::env;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::account_pool::{AccountRotation, JmAccount, DEFAULT_COOLDOWN_SECONDS};
+use crate::circuit_breaker::{DEFAULT_FAILURE_THRESHOLD, DEFAULT_OPEN_SECONDS};
+use crate::jm_client::{check_image_url_template, DEFAULT_IMAGE_URL_TEMPLATE};
+use crate::progress::JobLogLevel;
+use crate::service_mode::ServiceMode;
+use crate::scramble::{ScrambleOverrides, ScrambleRules, DEFAULT_SCRAMBLE_ID_SKIP_FROM};
 
 type Result<T> = std::result::Result<T, AppError>;
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct Config {
-    pub jm_username: String,
-    pub jm_password: String,
+    /// JM 账号，与密码都未设置时以匿名模式运行
+    #[serde(default)]
+    pub jm_username: Option<String>,
+    #[serde(default)]
+    pub jm_password: Option<String>,
+    /// 账号池中的其他账号，元数据请求在主账号与这些账号之间轮换
+    #[serde(default)]
+    pub jm_accounts: Vec<JmAccount>,
+    /// 账号轮换策略
+    #[serde(default)]
+    pub account_rotation: AccountRotation,
+    /// 账号触发风控后的冷却时长（秒）
+    #[serde(default = "default_account_cooldown_seconds")]
+    pub account_cooldown_seconds: u64,
     #[serde(default = "default_api_domain")]
     pub api_domain: String,
+    /// 备用移动端 API 域名，主域名返回拦截页面时依次切换
+    #[serde(default)]
+    pub api_domain_fallbacks: Vec<String>,
     #[serde(default = "default_image_domain")]
     pub image_domain: String,
+    /// 备用图片域名，图片被 CDN 拒绝或返回屏蔽占位图时依次尝试
+    #[serde(default)]
+    pub image_domain_fallbacks: Vec<String>,
+    /// 图片域名探测间隔（秒），0 表示不探测、按配置顺序使用
+    #[serde(default = "default_image_probe_seconds")]
+    pub image_probe_seconds: u64,
+    /// 已知屏蔽占位图的 MD5（小写十六进制），命中时视为被屏蔽
+    #[serde(default)]
+    pub image_blocked_md5: Vec<String>,
+    /// 保存页面时的 PNG 压缩级别
+    #[serde(default)]
+    pub png_compression: PngCompression,
+    /// 保存页面时的 PNG 行过滤方式
+    #[serde(default)]
+    pub png_filter: PngFilter,
+    /// 章节图片地址模板，占位符见 [`crate::jm_client::ImageUrlBuilder`]
+    #[serde(default = "default_image_url_template")]
+    pub image_url_template: String,
+    /// 获取漫画/章节/scramble_id 请求的最小间隔（毫秒），0 表示不限制
+    #[serde(default)]
+    pub api_min_interval_ms: u64,
+    /// 获取漫画/章节/scramble_id 每小时的请求数上限，超出时排队，0 表示不限制
+    #[serde(default)]
+    pub api_hourly_limit: u64,
+    /// 移动端 API 连续失败多少次后熔断，0 表示不熔断
+    #[serde(default = "default_breaker_failure_threshold")]
+    pub breaker_failure_threshold: u32,
+    /// 熔断持续的秒数，到期后放行一个探测请求
+    #[serde(default = "default_breaker_open_seconds")]
+    pub breaker_open_seconds: u64,
     #[serde(default = "default_img_concurrency")]
     pub img_concurrency: usize,
+    /// 所有任务的图片下载共享按 429/503 自动调整的并发窗口，上限为 img_concurrency
+    #[serde(default)]
+    pub adaptive_concurrency: bool,
+    #[serde(default = "default_web_domain")]
+    pub web_domain: String,
+    #[serde(default = "default_web_fallback")]
+    pub web_fallback: bool,
+    /// 图片解码/拼接/编码使用的 CPU 线程数
+    #[serde(default = "default_cpu_threads")]
+    pub cpu_threads: usize,
+    /// 合并 PDF 时每个分段的最大页数，用于限制内存占用
+    #[serde(default = "default_pdf_batch_pages")]
+    pub pdf_batch_pages: usize,
+    /// PDF 标题页使用的 TrueType 字体文件（需包含中日文字形），未配置时不能生成标题页
+    #[serde(default)]
+    pub pdf_font: Option<String>,
+    /// 外部放大命令，以 `<命令> <输入.png> <输出.png>` 逐页调用，未配置时不放大
+    #[serde(default)]
+    pub upscale_cmd: Option<String>,
+    /// 同时运行的放大命令数
+    #[serde(default = "default_upscale_concurrency")]
+    pub upscale_concurrency: usize,
+    /// 单页放大的超时时间（秒），超时后终止命令并保留原页面
+    #[serde(default = "default_upscale_timeout_seconds")]
+    pub upscale_timeout_seconds: u64,
+    /// 下载链接签名密钥，未配置时启动时随机生成
+    #[serde(default)]
+    pub download_signing_key: Option<String>,
+    /// 下载链接有效期（秒）
+    #[serde(default = "default_download_url_ttl")]
+    pub download_url_ttl: u64,
+    /// 管理接口 API Key，未配置时管理接口不可用
+    #[serde(default)]
+    pub admin_api_key: Option<String>,
+    /// 启动时的服务模式，运行时通过 /api/admin/mode 切换
+    #[serde(default)]
+    pub service_mode: ServiceMode,
+    /// 会话保活间隔（分钟），0 表示关闭
+    #[serde(default = "default_keep_alive_minutes")]
+    pub keep_alive_minutes: u64,
+    /// JM API、网页端与图片请求的最大重试次数
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// 移动端 API 响应数据的候选解密密钥，按顺序尝试，用于应对 JM 更换密钥
+    #[serde(default = "default_data_secrets")]
+    pub data_secrets: Vec<String>,
+    /// 下载时写入 ComicInfo.xml 与 metadata.json，供 Komga/Kavita 等书库识别
+    #[serde(default = "default_true")]
+    pub write_metadata: bool,
+    /// 在章节目录写入 pages.json，记录每个页面的源文件名与内容哈希，复用本地页面前核对
+    #[serde(default)]
+    pub page_sidecar: bool,
+    /// 书库目录：书库模式下把章节打包为 CBZ 按 `漫画标题/章节.cbz` 写入此目录
+    #[serde(default)]
+    pub library_dir: Option<String>,
+    /// 默认对所有下载启用书库模式，请求中的 library_mode 可单独开启
+    #[serde(default)]
+    pub library_mode: bool,
+    /// 书库中漫画目录与 CBZ 文件的命名方式
+    #[serde(default)]
+    pub filename_style: FilenameStyle,
+    /// 图片打乱规则表：按章节 ID 区间决定分块数
+    #[serde(default)]
+    pub scramble_rules: ScrambleRules,
+    /// 单独指定打乱规则的章节
+    #[serde(default)]
+    pub scramble_overrides: ScrambleOverrides,
+    /// 章节 ID 不小于该值时视为已打乱，不再请求 scramble_id；0 表示总是请求
+    #[serde(default = "default_scramble_id_skip_from")]
+    pub scramble_id_skip_from: i64,
+    /// scramble_id 持久化缓存文件（JSONL），未设置时只缓存在内存中
+    #[serde(default)]
+    pub scramble_cache_file: Option<String>,
+    /// 下载文件的存储根目录
+    #[serde(default = "default_download_dir")]
+    pub download_dir: String,
+    /// 下载历史文件（JSONL），未设置时为 `{download_dir}/.history.jsonl`
+    #[serde(default)]
+    pub history_file: Option<String>,
+    /// 全局图片下载速率上限（MB/s），0 表示不限速
+    #[serde(default)]
+    pub max_download_mbps: f64,
+    /// 图片解码的内存预算（MB），按预估的解码后大小占用，0 表示不限制
+    #[serde(default)]
+    pub memory_budget_mb: u64,
+    /// 图片响应体超过该大小（MB）时先写入临时文件，0 表示始终保存在内存中
+    #[serde(default = "default_spool_threshold_mb")]
+    pub spool_threshold_mb: u64,
+    /// 电子墨水屏优化（请求 eink: true）时页面长边的目标像素数，0 表示不缩小
+    #[serde(default = "default_eink_long_edge")]
+    pub eink_long_edge: u32,
+    /// 预览接口未指定 count 时返回的页数
+    #[serde(default = "default_preview_pages")]
+    pub preview_pages: usize,
+    /// 同时执行的下载任务数上限，0 表示不限制；超出的任务按优先级排队
+    #[serde(default)]
+    pub max_concurrent_jobs: usize,
+    /// 排队等待的下载任务数上限，队列满时新请求直接返回错误
+    #[serde(default = "default_max_queued_jobs")]
+    pub max_queued_jobs: usize,
+    /// 单个下载任务的最长耗时（秒），超过后取消未完成的下载并返回已完成的部分，0 表示不限制
+    #[serde(default)]
+    pub max_job_seconds: u64,
+    /// 成功完成的任务结果保留时长（秒），期间可通过 `/api/job/<id>/result` 取回，0 表示不保留
+    #[serde(default = "default_job_result_retention_seconds")]
+    pub job_result_retention_seconds: u64,
+    /// 单次请求最多包含的章节数，超出时直接返回 BadRequest
+    #[serde(default = "default_max_chapters_per_request")]
+    pub max_chapters_per_request: usize,
+    /// 自动购买时单次请求最多花费的 JM 币，请求的 max_coins 超出时按此截断；未设置时不允许自动购买
+    #[serde(default)]
+    pub auto_buy_max_coins: Option<u64>,
+    /// 下载链接过期时间（expire_seconds）的上限（秒），0 表示不限制
+    #[serde(default = "default_max_expire_seconds")]
+    pub max_expire_seconds: u64,
+    /// REST 请求体（JSON）与 gRPC 请求消息的大小上限（KB）
+    #[serde(default = "default_max_body_kb")]
+    pub max_body_kb: u64,
+    /// 单个章节最多的图片数，JM 返回的图片列表超过时拒绝下载，0 表示不限制
+    #[serde(default = "default_max_pages_per_chapter")]
+    pub max_pages_per_chapter: usize,
+    /// 单张图片的大小上限（MB），下载超过时中止该页，0 表示不限制
+    #[serde(default = "default_max_image_mb")]
+    pub max_image_mb: u64,
+    /// 错误以 RFC 7807 `application/problem+json` 与真实 HTTP 状态码返回，默认 false 保持 HTTP 200 + `R` 信封
+    #[serde(default)]
+    pub problem_json: bool,
+    /// 下载进度日志输出间隔（秒），0 表示只在完成时输出汇总
+    #[serde(default = "default_progress_log_seconds")]
+    pub progress_log_seconds: u64,
+    /// 请求未指定 log_level 时任务的日志详细程度：info（默认）/quiet
+    #[serde(default)]
+    pub job_log_level: JobLogLevel,
+    /// 没有 log4rs.yaml 时内置日志配置的文件路径，None（JM_LOG_FILE=none）为只输出到控制台
+    #[serde(default = "default_log_file")]
+    pub log_file: Option<String>,
+    /// 内置日志配置的级别，默认 info
+    #[serde(default = "default_log_level")]
+    pub log_level: LevelFilter,
+    /// 日志文件超过该大小（MB）时滚动
+    #[serde(default = "default_log_max_size_mb")]
+    pub log_max_size_mb: u64,
+    /// 另外每天零点滚动一次日志文件
+    #[serde(default)]
+    pub log_rotate_daily: bool,
+    /// 最多保留的日志归档数，更早的归档被删除
+    #[serde(default = "default_log_max_files")]
+    pub log_max_files: u32,
+    /// OTLP/HTTP 链路导出地址（如 http://tempo:4318/v1/traces），需以 `--features otel` 编译，未配置时不导出
+    #[serde(default)]
+    pub otel_endpoint: Option<String>,
+    /// 导出链路中的 service.name，多实例部署时可区分实例
+    #[serde(default = "default_otel_service_name")]
+    pub otel_service_name: String,
+    /// SMTP 服务器地址，未配置时不支持邮件发送（email_to）
+    #[serde(default)]
+    pub smtp_host: Option<String>,
+    /// SMTP 端口，默认 587
+    #[serde(default = "default_smtp_port")]
+    pub smtp_port: u16,
+    /// SMTP 连接加密方式：starttls（默认）/tls/none
+    #[serde(default)]
+    pub smtp_security: SmtpSecurity,
+    #[serde(default)]
+    pub smtp_username: Option<String>,
+    #[serde(default)]
+    pub smtp_password: Option<String>,
+    /// 发件人地址，如 `JM Downloader <bot@example.com>`；发送到 Kindle 时需加入亚马逊的认可发件人列表
+    #[serde(default)]
+    pub smtp_from: Option<String>,
+    /// 单封邮件附件的最大体积（MB），超过时拆分为多卷分多封发送
+    #[serde(default = "default_smtp_max_attachment_mb")]
+    pub smtp_max_attachment_mb: u64,
+    /// 允许的收件人：完整地址（如 `me@kindle.com`）或域名（如 `kindle.com`、`@kindle.com`），为空时不允许发送邮件
+    #[serde(default)]
+    pub smtp_allowed_recipients: Vec<String>,
+    /// 生成种子时写入的 Tracker 地址，第一个为 announce
+    #[serde(default)]
+    pub torrent_trackers: Vec<String>,
+    /// 生成的种子是否标记为私有（禁用 DHT/PEX），默认 true
+    #[serde(default = "default_true")]
+    pub torrent_private: bool,
+    /// 生成种子后调用的做种命令，参数为种子文件与产物目录
+    #[serde(default)]
+    pub torrent_seed_command: Option<String>,
+    /// 下载文件的存储后端：local（默认，由本服务的 /download 提供）/s3/webdav
+    #[serde(default)]
+    pub storage: StorageKind,
+    /// S3 兼容服务地址，如 `https://s3.us-east-1.amazonaws.com`、`http://minio:9000`
+    #[serde(default)]
+    pub s3_endpoint: Option<String>,
+    #[serde(default)]
+    pub s3_bucket: Option<String>,
+    #[serde(default = "default_s3_region")]
+    pub s3_region: String,
+    #[serde(default)]
+    pub s3_access_key: Option<String>,
+    #[serde(default)]
+    pub s3_secret_key: Option<String>,
+    /// 对象键前缀，如 `jm/`
+    #[serde(default)]
+    pub s3_prefix: String,
+    /// 是否使用路径风格访问（`endpoint/bucket/key`），MinIO 等自建服务通常需要
+    #[serde(default = "default_true")]
+    pub s3_path_style: bool,
+    /// WebDAV 根目录地址，如 `https://cloud.example.com/remote.php/dav/files/user/jm`
+    #[serde(default)]
+    pub webdav_url: Option<String>,
+    #[serde(default)]
+    pub webdav_username: Option<String>,
+    #[serde(default)]
+    pub webdav_password: Option<String>,
+    /// 本服务对外访问地址，如 `https://jm.example.com`，用于在通知中拼出完整的下载链接
+    #[serde(default)]
+    pub public_base_url: Option<String>,
+    /// Telegram Bot Token，与 telegram_chat_id 同时设置时在任务完成或失败时发送通知
+    #[serde(default)]
+    pub telegram_bot_token: Option<String>,
+    /// 接收通知的 Telegram 会话 ID 或频道名（如 `@my_channel`）
+    #[serde(default)]
+    pub telegram_chat_id: Option<String>,
+    /// HTTP 服务监听的 IP 地址，未设置时沿用 Rocket.toml / ROCKET_ADDRESS（默认 127.0.0.1）
+    #[serde(default)]
+    pub bind_addr: Option<IpAddr>,
+    /// HTTP 服务端口，未设置时沿用 Rocket.toml / ROCKET_PORT（默认 8000）
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// HTTPS 证书链（PEM）路径，与 tls_key 同时设置时以 rustls 提供 HTTPS
+    #[serde(default)]
+    pub tls_cert: Option<String>,
+    /// HTTPS 私钥（PEM，PKCS#8/RSA/SEC1）路径
+    #[serde(default)]
+    pub tls_key: Option<String>,
+    /// 启用 HTTPS 时另外监听的 HTTP 端口，该端口上的请求一律重定向到 HTTPS，未设置时不监听
+    #[serde(default)]
+    pub https_redirect_port: Option<u16>,
+    /// gRPC 接口监听地址，如 `0.0.0.0:50051`，未设置时不启动；需以 `--features grpc` 编译
+    #[serde(default)]
+    pub grpc_addr: Option<SocketAddr>,
+    /// 管理接口单独监听的地址，如 `127.0.0.1:8001`；设置后管理、排查与报表接口只在该地址提供，未设置时与其他接口共用端口
+    #[serde(default)]
+    pub admin_addr: Option<SocketAddr>,
+    /// 监视的请求文件目录，未设置时不启用批量导入
+    #[serde(default)]
+    pub watch_dir: Option<String>,
+    /// 扫描监视目录的间隔（秒）
+    #[serde(default = "default_watch_interval_seconds")]
+    pub watch_interval_seconds: u64,
+}
+
+/// 下载文件的存储后端
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageKind {
+    #[default]
+    Local,
+    S3,
+    WebDav,
+}
+
+/// 书库目录与文件的命名方式，无论哪种都会替换 Windows/SMB 共享不允许的字符
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FilenameStyle {
+    /// 使用漫画与章节标题原文
+    #[default]
+    Original,
+    /// 把中日韩等非 ASCII 标题转写为拼音/罗马字，只含 ASCII 字符
+    Pinyin,
+    /// 只用 ID 命名，如 `JM123/JM123 - 456.cbz`
+    Id,
+}
+
+/// SMTP 连接加密方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// 明文连接后升级为 TLS（通常为 587 端口）
+    #[default]
+    Starttls,
+    /// 直接建立 TLS 连接（通常为 465 端口）
+    Tls,
+    /// 不加密，仅用于本机或内网中继
+    None,
+}
+
+/// 可热更新的配置，克隆后共享同一份；进行中的请求持有各自加载时的快照
+#[derive(Clone)]
+pub struct LiveConfig(Arc<ArcSwap<Config>>);
+
+impl LiveConfig {
+    pub fn new(config: Config) -> Self {
+        Self(Arc::new(ArcSwap::from_pointee(config)))
+    }
+
+    /// 获取当前配置快照
+    pub fn load(&self) -> Arc<Config> {
+        self.0.load_full()
+    }
+
+    pub fn store(&self, config: Config) {
+        self.0.store(Arc::new(config));
+    }
+}
+
+impl Config {
+    /// JM 账号密码，未配置时为 None（匿名模式）
+    pub fn credentials(&self) -> Option<(&str, &str)> {
+        Some((self.jm_username.as_deref()?, self.jm_password.as_deref()?))
+    }
+
+    /// 账号池中的全部账号，第一个为主账号（JM_USERNAME），匿名模式下为空
+    pub fn accounts(&self) -> Vec<(&str, &str)> {
+        self.credentials()
+            .into_iter()
+            .chain(self.jm_accounts.iter().map(|account| (account.username.as_str(), account.password.as_str())))
+            .collect()
+    }
+
+    /// 下载历史文件路径
+    pub fn history_path(&self) -> PathBuf {
+        match &self.history_file {
+            Some(file) => PathBuf::from(file),
+            None => Path::new(&self.download_dir).join(".history.jsonl"),
+        }
+    }
+
+    /// 请求体大小上限（字节）
+    pub fn max_body_bytes(&self) -> u64 {
+        self.max_body_kb * 1024
+    }
+
+    /// 保存页面时的 PNG 编码参数
+    pub fn png_settings(&self) -> PngSettings {
+        PngSettings { compression: self.png_compression, filter: self.png_filter }
+    }
+
+    /// 将只在启动时生效的字段恢复为 `running` 中的值，返回其中被修改过的字段名
+    pub fn keep_startup_only(&mut self, running: &Config) -> Vec<&'static str> {
+        let mut pinned = Vec::new();
+        macro_rules! keep {
+            ($($field:ident),*) => {$(
+                if self.$field != running.$field {
+                    pinned.push(stringify!($field));
+                    self.$field = running.$field.clone();
+                }
+            )*};
+        }
+        keep!(
+            jm_username, jm_password, jm_accounts, scramble_cache_file, cpu_threads, download_dir,
+            download_signing_key, service_mode, keep_alive_minutes, image_probe_seconds, storage,
+            s3_endpoint, s3_bucket, s3_region, s3_access_key, s3_secret_key, s3_prefix,
+            s3_path_style, webdav_url, webdav_username, webdav_password, bind_addr, port, tls_cert, tls_key,
+            https_redirect_port, max_body_kb, grpc_addr, admin_addr, watch_dir,
+            watch_interval_seconds, log_file, log_level, log_max_size_mb, log_rotate_daily,
+            log_max_files, otel_endpoint, otel_service_name
+        );
+        pinned
+    }
+
+    /// 与 `other` 相比取值不同的字段名
+    pub fn changed_fields(&self, other: &Config) -> Vec<&'static str> {
+        let mut changed = Vec::new();
+        macro_rules! diff {
+            ($($field:ident),*) => {$(
+                if self.$field != other.$field {
+                    changed.push(stringify!($field));
+                }
+            )*};
+        }
+        diff!(
+            account_rotation, account_cooldown_seconds, api_domain, api_domain_fallbacks,
+            image_domain, image_domain_fallbacks, image_blocked_md5, png_compression, png_filter,
+            image_url_template, api_min_interval_ms, api_hourly_limit, breaker_failure_threshold,
+            breaker_open_seconds, img_concurrency, adaptive_concurrency, web_domain, web_fallback, pdf_batch_pages,
+            pdf_font, upscale_cmd, upscale_concurrency, upscale_timeout_seconds, download_url_ttl, admin_api_key, max_retries, data_secrets, write_metadata, page_sidecar,
+            library_dir, library_mode, filename_style, scramble_rules, scramble_overrides, scramble_id_skip_from,
+            history_file, progress_log_seconds, job_log_level, max_download_mbps, memory_budget_mb,
+            spool_threshold_mb, eink_long_edge, preview_pages, max_concurrent_jobs, max_queued_jobs,
+            max_job_seconds, job_result_retention_seconds, max_chapters_per_request, auto_buy_max_coins,
+            max_expire_seconds, max_pages_per_chapter, max_image_mb, problem_json, smtp_host, smtp_port, smtp_security, smtp_username, smtp_password,
+            smtp_from, smtp_max_attachment_mb, smtp_allowed_recipients, torrent_trackers, torrent_private,
+            torrent_seed_command, public_base_url, telegram_bot_token, telegram_chat_id
+        );
+        changed
+    }
 }
 
 fn default_api_domain() -> String {
@@ -24,37 +468,545 @@ fn default_image_domain() -> String {
     "cdn-msp2.jmapiproxy2.cc".to_string()
 }
 
+fn default_image_url_template() -> String {
+    DEFAULT_IMAGE_URL_TEMPLATE.to_string()
+}
+
 fn default_img_concurrency() -> usize {
     32
 }
 
+fn default_web_domain() -> String {
+    "18comic.vip".to_string()
+}
+
+fn default_web_fallback() -> bool {
+    true
+}
+
+fn default_pdf_batch_pages() -> usize {
+    100
+}
+
+fn default_upscale_concurrency() -> usize {
+    1
+}
+
+fn default_upscale_timeout_seconds() -> u64 {
+    300
+}
+
+fn default_download_url_ttl() -> u64 {
+    3600
+}
+
+fn default_image_probe_seconds() -> u64 {
+    300
+}
+
+fn default_keep_alive_minutes() -> u64 {
+    20
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_data_secrets() -> Vec<String> {
+    vec!["185Hcomic3PAPP7R".to_string()]
+}
+
+fn default_eink_long_edge() -> u32 {
+    1600
+}
+
+fn default_spool_threshold_mb() -> u64 {
+    8
+}
+
+fn default_preview_pages() -> usize {
+    3
+}
+
+fn default_max_queued_jobs() -> usize {
+    100
+}
+
+fn default_job_result_retention_seconds() -> u64 {
+    3600
+}
+
+fn default_max_chapters_per_request() -> usize {
+    200
+}
+
+fn default_max_body_kb() -> u64 {
+    1024
+}
+
+fn default_max_pages_per_chapter() -> usize {
+    2000
+}
+
+fn default_max_image_mb() -> u64 {
+    50
+}
+
+fn default_max_expire_seconds() -> u64 {
+    30 * 24 * 3600
+}
+
+fn default_log_file() -> Option<String> {
+    Some("logs/app.log".to_string())
+}
+
+fn default_log_level() -> LevelFilter {
+    LevelFilter::Info
+}
+
+fn default_log_max_size_mb() -> u64 {
+    10
+}
+
+fn default_log_max_files() -> u32 {
+    5
+}
+
+fn default_otel_service_name() -> String {
+    env!("CARGO_PKG_NAME").to_string()
+}
+
+fn default_progress_log_seconds() -> u64 {
+    10
+}
+
+fn default_scramble_id_skip_from() -> i64 {
+    DEFAULT_SCRAMBLE_ID_SKIP_FROM
+}
+
+fn default_breaker_failure_threshold() -> u32 {
+    DEFAULT_FAILURE_THRESHOLD
+}
+
+fn default_breaker_open_seconds() -> u64 {
+    DEFAULT_OPEN_SECONDS
+}
+
+fn default_account_cooldown_seconds() -> u64 {
+    DEFAULT_COOLDOWN_SECONDS
+}
+
+fn default_watch_interval_seconds() -> u64 {
+    5
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+fn default_smtp_max_attachment_mb() -> u64 {
+    25
+}
+
+fn default_download_dir() -> String {
+    "./download".to_string()
+}
+
+fn default_cpu_threads() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+}
+
+/// 加载配置：环境变量 > 配置文件（`JM_CONFIG_FILE` 指定的 TOML）> 默认值
+///
+/// 配置文件字段名与 `Config` 字段一致，所有字段错误（含未知字段）会一次性汇总返回
 pub fn load_config() -> Result<Config> {
-    let jm_username = read_required_env("JM_USERNAME")?;
-    let jm_password = read_required_env("JM_PASSWORD")?;
-    let api_domain = read_optional_env("JM_API_DOMAIN").unwrap_or_else(default_api_domain);
-    let image_domain = read_optional_env("JM_IMAGE_DOMAIN").unwrap_or_else(default_image_domain);
-    let img_concurrency = read_optional_env("JM_IMG_CONCURRENCY")
-        .map(|value| parse_img_concurrency(&value))
-        .transpose()?
-        .unwrap_or_else(default_img_concurrency);
+    load_config_from(ConfigSource::from_env()?)
+}
+
+/// 从给定的配置来源加载配置，测试中以构造的来源代替进程环境变量与配置文件
+fn load_config_from(mut source: ConfigSource) -> Result<Config> {
+
+    let jm_username = source.get("JM_USERNAME", "jm_username", parse_string);
+    let jm_password = source.get("JM_PASSWORD", "jm_password", parse_string);
+    if jm_username.is_some() != jm_password.is_some() {
+        source.errors.push("JM_USERNAME 与 JM_PASSWORD 必须同时设置，都不设置时以匿名模式运行".to_string());
+    }
+    let jm_accounts = source.get("JM_ACCOUNTS", "jm_accounts", parse_accounts);
+    if let Some(accounts) = &jm_accounts {
+        if jm_username.is_none() {
+            source.errors.push("设置 JM_ACCOUNTS 时必须同时设置主账号 JM_USERNAME 与 JM_PASSWORD".to_string());
+        }
+        let mut usernames: Vec<&str> = jm_username.iter().map(String::as_str).collect();
+        for account in accounts {
+            if usernames.contains(&account.username.as_str()) {
+                source.errors.push(format!("JM_ACCOUNTS 中的账号 {} 重复", account.username));
+            }
+            usernames.push(&account.username);
+        }
+    }
+    let account_rotation = source.get("JM_ACCOUNT_ROTATION", "account_rotation", parse_from_str);
+    let account_cooldown_seconds =
+        source.get("JM_ACCOUNT_COOLDOWN_SECONDS", "account_cooldown_seconds", parse_positive_u64);
+    let api_domain = source.get("JM_API_DOMAIN", "api_domain", parse_string);
+    let api_domain_fallbacks =
+        source.get("JM_API_DOMAIN_FALLBACKS", "api_domain_fallbacks", parse_list);
+    let image_domain = source.get("JM_IMAGE_DOMAIN", "image_domain", parse_string);
+    let image_domain_fallbacks =
+        source.get("JM_IMAGE_DOMAIN_FALLBACKS", "image_domain_fallbacks", parse_list);
+    let image_probe_seconds = source.get("JM_IMAGE_PROBE_SECONDS", "image_probe_seconds", parse_u64);
+    let image_blocked_md5 = source.get("JM_IMAGE_BLOCKED_MD5", "image_blocked_md5", parse_md5_list);
+    let png_compression = source.get("JM_PNG_COMPRESSION", "png_compression", parse_from_str);
+    let png_filter = source.get("JM_PNG_FILTER", "png_filter", parse_from_str);
+    let image_url_template =
+        source.get("JM_IMAGE_URL_TEMPLATE", "image_url_template", parse_image_url_template);
+    let api_min_interval_ms = source.get("JM_API_MIN_INTERVAL_MS", "api_min_interval_ms", parse_u64);
+    let api_hourly_limit = source.get("JM_API_HOURLY_LIMIT", "api_hourly_limit", parse_u64);
+    let breaker_failure_threshold = source.get(
+        "JM_BREAKER_FAILURE_THRESHOLD",
+        "breaker_failure_threshold",
+        parse_number::<u32>,
+    );
+    let breaker_open_seconds =
+        source.get("JM_BREAKER_OPEN_SECONDS", "breaker_open_seconds", parse_positive_u64);
+    let img_concurrency = source.get("JM_IMG_CONCURRENCY", "img_concurrency", parse_positive_usize);
+    let adaptive_concurrency = source.get("JM_ADAPTIVE_CONCURRENCY", "adaptive_concurrency", parse_bool);
+    let web_domain = source.get("JM_WEB_DOMAIN", "web_domain", parse_string);
+    let web_fallback = source.get("JM_WEB_FALLBACK", "web_fallback", parse_bool);
+    let cpu_threads = source.get("JM_CPU_THREADS", "cpu_threads", parse_positive_usize);
+    let pdf_batch_pages = source.get("JM_PDF_BATCH_PAGES", "pdf_batch_pages", parse_positive_usize);
+    let pdf_font = source.get("JM_PDF_FONT", "pdf_font", parse_string);
+    let upscale_cmd = source.get("JM_UPSCALE_CMD", "upscale_cmd", parse_string);
+    let upscale_concurrency =
+        source.get("JM_UPSCALE_CONCURRENCY", "upscale_concurrency", parse_positive_usize);
+    let upscale_timeout_seconds =
+        source.get("JM_UPSCALE_TIMEOUT_SECONDS", "upscale_timeout_seconds", parse_positive_u64);
+    let download_signing_key =
+        source.get("JM_DOWNLOAD_SIGNING_KEY", "download_signing_key", parse_string);
+    let download_url_ttl = source.get("JM_DOWNLOAD_URL_TTL", "download_url_ttl", parse_positive_u64);
+    let admin_api_key = source.get("JM_ADMIN_API_KEY", "admin_api_key", parse_string);
+    let service_mode = source.get("JM_SERVICE_MODE", "service_mode", parse_from_str);
+    let keep_alive_minutes = source.get("JM_KEEPALIVE_MINUTES", "keep_alive_minutes", parse_u64);
+    let max_retries = source.get("JM_MAX_RETRIES", "max_retries", parse_u32);
+    let data_secrets = source.get("JM_DATA_SECRETS", "data_secrets", parse_list);
+    let scramble_rules = source.get("JM_SCRAMBLE_RULES", "scramble_rules", parse_from_str);
+    let scramble_overrides =
+        source.get("JM_SCRAMBLE_OVERRIDES", "scramble_overrides", parse_from_str);
+    let scramble_id_skip_from =
+        source.get("JM_SCRAMBLE_ID_SKIP_FROM", "scramble_id_skip_from", parse_number::<u32>);
+    let scramble_cache_file = source.get("JM_SCRAMBLE_CACHE_FILE", "scramble_cache_file", parse_string);
+    let write_metadata = source.get("JM_WRITE_METADATA", "write_metadata", parse_bool);
+    let page_sidecar = source.get("JM_PAGE_SIDECAR", "page_sidecar", parse_bool);
+    let library_dir = source.get("JM_LIBRARY_DIR", "library_dir", parse_string);
+    let library_mode = source.get("JM_LIBRARY_MODE", "library_mode", parse_bool);
+    if library_mode == Some(true) && library_dir.is_none() {
+        source.errors.push("JM_LIBRARY_MODE 为 true 时必须设置 JM_LIBRARY_DIR".to_string());
+    }
+    let filename_style = source.get("JM_FILENAME_STYLE", "filename_style", parse_filename_style);
+    let download_dir = source.get("JM_DOWNLOAD_DIR", "download_dir", parse_string);
+    let history_file = source.get("JM_HISTORY_FILE", "history_file", parse_string);
+    let progress_log_seconds =
+        source.get("JM_PROGRESS_LOG_SECONDS", "progress_log_seconds", parse_u64);
+    let job_log_level = source.get("JM_JOB_LOG_LEVEL", "job_log_level", parse_from_str);
+    let log_file = source.get("JM_LOG_FILE", "log_file", parse_log_file);
+    let log_level = source.get("JM_LOG_LEVEL", "log_level", parse_log_level);
+    let log_max_size_mb = source.get("JM_LOG_MAX_SIZE_MB", "log_max_size_mb", parse_positive_u64);
+    let log_rotate_daily = source.get("JM_LOG_ROTATE_DAILY", "log_rotate_daily", parse_bool);
+    let log_max_files = source.get("JM_LOG_MAX_FILES", "log_max_files", parse_positive_u32);
+    let otel_endpoint = source.get("JM_OTEL_ENDPOINT", "otel_endpoint", parse_string);
+    let otel_service_name = source.get("JM_OTEL_SERVICE_NAME", "otel_service_name", parse_string);
+    let eink_long_edge = source.get("JM_EINK_LONG_EDGE", "eink_long_edge", parse_u32);
+    let preview_pages = source.get("JM_PREVIEW_PAGES", "preview_pages", parse_positive_usize);
+    let max_download_mbps =
+        source.get("JM_MAX_DOWNLOAD_MBPS", "max_download_mbps", parse_non_negative_f64);
+    let memory_budget_mb = source.get("JM_MEMORY_BUDGET_MB", "memory_budget_mb", parse_u64);
+    let spool_threshold_mb = source.get("JM_SPOOL_THRESHOLD_MB", "spool_threshold_mb", parse_u64);
+    let max_concurrent_jobs = source.get("JM_MAX_CONCURRENT_JOBS", "max_concurrent_jobs", parse_number);
+    let max_queued_jobs = source.get("JM_MAX_QUEUED_JOBS", "max_queued_jobs", parse_number);
+    let max_job_seconds = source.get("JM_MAX_JOB_SECONDS", "max_job_seconds", parse_u64);
+    let job_result_retention_seconds =
+        source.get("JM_JOB_RESULT_RETENTION_SECONDS", "job_result_retention_seconds", parse_u64);
+    let max_chapters_per_request =
+        source.get("JM_MAX_CHAPTERS_PER_REQUEST", "max_chapters_per_request", parse_positive_usize);
+    let auto_buy_max_coins = source.get("JM_AUTO_BUY_MAX_COINS", "auto_buy_max_coins", parse_positive_u64);
+    let max_expire_seconds = source.get("JM_MAX_EXPIRE_SECONDS", "max_expire_seconds", parse_u64);
+    let max_body_kb = source.get("JM_MAX_BODY_KB", "max_body_kb", parse_positive_u64);
+    let max_pages_per_chapter =
+        source.get("JM_MAX_PAGES_PER_CHAPTER", "max_pages_per_chapter", parse_number);
+    let max_image_mb = source.get("JM_MAX_IMAGE_MB", "max_image_mb", parse_u64);
+    let problem_json = source.get("JM_PROBLEM_JSON", "problem_json", parse_bool);
+    let smtp_host = source.get("JM_SMTP_HOST", "smtp_host", parse_string);
+    let smtp_port = source.get("JM_SMTP_PORT", "smtp_port", parse_number);
+    let smtp_security = source.get("JM_SMTP_SECURITY", "smtp_security", parse_smtp_security);
+    let smtp_username = source.get("JM_SMTP_USERNAME", "smtp_username", parse_string);
+    let smtp_password = source.get("JM_SMTP_PASSWORD", "smtp_password", parse_string);
+    let smtp_from = source.get("JM_SMTP_FROM", "smtp_from", parse_mailbox);
+    let smtp_max_attachment_mb =
+        source.get("JM_SMTP_MAX_ATTACHMENT_MB", "smtp_max_attachment_mb", parse_positive_u64);
+    let smtp_allowed_recipients =
+        source.get("JM_SMTP_ALLOWED_RECIPIENTS", "smtp_allowed_recipients", parse_list);
+    let torrent_trackers = source.get("JM_TORRENT_TRACKERS", "torrent_trackers", parse_list);
+    let torrent_private = source.get("JM_TORRENT_PRIVATE", "torrent_private", parse_bool);
+    let torrent_seed_command =
+        source.get("JM_TORRENT_SEED_COMMAND", "torrent_seed_command", parse_string);
+    if smtp_host.is_some() && smtp_from.is_none() {
+        source.errors.push("设置了 JM_SMTP_HOST 时必须同时设置 JM_SMTP_FROM".to_string());
+    }
+    let storage = source.get("JM_STORAGE", "storage", parse_storage_kind);
+    let s3_endpoint = source.get("JM_S3_ENDPOINT", "s3_endpoint", parse_url);
+    let s3_bucket = source.get("JM_S3_BUCKET", "s3_bucket", parse_string);
+    let s3_region = source.get("JM_S3_REGION", "s3_region", parse_string);
+    let s3_access_key = source.get("JM_S3_ACCESS_KEY", "s3_access_key", parse_string);
+    let s3_secret_key = source.get("JM_S3_SECRET_KEY", "s3_secret_key", parse_string);
+    let s3_prefix = source.get("JM_S3_PREFIX", "s3_prefix", parse_string);
+    let s3_path_style = source.get("JM_S3_PATH_STYLE", "s3_path_style", parse_bool);
+    let webdav_url = source.get("JM_WEBDAV_URL", "webdav_url", parse_url);
+    let webdav_username = source.get("JM_WEBDAV_USERNAME", "webdav_username", parse_string);
+    let webdav_password = source.get("JM_WEBDAV_PASSWORD", "webdav_password", parse_string);
+    if storage == Some(StorageKind::WebDav) && webdav_url.is_none() {
+        source.errors.push("JM_STORAGE 为 webdav 时必须设置 JM_WEBDAV_URL".to_string());
+    }
+    let public_base_url = source.get("JM_PUBLIC_BASE_URL", "public_base_url", parse_url);
+    let telegram_bot_token = source.get("JM_TELEGRAM_BOT_TOKEN", "telegram_bot_token", parse_string);
+    let telegram_chat_id = source.get("JM_TELEGRAM_CHAT_ID", "telegram_chat_id", parse_string);
+    if telegram_bot_token.is_some() != telegram_chat_id.is_some() {
+        source
+            .errors
+            .push("JM_TELEGRAM_BOT_TOKEN 与 JM_TELEGRAM_CHAT_ID 需同时设置".to_string());
+    }
+    let bind_addr = source.get("JM_BIND_ADDR", "bind_addr", parse_number);
+    let port = source.get("JM_PORT", "port", parse_number);
+    let tls_cert = source.get("JM_TLS_CERT", "tls_cert", parse_string);
+    let tls_key = source.get("JM_TLS_KEY", "tls_key", parse_string);
+    if tls_cert.is_some() != tls_key.is_some() {
+        source.errors.push("JM_TLS_CERT 与 JM_TLS_KEY 需同时设置".to_string());
+    }
+    let https_redirect_port =
+        source.get("JM_HTTPS_REDIRECT_PORT", "https_redirect_port", parse_number);
+    let grpc_addr = source.get("JM_GRPC_ADDR", "grpc_addr", parse_number);
+    let admin_addr = source.get("JM_ADMIN_ADDR", "admin_addr", parse_number);
+    let watch_dir = source.get("JM_WATCH_DIR", "watch_dir", parse_string);
+    let watch_interval_seconds =
+        source.get("JM_WATCH_INTERVAL_SECONDS", "watch_interval_seconds", parse_positive_u64);
+    if storage == Some(StorageKind::S3) {
+        for (env_key, value) in [
+            ("JM_S3_ENDPOINT", s3_endpoint.is_some()),
+            ("JM_S3_BUCKET", s3_bucket.is_some()),
+            ("JM_S3_ACCESS_KEY", s3_access_key.is_some()),
+            ("JM_S3_SECRET_KEY", s3_secret_key.is_some()),
+        ] {
+            if !value {
+                source.errors.push(format!("JM_STORAGE 为 s3 时必须设置 {}", env_key));
+            }
+        }
+    }
+
+    source.finish()?;
 
     Ok(Config {
         jm_username,
         jm_password,
-        api_domain,
-        image_domain,
-        img_concurrency,
+        jm_accounts: jm_accounts.unwrap_or_default(),
+        account_rotation: account_rotation.unwrap_or_default(),
+        account_cooldown_seconds: account_cooldown_seconds.unwrap_or_else(default_account_cooldown_seconds),
+        api_domain: api_domain.unwrap_or_else(default_api_domain),
+        api_domain_fallbacks: api_domain_fallbacks.unwrap_or_default(),
+        image_domain: image_domain.unwrap_or_else(default_image_domain),
+        image_domain_fallbacks: image_domain_fallbacks.unwrap_or_default(),
+        image_probe_seconds: image_probe_seconds.unwrap_or_else(default_image_probe_seconds),
+        image_blocked_md5: image_blocked_md5.unwrap_or_default(),
+        png_compression: png_compression.unwrap_or_default(),
+        png_filter: png_filter.unwrap_or_default(),
+        image_url_template: image_url_template.unwrap_or_else(default_image_url_template),
+        api_min_interval_ms: api_min_interval_ms.unwrap_or_default(),
+        api_hourly_limit: api_hourly_limit.unwrap_or_default(),
+        breaker_failure_threshold: breaker_failure_threshold.unwrap_or_else(default_breaker_failure_threshold),
+        breaker_open_seconds: breaker_open_seconds.unwrap_or_else(default_breaker_open_seconds),
+        img_concurrency: img_concurrency.unwrap_or_else(default_img_concurrency),
+        adaptive_concurrency: adaptive_concurrency.unwrap_or_default(),
+        web_domain: web_domain.unwrap_or_else(default_web_domain),
+        web_fallback: web_fallback.unwrap_or_else(default_web_fallback),
+        cpu_threads: cpu_threads.unwrap_or_else(default_cpu_threads),
+        pdf_batch_pages: pdf_batch_pages.unwrap_or_else(default_pdf_batch_pages),
+        pdf_font,
+        upscale_cmd,
+        upscale_concurrency: upscale_concurrency.unwrap_or_else(default_upscale_concurrency),
+        upscale_timeout_seconds: upscale_timeout_seconds.unwrap_or_else(default_upscale_timeout_seconds),
+        download_signing_key,
+        download_url_ttl: download_url_ttl.unwrap_or_else(default_download_url_ttl),
+        admin_api_key,
+        service_mode: service_mode.unwrap_or_default(),
+        keep_alive_minutes: keep_alive_minutes.unwrap_or_else(default_keep_alive_minutes),
+        max_retries: max_retries.unwrap_or_else(default_max_retries),
+        data_secrets: data_secrets.unwrap_or_else(default_data_secrets),
+        write_metadata: write_metadata.unwrap_or_else(default_true),
+        page_sidecar: page_sidecar.unwrap_or_default(),
+        library_dir,
+        library_mode: library_mode.unwrap_or_default(),
+        filename_style: filename_style.unwrap_or_default(),
+        scramble_rules: scramble_rules.unwrap_or_default(),
+        scramble_overrides: scramble_overrides.unwrap_or_default(),
+        scramble_id_skip_from: scramble_id_skip_from.map_or_else(default_scramble_id_skip_from, i64::from),
+        scramble_cache_file,
+        download_dir: download_dir.unwrap_or_else(default_download_dir),
+        history_file,
+        progress_log_seconds: progress_log_seconds.unwrap_or_else(default_progress_log_seconds),
+        job_log_level: job_log_level.unwrap_or_default(),
+        log_file: log_file.unwrap_or_else(default_log_file),
+        log_level: log_level.unwrap_or_else(default_log_level),
+        log_max_size_mb: log_max_size_mb.unwrap_or_else(default_log_max_size_mb),
+        log_rotate_daily: log_rotate_daily.unwrap_or_default(),
+        log_max_files: log_max_files.unwrap_or_else(default_log_max_files),
+        otel_endpoint,
+        otel_service_name: otel_service_name.unwrap_or_else(default_otel_service_name),
+        max_download_mbps: max_download_mbps.unwrap_or_default(),
+        memory_budget_mb: memory_budget_mb.unwrap_or_default(),
+        spool_threshold_mb: spool_threshold_mb.unwrap_or_else(default_spool_threshold_mb),
+        eink_long_edge: eink_long_edge.unwrap_or_else(default_eink_long_edge),
+        preview_pages: preview_pages.unwrap_or_else(default_preview_pages),
+        max_concurrent_jobs: max_concurrent_jobs.unwrap_or_default(),
+        max_queued_jobs: max_queued_jobs.unwrap_or_else(default_max_queued_jobs),
+        max_job_seconds: max_job_seconds.unwrap_or_default(),
+        job_result_retention_seconds: job_result_retention_seconds
+            .unwrap_or_else(default_job_result_retention_seconds),
+        max_chapters_per_request: max_chapters_per_request
+            .unwrap_or_else(default_max_chapters_per_request),
+        auto_buy_max_coins,
+        max_expire_seconds: max_expire_seconds.unwrap_or_else(default_max_expire_seconds),
+        max_body_kb: max_body_kb.unwrap_or_else(default_max_body_kb),
+        max_pages_per_chapter: max_pages_per_chapter.unwrap_or_else(default_max_pages_per_chapter),
+        max_image_mb: max_image_mb.unwrap_or_else(default_max_image_mb),
+        problem_json: problem_json.unwrap_or_default(),
+        smtp_host,
+        smtp_port: smtp_port.unwrap_or_else(default_smtp_port),
+        smtp_security: smtp_security.unwrap_or_default(),
+        smtp_username,
+        smtp_password,
+        smtp_from,
+        smtp_max_attachment_mb: smtp_max_attachment_mb
+            .unwrap_or_else(default_smtp_max_attachment_mb),
+        smtp_allowed_recipients: smtp_allowed_recipients.unwrap_or_default(),
+        torrent_trackers: torrent_trackers.unwrap_or_default(),
+        torrent_private: torrent_private.unwrap_or_else(default_true),
+        torrent_seed_command,
+        storage: storage.unwrap_or_default(),
+        s3_endpoint,
+        s3_bucket,
+        s3_region: s3_region.unwrap_or_else(default_s3_region),
+        s3_access_key,
+        s3_secret_key,
+        s3_prefix: s3_prefix.unwrap_or_default(),
+        s3_path_style: s3_path_style.unwrap_or_else(default_true),
+        webdav_url,
+        webdav_username,
+        webdav_password,
+        public_base_url,
+        telegram_bot_token,
+        telegram_chat_id,
+        bind_addr,
+        port,
+        tls_cert,
+        tls_key,
+        https_redirect_port,
+        grpc_addr,
+        admin_addr,
+        watch_dir,
+        watch_interval_seconds: watch_interval_seconds.unwrap_or_else(default_watch_interval_seconds),
     })
 }
 
-fn read_required_env(key: &str) -> Result<String> {
-    let value = env::var(key)
-        .map_err(|e| AppError::Internal(format!("读取环境变量 {} 失败或未设置: {}", key, e)))?;
-    let value = value.trim().to_string();
-    if value.is_empty() {
-        return Err(AppError::Internal(format!("环境变量 {} 不能为空", key)));
+/// 按名称读取环境变量（已去除首尾空白，空值视为未设置）
+type EnvLookup = Box<dyn Fn(&str) -> Option<String>>;
+
+/// 配置来源：环境变量与可选的配置文件，读取过程中收集所有错误
+struct ConfigSource {
+    file: toml::Table,
+    file_path: Option<String>,
+    env: EnvLookup,
+    errors: Vec<String>,
+}
+
+impl ConfigSource {
+    fn from_env() -> Result<Self> {
+        let file_path = read_optional_env("JM_CONFIG_FILE");
+        let file = match &file_path {
+            Some(path) => {
+                let content = std::fs::read_to_string(path)
+                    .map_err(|e| AppError::Internal(format!("读取配置文件 {} 失败: {}", path, e)))?;
+                content
+                    .parse::<toml::Table>()
+                    .map_err(|e| AppError::Internal(format!("解析配置文件 {} 失败: {}", path, e)))?
+            }
+            None => toml::Table::new(),
+        };
+        Ok(Self {
+            file,
+            file_path,
+            env: Box::new(read_optional_env),
+            errors: Vec::new(),
+        })
+    }
+
+    /// 读取一个配置项：优先环境变量，其次配置文件；两处都未设置时返回 None
+    fn get<T>(&mut self, env_key: &str, field: &str, parse: fn(&str, &str) -> Result<T>) -> Option<T> {
+        let file_value = self.file.remove(field);
+        let (key, value) = match (self.env)(env_key) {
+            Some(value) => (format!("环境变量 {}", env_key), value),
+            None => {
+                let value = match file_value? {
+                    toml::Value::String(value) => value.trim().to_string(),
+                    toml::Value::Integer(value) => value.to_string(),
+                    toml::Value::Float(value) => value.to_string(),
+                    toml::Value::Boolean(value) => value.to_string(),
+                    other => {
+                        self.errors.push(format!(
+                            "配置文件字段 {} 类型不支持: {}",
+                            field,
+                            other.type_str()
+                        ));
+                        return None;
+                    }
+                };
+                if value.is_empty() {
+                    return None;
+                }
+                (format!("配置文件字段 {}", field), value)
+            }
+        };
+
+        match parse(&key, &value) {
+            Ok(parsed) => Some(parsed),
+            Err(e) => {
+                self.errors.push(e.to_string());
+                None
+            }
+        }
+    }
+
+    /// 检查未知字段并汇总所有错误
+    fn finish(mut self) -> Result<()> {
+        if let Some(path) = &self.file_path {
+            for field in self.file.keys() {
+                self.errors.push(format!("配置文件 {} 中存在未知字段 {}", path, field));
+            }
+        }
+        if self.errors.is_empty() {
+            return Ok(());
+        }
+        Err(AppError::Internal(format!(
+            "配置校验失败:\n  - {}",
+            self.errors.join("\n  - ")
+        )))
     }
-    Ok(value)
 }
 
 fn read_optional_env(key: &str) -> Option<String> {
@@ -64,14 +1016,232 @@ fn read_optional_env(key: &str) -> Option<String> {
         .filter(|value| !value.is_empty())
 }
 
-fn parse_img_concurrency(value: &str) -> Result<usize> {
-    let parsed = value
-        .parse::<usize>()
-        .map_err(|e| AppError::Internal(format!("环境变量 JM_IMG_CONCURRENCY 解析失败: {}: {}", value, e)))?;
+fn parse_string(_key: &str, value: &str) -> Result<String> {
+    Ok(value.to_string())
+}
+
+/// 逗号分隔的列表，忽略空项
+fn parse_list(key: &str, value: &str) -> Result<Vec<String>> {
+    let items: Vec<String> = value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect();
+    if items.is_empty() {
+        return Err(AppError::Internal(format!("{} 至少需要一项", key)));
+    }
+    Ok(items)
+}
+
+/// 逗号分隔的 `用户名:密码` 列表，密码中可以包含冒号但不能包含逗号；错误信息中不回显密码
+fn parse_accounts(key: &str, value: &str) -> Result<Vec<JmAccount>> {
+    parse_list(key, value)?
+        .into_iter()
+        .enumerate()
+        .map(|(index, item)| match item.split_once(':') {
+            Some((username, password)) if !username.trim().is_empty() && !password.is_empty() => Ok(JmAccount {
+                username: username.trim().to_string(),
+                password: password.to_string(),
+            }),
+            _ => Err(AppError::Internal(format!("{} 第 {} 项应为 用户名:密码", key, index + 1))),
+        })
+        .collect()
+}
+
+fn parse_from_str<T: std::str::FromStr<Err = String>>(key: &str, value: &str) -> Result<T> {
+    value
+        .parse()
+        .map_err(|e| AppError::Internal(format!("{} 解析失败: {}", key, e)))
+}
+
+fn parse_number<T: std::str::FromStr>(key: &str, value: &str) -> Result<T>
+where
+    T::Err: std::fmt::Display,
+{
+    value
+        .parse::<T>()
+        .map_err(|e| AppError::Internal(format!("{} 解析失败: {}: {}", key, value, e)))
+}
+
+fn parse_u32(key: &str, value: &str) -> Result<u32> {
+    parse_number(key, value)
+}
+
+fn parse_u64(key: &str, value: &str) -> Result<u64> {
+    parse_number(key, value)
+}
+
+fn parse_positive_usize(key: &str, value: &str) -> Result<usize> {
+    let parsed = parse_number::<usize>(key, value)?;
+    if parsed == 0 {
+        return Err(AppError::Internal(format!("{} 必须大于 0", key)));
+    }
+    Ok(parsed)
+}
+
+fn parse_positive_u64(key: &str, value: &str) -> Result<u64> {
+    let parsed = parse_u64(key, value)?;
+    if parsed == 0 {
+        return Err(AppError::Internal(format!("{} 必须大于 0", key)));
+    }
+    Ok(parsed)
+}
+
+fn parse_positive_u32(key: &str, value: &str) -> Result<u32> {
+    let parsed = parse_u32(key, value)?;
     if parsed == 0 {
-        return Err(AppError::Internal(
-            "环境变量 JM_IMG_CONCURRENCY 必须大于 0".to_string(),
-        ));
+        return Err(AppError::Internal(format!("{} 必须大于 0", key)));
+    }
+    Ok(parsed)
+}
+
+/// `none` 表示不写日志文件
+fn parse_log_file(_key: &str, value: &str) -> Result<Option<String>> {
+    Ok((!value.eq_ignore_ascii_case("none")).then(|| value.to_string()))
+}
+
+fn parse_log_level(key: &str, value: &str) -> Result<LevelFilter> {
+    value.parse().map_err(|_| {
+        AppError::Internal(format!("{} 解析失败: {}，应为 off/error/warn/info/debug/trace", key, value))
+    })
+}
+
+fn parse_non_negative_f64(key: &str, value: &str) -> Result<f64> {
+    let parsed: f64 = parse_number(key, value)?;
+    if !parsed.is_finite() || parsed < 0.0 {
+        return Err(AppError::Internal(format!("{} 必须为非负数: {}", key, value)));
     }
     Ok(parsed)
 }
+
+fn parse_url(key: &str, value: &str) -> Result<String> {
+    let url = reqwest::Url::parse(value)
+        .map_err(|e| AppError::Internal(format!("{} 不是有效的 URL: {}: {}", key, value, e)))?;
+    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+        return Err(AppError::Internal(format!("{} 必须为 http(s) 地址: {}", key, value)));
+    }
+    Ok(value.trim_end_matches('/').to_string())
+}
+
+/// 逗号分隔的 MD5 列表，统一为小写
+fn parse_md5_list(key: &str, value: &str) -> Result<Vec<String>> {
+    let items = parse_list(key, value)?;
+    if let Some(item) = items.iter().find(|item| item.len() != 32 || !item.bytes().all(|b| b.is_ascii_hexdigit())) {
+        return Err(AppError::Internal(format!("{} 中的 {} 不是有效的 MD5", key, item)));
+    }
+    Ok(items.into_iter().map(|item| item.to_ascii_lowercase()).collect())
+}
+
+fn parse_image_url_template(key: &str, value: &str) -> Result<String> {
+    check_image_url_template(value)
+        .map_err(|e| AppError::Internal(format!("{} 无效: {}: {}", key, value, e)))?;
+    Ok(value.to_string())
+}
+
+fn parse_storage_kind(key: &str, value: &str) -> Result<StorageKind> {
+    match value.to_ascii_lowercase().as_str() {
+        "local" => Ok(StorageKind::Local),
+        "s3" => Ok(StorageKind::S3),
+        "webdav" => Ok(StorageKind::WebDav),
+        _ => Err(AppError::Internal(format!(
+            "{} 解析失败: {}，应为 local、s3 或 webdav",
+            key, value
+        ))),
+    }
+}
+
+fn parse_filename_style(key: &str, value: &str) -> Result<FilenameStyle> {
+    match value.to_ascii_lowercase().as_str() {
+        "original" => Ok(FilenameStyle::Original),
+        "pinyin" => Ok(FilenameStyle::Pinyin),
+        "id" => Ok(FilenameStyle::Id),
+        _ => Err(AppError::Internal(format!(
+            "{} 解析失败: {}，应为 original、pinyin 或 id",
+            key, value
+        ))),
+    }
+}
+
+fn parse_mailbox(key: &str, value: &str) -> Result<String> {
+    value
+        .parse::<lettre::message::Mailbox>()
+        .map_err(|e| AppError::Internal(format!("{} 不是有效的邮箱地址: {}: {}", key, value, e)))?;
+    Ok(value.to_string())
+}
+
+fn parse_smtp_security(key: &str, value: &str) -> Result<SmtpSecurity> {
+    match value.to_ascii_lowercase().as_str() {
+        "starttls" => Ok(SmtpSecurity::Starttls),
+        "tls" => Ok(SmtpSecurity::Tls),
+        "none" => Ok(SmtpSecurity::None),
+        _ => Err(AppError::Internal(format!(
+            "{} 解析失败: {}，应为 starttls、tls 或 none",
+            key, value
+        ))),
+    }
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(AppError::Internal(format!(
+            "{} 解析失败: {}，应为 true 或 false",
+            key, value
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    /// 以给定的配置文件内容与环境变量构造配置来源
+    fn source(file: &str, env: &[(&str, &str)]) -> ConfigSource {
+        let env: HashMap<String, String> = env.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect();
+        ConfigSource {
+            file: file.parse().unwrap(),
+            file_path: Some("config.toml".to_string()),
+            env: Box::new(move |key| env.get(key).cloned()),
+            errors: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn env_overrides_file() {
+        let config = load_config_from(source(
+            "port = 9000\nmax_retries = 7\nimage_domain = \"file.example.com\"",
+            &[("JM_PORT", "9100"), ("JM_IMAGE_DOMAIN", "env.example.com")],
+        ))
+        .unwrap();
+        assert_eq!(config.port, Some(9100));
+        assert_eq!(config.image_domain, "env.example.com");
+        // 只在配置文件中设置的字段取文件中的值，都未设置的取默认值
+        assert_eq!(config.max_retries, 7);
+        assert_eq!(config.download_url_ttl, load_config_from(source("", &[])).unwrap().download_url_ttl);
+    }
+
+    #[test]
+    fn reports_all_errors_at_once() {
+        let error = load_config_from(source(
+            "max_retries = \"many\"\nport = [1]",
+            &[("JM_DOWNLOAD_URL_TTL", "0"), ("JM_USERNAME", "user")],
+        ))
+        .unwrap_err()
+        .to_string();
+        for expected in ["max_retries", "port", "JM_DOWNLOAD_URL_TTL", "JM_PASSWORD"] {
+            assert!(error.contains(expected), "错误信息应包含 {}: {}", expected, error);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_file_fields() {
+        let error = load_config_from(source("prot = 9000", &[])).unwrap_err().to_string();
+        assert!(error.contains("配置文件 config.toml 中存在未知字段 prot"), "{}", error);
+        // 环境变量中的未知名称不属于配置，不报错
+        assert!(load_config_from(source("", &[("JM_PROT", "9000")])).is_ok());
+    }
+}
@@ -0,0 +1,258 @@
+// 内置仪表盘（/ui）
+// maud 渲染的单页，无需额外的前端构建：执行中与最近结束的任务由 /api/job/events 事件流驱动，
+// 存储占用、暂停/恢复/取消与清理调用现有的管理接口；管理 API Key 只保存在浏览器的 localStorage 中
+
+use maud::{html, Markup, PreEscaped, DOCTYPE};
+use rocket::response::content::RawHtml;
+use rocket::State;
+
+use crate::config::LiveConfig;
+
+/// 仪表盘页面，不列入 OpenAPI 文档
+#[get("/ui")]
+pub fn ui(config: &State<LiveConfig>) -> RawHtml<String> {
+    RawHtml(page(config.load().admin_api_key.is_some()).into_string())
+}
+
+fn page(admin_enabled: bool) -> Markup {
+    html! {
+        (DOCTYPE)
+        html lang="zh-CN" {
+            head {
+                meta charset="utf-8";
+                meta name="viewport" content="width=device-width, initial-scale=1";
+                title { "jm-downloader-rs 仪表盘" }
+                style { (PreEscaped(STYLE)) }
+            }
+            body {
+                header {
+                    h1 { "jm-downloader-rs 仪表盘" }
+                    nav { a href="/docs" { "API 文档" } }
+                    form #key-form {
+                        input #admin-key type="password" placeholder="管理 API Key（X-Admin-Key）" autocomplete="off";
+                        button type="submit" { "保存" }
+                    }
+                }
+                @if !admin_enabled {
+                    p .notice { "未配置 JM_ADMIN_API_KEY：可以查看任务进度，暂停/取消、存储占用与清理不可用。" }
+                }
+                p #status .muted { "正在连接任务事件流…" }
+
+                section {
+                    h2 { "执行中的任务" }
+                    table {
+                        thead { tr { th { "ID" } th { "接口" } th { "漫画" } th { "状态" } th { "进度" } th { "速度" } th { "剩余" } th { "操作" } } }
+                        tbody #active { tr { td colspan="8" .muted { "暂无任务" } } }
+                    }
+                }
+
+                section {
+                    h2 { "最近结束的任务" }
+                    table {
+                        thead { tr { th { "ID" } th { "接口" } th { "漫画" } th { "结果" } th { "页数" } th { "流量" } th { "耗时" } th { "结束时间" } } }
+                        tbody #recent { tr { td colspan="8" .muted { "暂无记录" } } }
+                    }
+                }
+
+                section {
+                    h2 { "存储占用" button #refresh-storage type="button" { "刷新" } }
+                    p #storage-total .muted { "输入管理 API Key 后显示" }
+                    table {
+                        thead { tr { th { "漫画" } th { "章节目录" } th { "文件" } th { "占用" } } }
+                        tbody #storage {}
+                    }
+                    form #cleanup-form {
+                        label { "早于（小时）" input #cleanup-hours type="number" min="1"; }
+                        label { "漫画 ID" input #cleanup-comic type="number" min="1"; }
+                        button type="submit" { "清理" }
+                        span .muted { "都不填时清理全部，正在使用的目录会被跳过" }
+                    }
+                }
+                script { (PreEscaped(SCRIPT)) }
+            }
+        }
+    }
+}
+
+const STYLE: &str = r#"
+body { font-family: system-ui, sans-serif; margin: 0 auto; max-width: 1100px; padding: 16px; color: #222; }
+header { display: flex; flex-wrap: wrap; gap: 16px; align-items: center; }
+header h1 { font-size: 1.4em; margin: 0; flex: 1; }
+h2 { font-size: 1.1em; margin-top: 28px; }
+h2 button { margin-left: 8px; font-size: 0.8em; }
+table { border-collapse: collapse; width: 100%; font-size: 0.9em; }
+th, td { border-bottom: 1px solid #ddd; padding: 6px 8px; text-align: left; white-space: nowrap; }
+td.title { white-space: normal; }
+progress { width: 160px; }
+form { display: flex; flex-wrap: wrap; gap: 8px; align-items: center; margin-top: 12px; }
+button { cursor: pointer; }
+.muted { color: #888; }
+.notice { background: #fff4e5; border: 1px solid #f0c36d; padding: 8px 12px; }
+.ok { color: #1a7f37; }
+.fail { color: #c62828; }
+"#;
+
+const SCRIPT: &str = r#"
+const keyInput = document.getElementById('admin-key');
+keyInput.value = localStorage.getItem('jmAdminKey') || '';
+const statusLine = document.getElementById('status');
+
+function setStatus(text, failed) {
+  statusLine.textContent = text;
+  statusLine.className = failed ? 'fail' : 'muted';
+}
+
+// 统一以 R<T> 信封返回，失败时抛出 message
+async function api(method, path, body) {
+  const response = await fetch(path, {
+    method,
+    headers: { 'X-Admin-Key': keyInput.value, 'Accept-Problem': 'false', 'Content-Type': 'application/json' },
+    body: body === undefined ? undefined : JSON.stringify(body),
+  });
+  const result = await response.json();
+  if (!result.success) throw new Error(result.message);
+  return result.data;
+}
+
+function bytes(value) {
+  const units = ['B', 'KB', 'MB', 'GB', 'TB'];
+  let index = 0;
+  while (value >= 1024 && index < units.length - 1) { value /= 1024; index++; }
+  return value.toFixed(index ? 1 : 0) + ' ' + units[index];
+}
+
+function duration(seconds) {
+  if (seconds === undefined || seconds === null) return '-';
+  const m = Math.floor(seconds / 60), s = seconds % 60;
+  return m ? m + ' 分 ' + s + ' 秒' : s + ' 秒';
+}
+
+function row(cells) {
+  const tr = document.createElement('tr');
+  for (const cell of cells) {
+    const td = document.createElement('td');
+    if (cell instanceof Node) td.appendChild(cell); else td.textContent = cell;
+    tr.appendChild(td);
+  }
+  return tr;
+}
+
+function fill(tbody, rows, empty) {
+  tbody.replaceChildren(...rows);
+  if (!rows.length) {
+    const tr = row([empty]);
+    tr.firstChild.colSpan = 8;
+    tr.firstChild.className = 'muted';
+    tbody.appendChild(tr);
+  }
+}
+
+function action(label, path) {
+  const button = document.createElement('button');
+  button.textContent = label;
+  button.onclick = async () => {
+    try { await api('POST', path); setStatus(label + '请求已发送'); } catch (e) { setStatus(label + '失败：' + e.message, true); }
+  };
+  return button;
+}
+
+function renderActive(jobs) {
+  fill(document.getElementById('active'), jobs.map(job => {
+    const bar = document.createElement('progress');
+    bar.max = job.total_pages || 1;
+    bar.value = job.completed_pages;
+    const progress = document.createElement('span');
+    progress.append(bar, ' ' + job.completed_pages + '/' + job.total_pages);
+    const state = job.cancelled ? '取消中' : job.queued ? '排队中' : job.paused ? '已暂停' : '下载中';
+    const actions = document.createElement('span');
+    if (!job.cancelled) {
+      actions.append(job.paused
+        ? action('恢复', '/api/job/' + job.job_id + '/resume')
+        : action('暂停', '/api/job/' + job.job_id + '/pause'));
+      actions.append(' ', action('取消', '/api/job/' + job.job_id + '/cancel'));
+    }
+    return row([job.job_id, job.kind, job.comic_id, state, progress,
+      job.speed_mbps.toFixed(2) + ' MB/s', duration(job.eta_seconds), actions]);
+  }), '暂无任务');
+}
+
+function renderRecent(jobs) {
+  fill(document.getElementById('recent'), jobs.map(job => {
+    const result = document.createElement('span');
+    result.textContent = job.success ? '成功' : job.cancelled ? '已取消' : '失败';
+    result.className = job.success ? 'ok' : 'fail';
+    const comic = job.title ? job.comic_id + ' ' + job.title : String(job.comic_id);
+    const tr = row([job.job_id, job.kind, comic, result, job.completed_pages,
+      bytes(job.downloaded_bytes), duration(job.elapsed_seconds), job.finished_at.replace('T', ' ').slice(0, 19)]);
+    tr.children[2].className = 'title';
+    return tr;
+  }), '暂无记录');
+}
+
+async function refreshStorage() {
+  const total = document.getElementById('storage-total');
+  try {
+    const data = await api('GET', '/api/admin/storage');
+    total.textContent = '共 ' + bytes(data.total_bytes) + '，' + data.comics.length + ' 部漫画';
+    total.className = '';
+    document.getElementById('storage').replaceChildren(...data.comics.slice(0, 50).map(comic =>
+      row([comic.comic_id, comic.chapters, comic.files, bytes(comic.bytes)])));
+  } catch (e) {
+    total.textContent = '无法获取存储占用：' + e.message;
+    total.className = 'fail';
+  }
+}
+
+document.getElementById('key-form').onsubmit = event => {
+  event.preventDefault();
+  localStorage.setItem('jmAdminKey', keyInput.value);
+  setStatus('管理 API Key 已保存在本浏览器中');
+  refreshStorage();
+};
+document.getElementById('refresh-storage').onclick = refreshStorage;
+document.getElementById('cleanup-form').onsubmit = async event => {
+  event.preventDefault();
+  const hours = document.getElementById('cleanup-hours').value;
+  const comic = document.getElementById('cleanup-comic').value;
+  const request = {};
+  if (hours) request.older_than_hours = Number(hours);
+  if (comic) request.comic_id = Number(comic);
+  if (!hours && !comic) {
+    if (!confirm('确定清理全部下载目录？')) return;
+    request.all = true;
+  }
+  try {
+    const data = await api('POST', '/api/admin/cleanup', request);
+    setStatus('已删除 ' + data.removed_dirs + ' 个目录，释放 ' + bytes(data.freed_bytes) + '，跳过使用中的 ' + data.skipped_in_use + ' 个');
+    refreshStorage();
+  } catch (e) {
+    setStatus('清理失败：' + e.message, true);
+  }
+};
+
+const events = new EventSource('/api/job/events');
+events.addEventListener('jobs', event => {
+  const data = JSON.parse(event.data);
+  renderActive(data.active);
+  renderRecent(data.recent);
+  if (statusLine.textContent.startsWith('正在连接') || statusLine.textContent.startsWith('事件流')) {
+    setStatus('已连接，每秒刷新');
+  }
+});
+events.onerror = () => setStatus('事件流已断开，正在重连…', true);
+if (keyInput.value) refreshStorage();
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_notice_only_without_admin_key() {
+        let html = page(false).into_string();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("/api/job/events"));
+        assert!(html.contains("未配置 JM_ADMIN_API_KEY"));
+        assert!(!page(true).into_string().contains("未配置 JM_ADMIN_API_KEY"));
+    }
+}
@@ -0,0 +1,222 @@
+// 下载目录租约模块
+// 请求处理或文件下载期间持有目录租约，到期删除会推迟到所有租约释放之后，避免删除正在使用的目录
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+#[derive(Default)]
+struct LeaseEntry {
+    /// 当前持有的租约数
+    refs: usize,
+    /// 最晚的到期删除时间
+    delete_at: Option<Instant>,
+    /// 有请求要求永不过期（expire_seconds 为 -1）
+    keep: bool,
+    /// 正在锁外删除目录
+    deleting: bool,
+}
+
+/// 下载目录租约管理器，克隆后共享同一份状态
+#[derive(Clone, Default)]
+pub struct DirLeases {
+    entries: Arc<Mutex<HashMap<PathBuf, LeaseEntry>>>,
+}
+
+/// 目录租约，释放（drop）时若目录已到期且无其他租约则删除
+pub struct DirLease {
+    leases: DirLeases,
+    dir: PathBuf,
+}
+
+impl Drop for DirLease {
+    fn drop(&mut self) {
+        let removable = {
+            let mut entries = self.leases.entries.lock().unwrap();
+            let Some(entry) = entries.get_mut(&self.dir) else {
+                return;
+            };
+            entry.refs -= 1;
+            if entry.refs == 0 && entry.delete_at.is_none() && !entry.keep {
+                entries.remove(&self.dir);
+                false
+            } else {
+                entry.refs == 0
+            }
+        };
+        if removable {
+            self.leases.spawn_delete(self.dir.clone());
+        }
+    }
+}
+
+/// 取出目录的记录；正在删除的目录视为全新目录，重新开始记录
+fn live_entry<'a>(entries: &'a mut HashMap<PathBuf, LeaseEntry>, dir: &Path) -> &'a mut LeaseEntry {
+    let entry = entries.entry(dir.to_path_buf()).or_default();
+    if entry.deleting {
+        *entry = LeaseEntry::default();
+    }
+    entry
+}
+
+impl DirLeases {
+    /// 获取目录租约
+    pub fn acquire(&self, dir: impl Into<PathBuf>) -> DirLease {
+        let dir = dir.into();
+        live_entry(&mut self.entries.lock().unwrap(), &dir).refs += 1;
+        DirLease {
+            leases: self.clone(),
+            dir,
+        }
+    }
+
+    /// 安排目录在 `expire_seconds` 秒后删除，-1 表示永不删除
+    ///
+    /// 多个请求先后安排时以最晚的到期时间为准；到期时仍有租约则推迟到最后一个租约释放
+    pub fn schedule_delete(&self, dir: PathBuf, expire_seconds: i64) {
+        let delete_at = {
+            let mut entries = self.entries.lock().unwrap();
+            let entry = live_entry(&mut entries, &dir);
+            // 到期时间大到无法表示时与 -1 一样永不删除
+            let delete_at = u64::try_from(expire_seconds)
+                .ok()
+                .and_then(|seconds| Instant::now().checked_add(Duration::from_secs(seconds)));
+            let Some(delete_at) = delete_at else {
+                entry.keep = true;
+                entry.delete_at = None;
+                return;
+            };
+            if entry.keep {
+                return;
+            }
+            let delete_at = entry.delete_at.map_or(delete_at, |current| current.max(delete_at));
+            entry.delete_at = Some(delete_at);
+            delete_at
+        };
+
+        let leases = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep_until(delete_at).await;
+            leases.spawn_delete(dir);
+        });
+    }
+
+    /// 目录未被租约占用时立即删除（阻塞调用），返回是否已删除
+    pub fn remove_if_unused(&self, dir: &Path) -> std::io::Result<bool> {
+        {
+            let mut entries = self.entries.lock().unwrap();
+            let entry = entries.entry(dir.to_path_buf()).or_default();
+            if entry.refs > 0 || entry.deleting {
+                return Ok(false);
+            }
+            entry.deleting = true;
+        }
+        let result = std::fs::remove_dir_all(dir);
+        self.finish_delete(dir);
+        result.map(|()| true)
+    }
+
+    /// 在阻塞线程中检查并删除目录；检查时在锁内标记为删除中，删除本身在锁外进行，不阻塞其他请求获取租约
+    fn spawn_delete(&self, dir: PathBuf) {
+        let leases = self.clone();
+        tokio::task::spawn_blocking(move || {
+            {
+                let mut entries = leases.entries.lock().unwrap();
+                let Some(entry) = entries.get_mut(&dir) else {
+                    return;
+                };
+                if entry.refs > 0 {
+                    debug!("目录 {} 仍在使用，推迟删除", dir.display());
+                    return;
+                }
+                // 到期时间已被后来的请求延后，由对应的定时任务处理；已在删除中则交给正在删除的一方
+                if entry.deleting || entry.keep || entry.delete_at.is_none_or(|at| at > Instant::now()) {
+                    return;
+                }
+                entry.deleting = true;
+            }
+            remove_dir(&dir);
+            leases.finish_delete(&dir);
+        });
+    }
+
+    /// 删除结束后移除记录；删除期间被重新获取的目录已换成新记录，保留不动
+    fn finish_delete(&self, dir: &Path) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.get(dir).is_some_and(|entry| entry.deleting) {
+            entries.remove(dir);
+        }
+    }
+}
+
+fn remove_dir(dir: &Path) {
+    match std::fs::remove_dir_all(dir) {
+        Ok(()) => info!("已删除目录: {}", dir.display()),
+        Err(e) => warn!("删除目录 {} 失败: {}", dir.display(), e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn wait_until_removed(dir: &Path) -> bool {
+        for _ in 0..50 {
+            if !dir.exists() {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        false
+    }
+
+    #[tokio::test]
+    async fn deletion_waits_for_last_lease() {
+        let dir = std::env::temp_dir().join(format!("jm-lease-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let leases = DirLeases::default();
+
+        let lease = leases.acquire(dir.clone());
+        leases.schedule_delete(dir.clone(), 0);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(dir.exists(), "持有租约时不应删除目录");
+
+        drop(lease);
+        assert!(wait_until_removed(&dir).await, "释放租约后应删除到期目录");
+    }
+
+    #[tokio::test]
+    async fn huge_expiry_keeps_directory() {
+        let leases = DirLeases::default();
+        let dir = PathBuf::from("/tmp/jm-lease-huge-expiry");
+        leases.schedule_delete(dir.clone(), i64::MAX);
+        let entries = leases.entries.lock().unwrap();
+        assert!(entries[&dir].keep && entries[&dir].delete_at.is_none());
+    }
+
+    #[test]
+    fn acquire_during_delete_starts_fresh() {
+        let leases = DirLeases::default();
+        let dir = PathBuf::from("/tmp/jm-lease-deleting");
+        leases.entries.lock().unwrap().insert(
+            dir.clone(),
+            LeaseEntry { deleting: true, keep: true, ..Default::default() },
+        );
+
+        let lease = leases.acquire(dir.clone());
+        {
+            let entries = leases.entries.lock().unwrap();
+            let entry = &entries[&dir];
+            assert!(!entry.deleting && !entry.keep);
+            assert_eq!(entry.refs, 1);
+        }
+        // 先前的删除结束时不应移除新的记录
+        leases.finish_delete(&dir);
+        assert!(leases.entries.lock().unwrap().contains_key(&dir));
+        drop(lease);
+        assert!(!leases.entries.lock().unwrap().contains_key(&dir));
+    }
+}
@@ -0,0 +1,216 @@
+// 启动自检（--doctor）
+// 依次检查日志配置、环境变量/配置文件、下载目录写权限、GhostScript、HTTPS 证书、JM 登录、获取漫画与图片 CDN，
+// 打印诊断报告后退出；部署问题不再以启动时的 panic 形式出现
+
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use jm_downloader_rs::AppError;
+use reqwest_middleware::ClientBuilder;
+
+use crate::config;
+use crate::global_client::GlobalJmClient;
+use crate::image_processor::download_image;
+use crate::logging::LOG_CONFIG_FILE;
+use crate::progress::Progress;
+use crate::storage::Storage;
+use crate::title_page;
+use crate::url_signer::UrlSigner;
+
+type Result<T> = std::result::Result<T, AppError>;
+
+/// 默认用于检查的免费普通漫画
+const DEFAULT_COMIC_ID: i64 = 422866;
+
+/// 命令行参数为 `--doctor` 或 `--doctor=<comic_id>` 时返回用于检查的漫画 ID
+pub fn requested() -> Option<i64> {
+    std::env::args().find_map(|arg| match arg.strip_prefix("--doctor") {
+        Some("") => Some(DEFAULT_COMIC_ID),
+        Some(id) => id.strip_prefix('=').and_then(|id| id.parse().ok()),
+        None => None,
+    })
+}
+
+/// 执行全部检查并打印报告，全部通过时返回 true
+pub async fn run(comic_id: i64) -> bool {
+    let mut report = Report::default();
+    println!("jm-downloader-rs 自检\n");
+
+    report.check("日志配置", || {
+        if Path::new(LOG_CONFIG_FILE).is_file() {
+            Ok(format!("{} 存在", LOG_CONFIG_FILE))
+        } else {
+            Ok(format!("未找到 {}，使用内置日志配置", LOG_CONFIG_FILE))
+        }
+    });
+
+    let config = match config::load_config() {
+        Ok(config) => {
+            let account = match config.credentials() {
+                Some((username, _)) => format!("JM 账号 {}", username),
+                None => "未配置 JM 账号，匿名模式".to_string(),
+            };
+            report.pass("环境变量/配置文件", account);
+            config
+        }
+        Err(e) => {
+            report.fail("环境变量/配置文件", &e);
+            report.skip("其余检查", "配置无效");
+            return report.finish();
+        }
+    };
+
+    report.check("下载目录写权限", || check_writable(Path::new(&config.download_dir)));
+    if let Some(dir) = &config.library_dir {
+        report.check("书库目录写权限", || check_writable(Path::new(dir)));
+    }
+    report.check("GhostScript", check_gs);
+    if let Some(font) = &config.pdf_font {
+        match title_page::load_font(font).await {
+            Ok(_) => report.pass("PDF 标题页字体", font),
+            Err(e) => report.fail("PDF 标题页字体", &e),
+        }
+    }
+    if let (Some(cert), Some(key)) = (&config.tls_cert, &config.tls_key) {
+        report.check("HTTPS 证书", || check_tls(Path::new(cert), Path::new(key)));
+    }
+    report.check("存储后端", || {
+        Storage::from_config(&config, &UrlSigner::from_config(&config))
+            .map(|_| format!("{:?}", config.storage))
+    });
+
+    let start = Instant::now();
+    let client = match GlobalJmClient::new(&config).await {
+        Ok(client) if client.is_anonymous() => {
+            report.skip("JM 登录", "匿名模式");
+            client
+        }
+        Ok(client) => {
+            report.pass("JM 登录", format!("{}，耗时 {}ms", config.api_domain, start.elapsed().as_millis()));
+            client
+        }
+        Err(e) => {
+            report.fail("JM 登录", &e);
+            report.skip("获取漫画与图片 CDN", "未登录");
+            return report.finish();
+        }
+    };
+
+    match check_comic(&client, comic_id).await {
+        Ok((album, image)) => {
+            report.pass("获取漫画", album);
+            match image {
+                Ok(detail) => report.pass("图片 CDN", detail),
+                Err(e) => report.fail("图片 CDN", &e),
+            }
+        }
+        Err(e) => {
+            report.fail("获取漫画", &e);
+            report.skip("图片 CDN", "未获取到章节图片列表");
+        }
+    }
+
+    report.finish()
+}
+
+fn check_writable(dir: &Path) -> Result<String> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| AppError::Internal(format!("创建 {} 失败: {}", dir.display(), e)))?;
+    let probe = dir.join(".doctor-probe");
+    std::fs::write(&probe, b"ok")
+        .map_err(|e| AppError::Internal(format!("写入 {} 失败: {}", dir.display(), e)))?;
+    let _ = std::fs::remove_file(&probe);
+    Ok(dir.display().to_string())
+}
+
+fn check_gs() -> Result<String> {
+    let output = Command::new("gs")
+        .arg("--version")
+        .output()
+        .map_err(|e| AppError::Internal(format!("无法执行 gs（合并 PDF 需要 GhostScript）: {}", e)))?;
+    if !output.status.success() {
+        return Err(AppError::Internal(format!("gs --version 退出码 {}", output.status)));
+    }
+    Ok(format!("版本 {}", String::from_utf8_lossy(&output.stdout).trim()))
+}
+
+/// 证书与私钥文件可读且为 PEM 格式
+fn check_tls(cert: &Path, key: &Path) -> Result<String> {
+    // 私钥可能为 PKCS#8（PRIVATE KEY）、RSA（RSA PRIVATE KEY）或 SEC1（EC PRIVATE KEY）
+    for (path, marker, what) in [(cert, "-----BEGIN CERTIFICATE-----", "证书"), (key, "PRIVATE KEY-----", "私钥")] {
+        let pem = std::fs::read_to_string(path)
+            .map_err(|e| AppError::Internal(format!("读取 {} 失败: {}", path.display(), e)))?;
+        if !pem.contains(marker) {
+            return Err(AppError::Internal(format!("{} 不是 PEM 格式的{}", path.display(), what)));
+        }
+    }
+    Ok(cert.display().to_string())
+}
+
+/// 获取漫画与首个章节的图片列表，再下载第一张图片；外层错误表示漫画或章节获取失败
+async fn check_comic(client: &GlobalJmClient, comic_id: i64) -> Result<(String, Result<String>)> {
+    let comic = client.get_comic(comic_id).await?;
+    let chapter_id = match comic.series.first() {
+        Some(series) => series
+            .id
+            .parse()
+            .map_err(|_| AppError::Internal(format!("章节 ID 无效: {}", series.id)))?,
+        None => comic_id,
+    };
+    let chapter = client.get_chapter(chapter_id).await?;
+    let album = format!("{} {}（章节 {}，{} 页）", comic_id, comic.name, chapter_id, chapter.images.len());
+    let Some(filename) = chapter.images.first() else {
+        return Ok((album, Err(AppError::Internal("章节未返回任何图片".to_string()))));
+    };
+
+    let urls = client.image_urls().await.urls(chapter_id, filename);
+    let image = async {
+        let http_client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .map_err(|e| AppError::Internal(format!("创建HTTP客户端失败: {}", e)))?;
+        let start = Instant::now();
+        let bytes = download_image(&ClientBuilder::new(http_client).build(), &urls, &Progress::new("doctor")).await?;
+        Ok(format!("{}，{} 字节，耗时 {}ms", urls[0], bytes.len(), start.elapsed().as_millis()))
+    }
+    .await;
+    Ok((album, image))
+}
+
+/// 检查结果汇总
+#[derive(Default)]
+struct Report {
+    failed: usize,
+}
+
+impl Report {
+    fn check(&mut self, name: &str, check: impl FnOnce() -> Result<String>) {
+        match check() {
+            Ok(detail) => self.pass(name, detail),
+            Err(e) => self.fail(name, &e),
+        }
+    }
+
+    fn pass(&mut self, name: &str, detail: impl AsRef<str>) {
+        println!("[ OK ] {}: {}", name, detail.as_ref());
+    }
+
+    fn fail(&mut self, name: &str, error: &AppError) {
+        self.failed += 1;
+        println!("[FAIL] {}: {}", name, error);
+    }
+
+    fn skip(&mut self, name: &str, reason: &str) {
+        println!("[SKIP] {}: {}", name, reason);
+    }
+
+    fn finish(self) -> bool {
+        if self.failed == 0 {
+            println!("\n全部检查通过");
+        } else {
+            println!("\n{} 项检查失败", self.failed);
+        }
+        self.failed == 0
+    }
+}
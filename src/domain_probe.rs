@@ -0,0 +1,154 @@
+// 图片域名健康探测
+// 每隔 JM_IMAGE_PROBE_SECONDS 向每个图片域名（JM_IMAGE_DOMAIN 与 JM_IMAGE_DOMAIN_FALLBACKS）发送一个 HEAD 请求，
+// 记录延迟（指数平均）与最近若干次的失败率；下载时把健康且最快的域名排在最前，其余镜像仍作为屏蔽时的后备。
+// 收到任何非 5xx 响应即视为可达，探测不判断图片是否被屏蔽（由下载时的屏蔽检测处理）
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::models::DomainHealth;
+
+/// 失败率统计的探测次数
+const WINDOW: usize = 20;
+/// 失败率达到该值的域名视为不健康
+const UNHEALTHY_ERROR_RATE: f64 = 0.5;
+/// 单次探测超时
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Default)]
+struct Stats {
+    /// 成功探测的延迟指数平均（毫秒）
+    latency_ms: Option<f64>,
+    /// 最近 `WINDOW` 次探测是否成功
+    outcomes: VecDeque<bool>,
+    last_error: Option<String>,
+    last_probe_at: Option<String>,
+}
+
+impl Stats {
+    fn error_rate(&self) -> f64 {
+        if self.outcomes.is_empty() {
+            return 0.0;
+        }
+        self.outcomes.iter().filter(|ok| !**ok).count() as f64 / self.outcomes.len() as f64
+    }
+
+    /// 最近一次探测成功且失败率低于阈值
+    fn healthy(&self) -> bool {
+        self.outcomes.back() == Some(&true) && self.error_rate() < UNHEALTHY_ERROR_RATE
+    }
+}
+
+/// 各图片域名的探测结果
+#[derive(Default)]
+pub struct DomainProbe {
+    stats: Mutex<HashMap<String, Stats>>,
+}
+
+impl DomainProbe {
+    /// 依次探测 `domains` 并记录结果
+    pub async fn probe_all(&self, http: &reqwest::Client, domains: &[String]) {
+        for domain in domains {
+            let start = Instant::now();
+            let outcome = match http.head(format!("https://{}/", domain)).send().await {
+                Ok(response) if response.status().is_server_error() => Err(format!("HTTP {}", response.status())),
+                Ok(_) => Ok(start.elapsed()),
+                Err(e) => Err(e.to_string()),
+            };
+            if let Err(e) = &outcome {
+                debug!("图片域名 {} 探测失败: {}", domain, e);
+            }
+            self.record(domain, outcome);
+        }
+    }
+
+    fn record(&self, domain: &str, outcome: Result<Duration, String>) {
+        let mut stats = self.stats.lock().unwrap();
+        let entry = stats.entry(domain.to_string()).or_default();
+        match outcome {
+            Ok(latency) => {
+                let latency = latency.as_secs_f64() * 1000.0;
+                entry.latency_ms = Some(entry.latency_ms.map_or(latency, |average| average * 0.7 + latency * 0.3));
+                entry.outcomes.push_back(true);
+            }
+            Err(e) => {
+                entry.outcomes.push_back(false);
+                entry.last_error = Some(e);
+            }
+        }
+        if entry.outcomes.len() > WINDOW {
+            entry.outcomes.pop_front();
+        }
+        entry.last_probe_at = Some(chrono::Utc::now().with_timezone(&chrono_tz::Asia::Shanghai).to_rfc3339());
+    }
+
+    /// 按探测结果排序：健康的域名按延迟从低到高在前，尚未探测的其次，不健康的最后；同类保持配置顺序
+    pub fn rank(&self, domains: &[String]) -> Vec<String> {
+        let stats = self.stats.lock().unwrap();
+        let mut ranked: Vec<(usize, f64, &String)> = domains
+            .iter()
+            .map(|domain| match stats.get(domain) {
+                Some(entry) if entry.healthy() => (0, entry.latency_ms.unwrap_or(f64::MAX), domain),
+                Some(_) => (2, 0.0, domain),
+                None => (1, 0.0, domain),
+            })
+            .collect();
+        // sort_by 是稳定排序，同类同延迟时保持配置顺序
+        ranked.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)));
+        ranked.into_iter().map(|(_, _, domain)| domain.clone()).collect()
+    }
+
+    /// `domains` 的探测结果，按 [`DomainProbe::rank`] 的顺序
+    pub fn report(&self, domains: &[String]) -> Vec<DomainHealth> {
+        let ranked = self.rank(domains);
+        let stats = self.stats.lock().unwrap();
+        ranked
+            .into_iter()
+            .enumerate()
+            .map(|(index, domain)| {
+                let entry = stats.get(&domain);
+                DomainHealth {
+                    preferred: index == 0,
+                    healthy: entry.is_some_and(Stats::healthy),
+                    latency_ms: entry.and_then(|entry| entry.latency_ms).map(|latency| latency.round() as u64),
+                    probes: entry.map_or(0, |entry| entry.outcomes.len()),
+                    error_rate: entry.map_or(0.0, Stats::error_rate),
+                    last_error: entry.and_then(|entry| entry.last_error.clone()),
+                    last_probe_at: entry.and_then(|entry| entry.last_probe_at.clone()),
+                    domain,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranks_healthy_domains_by_latency() {
+        let domains: Vec<String> = ["a", "b", "c", "d"].iter().map(|d| d.to_string()).collect();
+        let probe = DomainProbe::default();
+        assert_eq!(probe.rank(&domains), domains);
+
+        probe.record("a", Err("timed out".to_string()));
+        probe.record("b", Ok(Duration::from_millis(300)));
+        probe.record("c", Ok(Duration::from_millis(80)));
+        assert_eq!(probe.rank(&domains), ["c", "b", "d", "a"]);
+
+        // 最近一次失败时不健康
+        probe.record("c", Err("HTTP 502 Bad Gateway".to_string()));
+        assert_eq!(probe.rank(&domains), ["b", "d", "a", "c"]);
+        let report = probe.report(&domains);
+        assert!(report[0].preferred && report[0].healthy);
+        assert_eq!(report[0].latency_ms, Some(300));
+        assert_eq!(report[2].last_error.as_deref(), Some("timed out"));
+        assert_eq!(report[3].error_rate, 0.5);
+
+        // 恢复后失败率低于一半，重新成为首选
+        probe.record("c", Ok(Duration::from_millis(80)));
+        assert_eq!(probe.rank(&domains), ["c", "b", "d", "a"]);
+    }
+}
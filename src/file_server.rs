@@ -0,0 +1,308 @@
+// 受保护的下载文件服务
+// 校验下载链接签名，支持 Range 断点续传，并按漫画/章节标题设置 Content-Disposition 文件名
+
+use std::io::SeekFrom;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use rocket::http::{ContentType, Header, Status};
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::response::{self, Responder, Response};
+use rocket::State;
+use tokio::fs::File;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, ReadBuf};
+
+use crate::artifact;
+use crate::dir_lease::{DirLease, DirLeases};
+use crate::image_processor::download_root;
+use crate::service_mode;
+use crate::url_signer::{pct_encode, UrlSigner};
+
+/// 请求中的 Range 头
+pub struct RangeHeader(Option<String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RangeHeader {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(RangeHeader(req.headers().get_one("Range").map(str::to_string)))
+    }
+}
+
+/// 解析后的字节范围（闭区间）
+#[derive(Debug, PartialEq, Eq)]
+enum ByteRange {
+    /// 无 Range 头或包含多个范围：返回完整文件
+    Full,
+    Partial(u64, u64),
+    /// 范围无法满足
+    Unsatisfiable,
+}
+
+/// 解析 `bytes=start-end` / `bytes=start-` / `bytes=-suffix`，多范围请求按完整文件返回
+fn parse_range(header: Option<&str>, len: u64) -> ByteRange {
+    let Some(spec) = header.and_then(|value| value.trim().strip_prefix("bytes=")) else {
+        return ByteRange::Full;
+    };
+    if spec.contains(',') {
+        return ByteRange::Full;
+    }
+    let Some((start, end)) = spec.split_once('-') else {
+        return ByteRange::Unsatisfiable;
+    };
+    let (start, end) = (start.trim(), end.trim());
+
+    let range = if start.is_empty() {
+        // 后缀范围：最后 N 个字节
+        match end.parse::<u64>() {
+            Ok(suffix) if suffix > 0 && len > 0 => (len.saturating_sub(suffix), len - 1),
+            _ => return ByteRange::Unsatisfiable,
+        }
+    } else {
+        let Ok(start) = start.parse::<u64>() else {
+            return ByteRange::Unsatisfiable;
+        };
+        let end = if end.is_empty() {
+            len.saturating_sub(1)
+        } else {
+            match end.parse::<u64>() {
+                Ok(end) => end.min(len.saturating_sub(1)),
+                Err(_) => return ByteRange::Unsatisfiable,
+            }
+        };
+        (start, end)
+    };
+
+    if range.0 >= len || range.0 > range.1 {
+        ByteRange::Unsatisfiable
+    } else {
+        ByteRange::Partial(range.0, range.1)
+    }
+}
+
+/// 支持 Range 的文件响应
+pub struct DownloadFile {
+    file: File,
+    /// 所在目录的租约，随响应体一起释放
+    lease: DirLease,
+    /// 文件总大小
+    len: u64,
+    /// 本次返回的范围，None 表示完整文件
+    range: Option<(u64, u64)>,
+    content_type: ContentType,
+    filename: String,
+}
+
+impl<'r> Responder<'r, 'static> for DownloadFile {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        let (status, body_len) = match self.range {
+            Some((start, end)) => (Status::PartialContent, end - start + 1),
+            None => (Status::Ok, self.len),
+        };
+
+        let mut builder = Response::build();
+        builder
+            .status(status)
+            .header(self.content_type)
+            .raw_header("Accept-Ranges", "bytes")
+            .raw_header("Content-Length", body_len.to_string())
+            .header(content_disposition(&self.filename));
+        if let Some((start, end)) = self.range {
+            builder.raw_header("Content-Range", format!("bytes {}-{}/{}", start, end, self.len));
+        }
+        // 流式返回限定长度的文件内容，Content-Length 已显式设置
+        builder.streamed_body(LeasedReader {
+            inner: self.file.take(body_len),
+            _lease: self.lease,
+        });
+        builder.ok()
+    }
+}
+
+/// 传输期间持有目录租约的读取器，防止文件在下载过程中被过期清理删除
+struct LeasedReader<R> {
+    inner: R,
+    _lease: DirLease,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for LeasedReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+/// 文件服务错误，使用真实 HTTP 状态码（非 JSON 接口）
+pub enum DownloadError {
+    /// 缺少签名、签名无效或已过期
+    Forbidden,
+    NotFound,
+    Internal,
+    /// 服务处于维护模式
+    Unavailable,
+    /// 携带文件总大小，用于返回 `Content-Range: bytes */len`
+    RangeNotSatisfiable(u64),
+}
+
+impl<'r> Responder<'r, 'static> for DownloadError {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        let mut builder = Response::build();
+        match self {
+            DownloadError::Forbidden => builder.status(Status::Forbidden),
+            DownloadError::NotFound => builder.status(Status::NotFound),
+            DownloadError::Internal => builder.status(Status::InternalServerError),
+            DownloadError::Unavailable => builder.status(Status::ServiceUnavailable),
+            DownloadError::RangeNotSatisfiable(len) => builder
+                .status(Status::RangeNotSatisfiable)
+                .raw_header("Content-Range", format!("bytes */{}", len)),
+        };
+        builder.ok()
+    }
+}
+
+/// 生成 Content-Disposition 头
+fn content_disposition(filename: &str) -> Header<'static> {
+    Header::new("Content-Disposition", content_disposition_value(filename))
+}
+
+/// Content-Disposition 取值：ASCII 回退名 + RFC 5987 UTF-8 文件名
+pub fn content_disposition_value(filename: &str) -> String {
+    let fallback: String = filename
+        .chars()
+        .map(|c| if c.is_ascii_graphic() && c != '"' && c != '\\' { c } else { '_' })
+        .collect();
+    format!(
+        "inline; filename=\"{}\"; filename*=UTF-8''{}",
+        fallback,
+        pct_encode(filename)
+    )
+}
+
+/// 文件名中去除路径分隔符与控制字符
+pub fn sanitize_filename(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_control())
+        .map(|c| if matches!(c, '/' | '\\') { '_' } else { c })
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// 受签名保护的下载文件服务
+#[get("/download/<path..>?<expires>&<sig>&<name>")]
+pub async fn serve_download(
+    signer: &State<UrlSigner>,
+    leases: &State<DirLeases>,
+    range: RangeHeader,
+    path: PathBuf,
+    expires: Option<u64>,
+    sig: Option<&str>,
+    name: Option<String>,
+) -> Result<DownloadFile, DownloadError> {
+    service_mode::ensure_available().map_err(|_| DownloadError::Unavailable)?;
+    let (Some(expires), Some(sig)) = (expires, sig) else {
+        return Err(DownloadError::Forbidden);
+    };
+    // Rocket 的多段路径已拒绝 `..` 等不安全片段；签名统一使用 `/` 分隔
+    let file_path = path
+        .iter()
+        .map(|segment| segment.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    if !signer.verify(&file_path, expires, sig) {
+        warn!("下载链接签名无效或已过期: {}", file_path);
+        return Err(DownloadError::Forbidden);
+    }
+
+    let full_path = download_root().join(&path);
+    let lease_dir = artifact::lease_dir(&path).map(|dir| download_root().join(dir));
+    let lease = leases.acquire(lease_dir.unwrap_or_else(|| download_root().to_path_buf()));
+    let mut file = File::open(&full_path)
+        .await
+        .map_err(|_| DownloadError::NotFound)?;
+    let len = file
+        .metadata()
+        .await
+        .map_err(|_| DownloadError::NotFound)?
+        .len();
+
+    let range = match parse_range(range.0.as_deref(), len) {
+        ByteRange::Full => None,
+        ByteRange::Partial(start, end) => {
+            file.seek(SeekFrom::Start(start))
+                .await
+                .map_err(|_| DownloadError::Internal)?;
+            Some((start, end))
+        }
+        ByteRange::Unsatisfiable => {
+            return Err(DownloadError::RangeNotSatisfiable(len));
+        }
+    };
+
+    let content_type = full_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(ContentType::from_extension)
+        .unwrap_or(ContentType::Binary);
+    let default_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let filename = name
+        .map(|name| sanitize_filename(&name))
+        .filter(|name| !name.is_empty())
+        .unwrap_or(default_name);
+
+    Ok(DownloadFile {
+        file,
+        lease,
+        len,
+        range,
+        content_type,
+        filename,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_range_headers() {
+        use ByteRange::*;
+        let cases: [(Option<&str>, u64, ByteRange); 18] = [
+            (None, 100, Full),
+            (Some("items=0-1"), 100, Full),
+            (Some("bytes=0-9"), 100, Partial(0, 9)),
+            (Some(" bytes=10-10 "), 100, Partial(10, 10)),
+            // 结束位置超出文件时截到末尾
+            (Some("bytes=90-200"), 100, Partial(90, 99)),
+            // 不指定结束位置
+            (Some("bytes=50-"), 100, Partial(50, 99)),
+            (Some("bytes=99-"), 100, Partial(99, 99)),
+            // 后缀范围：最后 N 个字节，超过文件长度时为整个文件
+            (Some("bytes=-10"), 100, Partial(90, 99)),
+            (Some("bytes=-500"), 100, Partial(0, 99)),
+            (Some("bytes=-0"), 100, Unsatisfiable),
+            (Some("bytes=-10"), 0, Unsatisfiable),
+            // 起始位置越界或起止颠倒
+            (Some("bytes=100-"), 100, Unsatisfiable),
+            (Some("bytes=150-200"), 100, Unsatisfiable),
+            (Some("bytes=20-10"), 100, Unsatisfiable),
+            // 格式错误
+            (Some("bytes=abc"), 100, Unsatisfiable),
+            (Some("bytes=a-5"), 100, Unsatisfiable),
+            // 多范围按完整文件返回
+            (Some("bytes=0-9,20-29"), 100, Full),
+            (Some("bytes=-5, 0-1"), 100, Full),
+        ];
+        for (header, len, expected) in cases {
+            assert_eq!(parse_range(header, len), expected, "Range: {:?}，文件长度 {}", header, len);
+        }
+    }
+}
@@ -1,218 +1,750 @@
 // 全局 JmClient 管理模块
-// 提供线程安全的客户端访问和自动会话管理
+// 提供线程安全的客户端访问和自动会话管理；配置了多个账号时元数据请求在账号池中轮换
 
+use std::future::Future;
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Duration;
+use arc_swap::ArcSwapOption;
+use rand::Rng;
+use tokio::sync::OnceCell;
 use jm_downloader_rs::AppError;
 
-use crate::jm_client::JmClient;
+use crate::account_pool::{login_required, AccountPool, Session};
+use crate::circuit_breaker::CircuitBreaker;
+use crate::jm_api::{JmApi, SearchPage};
+use crate::jm_client::{ImageUrlBuilder, JmClient};
 use crate::config::Config;
-use crate::models::{GetComicRespData, GetChapterRespData};
+use crate::domain_probe::{DomainProbe, PROBE_TIMEOUT};
+use crate::pacing::Pacer;
+use crate::scramble_cache::ScrambleIdCache;
+use crate::models::{CheckinData, CommentData, ComicSummary, DomainHealth, GetComicRespData, GetChapterRespData, LikeData, UserProfile};
+use crate::web_client::WebJmClient;
 
 type Result<T> = std::result::Result<T, AppError>;
 
 /// 全局 JmClient 管理器，提供线程安全的客户端访问和自动会话管理
-#[derive(Clone)]
-pub struct GlobalJmClient {
-    /// 内部客户端实例，使用 RwLock 保证并发安全
-    client: Arc<RwLock<JmClient>>,
-    /// 认证凭据 - 用户名
-    username: String,
-    /// 认证凭据 - 密码
-    password: String,
-    /// 图片域名
-    pub image_domain: String,
-    /// 会话状态标记（用于优化：避免频繁检查）
-    session_valid: Arc<RwLock<bool>>,
+///
+/// `C` 为主客户端（默认移动端 API），`W` 为备用客户端（默认网页端），测试时可替换为 Mock 实现
+pub struct GlobalJmClient<C: JmApi = JmClient, W: JmApi = WebJmClient> {
+    /// 账号池，每个账号持有独立的客户端与会话，第一个为主账号；未配置账号时只有一个匿名会话，
+    /// 需要登录的操作返回 [`AppError::LoginRequired`]
+    pool: Arc<AccountPool<C>>,
+    /// 备用客户端（主客户端失败时使用，未启用时为 None），重新加载配置时整体替换
+    web: Arc<ArcSwapOption<WebFallback<W>>>,
+    /// 获取漫画、章节与 scramble_id 的请求节流
+    pacer: Arc<Pacer>,
+    /// 已获取的 scramble_id
+    scramble_ids: Arc<ScrambleIdCache>,
+    /// 移动端 API 熔断器
+    breaker: Arc<CircuitBreaker>,
+    /// 图片域名探测结果
+    domain_probe: Arc<DomainProbe>,
+}
+
+/// 备用客户端及其登录状态
+struct WebFallback<W> {
+    client: Arc<W>,
+    /// 备用客户端登录只尝试一次
+    login: OnceCell<()>,
+}
+
+impl<W> WebFallback<W> {
+    fn new(client: W) -> Self {
+        Self {
+            client: Arc::new(client),
+            login: OnceCell::new(),
+        }
+    }
+}
+
+// 手动实现 Clone：字段均为 Arc，无需要求 C、W 实现 Clone
+impl<C: JmApi, W: JmApi> Clone for GlobalJmClient<C, W> {
+    fn clone(&self) -> Self {
+        Self {
+            pool: self.pool.clone(),
+            web: self.web.clone(),
+            pacer: self.pacer.clone(),
+            scramble_ids: self.scramble_ids.clone(),
+            breaker: self.breaker.clone(),
+            domain_probe: self.domain_probe.clone(),
+        }
+    }
 }
 
 impl GlobalJmClient {
-    /// 创建新的全局客户端实例并立即登录
+    /// 创建新的全局客户端实例，配置了账号时立即登录
     ///
     /// # 参数
     /// - config: 应用配置
     ///
     /// # 返回
-    /// - Ok(GlobalJmClient): 成功创建并登录的客户端
-    /// - Err: 创建或登录失败
+    /// - Ok(GlobalJmClient): 成功创建（并登录）的客户端
+    /// - Err: 创建或主账号登录失败
     pub async fn new(config: &Config) -> Result<Self> {
-        let client = JmClient::new(
-            config.api_domain.clone(),
-            config.image_domain.clone(),
+        let accounts = config.accounts();
+        let mut client = if accounts.is_empty() {
+            Self::with_clients(build_app_client(config), build_web_client(config), None).await?
+        } else {
+            // 每个账号使用独立的客户端，cookie 与登录数据互不影响
+            let accounts = accounts
+                .into_iter()
+                .map(|account| (build_app_client(config), account))
+                .collect();
+            Self::with_accounts(accounts, build_web_client(config)).await?
+        };
+        client.pacer.configure(config.api_min_interval_ms, config.api_hourly_limit);
+        client.breaker.configure(config.breaker_failure_threshold, config.breaker_open_seconds);
+        client.pool.configure(
+            config.account_rotation,
+            Duration::from_secs(config.account_cooldown_seconds),
         );
+        client.scramble_ids = Arc::new(ScrambleIdCache::open(
+            config.scramble_cache_file.as_ref().map(PathBuf::from),
+        ));
+        Ok(client)
+    }
 
-        // 立即执行登录
-        client
-            .login(&config.jm_username, &config.jm_password)
-            .await?;
-
-        info!("全局 JmClient 初始化成功，已完成登录");
+    /// 当前主客户端的章节图片地址生成器，图片域名按探测结果排序
+    pub async fn image_urls(&self) -> ImageUrlBuilder {
+        let urls = self.pool.primary().read().await.image_urls().clone();
+        let domains = self.domain_probe.rank(urls.domains());
+        urls.with_domains(domains)
+    }
 
-        Ok(Self {
-            client: Arc::new(RwLock::new(client)),
-            username: config.jm_username.clone(),
-            password: config.jm_password.clone(),
-            image_domain: config.image_domain.clone(),
-            session_valid: Arc::new(RwLock::new(true)),
-        })
+    /// 当前配置的图片域名的探测结果
+    pub async fn domain_health(&self) -> Vec<DomainHealth> {
+        let urls = self.pool.primary().read().await.image_urls().clone();
+        self.domain_probe.report(urls.domains())
     }
 
-    /// 获取客户端的只读引用（用于读操作）
-    ///
-    /// 在执行操作前会自动检查会话有效性，如果会话失效会自动重新登录
-    async fn get_client(&self) -> Result<tokio::sync::RwLockReadGuard<'_, JmClient>> {
-        // 先检查会话是否有效
-        self.ensure_session_valid().await?;
+    /// 搜索漫画；查询为车号时 JM 只返回跳转目标，在此获取该漫画补全为单条结果
+    pub async fn search(&self, query: &str, page: u32) -> Result<SearchPage> {
+        let mut result = self
+            .rotate("搜索漫画", |_| false, |session| async move {
+                session.client().await?.search(query, page).await
+            })
+            .await?;
+        if let Some(aid) = result.redirect_aid {
+            result.total = 1;
+            result.comics.clear();
+            if page == 1 {
+                let comic = self.get_comic(aid).await?;
+                result.comics.push(ComicSummary {
+                    comic_id: aid,
+                    title: comic.name,
+                    author: comic.author.join(", "),
+                    cover: self.image_urls().await.cover(aid),
+                    category: None,
+                });
+            }
+        }
+        Ok(result)
+    }
 
-        // 返回只读锁
-        Ok(self.client.read().await)
+    /// 启动图片域名探测后台任务，启动时立即探测一次，之后每隔 `interval` 探测当前配置的全部图片域名
+    pub fn spawn_domain_probe(&self, interval: Duration) {
+        let this = self.clone();
+        tokio::spawn(async move {
+            let http = match reqwest::Client::builder().timeout(PROBE_TIMEOUT).build() {
+                Ok(http) => http,
+                Err(e) => {
+                    error!("创建图片域名探测客户端失败: {}", e);
+                    return;
+                }
+            };
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let urls = this.pool.primary().read().await.image_urls().clone();
+                this.domain_probe.probe_all(&http, urls.domains()).await;
+            }
+        });
     }
 
-    /// 确保会话有效，如果无效则重新登录
-    async fn ensure_session_valid(&self) -> Result<()> {
-        // 快速路径：如果标记为有效，直接返回
+    /// 按新配置替换客户端：域名或重试次数变化时为每个账号重建并重新登录客户端，网页端配置变化时重建备用客户端
+    ///
+    /// 主账号的新客户端登录成功后才会替换，失败时保持全部客户端不变；其他账号登录失败时继续使用原客户端；
+    /// 进行中的请求继续使用各自持有的客户端
+    pub async fn apply_config(&self, old: &Config, new: &Config) -> Result<()> {
+        self.pacer.configure(new.api_min_interval_ms, new.api_hourly_limit);
+        self.breaker.configure(new.breaker_failure_threshold, new.breaker_open_seconds);
+        self.pool.configure(new.account_rotation, Duration::from_secs(new.account_cooldown_seconds));
+        if old.api_domain != new.api_domain
+            || old.api_domain_fallbacks != new.api_domain_fallbacks
+            || old.image_domain != new.image_domain
+            || old.image_domain_fallbacks != new.image_domain_fallbacks
+            || old.image_url_template != new.image_url_template
+            || old.max_retries != new.max_retries
+            || old.data_secrets != new.data_secrets
         {
-            let valid = self.session_valid.read().await;
-            if *valid {
-                return Ok(());
+            for session in self.pool.sessions() {
+                let client = build_app_client(new);
+                let login = match session.credentials() {
+                    Some((username, password)) => client.login(username, password).await,
+                    None => Ok(()),
+                };
+                match login {
+                    Ok(()) => session.replace(client).await,
+                    Err(e) if self.pool.is_primary(session) => return Err(e),
+                    Err(e) => warn!("账号 {} 使用新配置登录失败，继续使用原客户端: {}", session.name(), e),
+                }
             }
+            info!("已切换移动端 API 域名为 {}", new.api_domain);
+        }
+
+        if old.web_fallback != new.web_fallback
+            || old.web_domain != new.web_domain
+            || old.max_retries != new.max_retries
+        {
+            self.web
+                .store(build_web_client(new).map(|client| Arc::new(WebFallback::new(client))));
         }
+        Ok(())
+    }
+}
+
+fn build_app_client(config: &Config) -> JmClient {
+    let api_domains = std::iter::once(&config.api_domain)
+        .chain(&config.api_domain_fallbacks)
+        .cloned()
+        .collect();
+    let image_domains = std::iter::once(&config.image_domain)
+        .chain(&config.image_domain_fallbacks)
+        .cloned()
+        .collect();
+    JmClient::new(
+        api_domains,
+        image_domains,
+        config.image_url_template.clone(),
+        config.max_retries,
+        config.data_secrets.clone(),
+    )
+}
 
-        // 会话可能失效，需要重新登录
-        self.relogin().await
+fn build_web_client(config: &Config) -> Option<WebJmClient> {
+    if config.web_fallback {
+        info!("已启用网页端备用接口: {}", config.web_domain);
+        Some(WebJmClient::new(config.web_domain.clone(), config.max_retries))
+    } else {
+        None
     }
+}
 
-    /// 重新登录（当检测到会话失效时调用）
-    async fn relogin(&self) -> Result<()> {
-        // 获取写锁以执行重新登录
-        let mut session_valid = self.session_valid.write().await;
+impl<C: JmApi, W: JmApi> GlobalJmClient<C, W> {
+    /// 使用指定的主客户端与备用客户端创建实例，`credentials` 为 None 时以匿名模式运行，否则立即登录
+    pub async fn with_clients(
+        client: C,
+        web_client: Option<W>,
+        credentials: Option<(&str, &str)>,
+    ) -> Result<Self> {
+        match credentials {
+            Some(account) => Self::with_accounts(vec![(client, account)], web_client).await,
+            None => {
+                info!("未配置 JM 账号，以匿名模式运行，需要登录的操作将返回错误");
+                Ok(Self::with_pool(vec![Session::new(client, None)], web_client))
+            }
+        }
+    }
 
-        // 双重检查：可能其他线程已经完成了重新登录
-        if *session_valid {
-            return Ok(());
+    /// 使用账号池创建实例，每个账号一个客户端，第一个为主账号，全部立即登录
+    ///
+    /// 主账号登录失败时返回错误；其他账号登录失败时只记录警告，首次被选中时再重新登录
+    pub async fn with_accounts(accounts: Vec<(C, (&str, &str))>, web_client: Option<W>) -> Result<Self> {
+        let mut sessions = Vec::with_capacity(accounts.len());
+        for (index, (client, account)) in accounts.into_iter().enumerate() {
+            let session = Session::new(client, Some(account));
+            match session.login().await {
+                Ok(()) if index == 0 => info!("全局 JmClient 初始化成功，已完成登录"),
+                Ok(()) => info!("账号池账号 {} 登录成功", session.name()),
+                Err(e) if index == 0 => return Err(e),
+                Err(e) => {
+                    warn!("账号池账号 {} 登录失败，将在首次使用时重试: {}", session.name(), e);
+                    session.mark_invalid().await;
+                }
+            }
+            sessions.push(session);
         }
+        if sessions.len() > 1 {
+            info!("已启用账号池，共 {} 个账号", sessions.len());
+        }
+        Ok(Self::with_pool(sessions, web_client))
+    }
 
-        warn!("检测到会话失效，正在重新登录...");
+    fn with_pool(sessions: Vec<Session<C>>, web_client: Option<W>) -> Self {
+        Self {
+            pool: Arc::new(AccountPool::new(sessions)),
+            web: Arc::new(ArcSwapOption::new(
+                web_client.map(|client| Arc::new(WebFallback::new(client))),
+            )),
+            pacer: Arc::new(Pacer::default()),
+            scramble_ids: Arc::new(ScrambleIdCache::default()),
+            breaker: Arc::new(CircuitBreaker::default()),
+            domain_probe: Arc::new(DomainProbe::default()),
+        }
+    }
 
-        // 获取客户端读锁
-        let client = self.client.read().await;
+    /// 启动会话保活后台任务，每隔 `interval`（附带 ±10% 抖动）主动请求一次需要登录态的接口
+    ///
+    /// 发现会话失效时立即重新登录，避免空闲一段时间后的首个请求失败
+    pub fn spawn_keep_alive(&self, interval: Duration)
+    where
+        C: 'static,
+        W: 'static,
+    {
+        let this = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let delay = interval.mul_f64(rand::thread_rng().gen_range(0.9..1.1));
+                tokio::time::sleep(delay).await;
+                this.keep_alive().await;
+            }
+        });
+    }
 
-        // 执行登录
-        client
-            .login(&self.username, &self.password)
-            .await?;
+    /// 为账号池中的每个账号执行一次会话保活
+    async fn keep_alive(&self) {
+        for session in self.pool.sessions() {
+            keep_alive(session).await;
+        }
+    }
 
-        // 标记会话为有效
-        *session_valid = true;
+    /// 获取漫画信息，移动端 API 失败时改用网页端
+    pub async fn get_comic(&self, aid: i64) -> Result<GetComicRespData> {
+        self.pacer.wait("获取漫画").await;
+        let result = self
+            .rotate("获取漫画信息", GetComicRespData::requires_purchase, |session| async move {
+                get_comic_from_app(&session, aid).await
+            })
+            .await;
+        match result {
+            Err(e) if can_fallback(&e) => {
+                self.fallback("获取漫画信息", e, |web| async move { web.get_comic(aid).await })
+                    .await
+            }
+            result => result,
+        }
+    }
 
-        info!("重新登录成功");
-        Ok(())
+    /// 获取章节信息，移动端 API 失败时改用网页端
+    pub async fn get_chapter(&self, id: i64) -> Result<GetChapterRespData> {
+        self.pacer.wait("获取章节").await;
+        let result = self
+            .rotate("获取章节信息", GetChapterRespData::requires_purchase, |session| async move {
+                get_chapter_from_app(&session, id).await
+            })
+            .await;
+        match result {
+            Err(e) if can_fallback(&e) => {
+                self.fallback("获取章节信息", e, |web| async move { web.get_chapter(id).await })
+                    .await
+            }
+            result => result,
+        }
     }
 
-    /// 标记会话为失效（当 API 调用返回认证错误时调用）
-    async fn mark_session_invalid(&self) {
-        let mut valid = self.session_valid.write().await;
-        *valid = false;
-        warn!("会话已标记为失效");
+    /// 获取 scramble ID，移动端 API 失败时改用网页端；获取成功的结果会缓存，同一章节不再重复请求
+    pub async fn get_scramble_id(&self, id: i64) -> Result<i64> {
+        if let Some(scramble_id) = self.scramble_ids.get(id) {
+            return Ok(scramble_id);
+        }
+        self.pacer.wait("获取 scramble_id").await;
+        let result = self
+            .rotate("获取 scramble_id", |_| false, |session| async move {
+                get_scramble_id_from_app(&session, id).await
+            })
+            .await;
+        let result = match result {
+            Err(e) if can_fallback(&e) => {
+                self.fallback("获取 scramble_id", e, |web| async move {
+                    web.get_scramble_id(id).await
+                })
+                .await
+            }
+            result => result,
+        };
+        if let Ok(scramble_id) = result {
+            self.scramble_ids.insert(id, scramble_id);
+        }
+        result
     }
 
-    /// 执行带自动重试的 API 调用 - 获取漫画信息
+    /// 按轮换策略选择账号执行一次元数据请求，熔断中时直接返回 [`AppError::ServiceUnavailable`]
     ///
-    /// 如果第一次调用因认证失败，会自动重新登录并重试一次
-    pub async fn get_comic(&self, aid: i64) -> Result<GetComicRespData> {
-        // 第一次尝试
-        let client = self.get_client().await?;
-        match client.get_comic(aid).await {
-            Ok(result) => Ok(result),
-            Err(e) => {
-                // 检查是否是认证错误
-                if is_auth_error(&e) {
-                    warn!("检测到认证错误，尝试重新登录: {}", e);
-                    drop(client); // 释放读锁
+    /// 账号触发风控（被拦截或登录失败）时进入冷却并换一个账号重试一次；结果因账号而异（需付费）且不是主账号时
+    /// 改用主账号重试，自动购买只在主账号上进行
+    async fn rotate<T, F, Fut>(&self, what: &str, needs_primary: fn(&T) -> bool, call: F) -> Result<T>
+    where
+        F: Fn(Arc<Session<C>>) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.breaker.check(what)?;
+        let mut session = self.pool.pick();
+        let mut result = call(session.clone()).await;
+        if let Err(e) = &result {
+            if is_risk_control(e) && self.pool.len() > 1 {
+                self.pool.cool_down(&session, e);
+                let next = self.pool.pick();
+                if !Arc::ptr_eq(&next, &session) {
+                    warn!("账号 {} {}失败，改用账号 {} 重试", session.name(), what, next.name());
+                    session = next;
+                    result = call(session.clone()).await;
+                }
+            }
+        }
 
-                    // 标记会话失效
-                    self.mark_session_invalid().await;
+        let account_specific = match &result {
+            Ok(value) => needs_primary(value),
+            Err(e) => matches!(e, AppError::PaymentRequired(_)),
+        };
+        if account_specific && !self.pool.is_primary(&session) {
+            debug!("账号 {} {}需要付费，改用主账号获取", session.name(), what);
+            result = call(self.pool.primary().clone()).await;
+        }
+        self.breaker.record(&result);
+        result
+    }
 
-                    // 重新登录
-                    self.relogin().await?;
+    /// 经熔断器执行一次只用主账号的调用
+    async fn guarded<T>(&self, what: &str, call: impl Future<Output = Result<T>>) -> Result<T> {
+        self.breaker.check(what)?;
+        let result = call.await;
+        self.breaker.record(&result);
+        result
+    }
 
-                    // 重试一次
-                    let client = self.get_client().await?;
-                    client.get_comic(aid).await
-                } else {
-                    // 非认证错误，直接返回
-                    Err(e)
+    /// 使用网页端客户端重试一次失败的调用
+    ///
+    /// 网页端也失败时返回移动端的原始错误（通常更能说明问题）
+    async fn fallback<T, F, Fut>(&self, what: &str, app_error: AppError, call: F) -> Result<T>
+    where
+        F: FnOnce(Arc<W>) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let Some(web) = self.web.load_full() else {
+            return Err(app_error);
+        };
+
+        warn!("移动端 API {}失败，改用网页端重试: {}", what, app_error);
+
+        // 网页端登录失败时仍以匿名身份尝试，多数漫画无需登录即可访问
+        web.login
+            .get_or_init(|| async {
+                let Some((username, password)) = self.pool.primary().credentials() else {
+                    return;
+                };
+                if let Err(e) = web.client.login(username, password).await {
+                    warn!("网页端登录失败，将以匿名身份访问: {}", e);
                 }
+            })
+            .await;
+
+        match call(web.client.clone()).await {
+            Ok(result) => {
+                info!("网页端{}成功", what);
+                Ok(result)
+            }
+            Err(e) => {
+                error!("网页端{}也失败: {}", what, e);
+                Err(app_error)
             }
         }
     }
 
-    /// 执行带自动重试的 API 调用 - 获取章节信息
-    pub async fn get_chapter(&self, id: i64) -> Result<GetChapterRespData> {
-        // 第一次尝试
-        let client = self.get_client().await?;
-        match client.get_chapter(id).await {
-            Ok(result) => Ok(result),
-            Err(e) => {
-                // 检查是否是认证错误
-                if is_auth_error(&e) {
-                    warn!("检测到认证错误，尝试重新登录: {}", e);
-                    drop(client); // 释放读锁
+    /// 获取最新上架列表
+    pub async fn latest(&self, page: u32) -> Result<Vec<ComicSummary>> {
+        self.rotate("获取最新上架", |_| false, |session| async move {
+            session.client().await?.latest(page).await
+        })
+        .await
+    }
+
+    /// 获取本周推荐列表
+    pub async fn week_best(&self, category: &str) -> Result<Vec<ComicSummary>> {
+        self.rotate("获取每周推荐", |_| false, |session| async move {
+            session.client().await?.week_best(category).await
+        })
+        .await
+    }
 
-                    // 标记会话失效
-                    self.mark_session_invalid().await;
+    /// 获取漫画接口解密后的原始 JSON（仅移动端 API）
+    pub async fn raw_album(&self, aid: i64) -> Result<serde_json::Value> {
+        self.pacer.wait("获取漫画原始数据").await;
+        self.rotate("获取漫画原始数据", |_| false, |session| async move {
+            session.client().await?.raw_album(aid).await
+        })
+        .await
+    }
+
+    /// 获取章节接口解密后的原始 JSON（仅移动端 API）
+    pub async fn raw_chapter(&self, id: i64) -> Result<serde_json::Value> {
+        self.pacer.wait("获取章节原始数据").await;
+        self.rotate("获取章节原始数据", |_| false, |session| async move {
+            session.client().await?.raw_chapter(id).await
+        })
+        .await
+    }
 
-                    // 重新登录
-                    self.relogin().await?;
+    /// 是否以匿名模式运行（未配置账号）
+    pub fn is_anonymous(&self) -> bool {
+        self.pool.primary().credentials().is_none()
+    }
 
-                    // 重试一次
-                    let client = self.get_client().await?;
-                    client.get_chapter(id).await
-                } else {
-                    // 非认证错误，直接返回
-                    Err(e)
+    /// 获取主账号资料（取自最近一次登录返回的数据）
+    pub async fn user_profile(&self) -> Result<UserProfile> {
+        if self.is_anonymous() {
+            return Err(login_required());
+        }
+        let client = self.pool.primary().client().await?;
+        client.user_profile().await
+    }
+
+    /// 用主账号的 JM 币购买漫画或章节，认证失败时重新登录并重试一次
+    pub async fn buy(&self, id: i64) -> Result<()> {
+        if self.is_anonymous() {
+            return Err(login_required());
+        }
+        self.guarded("购买", async {
+            let session = self.pool.primary();
+            let client = session.client().await?;
+            match client.buy(id).await {
+                Ok(()) => Ok(()),
+                Err(e) if is_auth_error(&e) => {
+                    warn!("购买时检测到认证错误，尝试重新登录: {}", e);
+                    drop(client); // 释放读锁
+
+                    session.mark_invalid().await;
+                    session.relogin().await?;
+
+                    let client = session.client().await?;
+                    client.buy(id).await
                 }
+                Err(e) => Err(e),
             }
+        })
+        .await
+    }
+
+    /// 主账号每日签到，认证失败时重新登录并重试一次
+    pub async fn checkin(&self) -> Result<CheckinData> {
+        if self.is_anonymous() {
+            return Err(login_required());
         }
+        self.guarded("签到", async {
+            let session = self.pool.primary();
+            let client = session.client().await?;
+            match client.checkin().await {
+                Ok(result) => Ok(result),
+                Err(e) if is_auth_error(&e) => {
+                    warn!("签到时检测到认证错误，尝试重新登录: {}", e);
+                    drop(client); // 释放读锁
+
+                    session.mark_invalid().await;
+                    session.relogin().await?;
+
+                    let client = session.client().await?;
+                    client.checkin().await
+                }
+                Err(e) => Err(e),
+            }
+        })
+        .await
     }
 
-    /// 执行带自动重试的 API 调用 - 获取 scramble ID
-    pub async fn get_scramble_id(&self, id: i64) -> Result<i64> {
-        // 第一次尝试
-        let client = self.get_client().await?;
-        match client.get_scramble_id(id).await {
-            Ok(result) => Ok(result),
-            Err(e) => {
-                // 检查是否是认证错误
-                if is_auth_error(&e) {
-                    warn!("检测到认证错误，尝试重新登录: {}", e);
+    /// 用主账号给漫画点赞，认证失败时重新登录并重试一次
+    pub async fn like(&self, aid: i64) -> Result<LikeData> {
+        if self.is_anonymous() {
+            return Err(login_required());
+        }
+        self.pacer.wait("点赞漫画").await;
+        self.guarded("点赞漫画", async {
+            let session = self.pool.primary();
+            let client = session.client().await?;
+            match client.like(aid).await {
+                Ok(result) => Ok(result),
+                Err(e) if is_auth_error(&e) => {
+                    warn!("点赞时检测到认证错误，尝试重新登录: {}", e);
                     drop(client); // 释放读锁
 
-                    // 标记会话失效
-                    self.mark_session_invalid().await;
+                    session.mark_invalid().await;
+                    session.relogin().await?;
+
+                    let client = session.client().await?;
+                    client.like(aid).await
+                }
+                Err(e) => Err(e),
+            }
+        })
+        .await
+    }
+
+    /// 用主账号在漫画下发表评论，认证失败时重新登录并重试一次
+    pub async fn comment(&self, aid: i64, text: &str, reply_to: Option<i64>) -> Result<CommentData> {
+        if self.is_anonymous() {
+            return Err(login_required());
+        }
+        self.pacer.wait("发表评论").await;
+        self.guarded("发表评论", async {
+            let session = self.pool.primary();
+            let client = session.client().await?;
+            match client.comment(aid, text, reply_to).await {
+                Ok(result) => Ok(result),
+                Err(e) if is_auth_error(&e) => {
+                    warn!("发表评论时检测到认证错误，尝试重新登录: {}", e);
+                    drop(client); // 释放读锁
 
-                    // 重新登录
-                    self.relogin().await?;
+                    session.mark_invalid().await;
+                    session.relogin().await?;
 
-                    // 重试一次
-                    let client = self.get_client().await?;
-                    client.get_scramble_id(id).await
-                } else {
-                    // 非认证错误，直接返回
-                    Err(e)
+                    let client = session.client().await?;
+                    client.comment(aid, text, reply_to).await
                 }
+                Err(e) => Err(e),
+            }
+        })
+        .await
+    }
+}
+
+/// 执行一次会话保活
+async fn keep_alive<C: JmApi>(session: &Session<C>) {
+    let client = match session.client().await {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("账号 {} 会话保活时重新登录失败: {}", session.name(), e);
+            return;
+        }
+    };
+
+    match client.keep_alive().await {
+        Ok(()) => debug!("账号 {} 会话保活成功", session.name()),
+        Err(e) if is_auth_error(&e) => {
+            warn!("账号 {} 会话保活检测到会话失效，正在重新登录: {}", session.name(), e);
+            drop(client); // 释放读锁
+
+            session.mark_invalid().await;
+            if let Err(e) = session.relogin().await {
+                warn!("账号 {} 会话保活重新登录失败: {}", session.name(), e);
             }
         }
+        Err(e) => warn!("账号 {} 会话保活请求失败: {}", session.name(), e),
     }
+}
 
-    /// 获取图片域名（用于构建图片 URL）
-    pub fn image_domain(&self) -> &str {
-        &self.image_domain
+/// 执行带自动重试的 API 调用 - 获取漫画信息
+///
+/// 如果第一次调用因认证失败，会自动重新登录并重试一次
+async fn get_comic_from_app<C: JmApi>(session: &Session<C>, aid: i64) -> Result<GetComicRespData> {
+    // 第一次尝试
+    let client = session.client().await?;
+    match client.get_comic(aid).await {
+        Ok(result) => Ok(result),
+        Err(e) => {
+            // 检查是否是认证错误
+            if is_auth_error(&e) {
+                warn!("检测到认证错误，尝试重新登录: {}", e);
+                drop(client); // 释放读锁
+
+                // 标记会话失效
+                session.mark_invalid().await;
+
+                // 重新登录
+                session.relogin().await?;
+
+                // 重试一次
+                let client = session.client().await?;
+                client.get_comic(aid).await
+            } else {
+                // 非认证错误，直接返回
+                Err(e)
+            }
+        }
     }
 }
 
+/// 执行带自动重试的 API 调用 - 获取章节信息
+async fn get_chapter_from_app<C: JmApi>(session: &Session<C>, id: i64) -> Result<GetChapterRespData> {
+    // 第一次尝试
+    let client = session.client().await?;
+    match client.get_chapter(id).await {
+        Ok(result) => Ok(result),
+        Err(e) => {
+            // 检查是否是认证错误
+            if is_auth_error(&e) {
+                warn!("检测到认证错误，尝试重新登录: {}", e);
+                drop(client); // 释放读锁
+
+                // 标记会话失效
+                session.mark_invalid().await;
+
+                // 重新登录
+                session.relogin().await?;
+
+                // 重试一次
+                let client = session.client().await?;
+                client.get_chapter(id).await
+            } else {
+                // 非认证错误，直接返回
+                Err(e)
+            }
+        }
+    }
+}
+
+/// 执行带自动重试的 API 调用 - 获取 scramble ID
+async fn get_scramble_id_from_app<C: JmApi>(session: &Session<C>, id: i64) -> Result<i64> {
+    // 第一次尝试
+    let client = session.client().await?;
+    match client.get_scramble_id(id).await {
+        Ok(result) => Ok(result),
+        Err(e) => {
+            // 检查是否是认证错误
+            if is_auth_error(&e) {
+                warn!("检测到认证错误，尝试重新登录: {}", e);
+                drop(client); // 释放读锁
+
+                // 标记会话失效
+                session.mark_invalid().await;
+
+                // 重新登录
+                session.relogin().await?;
+
+                // 重试一次
+                let client = session.client().await?;
+                client.get_scramble_id(id).await
+            } else {
+                // 非认证错误，直接返回
+                Err(e)
+            }
+        }
+    }
+}
+
+/// 判断失败的调用是否值得改用网页端重试（漫画不存在、已下架或需付费时无需重试）
+fn can_fallback(error: &AppError) -> bool {
+    !matches!(
+        error,
+        AppError::NotFound(_) | AppError::AlbumRemoved(_) | AppError::PaymentRequired(_)
+    )
+}
+
+/// 判断错误是否说明账号触发了风控（请求被拦截，或重新登录时被拒绝）
+fn is_risk_control(error: &AppError) -> bool {
+    matches!(error, AppError::Blocked(_) | AppError::InvalidCredentials(_))
+}
+
 /// 判断错误是否为认证错误
 fn is_auth_error(error: &AppError) -> bool {
+    match error {
+        AppError::Unauthorized(_) => return true,
+        // 账号密码错误、需付费、被拦截等重新登录也无法恢复
+        AppError::InvalidCredentials(_)
+        | AppError::PaymentRequired(_)
+        | AppError::AlbumRemoved(_)
+        | AppError::Blocked(_) => return false,
+        _ => {}
+    }
+
     let error_msg = error.to_string().to_lowercase();
 
     // 常见的认证失败标识
@@ -226,3 +758,121 @@ fn is_auth_error(error: &AppError) -> bool {
         || error_msg.contains("code 401")
         || error_msg.contains("code 403")
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use super::*;
+    use crate::mock_client::MockJmClient;
+
+    async fn global(
+        primary: MockJmClient,
+        web: Option<MockJmClient>,
+    ) -> GlobalJmClient<MockJmClient, MockJmClient> {
+        GlobalJmClient::with_clients(primary, web, Some(("user", "pass")))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn returns_primary_data() {
+        let client = global(MockJmClient::new().with_comic(1, "测试漫画", &[]), None).await;
+        let comic = client.get_comic(1).await.unwrap();
+        assert_eq!(comic.name, "测试漫画");
+        assert!(comic.series.is_empty());
+    }
+
+    #[tokio::test]
+    async fn relogins_once_on_auth_error() {
+        let primary = MockJmClient::new()
+            .with_chapter(10, &["00001.webp", "00002.webp"], 300_000)
+            .fail_auth(1);
+        let stats = primary.stats.clone();
+        let client = global(primary, None).await;
+
+        let chapter = client.get_chapter(10).await.unwrap();
+        assert_eq!(chapter.images.len(), 2);
+        // 初始登录 + 重新登录
+        assert_eq!(stats.logins.load(Ordering::SeqCst), 2);
+        assert_eq!(stats.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_web_client() {
+        let primary = MockJmClient::new().fail_always("Get comic failed with code 500: 加密方式变更");
+        let web = MockJmClient::new().with_comic(2, "网页端漫画", &[(3, "第1话")]);
+        let web_stats = web.stats.clone();
+        let client = global(primary, Some(web)).await;
+
+        let comic = client.get_comic(2).await.unwrap();
+        assert_eq!(comic.name, "网页端漫画");
+        assert_eq!(comic.series.len(), 1);
+        assert_eq!(web_stats.logins.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn keep_alive_relogins_on_auth_error() {
+        let primary = MockJmClient::new().fail_auth(1);
+        let stats = primary.stats.clone();
+        let client = global(primary, None).await;
+
+        client.keep_alive().await;
+        assert_eq!(stats.logins.load(Ordering::SeqCst), 2);
+
+        // 会话正常时不会重复登录
+        client.keep_alive().await;
+        assert_eq!(stats.logins.load(Ordering::SeqCst), 2);
+        assert_eq!(stats.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn anonymous_mode_never_logs_in() {
+        let primary = MockJmClient::new().with_comic(5, "匿名漫画", &[]).fail_auth(1);
+        let stats = primary.stats.clone();
+        let client = GlobalJmClient::<MockJmClient, MockJmClient>::with_clients(primary, None, None)
+            .await
+            .unwrap();
+
+        // 需要登录时返回明确的错误，之后的匿名请求不受影响
+        let err = client.get_comic(5).await.unwrap_err();
+        assert!(matches!(err, AppError::LoginRequired(_)));
+        assert_eq!(client.get_comic(5).await.unwrap().name, "匿名漫画");
+        assert!(matches!(client.checkin().await, Err(AppError::LoginRequired(_))));
+        assert!(matches!(client.like(5).await, Err(AppError::LoginRequired(_))));
+        assert_eq!(stats.logins.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn rotates_accounts_and_retries_after_risk_control() {
+        let first = MockJmClient::new().with_comic(6, "轮换漫画", &[]);
+        let blocked = MockJmClient::new().block();
+        let (first_stats, blocked_stats) = (first.stats.clone(), blocked.stats.clone());
+        let client = GlobalJmClient::<MockJmClient, MockJmClient>::with_accounts(
+            vec![(first, ("a", "pass")), (blocked, ("b", "pass"))],
+            None,
+        )
+        .await
+        .unwrap();
+
+        // 第一次由主账号处理，第二次轮到被风控的账号，失败后改用主账号重试
+        assert_eq!(client.get_comic(6).await.unwrap().name, "轮换漫画");
+        assert_eq!(client.get_comic(6).await.unwrap().name, "轮换漫画");
+        assert_eq!(blocked_stats.calls.load(Ordering::SeqCst), 1);
+        // 冷却期间不再选中该账号
+        assert_eq!(client.get_comic(6).await.unwrap().name, "轮换漫画");
+        assert_eq!(blocked_stats.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(first_stats.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn not_found_skips_fallback() {
+        let web = MockJmClient::new().with_comic(4, "不应返回", &[]);
+        let web_stats = web.stats.clone();
+        let client = global(MockJmClient::new(), Some(web)).await;
+
+        let err = client.get_comic(4).await.unwrap_err();
+        assert!(matches!(err, AppError::NotFound(_)));
+        assert_eq!(web_stats.calls.load(Ordering::SeqCst), 0);
+    }
+}
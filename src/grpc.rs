@@ -0,0 +1,434 @@
+// gRPC 接口（`--features grpc`）
+// 接口定义见 proto/jm_downloader.proto，与 REST 的 getInfo、downloadComic、downloadChapter、downloadChapterStream 一一对应，
+// 共用 GlobalJmClient、存储后端、任务队列与下载合并状态；设置 JM_GRPC_ADDR 时随服务一起启动。
+// 失败时按错误类型返回 gRPC 状态码，业务错误码（与 REST 的 code 相同）放在 metadata `jm-code` 中，
+// metadata `accept-language` 与 REST 的 Accept-Language 一样决定错误信息语言
+
+use std::net::SocketAddr;
+use std::pin::Pin;
+
+use jm_downloader_rs::i18n::{self, Lang};
+use jm_downloader_rs::AppError;
+use rocket::futures::{stream, Stream};
+use tonic::metadata::MetadataValue;
+use tonic::transport::Server;
+use tonic::{Code, Request, Response, Status};
+
+use crate::config::LiveConfig;
+use crate::dir_lease::DirLeases;
+use crate::global_client::GlobalJmClient;
+use crate::handlers::{self, InFlightDownloads};
+use crate::jobs::Jobs;
+use crate::models;
+use crate::progress::JobLogLevel;
+use crate::service_mode;
+use crate::storage::Storage;
+use crate::validation::Validate;
+
+pub mod proto {
+    tonic::include_proto!("jm_downloader.v1");
+}
+
+use proto::jm_downloader_server::{JmDownloader, JmDownloaderServer};
+
+/// gRPC 服务，持有与 Rocket 托管状态相同的共享对象
+pub struct GrpcService {
+    pub config: LiveConfig,
+    pub global_client: GlobalJmClient,
+    pub storage: Storage,
+    pub inflight: InFlightDownloads,
+    pub leases: DirLeases,
+    pub jobs: Jobs,
+}
+
+/// 在后台启动 gRPC 服务，监听失败时只记录错误，不影响 REST 接口
+pub fn spawn(addr: SocketAddr, service: GrpcService) {
+    // 请求消息大小与 REST 请求体共用 JM_MAX_BODY_KB 上限
+    let max_message_bytes = service.config.load().max_body_bytes() as usize;
+    tokio::spawn(async move {
+        info!("gRPC 接口监听 {}", addr);
+        if let Err(e) = Server::builder()
+            .add_service(JmDownloaderServer::new(service).max_decoding_message_size(max_message_bytes))
+            .serve(addr)
+            .await
+        {
+            error!("gRPC 服务启动失败或异常退出: {}", e);
+        }
+    });
+}
+
+type ChapterStream = Pin<Box<dyn Stream<Item = Result<proto::ChapterStreamItem, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl JmDownloader for GrpcService {
+    async fn get_comic_info(
+        &self,
+        request: Request<proto::GetComicInfoRequest>,
+    ) -> Result<Response<proto::ComicInfo>, Status> {
+        let lang = request_lang(&request);
+        let request = models::GetComicInfoRequest { id: request.into_inner().id };
+        let result = match service_mode::ensure_available().and_then(|()| request.validate(&self.config.load())) {
+            Ok(()) => handlers::load_comic_info(&self.global_client, request.id).await,
+            Err(e) => Err(e),
+        };
+        result.map(|info| Response::new(info.into())).map_err(|e| status(e, lang))
+    }
+
+    async fn download_comic(
+        &self,
+        request: Request<proto::DownloadComicRequest>,
+    ) -> Result<Response<proto::ComicDownloadData>, Status> {
+        let lang = request_lang(&request);
+        handlers::download_comic_coalesced(
+            &self.config.load(),
+            &self.global_client,
+            &self.storage,
+            &self.inflight,
+            &self.leases,
+            &self.jobs,
+            request.into_inner().into(),
+        )
+        .await
+        .map(|data| Response::new(data.into()))
+        .map_err(|e| status(e, lang))
+    }
+
+    async fn download_chapter(
+        &self,
+        request: Request<proto::DownloadChapterRequest>,
+    ) -> Result<Response<proto::ChapterDownloadData>, Status> {
+        let lang = request_lang(&request);
+        let request: models::DownloadChapterRequest = request.into_inner().into();
+        let outcome = handlers::download_chapters(
+            &self.config.load(),
+            &self.global_client,
+            &self.storage,
+            &self.inflight,
+            &self.leases,
+            &self.jobs,
+            &request,
+        )
+        .await
+        .map_err(|e| status(e, lang))?;
+        let mut data = proto::ChapterDownloadData::from(outcome.data);
+        data.interrupted = outcome.interrupted.map(|e| proto::Interrupted {
+            code: e.code().to_string(),
+            message: i18n::localize_message(e.code(), &e.message(), lang),
+        });
+        Ok(Response::new(data))
+    }
+
+    type DownloadChapterStreamStream = ChapterStream;
+
+    async fn download_chapter_stream(
+        &self,
+        request: Request<proto::DownloadChapterRequest>,
+    ) -> Result<Response<Self::DownloadChapterStreamStream>, Status> {
+        let lang = request_lang(&request);
+        let rx = handlers::spawn_chapter_stream(
+            self.config.load(),
+            self.global_client.clone(),
+            self.storage.clone(),
+            self.inflight.clone(),
+            self.leases.clone(),
+            self.jobs.clone(),
+            request.into_inner().into(),
+        );
+        let items = stream::unfold(rx, move |mut rx| async move {
+            let item = rx.recv().await?;
+            Some((item.map(Into::into).map_err(|e| status(e, lang)), rx))
+        });
+        Ok(Response::new(Box::pin(items)))
+    }
+}
+
+fn request_lang<T>(request: &Request<T>) -> Lang {
+    Lang::negotiate(request.metadata().get("accept-language").and_then(|value| value.to_str().ok()))
+}
+
+/// 错误对应的 gRPC 状态，与 `AppError::status()` 的 HTTP 状态码含义一致
+fn status(error: AppError, lang: Lang) -> Status {
+    let code = match &error {
+        AppError::BadRequest(_) => Code::InvalidArgument,
+        AppError::Unauthorized(_) | AppError::LoginRequired(_) => Code::Unauthenticated,
+        AppError::Forbidden(_) => Code::PermissionDenied,
+        AppError::NotFound(_) | AppError::AlbumRemoved(_) => Code::NotFound,
+        AppError::PaymentRequired(_) => Code::FailedPrecondition,
+        AppError::InvalidCredentials(_)
+        | AppError::Blocked(_)
+        | AppError::ServiceUnavailable { .. }
+        | AppError::ReadOnly(_)
+        | AppError::Maintenance(_) => Code::Unavailable,
+        AppError::QueueFull(_) | AppError::TooLarge(_) => Code::ResourceExhausted,
+        AppError::Timeout(_) => Code::DeadlineExceeded,
+        AppError::Cancelled(_) => Code::Cancelled,
+        AppError::Internal(_) => Code::Internal,
+    };
+    let mut status = Status::new(code, i18n::localize_message(error.code(), &error.message(), lang));
+    status.metadata_mut().insert("jm-code", MetadataValue::from_static(error.code()));
+    status
+}
+
+fn spread_order(order: proto::SpreadOrder) -> models::SpreadOrder {
+    match order {
+        proto::SpreadOrder::Ltr => models::SpreadOrder::Ltr,
+        proto::SpreadOrder::Rtl | proto::SpreadOrder::Unspecified => models::SpreadOrder::default(),
+    }
+}
+
+fn priority(priority: proto::JobPriority) -> models::JobPriority {
+    match priority {
+        proto::JobPriority::Low => models::JobPriority::Low,
+        proto::JobPriority::High => models::JobPriority::High,
+        proto::JobPriority::Normal | proto::JobPriority::Unspecified => models::JobPriority::default(),
+    }
+}
+
+fn log_level(level: proto::JobLogLevel) -> Option<JobLogLevel> {
+    match level {
+        proto::JobLogLevel::Info => Some(JobLogLevel::Info),
+        proto::JobLogLevel::Quiet => Some(JobLogLevel::Quiet),
+        proto::JobLogLevel::Unspecified => None,
+    }
+}
+
+fn pdf_quality(quality: proto::PdfQuality) -> models::PdfQuality {
+    match quality {
+        proto::PdfQuality::Screen => models::PdfQuality::Screen,
+        proto::PdfQuality::Ebook => models::PdfQuality::Ebook,
+        proto::PdfQuality::Prepress => models::PdfQuality::Prepress,
+        proto::PdfQuality::None => models::PdfQuality::None,
+        proto::PdfQuality::Printer | proto::PdfQuality::Unspecified => models::PdfQuality::default(),
+    }
+}
+
+fn page_range(range: proto::PageRange) -> models::PageRange {
+    models::PageRange { from: range.from as usize, to: range.to as usize }
+}
+
+fn pages(pages: &[u64]) -> Vec<usize> {
+    pages.iter().map(|&page| page as usize).collect()
+}
+
+/// 未设置 expire_seconds 时与 REST 接口的默认值一致
+const DEFAULT_EXPIRE_SECONDS: i64 = 600;
+
+impl From<proto::DownloadChapterRequest> for models::DownloadChapterRequest {
+    fn from(request: proto::DownloadChapterRequest) -> Self {
+        Self {
+            comic_id: request.comic_id,
+            expire_seconds: request.expire_seconds.unwrap_or(DEFAULT_EXPIRE_SECONDS),
+            dedupe: request.dedupe,
+            split_spreads: request.split_spreads,
+            spread_order: spread_order(request.spread_order()),
+            eink: request.eink,
+            preserve_filenames: request.preserve_filenames,
+            keep_original: request.keep_original,
+            trim_borders: request.trim_borders,
+            post_process: request.post_process.clone(),
+            library_mode: request.library_mode,
+            rtl: request.rtl,
+            page_range: request.page_range.map(page_range),
+            pages: pages(&request.pages),
+            exclude_pages: pages(&request.exclude_pages),
+            priority: priority(request.priority()),
+            log_level: log_level(request.log_level()),
+            timeout_seconds: request.timeout_seconds,
+            include_timings: request.include_timings,
+            checksums: request.checksums,
+            auto_buy: request.auto_buy,
+            max_coins: request.max_coins,
+            chapter_concurrency: request.chapter_concurrency.map_or(1, |n| n as usize),
+            chapter_ids: request.chapter_ids,
+        }
+    }
+}
+
+impl From<proto::DownloadComicRequest> for models::DownloadComicRequest {
+    fn from(request: proto::DownloadComicRequest) -> Self {
+        Self {
+            comic_id: request.comic_id,
+            merge: request.merge,
+            pdf_quality: pdf_quality(request.pdf_quality()),
+            pdf_dpi: request.pdf_dpi,
+            pdf_max_pages: request.pdf_max_pages.map(|pages| pages as usize),
+            pdf_max_size_mb: request.pdf_max_size_mb,
+            title_page: request.title_page,
+            keep_images: request.keep_images.unwrap_or(true),
+            expire_seconds: request.expire_seconds.unwrap_or(DEFAULT_EXPIRE_SECONDS),
+            dedupe: request.dedupe,
+            split_spreads: request.split_spreads,
+            spread_order: spread_order(request.spread_order()),
+            eink: request.eink,
+            preserve_filenames: request.preserve_filenames,
+            keep_original: request.keep_original,
+            trim_borders: request.trim_borders,
+            post_process: request.post_process.clone(),
+            library_mode: request.library_mode,
+            rtl: request.rtl,
+            page_range: request.page_range.map(page_range),
+            pages: pages(&request.pages),
+            exclude_pages: pages(&request.exclude_pages),
+            priority: priority(request.priority()),
+            log_level: log_level(request.log_level()),
+            timeout_seconds: request.timeout_seconds,
+            include_timings: request.include_timings,
+            checksums: request.checksums,
+            torrent: request.torrent,
+            auto_buy: request.auto_buy,
+            max_coins: request.max_coins,
+            encrypt: request.encrypt,
+            email_to: request.email_to,
+        }
+    }
+}
+
+impl From<models::ComicInfo> for proto::ComicInfo {
+    fn from(info: models::ComicInfo) -> Self {
+        Self {
+            comic_id: info.comic_id,
+            title: info.title,
+            comic_type: info.comic_type,
+            total_views: info.total_views,
+            likes: info.likes,
+            authors: info.authors,
+            description: info.description,
+            total_pages: info.total_pages.map(|pages| pages as u64),
+            tags: info.tags,
+            works: info.works,
+            actors: info.actors,
+            upload_time: info.upload_time,
+            update_time: info.update_time,
+            is_favorite: info.is_favorite,
+        }
+    }
+}
+
+impl From<models::PhaseTimings> for proto::PhaseTimings {
+    fn from(timings: models::PhaseTimings) -> Self {
+        Self {
+            metadata_ms: timings.metadata_ms,
+            download_ms: timings.download_ms,
+            processing_ms: timings.processing_ms,
+            pdf_merge_ms: timings.pdf_merge_ms,
+            compress_ms: timings.compress_ms,
+            total_ms: timings.total_ms,
+        }
+    }
+}
+
+impl From<models::ChecksumData> for proto::ChecksumData {
+    fn from(checksums: models::ChecksumData) -> Self {
+        Self {
+            manifest: checksums.manifest,
+            files: checksums
+                .files
+                .into_iter()
+                .map(|file| proto::FileChecksum { file: file.file, sha256: file.sha256 })
+                .collect(),
+        }
+    }
+}
+
+impl From<models::SingleChapterData> for proto::SingleChapterData {
+    fn from(chapter: models::SingleChapterData) -> Self {
+        Self {
+            chapter_id: chapter.chapter_id,
+            chapter_title: chapter.chapter_title,
+            images: chapter.images,
+            library_path: chapter.library_path,
+            timings: chapter.timings.map(Into::into),
+            checksums: chapter.checksums.map(Into::into),
+        }
+    }
+}
+
+impl From<models::ChapterDownloadData> for proto::ChapterDownloadData {
+    fn from(data: models::ChapterDownloadData) -> Self {
+        Self {
+            comic_id: data.comic_id,
+            comic_title: data.comic_title,
+            job_id: data.job_id,
+            chapters: data.chapters.into_iter().map(Into::into).collect(),
+            retried_pages: data.retried_pages as u64,
+            max_retries_used: data.max_retries_used,
+            timings: data.timings.map(Into::into),
+            coins_spent: data.coins_spent,
+            interrupted: None,
+        }
+    }
+}
+
+impl From<models::ChapterStreamItem> for proto::ChapterStreamItem {
+    fn from(item: models::ChapterStreamItem) -> Self {
+        Self {
+            comic_id: item.comic_id,
+            comic_title: item.comic_title,
+            completed: item.completed as u64,
+            total: item.total as u64,
+            chapter: Some(item.chapter.into()),
+        }
+    }
+}
+
+impl From<models::ComicDownloadData> for proto::ComicDownloadData {
+    fn from(data: models::ComicDownloadData) -> Self {
+        Self {
+            comic_id: data.comic_id,
+            comic_title: data.comic_title,
+            images: data.images.unwrap_or_default(),
+            job_id: data.job_id,
+            pdf_path: data.pdf_path,
+            pdf_paths: data.pdf_paths.unwrap_or_default(),
+            emails_sent: data.emails_sent.map(|sent| sent as u64),
+            library_path: data.library_path,
+            page_count: data.page_count as u64,
+            retried_pages: data.retried_pages as u64,
+            max_retries_used: data.max_retries_used,
+            timings: data.timings.map(Into::into),
+            checksums: data.checksums.map(Into::into),
+            coins_spent: data.coins_spent,
+            torrent: data.torrent.map(Into::into),
+        }
+    }
+}
+
+impl From<models::TorrentData> for proto::TorrentData {
+    fn from(torrent: models::TorrentData) -> Self {
+        Self {
+            torrent_path: torrent.torrent_path,
+            info_hash: torrent.info_hash,
+            piece_length: torrent.piece_length,
+            piece_count: torrent.piece_count as u64,
+            total_bytes: torrent.total_bytes,
+            seeding: torrent.seeding,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_requests_with_rest_defaults() {
+        let request = models::DownloadComicRequest::from(proto::DownloadComicRequest {
+            comic_id: 350234,
+            pdf_quality: proto::PdfQuality::Ebook as i32,
+            page_range: Some(proto::PageRange { from: 1, to: 5 }),
+            ..Default::default()
+        });
+        let rest: models::DownloadComicRequest = serde_json::from_value(serde_json::json!({
+            "comic_id": 350234,
+            "pdf_quality": "ebook",
+            "page_range": { "from": 1, "to": 5 }
+        }))
+        .unwrap();
+        assert_eq!(request, rest);
+
+        let error = status(AppError::QueueFull("队列已满".to_string()), Lang::En);
+        assert_eq!(error.code(), Code::ResourceExhausted);
+        assert_eq!(error.message(), "Download queue is full, please retry later: 队列已满");
+        assert_eq!(error.metadata().get("jm-code").unwrap(), "10009");
+    }
+}
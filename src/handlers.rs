@@ -1,27 +1,69 @@
 use rocket::serde::json::Json;
-use rocket::State;
+use rocket::futures::{stream, StreamExt};
+use rocket::{Shutdown, State};
 use rocket_okapi::openapi;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::Semaphore;
+use tokio::sync::{mpsc, Semaphore};
 use tokio::task::JoinSet;
-use tokio::time::sleep;
-use reqwest_middleware::ClientBuilder;
+use tracing::Instrument;
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
+use bytes::Bytes;
+use lettre::message::Mailbox;
 use reqwest_retry::{RetryTransientMiddleware, policies::ExponentialBackoff, Retryable, RetryableStrategy};
 
-use crate::config::Config;
+use crate::adaptive_concurrency;
+use crate::admin::AdminKey;
+use crate::artifact::{ArtifactKey, ArtifactLocks, VARIANTS_DIR};
+use crate::checksums;
+use crate::torrent;
+use crate::coalesce::Coalescer;
+use crate::comic_ref::{self, ComicRef};
+use crate::config::{Config, LiveConfig};
 use crate::global_client::GlobalJmClient;
-use crate::image_processor::{compress_pdf_with_gs, create_download_dir, download_image, merge_images_to_pdf, process_and_save_image};
-use crate::jm_client::calculate_block_num;
-use crate::models::{GetComicInfoRequest, ComicInfo, DownloadChapterRequest, DownloadComicRequest, ChapterDownloadData, SingleChapterData, ComicDownloadData};
-use jm_downloader_rs::{ApiResult, AppError, R};
-
-/// 自定义重试策略：对网络错误和5xx错误都进行重试
-struct CustomRetryStrategy;
+use crate::dir_lease::{DirLease, DirLeases};
+use crate::image_processor::{download_root, image_dimensions, is_complete_image, is_complete_pdf, is_spread, original_file_name, page_file_names, spread_part_paths, ProcessOptions, chapter_dir_path, compress_pdf_with_gs, download_image, download_image_body, merge_images_to_pdf, ImageBody, pdf_page_count, process_image, split_pdf, GsOptions, PdfPage, ProcessStats};
+use crate::jobs::{Job, JobLimits, Jobs};
+use crate::jm_client::{ImageUrlBuilder, SEARCH_PAGE_SIZE};
+use crate::mailer;
+use crate::memory_budget;
+use crate::library;
+use crate::metadata::{self, ChapterMeta};
+use crate::notifier::{self, JobEvent, JobOutcome};
+use crate::page_selection::PageSelection;
+use crate::page_sidecar::PageSidecar;
+use crate::post_process::PostProcessSteps;
+use crate::preview;
+use crate::purchase::AutoBuy;
+use crate::progress::Progress;
+use crate::scramble::{block_nums, known_scramble_id};
+use crate::service_mode;
+use crate::title_page::{self, TitlePage};
+use crate::models::{GetChapterRespData, GetComicRespData, GetComicInfoRequest, ComicInfo, DownloadChapterRequest, DownloadComicRequest, ChapterDownloadData, ChapterStreamItem, CheckLocalRequest, LocalChapterData, LocalComicData, LocalFileData, SingleChapterData, ChecksumData, ComicDownloadData, PhaseTimings, PreviewData, PreviewRequest, ResolveData, ResolveRequest, UserProfile, CheckinData, CommentData, CommentRequest, LikeData, ComicListData, SearchData, ChapterItem, ChapterListData, SyncChaptersData, SyncChaptersRequest, TorrentData};
+use crate::storage::{PublishFile, Storage, StorageBackend};
+use crate::validation::{Validate, Validator};
+use jm_downloader_rs::{ApiResult, AppError, NdJson, R};
+
+/// 自定义重试策略：对网络错误和5xx错误都进行重试，并计入下载进度的重试次数
+struct CustomRetryStrategy {
+    progress: Arc<Progress>,
+}
 
 impl RetryableStrategy for CustomRetryStrategy {
     fn handle(&self, res: &Result<reqwest::Response, reqwest_middleware::Error>) -> Option<Retryable> {
+        let retryable = self.classify(res);
+        if retryable == Some(Retryable::Transient) {
+            self.progress.record_retry();
+        }
+        retryable
+    }
+}
+
+impl CustomRetryStrategy {
+    fn classify(&self, res: &Result<reqwest::Response, reqwest_middleware::Error>) -> Option<Retryable> {
         match res {
             // 网络错误：重试
             Err(reqwest_middleware::Error::Reqwest(e)) => {
@@ -37,6 +79,7 @@ impl RetryableStrategy for CustomRetryStrategy {
             // HTTP 响应成功
             Ok(success) => {
                 let status = success.status();
+                adaptive_concurrency::record(status);
                 // 5xx 服务器错误：重试
                 if status.is_server_error() {
                     warn!("检测到服务器错误 {}，将重试", status);
@@ -61,14 +104,143 @@ impl RetryableStrategy for CustomRetryStrategy {
 #[openapi]
 #[post("/api/comic/getInfo", data = "<request>")]
 pub async fn get_comic_info(
+    config: &State<LiveConfig>,
     global_client: &State<GlobalJmClient>,
     request: Json<GetComicInfoRequest>,
 ) -> ApiResult<R<ComicInfo>> {
+    service_mode::ensure_available()?;
+    request.validate(&config.load())?;
+    load_comic_info(global_client, request.id).await.map(R::success)
+}
+
+/// # 解析漫画编号
+/// 从用户粘贴的文本中识别漫画：支持 `JM123456`、`禁漫123456`、纯数字、`https://18comic.vip/album/123456/...`
+/// 与章节链接 `/photo/123456`，也可以是包含它们的整条消息。返回规范的漫画 ID、链接与漫画信息。
+#[openapi]
+#[post("/api/comic/resolve", data = "<request>")]
+pub async fn resolve(
+    config: &State<LiveConfig>,
+    global_client: &State<GlobalJmClient>,
+    request: Json<ResolveRequest>,
+) -> ApiResult<R<ResolveData>> {
+    service_mode::ensure_available()?;
+    request.validate(&config.load())?;
+    let reference = comic_ref::parse(&request.input).ok_or_else(|| {
+        AppError::BadRequest("未能从输入中识别出 JM 漫画编号或链接".to_string())
+    })?;
+    let (comic_id, chapter_id) = match reference {
+        ComicRef::Album(id) => (id, None),
+        // 章节漫画的章节需查询所属漫画；普通漫画的章节 ID 即漫画 ID
+        ComicRef::Photo(id) => {
+            let chapter = global_client.get_chapter(id).await?;
+            (chapter.series_id.filter(|&series_id| series_id > 0).unwrap_or(id), Some(id))
+        }
+    };
+    let info = load_comic_info(global_client, comic_id).await?;
+    info!("解析输入 {:?} 为漫画 {}", request.input, comic_id);
+
+    Ok(R::success(ResolveData {
+        comic_id,
+        chapter_id,
+        url: format!("https://{}/album/{}", config.load().web_domain, comic_id),
+        info,
+    }))
+}
+
+/// 预览接口单次最多返回的页数
+const MAX_PREVIEW_PAGES: usize = 10;
+
+/// # 预览漫画
+/// 下载并还原章节的前 N 页（默认 `JM_PREVIEW_PAGES` 页），缩小后以 JPEG data URL 直接放在响应中；
+/// `collage` 为 true 时横向拼成一张预览图。图片不写入磁盘，适合聊天机器人在完整下载前预览。
+#[openapi]
+#[post("/api/comic/preview", data = "<request>")]
+pub async fn preview_comic(
+    config: &State<LiveConfig>,
+    global_client: &State<GlobalJmClient>,
+    request: Json<PreviewRequest>,
+) -> ApiResult<R<PreviewData>> {
+    service_mode::ensure_available()?;
+    let config = config.load();
+    request.validate(&config)?;
+    let comic_id = request.comic_id;
+    let count = request.count.unwrap_or(config.preview_pages);
+    if !(1..=MAX_PREVIEW_PAGES).contains(&count) {
+        return Err(AppError::BadRequest(format!("预览页数必须在 1~{} 之间", MAX_PREVIEW_PAGES)));
+    }
+
+    let comic = global_client.get_comic(comic_id).await?;
+    ensure_comic_purchased(comic_id, &comic)?;
+    let chapter_id = match (request.chapter_id, comic.series.first()) {
+        (Some(chapter_id), _) => chapter_id,
+        (None, Some(series)) => series
+            .id
+            .parse()
+            .map_err(|_| AppError::Internal(format!("章节 ID 无效: {}", series.id)))?,
+        (None, None) => comic_id,
+    };
+    let chapter = global_client.get_chapter(chapter_id).await?;
+    ensure_chapter_readable(chapter_id, &chapter)?;
+    let scramble_id = chapter_scramble_id(&config, global_client, chapter_id).await?;
+
+    let filenames = &chapter.images[..count.min(chapter.images.len())];
+    let block_nums = block_nums(
+        &config.scramble_rules,
+        &config.scramble_overrides,
+        scramble_id,
+        chapter_id,
+        filenames,
+    );
+    let progress = Progress::new(format!("预览章节 {}", chapter_id));
+    let http_client = build_image_http_client(config.max_retries, &progress)?;
+    let image_urls = global_client.image_urls().await;
+    let pages = rocket::futures::future::try_join_all(filenames.iter().zip(block_nums).enumerate().map(
+        |(index, (filename, block_num))| {
+            let urls = image_urls.urls(chapter_id, filename);
+            let (http_client, progress) = (&http_client, &progress);
+            async move {
+                let data = download_image(http_client, &urls, progress).await?;
+                let processed =
+                    process_image(data, block_num, None, true, false, ProcessOptions::default()).await?;
+                match processed.images.into_iter().next() {
+                    Some(PdfPage::Rgb(image)) => Ok((index + 1, image)),
+                    _ => Err(AppError::Internal(format!("处理预览页 {} 失败", index + 1))),
+                }
+            }
+        },
+    ))
+    .await?;
+
+    let collage = request.collage;
+    let (pages, collage) = tokio::task::spawn_blocking(move || -> ApiResult<_> {
+        if collage {
+            let images: Vec<_> = pages.into_iter().map(|(_, image)| image).collect();
+            Ok((Vec::new(), Some(preview::collage(&images)?)))
+        } else {
+            Ok((preview::encode_pages(pages)?, None))
+        }
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("生成预览图任务执行失败: {}", e)))??;
+    info!("已生成漫画 {} 章节 {} 的预览", comic_id, chapter_id);
+
+    Ok(R::success(PreviewData {
+        comic_id,
+        chapter_id,
+        title: comic.name,
+        total_pages: chapter.images.len(),
+        pages,
+        collage,
+    }))
+}
+
+/// 获取漫画信息，普通漫画额外查询页数
+pub(crate) async fn load_comic_info(global_client: &GlobalJmClient, id: i64) -> ApiResult<ComicInfo> {
     // 使用全局客户端获取漫画信息（带自动重试）
-    let comic = match global_client.get_comic(request.id).await {
+    let comic = match global_client.get_comic(id).await {
         Ok(comic) => comic,
         Err(e) => {
-            error!("获取漫画 {} 失败: {}", request.id, e);
+            error!("获取漫画 {} 失败: {}", id, e);
             return Err(e);
         }
     };
@@ -83,10 +255,10 @@ pub async fn get_comic_info(
     // 计算总页数（仅普通漫画返回，避免章节漫画因请求过多被风控）
     let total_pages = if comic.series.is_empty() {
         // 普通漫画：获取漫画本身的图片数量
-        let chapter = match global_client.get_chapter(request.id).await {
+        let chapter = match global_client.get_chapter(id).await {
             Ok(chapter) => chapter,
             Err(e) => {
-                error!("获取章节 {} 失败: {}", request.id, e);
+                error!("获取章节 {} 失败: {}", id, e);
                 return Err(e);
             }
         };
@@ -98,7 +270,7 @@ pub async fn get_comic_info(
 
     // 构建响应数据
     let comic_info = ComicInfo {
-        comic_id: request.id,
+        comic_id: id,
         title: comic.name,
         comic_type,
         total_views: if comic.total_views.is_empty() {
@@ -114,35 +286,625 @@ pub async fn get_comic_info(
         authors: comic.author,
         description: comic.description,
         total_pages,
+        tags: comic.tags,
+        works: comic.works,
+        actors: comic.actors,
+        upload_time: comic.add_time.and_then(format_timestamp),
+        update_time: comic.update_time.and_then(format_timestamp),
+        is_favorite: comic.is_favorite,
     };
 
-    info!("获取漫画 {} 信息成功", request.id);
+    info!("获取漫画 {} 信息成功", id);
+
+    Ok(comic_info)
+}
+
+/// # 查询本地已下载内容
+/// 只检查下载目录，不请求 JM、不下载也不延长过期时间：返回指定章节是否已在磁盘上、页数、占用、最后修改时间与已合并的 PDF，
+/// 供调用方判断是否需要重新下载。注意设置了过期时间的目录可能随后被删除。
+#[openapi]
+#[post("/api/comic/checkLocal", data = "<request>")]
+pub async fn check_local(
+    config: &State<LiveConfig>,
+    request: Json<CheckLocalRequest>,
+) -> ApiResult<R<LocalComicData>> {
+    service_mode::ensure_available()?;
+    request.validate(&config.load())?;
+    let comic_id = request.comic_id;
+    let chapter_ids = request.chapter_ids.clone();
+    let data = tokio::task::spawn_blocking(move || {
+        let comic_dir = download_root().join(comic_id.to_string());
+        let chapter_ids = if chapter_ids.is_empty() {
+            let mut ids: Vec<i64> = std::fs::read_dir(&comic_dir)
+                .map(|entries| {
+                    entries
+                        .flatten()
+                        .filter(|entry| entry.path().is_dir())
+                        .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+                        .collect()
+                })
+                .unwrap_or_default();
+            ids.sort_unstable();
+            ids
+        } else {
+            chapter_ids
+        };
+        let chapters: Vec<LocalChapterData> = chapter_ids
+            .into_iter()
+            .map(|chapter_id| local_chapter(chapter_id, &chapter_dir_path(comic_id, chapter_id)))
+            .collect();
+        LocalComicData {
+            comic_id,
+            exists: chapters.iter().any(|chapter| chapter.exists),
+            chapters,
+        }
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("检查本地文件任务执行失败: {}", e)))?;
+
+    Ok(R::success(data))
+}
 
-    Ok(R::success(comic_info))
+/// 统计章节目录及其各变体目录中的页面与 PDF
+fn local_chapter(chapter_id: i64, dir: &Path) -> LocalChapterData {
+    let mut chapter = LocalChapterData {
+        chapter_id,
+        exists: false,
+        page_count: 0,
+        bytes: 0,
+        modified: None,
+        pdfs: Vec::new(),
+        variants: Vec::new(),
+    };
+    if !dir.is_dir() {
+        return chapter;
+    }
+    chapter.exists = true;
+    let mut pages = std::collections::HashSet::new();
+    scan_local_dir(&mut chapter, &mut pages, dir, "");
+    if let Ok(entries) = std::fs::read_dir(dir.join(VARIANTS_DIR)) {
+        for entry in entries.flatten().filter(|entry| entry.path().is_dir()) {
+            let variant = entry.file_name().to_string_lossy().to_string();
+            scan_local_dir(&mut chapter, &mut pages, &entry.path(), &format!("{}/{}/", VARIANTS_DIR, variant));
+            chapter.variants.push(variant);
+        }
+    }
+    chapter.page_count = pages.len();
+    chapter.pdfs.sort_by(|a, b| a.name.cmp(&b.name));
+    chapter.variants.sort();
+    chapter
+}
+
+/// 累计一个产物目录中的文件，PDF 名称加上 `prefix`
+fn scan_local_dir(
+    chapter: &mut LocalChapterData,
+    pages: &mut std::collections::HashSet<String>,
+    dir: &Path,
+    prefix: &str,
+) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if !meta.is_file() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        let modified = meta
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|elapsed| elapsed.as_secs() as i64)
+            .unwrap_or_default();
+        chapter.bytes += meta.len();
+        chapter.modified = chapter.modified.max(Some(modified));
+        if name.ends_with(".pdf") {
+            chapter.pdfs.push(LocalFileData { name: format!("{}{}", prefix, name), bytes: meta.len(), modified });
+        } else if let Some(page) = page_key(&name) {
+            pages.insert(page.to_string());
+        }
+    }
+}
+
+/// 页面文件对应的页：`0005.png`、`0005-1R.png` 均为 `0005`（保留原始文件名时为原文件名）
+fn page_key(name: &str) -> Option<&str> {
+    let (stem, ext) = name.rsplit_once('.')?;
+    if !matches!(ext, "png" | "gif" | "jpg" | "jpeg" | "webp") {
+        return None;
+    }
+    let page = ["-1R", "-2L", "-1L", "-2R"]
+        .iter()
+        .find_map(|side| stem.strip_suffix(side))
+        .unwrap_or(stem);
+    (!page.is_empty()).then_some(page)
+}
+
+/// # 获取章节列表
+/// 返回漫画的全部章节（ID、名称、序号），可直接用于 `downloadChapter`；普通漫画返回以漫画 ID 为章节 ID 的单个章节。
+#[openapi]
+#[get("/api/comic/<id>/chapters")]
+pub async fn get_comic_chapters(
+    global_client: &State<GlobalJmClient>,
+    id: i64,
+) -> ApiResult<R<ChapterListData>> {
+    service_mode::ensure_available()?;
+    let mut v = Validator::default();
+    v.positive_id("id", id);
+    v.finish()?;
+    let comic = global_client.get_comic(id).await.map_err(|e| {
+        error!("获取漫画 {} 失败: {}", id, e);
+        e
+    })?;
+
+    Ok(R::success(ChapterListData {
+        comic_id: id,
+        chapters: chapter_items(id, &comic),
+        title: comic.name,
+    }))
+}
+
+/// 漫画的章节列表，普通漫画为章节 ID 等于漫画 ID 的单个章节
+fn chapter_items(id: i64, comic: &GetComicRespData) -> Vec<ChapterItem> {
+    if comic.series.is_empty() {
+        return vec![ChapterItem {
+            chapter_id: id,
+            name: "第1话".to_string(),
+            sort: 1,
+        }];
+    }
+    comic
+        .series
+        .iter()
+        .enumerate()
+        .filter_map(|(index, series)| {
+            let chapter_id = series.id.parse::<i64>().ok()?;
+            let name = if series.name.trim().is_empty() {
+                format!("第{}话", index + 1)
+            } else {
+                series.name.clone()
+            };
+            Some(ChapterItem {
+                chapter_id,
+                name,
+                sort: index + 1,
+            })
+        })
+        .collect()
+}
+
+/// 新章节在章节列表中的起始下标：位于 `last_chapter_id` 之后（该章节已不在列表中时为第一个 ID 更大的章节），
+/// 或序号大于 `last_sort`；都未设置时为 0
+fn first_new_chapter(chapters: &[ChapterItem], last_chapter_id: Option<i64>, last_sort: Option<usize>) -> usize {
+    match (last_chapter_id, last_sort) {
+        (Some(id), _) => match chapters.iter().position(|chapter| chapter.chapter_id == id) {
+            Some(index) => index + 1,
+            None => chapters
+                .iter()
+                .position(|chapter| chapter.chapter_id > id)
+                .unwrap_or(chapters.len()),
+        },
+        (None, Some(sort)) => chapters
+            .iter()
+            .position(|chapter| chapter.sort > sort)
+            .unwrap_or(chapters.len()),
+        (None, None) => 0,
+    }
+}
+
+/// # 增量同步新章节
+/// 按调用方上次同步到的章节（`last_chapter_id` 或 `last_sort`）从漫画的章节列表中找出新章节，只下载这些章节，
+/// 并返回新的同步位置 `high_water`；`remaining` 大于 0 时以新的位置继续调用。下载选项与下载章节漫画相同。
+#[openapi]
+#[allow(clippy::too_many_arguments)]
+#[post("/api/comic/syncNewChapters", data = "<request>")]
+pub async fn sync_new_chapters(
+    config: &State<LiveConfig>,
+    global_client: &State<GlobalJmClient>,
+    storage: &State<Storage>,
+    inflight: &State<InFlightDownloads>,
+    leases: &State<DirLeases>,
+    jobs: &State<Jobs>,
+    shutdown: Shutdown,
+    request: Json<SyncChaptersRequest>,
+) -> ApiResult<R<SyncChaptersData>> {
+    service_mode::ensure_downloads_allowed()?;
+    let config = config.load();
+    request.validate(&config)?;
+    let comic_id = request.comic_id;
+    let comic = global_client.get_comic(comic_id).await.map_err(|e| {
+        error!("获取漫画 {} 失败: {}", comic_id, e);
+        e
+    })?;
+    let chapters = chapter_items(comic_id, &comic);
+    let start = first_new_chapter(&chapters, request.last_chapter_id, request.last_sort);
+    let pending = &chapters[start..];
+    let batch = &pending[..pending.len().min(request.max_chapters.unwrap_or(config.max_chapters_per_request))];
+    let mut data = SyncChaptersData {
+        comic_id,
+        comic_title: comic.name,
+        total_chapters: chapters.len(),
+        new_chapters: batch.to_vec(),
+        remaining: pending.len(),
+        high_water: start.checked_sub(1).map(|index| chapters[index].clone()),
+        download: None,
+    };
+    if batch.is_empty() {
+        info!("漫画 {} 没有新章节", comic_id);
+        return Ok(R::success(data));
+    }
+
+    info!("漫画 {} 有 {} 个新章节，本次同步 {} 个", comic_id, pending.len(), batch.len());
+    let download_request = request.download_request(batch.iter().map(|chapter| chapter.chapter_id).collect());
+    let download = download_chapters(&config, global_client, storage, inflight, leases, jobs, &download_request);
+    let outcome = unless_shutdown(shutdown, download).await?;
+
+    // 同步位置只推进到连续完成的章节，中断时从第一个未完成的章节继续
+    let synced = batch
+        .iter()
+        .take_while(|chapter| outcome.data.chapters.iter().any(|done| done.chapter_id == chapter.chapter_id))
+        .count();
+    data.remaining = pending.len() - synced;
+    data.high_water = (start + synced).checked_sub(1).map(|index| chapters[index].clone());
+    data.download = Some(outcome.data);
+    Ok(match outcome.interrupted {
+        Some(e) => R::partial(e, data),
+        None => R::success(data),
+    })
+}
+
+/// Unix 秒格式化为东八区 RFC 3339 时间
+fn format_timestamp(ts: i64) -> Option<String> {
+    chrono::DateTime::from_timestamp(ts, 0)
+        .map(|time| time.with_timezone(&chrono_tz::Asia::Shanghai).to_rfc3339())
+}
+
+/// # 最新上架
+/// 获取 JM 最新上架的漫画列表，`page` 从 1 开始，默认 1。
+#[openapi]
+#[get("/api/comic/latest?<page>")]
+pub async fn get_latest(
+    global_client: &State<GlobalJmClient>,
+    page: Option<u32>,
+) -> ApiResult<R<ComicListData>> {
+    service_mode::ensure_available()?;
+    let page = page.unwrap_or(1);
+    if page == 0 {
+        return Err(AppError::BadRequest("页码从 1 开始".to_string()));
+    }
+    let comics = global_client.latest(page).await.map_err(|e| {
+        error!("获取最新上架第 {} 页失败: {}", page, e);
+        e
+    })?;
+
+    Ok(R::success(ComicListData { page, comics }))
+}
+
+/// # 搜索漫画
+/// 按关键词搜索 JM 漫画，`page` 从 1 开始，默认 1，按最新排序；结果已去重，
+/// 查询为车号时直接返回该漫画并附带 `redirect_aid`。
+#[openapi]
+#[get("/api/comic/search?<q>&<page>")]
+pub async fn search_comics(
+    global_client: &State<GlobalJmClient>,
+    q: String,
+    page: Option<u32>,
+) -> ApiResult<R<SearchData>> {
+    service_mode::ensure_available()?;
+    let query = q.trim();
+    if query.is_empty() {
+        return Err(AppError::BadRequest("搜索关键词不能为空".to_string()));
+    }
+    let page = page.unwrap_or(1);
+    if page == 0 {
+        return Err(AppError::BadRequest("页码从 1 开始".to_string()));
+    }
+    let result = global_client.search(query, page).await.map_err(|e| {
+        error!("搜索 {} 第 {} 页失败: {}", query, page, e);
+        e
+    })?;
+
+    Ok(R::success(SearchData {
+        query: query.to_string(),
+        total: result.total,
+        page,
+        page_size: SEARCH_PAGE_SIZE,
+        has_next: !result.comics.is_empty() && (page as usize) * SEARCH_PAGE_SIZE < result.total,
+        redirect_aid: result.redirect_aid,
+        comics: result.comics,
+    }))
+}
+
+/// # 每周推荐
+/// 获取 JM 最近一期的每周推荐，`type` 可选 manga（默认）、hanman、another。
+#[openapi]
+#[get("/api/comic/weekBest?<type>")]
+pub async fn get_week_best(
+    global_client: &State<GlobalJmClient>,
+    r#type: Option<String>,
+) -> ApiResult<R<ComicListData>> {
+    service_mode::ensure_available()?;
+    let category = r#type.unwrap_or_else(|| "manga".to_string());
+    if !matches!(category.as_str(), "manga" | "hanman" | "another") {
+        return Err(AppError::BadRequest("type 只能为 manga、hanman 或 another".to_string()));
+    }
+    let comics = global_client.week_best(&category).await.map_err(|e| {
+        error!("获取每周推荐失败: {}", e);
+        e
+    })?;
+
+    Ok(R::success(ComicListData { page: 1, comics }))
+}
+
+/// # 获取账号资料
+/// 返回当前登录账号的 JM 币、等级、经验与头像，可用于及时发现账号异常。读取运营者的账号，需要 `X-Admin-Key`。
+#[openapi]
+#[get("/api/user/profile")]
+pub async fn get_user_profile(
+    config: &State<LiveConfig>,
+    global_client: &State<GlobalJmClient>,
+    admin: AdminKey,
+) -> ApiResult<R<UserProfile>> {
+    service_mode::ensure_available()?;
+    admin.verify(&config.load())?;
+    let profile = global_client.user_profile().await.map_err(|e| {
+        error!("获取账号资料失败: {}", e);
+        e
+    })?;
+
+    Ok(R::success(profile))
+}
+
+/// # 每日签到
+/// 使用当前登录账号执行 JM 每日签到。以运营者的账号操作，需要 `X-Admin-Key`。
+#[openapi]
+#[post("/api/user/checkin")]
+pub async fn user_checkin(
+    config: &State<LiveConfig>,
+    global_client: &State<GlobalJmClient>,
+    admin: AdminKey,
+) -> ApiResult<R<CheckinData>> {
+    service_mode::ensure_available()?;
+    admin.verify(&config.load())?;
+    let result = global_client.checkin().await.map_err(|e| {
+        error!("每日签到失败: {}", e);
+        e
+    })?;
+
+    info!("每日签到完成: {}", result.message);
+
+    Ok(R::success(result))
+}
+
+/// # 点赞漫画
+/// 使用当前登录账号给漫画点赞。以运营者的账号发言，需要 `X-Admin-Key`。
+#[openapi]
+#[post("/api/comic/<id>/like")]
+pub async fn like_comic(
+    config: &State<LiveConfig>,
+    global_client: &State<GlobalJmClient>,
+    admin: AdminKey,
+    id: i64,
+) -> ApiResult<R<LikeData>> {
+    service_mode::ensure_available()?;
+    admin.verify(&config.load())?;
+    let mut v = Validator::default();
+    v.positive_id("id", id);
+    v.finish()?;
+    let result = global_client.like(id).await.map_err(|e| {
+        error!("点赞漫画 {} 失败: {}", id, e);
+        e
+    })?;
+
+    info!("点赞漫画 {} 完成: {}", id, result.message);
+
+    Ok(R::success(result))
+}
+
+/// # 发表评论
+/// 使用当前登录账号在漫画下发表评论，`reply_to` 为要回复的评论 ID。以运营者的账号发言，需要 `X-Admin-Key`。
+#[openapi]
+#[post("/api/comic/<id>/comment", data = "<request>")]
+pub async fn comment_comic(
+    config: &State<LiveConfig>,
+    global_client: &State<GlobalJmClient>,
+    admin: AdminKey,
+    id: i64,
+    request: Json<CommentRequest>,
+) -> ApiResult<R<CommentData>> {
+    service_mode::ensure_available()?;
+    admin.verify(&config.load())?;
+    let mut v = Validator::default();
+    v.positive_id("id", id);
+    request.check(&config.load(), &mut v);
+    v.finish()?;
+    let result = global_client
+        .comment(id, request.comment.trim(), request.reply_to)
+        .await
+        .map_err(|e| {
+            error!("评论漫画 {} 失败: {}", id, e);
+            e
+        })?;
+
+    info!("评论漫画 {} 完成: {}", id, result.message);
+
+    Ok(R::success(result))
 }
 
 /// # 下载章节漫画
 /// 批量下载指定章节，返回每章图片路径列表，支持过期自动清理。
 #[openapi]
+#[allow(clippy::too_many_arguments)]
 #[post("/api/comic/downloadChapter", data = "<request>")]
 pub async fn download_chapter(
-    config: &State<Config>,
+    config: &State<LiveConfig>,
     global_client: &State<GlobalJmClient>,
+    storage: &State<Storage>,
+    inflight: &State<InFlightDownloads>,
+    leases: &State<DirLeases>,
+    jobs: &State<Jobs>,
+    shutdown: Shutdown,
     request: Json<DownloadChapterRequest>,
 ) -> ApiResult<R<ChapterDownloadData>> {
+    let config = config.load();
+    let download = download_chapters(&config, global_client, storage, inflight, leases, jobs, &request);
+    let outcome = unless_shutdown(shutdown, download).await?;
+    Ok(match outcome.interrupted {
+        Some(e) => R::partial(e, outcome.data),
+        None => R::success(outcome.data),
+    })
+}
+
+/// 下载章节并发送通知，downloadChapter 与 gRPC 的 DownloadChapter 共用
+pub(crate) async fn download_chapters(
+    config: &Config,
+    global_client: &GlobalJmClient,
+    storage: &Storage,
+    inflight: &InFlightDownloads,
+    leases: &DirLeases,
+    jobs: &Jobs,
+    request: &DownloadChapterRequest,
+) -> ApiResult<ChapterOutcome> {
+    let result = run_download_chapter(config, global_client, storage, inflight, leases, jobs, request, &|_, _| {}).await;
+    notify_chapter_result(config, "downloadChapter", request.comic_id, &result);
+    result
+}
+
+/// # 流式下载章节漫画
+/// 与下载章节漫画参数相同，以 NDJSON（application/x-ndjson）逐行返回：每完成一个章节输出一行 `R<ChapterStreamItem>`，
+/// 出错时最后一行为失败的 `R`，`completed` 等于 `total` 表示全部完成。适合章节较多时边下载边处理。
+#[openapi]
+#[post("/api/comic/downloadChapterStream", data = "<request>")]
+pub async fn download_chapter_stream(
+    config: &State<LiveConfig>,
+    global_client: &State<GlobalJmClient>,
+    storage: &State<Storage>,
+    inflight: &State<InFlightDownloads>,
+    leases: &State<DirLeases>,
+    jobs: &State<Jobs>,
+    request: Json<DownloadChapterRequest>,
+) -> NdJson<ChapterStreamItem> {
+    let rx = spawn_chapter_stream(
+        config.load(),
+        global_client.inner().clone(),
+        storage.inner().clone(),
+        inflight.inner().clone(),
+        leases.inner().clone(),
+        jobs.inner().clone(),
+        request.into_inner(),
+    );
+    NdJson::new(stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|item| (item.map_or_else(R::from, R::success), rx))
+    }))
+}
+
+/// 在后台下载章节，每完成一个章节发送一条结果，出错时最后一条为错误；
+/// downloadChapterStream 与 gRPC 的 DownloadChapterStream 共用
+pub(crate) fn spawn_chapter_stream(
+    config: Arc<Config>,
+    global_client: GlobalJmClient,
+    storage: Storage,
+    inflight: InFlightDownloads,
+    leases: DirLeases,
+    jobs: Jobs,
+    request: DownloadChapterRequest,
+) -> mpsc::UnboundedReceiver<ApiResult<ChapterStreamItem>> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let total = request.chapter_ids.len();
+        let on_chapter = |comic_title: &str, chapters: &[SingleChapterData]| {
+            let Some(chapter) = chapters.last() else {
+                return;
+            };
+            let _ = tx.send(Ok(ChapterStreamItem {
+                comic_id: request.comic_id,
+                comic_title: comic_title.to_string(),
+                completed: chapters.len(),
+                total,
+                chapter: chapter.clone(),
+            }));
+        };
+        let download = run_download_chapter(
+            &config,
+            &global_client,
+            &storage,
+            &inflight,
+            &leases,
+            &jobs,
+            &request,
+            &on_chapter,
+        );
+        // 客户端断开后接收端随响应流释放，不再为它下载
+        let Some(result) = until_cancelled(tx.closed(), download).await else {
+            info!("客户端已断开，取消流式章节下载 comic_id={}", request.comic_id);
+            return;
+        };
+        notify_chapter_result(&config, "downloadChapterStream", request.comic_id, &result);
+        match result {
+            Ok(ChapterOutcome { interrupted: Some(e), .. }) | Err(e) => {
+                let _ = tx.send(Err(e));
+            }
+            Ok(_) => {}
+        }
+    });
+    rx
+}
+
+/// 章节下载完成或失败后发送通知（参数错误与排队已满除外）
+fn notify_chapter_result(
+    config: &Config,
+    kind: &'static str,
+    comic_id: i64,
+    result: &ApiResult<ChapterOutcome>,
+) {
+    if !notifier::enabled(config) || is_rejected(result) {
+        return;
+    }
+    let outcome = match result {
+        Ok(ChapterOutcome { interrupted: Some(e), .. }) => JobOutcome::Failed { error: e.to_string() },
+        Ok(ChapterOutcome { data, .. }) => JobOutcome::Completed {
+            title: data.comic_title.clone(),
+            pages: data.chapters.iter().map(|chapter| chapter.images.len()).sum(),
+            link: None,
+            pdf: None,
+        },
+        Err(e) => JobOutcome::Failed { error: e.to_string() },
+    };
+    notifier::notify(config, JobEvent { kind, comic_id, outcome });
+}
+
+/// 下载请求中的各个章节，每完成一个章节调用一次 `on_chapter(漫画标题, 已完成的章节)`
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(name = "download_chapter", skip_all, fields(comic_id = request.comic_id))]
+async fn run_download_chapter(
+    config: &Config,
+    global_client: &GlobalJmClient,
+    storage: &Storage,
+    inflight: &InFlightDownloads,
+    leases: &DirLeases,
+    jobs: &Jobs,
+    request: &DownloadChapterRequest,
+    on_chapter: &(dyn Fn(&str, &[SingleChapterData]) + Sync),
+) -> ApiResult<ChapterOutcome> {
     let comic_id = request.comic_id;
     let chapter_ids = &request.chapter_ids;
     let expire_seconds = request.expire_seconds;
 
-    // 验证章节ID列表不为空
-    if chapter_ids.is_empty() {
-        return Err(AppError::BadRequest("章节ID列表不能为空".to_string()));
-    }
-    if expire_seconds < -1 {
-        return Err(AppError::BadRequest("过期时间必须为-1或非负数".to_string()));
+    service_mode::ensure_downloads_allowed()?;
+    request.validate(config)?;
+    let selection = PageSelection::new(request.page_range, &request.pages, &request.exclude_pages)?;
+    let library_mode = request.library_mode || config.library_mode;
+    if library_mode {
+        library::library_root(config)?;
+        ensure_all_pages(&selection)?;
     }
+    let deadline = Deadline::new(config, request.timeout_seconds)?;
+    let auto_buy = AutoBuy::new(request.auto_buy, request.max_coins, config.auto_buy_max_coins)?;
 
     info!("开始下载章节漫画: comic_id={}, chapter_ids={:?}", comic_id, chapter_ids);
+    let total_start = Instant::now();
 
     // 使用全局客户端获取漫画信息（带自动重试）
     let comic = match global_client.get_comic(comic_id).await {
@@ -152,207 +914,611 @@ pub async fn download_chapter(
             return Err(e);
         }
     };
+    let comic = auto_buy.comic(global_client, comic_id, comic).await?;
+    ensure_comic_purchased(comic_id, &comic)?;
+    let mut timings = PhaseTimings { metadata_ms: elapsed_ms(total_start), ..PhaseTimings::default() };
+
+    // 登记为任务，汇总所有章节的下载进度并定时输出一行日志
+    let job = before_deadline(
+        deadline,
+        jobs.start("downloadChapter", comic_id, request.priority, JobLimits::from(config)),
+    )
+    .await?;
+    job.job().progress().set_log_level(request.log_level.unwrap_or(config.job_log_level));
+    let detail = job.job().progress().detail_level();
+    let reporter = job.job().progress().start_reporter(Duration::from_secs(config.progress_log_seconds));
 
     // 创建用于下载图片的HTTP客户端，带重试机制
-    let reqwest_client = match reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(60))
-        .build()
-    {
-        Ok(client) => client,
-        Err(e) => {
-            return Err(AppError::Internal(format!("创建HTTP客户端失败: {}", e)));
-        }
-    };
+    let http_client = build_image_http_client(config.max_retries, job.job().progress())?;
 
-    // 配置指数退避重试策略：最多重试3次
-    let retry_policy = ExponentialBackoff::builder()
-        .build_with_max_retries(3);
+    // 创建信号量控制并发数
+    let semaphore = Arc::new(Semaphore::new(config.img_concurrency));
+
+    let output = PageOutput {
+        dedupe: request.dedupe,
+        sidecar: config.page_sidecar,
+        process: ProcessOptions {
+            split_spreads: request.split_spreads.then_some(request.spread_order),
+            eink: request.eink.then_some(config.eink_long_edge),
+            preserve_filenames: request.preserve_filenames,
+            keep_original: request.keep_original,
+            trim_borders: request.trim_borders,
+            upscale: config.upscale_cmd.is_some(),
+            // 已在 validate 中校验
+            post_process: PostProcessSteps::parse(&request.post_process).unwrap_or_default(),
+        },
+        ..PageOutput::DISK
+    };
 
-    let http_client = ClientBuilder::new(reqwest_client)
-        .with(RetryTransientMiddleware::new_with_policy_and_strategy(
-            retry_policy,
-            CustomRetryStrategy,
-        ))
-        .build();
+    // 单个章节的下载、发布与打包；多个章节并发时共用同一个图片信号量，总并发仍受 img_concurrency 限制
+    let download_one = {
+        let (comic, selection, auto_buy, http_client, semaphore, job) =
+            (&comic, &selection, &auto_buy, &http_client, &semaphore, &job);
+        move |chapter_id: i64| async move {
+            log::log!(detail, "处理章节: {}", chapter_id);
+            // 处理期间持有目录租约，防止之前请求安排的过期删除清理掉正在复用的目录
+            let _lease = leases.acquire(chapter_dir_path(comic_id, chapter_id));
+
+            // 查找指定的章节
+            let chapter_name = if comic.series.is_empty() {
+                // 普通漫画没有章节列表，检查 chapter_id 是否等于 comic_id
+                if chapter_id != comic_id {
+                    return Err(AppError::NotFound(format!(
+                        "章节 {} 不存在，该漫画为普通漫画，章节ID应等于漫画ID {}",
+                        chapter_id, comic_id
+                    )));
+                }
+                "第1话".to_string()
+            } else {
+                // 章节漫画，查找章节名称
+                comic
+                    .series
+                    .iter()
+                    .find(|s| s.id.parse::<i64>().ok() == Some(chapter_id))
+                    .map(|s| s.name.clone())
+                    .ok_or_else(|| {
+                        AppError::NotFound(format!("章节 {} 不存在", chapter_id))
+                    })?
+            };
 
-    info!("已配置图片下载重试策略：最多重试3次，使用指数退避");
+            // 相同章节正在被其他请求下载时，等待并共享其结果，避免重复下载和写文件冲突
+            let chapter_pages = inflight
+                .chapters
+                .run((comic_id, chapter_id, output.process, selection.clone()), || {
+                    download_chapter_pages(
+                        global_client,
+                        &inflight.artifacts,
+                        auto_buy,
+                        http_client,
+                        semaphore,
+                        job.job(),
+                        config,
+                        comic_id,
+                        chapter_id,
+                        selection,
+                        output,
+                    )
+                });
+            let chapter_pages = before_deadline(deadline, chapter_pages).await?;
+            let artifact = &chapter_pages.artifact;
+            let files = chapter_pages
+                .relative_paths
+                .iter()
+                .map(|relative_path| PublishFile {
+                    relative_path: relative_path.clone(),
+                    name: format!("{} - {} - {}", comic.name, chapter_name, file_name(relative_path)),
+                    folder: artifact.remote_folder(vec![comic.name.clone(), chapter_name.clone()]),
+                    file_name: file_name(relative_path).to_string(),
+                })
+                .collect();
+            let images = storage.publish_all(files).await?;
+
+            log::log!(detail, "完成下载章节 {} 的 {} 张图片", chapter_id, images.len());
+
+            // 书库模式下在过期删除之前打包为 CBZ
+            let library_path = if library_mode {
+                let meta = ChapterMeta {
+                    chapter_id,
+                    title: &chapter_name,
+                    page_count: chapter_pages.relative_paths.len(),
+                };
+                Some(
+                    library::export_chapter(config, comic_id, comic, &meta, &chapter_pages.relative_paths, request.rtl)
+                        .await?,
+                )
+            } else {
+                None
+            };
 
-    let img_concurrency = config.img_concurrency;
-    let image_domain = global_client.image_domain().to_string();
+            let checksums = if request.checksums {
+                let files = chapter_pages.relative_paths.iter().map(|path| file_name(path).to_string()).collect();
+                let folder = artifact.remote_folder(vec![comic.name.clone(), chapter_name.clone()]);
+                let name = format!("{} - {}", comic.name, chapter_name);
+                let _guard = inflight.artifacts.lock(artifact).await;
+                Some(publish_checksums(storage, artifact, files, folder, &name).await?)
+            } else {
+                None
+            };
 
-    // 创建信号量控制并发数
-    let semaphore = Arc::new(Semaphore::new(img_concurrency));
+            leases.schedule_delete(artifact.chapter_dir(), expire_seconds);
+            let chapter = SingleChapterData {
+                chapter_id,
+                chapter_title: chapter_name,
+                images,
+                library_path,
+                timings: request.include_timings.then_some(chapter_pages.timings),
+                checksums,
+            };
+            Ok((chapter, chapter_pages.timings))
+        }
+    };
 
     // 存储所有章节的下载结果
     let mut all_chapters_data = Vec::new();
+    let mut interrupted = None;
 
-    // 遍历每个章节ID进行下载
-    for &chapter_id in chapter_ids {
-        info!("处理章节: {}", chapter_id);
-
-        // 查找指定的章节
-        let chapter_name = if comic.series.is_empty() {
-            // 普通漫画没有章节列表，检查 chapter_id 是否等于 comic_id
-            if chapter_id != comic_id {
-                return Err(AppError::NotFound(format!(
-                    "章节 {} 不存在，该漫画为普通漫画，章节ID应等于漫画ID {}",
-                    chapter_id, comic_id
-                )));
-            }
-            "第1话".to_string()
-        } else {
-            // 章节漫画，查找章节名称
-            comic
-                .series
-                .iter()
-                .find(|s| s.id.parse::<i64>().ok() == Some(chapter_id))
-                .map(|s| s.name.clone())
-                .ok_or_else(|| {
-                    AppError::NotFound(format!("章节 {} 不存在", chapter_id))
-                })?
-        };
+    // 按 chapter_concurrency 并发下载章节，结果仍按请求中的顺序逐个收集
+    {
+        let mut chapters = stream::iter(chapter_ids.iter().copied())
+            .map(download_one)
+            .buffered(request.chapter_concurrency);
+        while let Some(result) = chapters.next().await {
+            // 到达截止时间或任务被取消时放弃未完成的章节，返回已完成的章节
+            let (chapter, chapter_timings) = match result {
+                Err(e @ (AppError::Timeout(_) | AppError::Cancelled(_))) => {
+                    warn!("章节下载未完成（{}），已完成 {} 个章节", e, all_chapters_data.len());
+                    interrupted = Some(e);
+                    break;
+                }
+                result => result?,
+            };
+            timings.add(&chapter_timings);
+            all_chapters_data.push(chapter);
+            on_chapter(&comic.name, &all_chapters_data);
+        }
+    }
 
-        // 使用全局客户端获取章节详情和 scramble ID
-        let chapter = match global_client.get_chapter(chapter_id).await {
-            Ok(chapter) => chapter,
-            Err(e) => {
-                error!("获取章节 {} 失败: {}", chapter_id, e);
-                return Err(e);
-            }
-        };
+    reporter.finish();
+
+    // 只下载部分页时页数不代表整个章节，不写入元数据
+    if selection.is_all() {
+        let chapter_meta: Vec<ChapterMeta> = all_chapters_data
+            .iter()
+            .map(|chapter| ChapterMeta {
+                chapter_id: chapter.chapter_id,
+                title: &chapter.chapter_title,
+                page_count: chapter.images.len(),
+            })
+            .collect();
+        metadata::write(config, comic_id, &comic, &chapter_meta).await;
+    }
 
-        let scramble_id = match global_client.get_scramble_id(chapter_id).await {
-            Ok(scramble_id) => scramble_id,
-            Err(e) => {
-                error!("获取 scramble_id 失败: {}", e);
-                return Err(e);
-            }
-        };
+    let progress = job.job().progress().snapshot();
+    timings.total_ms = elapsed_ms(total_start);
+    info!("downloadChapter完成，总耗时: {}ms", timings.total_ms);
+    let response_data = ChapterDownloadData {
+        job_id: Some(job.job().id()),
+        comic_id,
+        comic_title: comic.name,
+        chapters: all_chapters_data,
+        retried_pages: progress.retried_pages,
+        max_retries_used: progress.max_page_retries,
+        timings: request.include_timings.then_some(timings),
+        coins_spent: auto_buy.spent(),
+    };
 
-        // 创建下载目录
-        let chapter_dir = match create_download_dir(comic_id, chapter_id) {
-            Ok(chapter_dir) => chapter_dir,
-            Err(e) => {
-                error!("创建下载目录失败: {}", e);
-                return Err(e);
-            }
-        };
+    if interrupted.is_none() {
+        job.succeed(&response_data.comic_title, &response_data);
+    }
+    Ok(ChapterOutcome { data: response_data, interrupted })
+}
 
-        info!("开始并发下载章节 {} 的 {} 张图片，并发数 {}",
-            chapter_id, chapter.images.len(), img_concurrency);
+/// 章节下载结果；到达截止时间时 `interrupted` 为超时错误，`data` 只含已完成的章节
+pub(crate) struct ChapterOutcome {
+    pub data: ChapterDownloadData,
+    pub interrupted: Option<AppError>,
+}
 
-        // 创建 JoinSet 用于并发下载
-        let mut join_set = JoinSet::new();
+/// 下载任务的截止时间
+#[derive(Debug, Clone, Copy)]
+struct Deadline {
+    at: tokio::time::Instant,
+    seconds: u64,
+}
 
-        let total_images = chapter.images.len();
+impl Deadline {
+    /// 请求的 timeout_seconds 与 JM_MAX_JOB_SECONDS 取较小者，都未设置或大到无法表示时不限制
+    fn new(config: &Config, timeout_seconds: Option<u64>) -> ApiResult<Option<Self>> {
+        if timeout_seconds == Some(0) {
+            return Err(AppError::BadRequest("timeout_seconds 必须大于0".to_string()));
+        }
+        let limit = Some(config.max_job_seconds).filter(|&seconds| seconds > 0);
+        let Some(seconds) = timeout_seconds.into_iter().chain(limit).min() else {
+            return Ok(None);
+        };
+        Ok(tokio::time::Instant::now()
+            .checked_add(Duration::from_secs(seconds))
+            .map(|at| Self { at, seconds }))
+    }
+}
 
-        for (index, filename) in chapter.images.iter().enumerate() {
-            let url = format!(
-                "https://{}/media/photos/{}/{}",
-                image_domain, chapter_id, filename
-            );
-            let block_num = calculate_block_num(scramble_id, chapter_id, filename);
-            let save_filename = format!("{:04}.png", index + 1);
-            let save_path = chapter_dir.join(&save_filename);
-            let relative_path = format!("download/{}/{}/{}", comic_id, chapter_id, save_filename);
+/// 在截止时间前执行 `future`，超时后丢弃它（进行中的图片下载随 JoinSet 一并取消）并返回 [`AppError::Timeout`]
+async fn before_deadline<T>(
+    deadline: Option<Deadline>,
+    future: impl Future<Output = ApiResult<T>>,
+) -> ApiResult<T> {
+    let Some(deadline) = deadline else {
+        return future.await;
+    };
+    tokio::time::timeout_at(deadline.at, future).await.unwrap_or_else(|_| {
+        Err(AppError::Timeout(format!(
+            "下载超过截止时间（{} 秒），已取消未完成的下载",
+            deadline.seconds
+        )))
+    })
+}
 
-            // 克隆用于异步任务
-            let http_client = http_client.clone();
-            let filename = filename.clone();
-            let semaphore = semaphore.clone();
+/// 执行 `future`，`cancelled` 先完成时丢弃它并返回 None：
+/// 排队与进行中的图片下载随 JoinSet 一并取消，立即释放带宽与并发许可；合并到同一下载的其他请求会接手执行
+async fn until_cancelled<T>(cancelled: impl Future<Output = ()>, future: impl Future<Output = T>) -> Option<T> {
+    tokio::select! {
+        result = future => Some(result),
+        () = cancelled => None,
+    }
+}
 
-            // 启动并发下载任务
-            join_set.spawn(async move {
-                // 获取信号量许可
-                let _permit = semaphore.acquire().await.unwrap();
+/// 服务关闭时取消下载。Rocket 在独立任务中执行处理器，普通 JSON 请求无法感知客户端断开，
+/// 需要随客户端断开而取消时使用流式接口或 gRPC
+async fn unless_shutdown<T>(shutdown: Shutdown, future: impl Future<Output = ApiResult<T>>) -> ApiResult<T> {
+    until_cancelled(shutdown, future).await.unwrap_or_else(|| {
+        warn!("服务正在关闭，已取消进行中的下载");
+        Err(AppError::Internal("服务正在关闭，下载已取消".to_string()))
+    })
+}
 
-                if tokio::fs::metadata(&save_path).await.is_ok() {
-                    info!("图片已存在，跳过下载: {}", save_path.display());
-                    return Ok::<(usize, String), AppError>((index, relative_path));
-                }
+/// 一个章节已下载到磁盘的页面
+struct ChapterPages {
+    artifact: ArtifactKey,
+    relative_paths: Vec<String>,
+    /// 该章节的各阶段耗时，合并到同一下载的请求共用
+    timings: PhaseTimings,
+}
 
-                info!("下载图片 {}/{}: {}", index + 1, total_images, url);
+/// 按 (漫画, 章节, 处理选项, 所选页) 合并的章节下载
+type ChapterCoalescer = Coalescer<(i64, i64, ProcessOptions, PageSelection), ApiResult<Arc<ChapterPages>>>;
 
-                // 下载图片
-                let img_data = download_image(&http_client, &url).await?;
+/// 进行中的下载任务，用于合并相同的并发请求；克隆后共享同一份状态
+#[derive(Clone, Default)]
+pub struct InFlightDownloads {
+    chapters: Arc<ChapterCoalescer>,
+    comics: Arc<Coalescer<DownloadComicRequest, ApiResult<ComicDownloadData>>>,
+    /// 合并后仍可能写入同一变体目录的请求（如所选页不同）按产物加锁
+    artifacts: ArtifactLocks,
+}
 
-                // 处理并保存图片
-                info!("处理图片: {} (block_num: {})", filename, block_num);
-                process_and_save_image(img_data, block_num, &save_path).await?;
+/// 获取章节详情并下载所选页面到磁盘
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(name = "chapter", skip_all, fields(chapter_id = chapter_id))]
+async fn download_chapter_pages(
+    global_client: &GlobalJmClient,
+    artifacts: &ArtifactLocks,
+    auto_buy: &AutoBuy,
+    http_client: &ClientWithMiddleware,
+    semaphore: &Arc<Semaphore>,
+    job: &Arc<Job>,
+    config: &Config,
+    comic_id: i64,
+    chapter_id: i64,
+    selection: &PageSelection,
+    output: PageOutput,
+) -> ApiResult<Arc<ChapterPages>> {
+    let start = Instant::now();
+    // 使用全局客户端获取章节详情和 scramble ID
+    let chapter = match global_client.get_chapter(chapter_id).await {
+        Ok(chapter) => chapter,
+        Err(e) => {
+            error!("获取章节 {} 失败: {}", chapter_id, e);
+            return Err(e);
+        }
+    };
+    let chapter = auto_buy.chapter(global_client, comic_id, chapter_id, chapter).await?;
+    ensure_chapter_readable(chapter_id, &chapter)?;
+    ensure_page_limit(chapter_id, chapter.images.len(), config.max_pages_per_chapter)?;
+    let selected = selection.indices(chapter.images.len())?;
 
-                // 返回图片路径
-                Ok::<(usize, String), AppError>((index, relative_path))
-            });
+    let scramble_id = match chapter_scramble_id(config, global_client, chapter_id).await {
+        Ok(scramble_id) => scramble_id,
+        Err(e) => {
+            error!("获取 scramble_id 失败: {}", e);
+            return Err(e);
         }
+    };
+    let metadata_ms = elapsed_ms(start);
+
+    // 写入期间持有该变体的写锁，创建下载目录
+    let artifact = ArtifactKey::pages(comic_id, chapter_id, &output.process);
+    let _guard = artifacts.lock(&artifact).await;
+    if let Err(e) = artifact.create_dir() {
+        error!("创建下载目录失败: {}", e);
+        return Err(e);
+    }
 
-        // 等待所有下载完成并收集结果
-        let mut images = Vec::new();
-        while let Some(result) = join_set.join_next().await {
-            match result {
-                Ok(Ok((index, file_path))) => {
-                    images.push((index, file_path));
-                }
-                Ok(Err(e)) => {
-                    error!("下载图片失败: {}", e);
-                    return Err(e);
-                }
-                Err(e) => {
-                    error!("任务崩溃: {}", e);
-                    return Err(AppError::Internal(format!("任务崩溃: {}", e)));
+    let detail = job.progress().detail_level();
+    log::log!(detail, "开始并发下载章节 {} 的 {}/{} 张图片，并发数 {}",
+        chapter_id, selected.len(), chapter.images.len(), config.img_concurrency);
+
+    let block_nums = block_nums(
+        &config.scramble_rules,
+        &config.scramble_overrides,
+        scramble_id,
+        chapter_id,
+        &chapter.images,
+    );
+    let download_start = Instant::now();
+    let (pages, process_stats) = download_pages(
+        http_client,
+        semaphore,
+        job,
+        &global_client.image_urls().await,
+        &artifact,
+        block_nums,
+        &chapter.images,
+        &selected,
+        output,
+    )
+    .await?;
+
+    let download_ms = elapsed_ms(download_start);
+    log::log!(detail, "章节 {} 图片下载耗时: {}ms", chapter_id, download_ms);
+
+    Ok(Arc::new(ChapterPages {
+        relative_paths: pages.iter().flat_map(DownloadedPage::relative_paths).cloned().collect(),
+        artifact,
+        timings: PhaseTimings {
+            metadata_ms,
+            download_ms,
+            processing_ms: process_stats.cpu_time.as_millis() as u64,
+            total_ms: elapsed_ms(start),
+            ..PhaseTimings::default()
+        },
+    }))
+}
+
+/// # 下载普通漫画
+/// 仅支持无章节漫画，merge为true时会合并为PDF，encrypt传入则启用加密，pdf_quality/pdf_dpi控制压缩，支持过期自动清理。
+/// 到达截止时间时整体失败（错误码 10010，不返回部分结果）；已保存的页面留在磁盘上，重试时直接复用。
+#[openapi]
+#[allow(clippy::too_many_arguments)]
+#[post("/api/comic/downloadComic", data = "<request>")]
+pub async fn download_comic(
+    config: &State<LiveConfig>,
+    global_client: &State<GlobalJmClient>,
+    storage: &State<Storage>,
+    inflight: &State<InFlightDownloads>,
+    leases: &State<DirLeases>,
+    jobs: &State<Jobs>,
+    shutdown: Shutdown,
+    request: Json<DownloadComicRequest>,
+) -> ApiResult<R<ComicDownloadData>> {
+    let config = config.load();
+    let download =
+        download_comic_coalesced(&config, global_client, storage, inflight, leases, jobs, request.into_inner());
+    unless_shutdown(shutdown, download).await.map(R::success)
+}
+
+/// 下载普通漫画并发送通知，相同的并发请求合并为一次；downloadComic 与 gRPC 的 DownloadComic 共用
+pub(crate) async fn download_comic_coalesced(
+    config: &Config,
+    global_client: &GlobalJmClient,
+    storage: &Storage,
+    inflight: &InFlightDownloads,
+    leases: &DirLeases,
+    jobs: &Jobs,
+    request: DownloadComicRequest,
+) -> ApiResult<ComicDownloadData> {
+    service_mode::ensure_downloads_allowed()?;
+    let _lease = leases.acquire(chapter_dir_path(request.comic_id, request.comic_id));
+    // 完全相同的请求正在处理时，等待并共享其结果
+    inflight
+        .comics
+        .run(request.clone(), || async {
+            // 通知需要发送合并的 PDF 时，持有目录租约直到发送完成
+            let pdf_lease = (notifier::enabled(config) && request.merge)
+                .then(|| leases.acquire(chapter_dir_path(request.comic_id, request.comic_id)));
+            let result = match Deadline::new(config, request.timeout_seconds) {
+                Ok(deadline) => {
+                    let download = run_download_comic(
+                        config,
+                        global_client,
+                        storage,
+                        leases,
+                        jobs,
+                        &inflight.artifacts,
+                        &request,
+                    );
+                    before_deadline(deadline, download).await
                 }
+                Err(e) => Err(e),
+            };
+            if notifier::enabled(config) && !is_rejected(&result) {
+                notifier::notify(config, comic_event(config, &request, &result, pdf_lease));
             }
-        }
+            result
+        })
+        .await
+}
+
+/// 参数错误、队列已满或服务模式拒绝下载时请求未成为任务，不发送通知
+fn is_rejected<T>(result: &ApiResult<T>) -> bool {
+    matches!(
+        result,
+        Err(AppError::BadRequest(_) | AppError::QueueFull(_) | AppError::ReadOnly(_) | AppError::Maintenance(_))
+    )
+}
+
+fn comic_event(
+    config: &Config,
+    request: &DownloadComicRequest,
+    result: &ApiResult<ComicDownloadData>,
+    pdf_lease: Option<DirLease>,
+) -> JobEvent {
+    let outcome = match result {
+        Ok(data) => JobOutcome::Completed {
+            title: data.comic_title.clone(),
+            pages: data.page_count,
+            link: data.pdf_path.clone(),
+            pdf: pdf_lease.map(|lease| {
+                let dir = comic_artifact(config, request).dir();
+                let selection = PageSelection::new(request.page_range, &request.pages, &request.exclude_pages).unwrap_or_default();
+                (dir.join(merged_pdf_name(&selection, request.rtl)), lease)
+            }),
+        },
+        Err(e) => JobOutcome::Failed { error: e.to_string() },
+    };
+    JobEvent { kind: "downloadComic", comic_id: request.comic_id, outcome }
+}
 
-        // 按索引排序以保持顺序
-        images.sort_by_key(|(index, _)| *index);
-        let images: Vec<String> = images.into_iter().map(|(_, path)| path).collect();
+/// 书库中的 CBZ 应包含完整章节，不能与部分页下载同时使用；排除个别页面不受限制
+fn ensure_all_pages(selection: &PageSelection) -> ApiResult<()> {
+    if !selection.is_partial() {
+        Ok(())
+    } else {
+        Err(AppError::BadRequest("书库模式需要下载完整章节，不能同时设置 page_range 或 pages".to_string()))
+    }
+}
 
-        info!("完成下载章节 {} 的 {} 张图片", chapter_id, images.len());
+/// 漫画需要购买而当前账号未购买时，在下载前直接失败
+fn ensure_comic_purchased(comic_id: i64, comic: &GetComicRespData) -> ApiResult<()> {
+    if comic.requires_purchase() {
+        warn!("漫画 {} 需要购买（{} JM 币），当前账号未购买", comic_id, comic.price.unwrap_or(0));
+        return Err(AppError::PaymentRequired(format!(
+            "漫画 {} 需要 {} JM 币购买，当前账号尚未购买",
+            comic_id,
+            comic.price.unwrap_or(0)
+        )));
+    }
+    Ok(())
+}
 
-        // 添加到结果列表
-        all_chapters_data.push(SingleChapterData {
+/// 章节需要购买或未返回任何图片时直接失败，避免生成残缺的下载
+///
+/// 账号缺少购买或 VIP 时 JM 可能返回空的图片列表而不报错
+fn ensure_chapter_readable(chapter_id: i64, chapter: &GetChapterRespData) -> ApiResult<()> {
+    if chapter.requires_purchase() {
+        return Err(AppError::PaymentRequired(format!(
+            "章节 {} 需要 {} JM 币购买，当前账号尚未购买",
             chapter_id,
-            chapter_title: chapter_name,
-            images,
-        });
+            chapter.price.unwrap_or(0)
+        )));
+    }
+    if chapter.images.is_empty() {
+        warn!("章节 {} 未返回任何图片，可能需要购买或 VIP", chapter_id);
+        return Err(AppError::PaymentRequired(format!(
+            "章节 {} 未返回任何图片，可能需要 JM 币购买或 VIP 才能查看",
+            chapter_id
+        )));
+    }
+    Ok(())
+}
 
-        schedule_delete_dir(chapter_dir, expire_seconds);
+/// 章节图片数超过 `limit`（JM_MAX_PAGES_PER_CHAPTER，0 为不限制）时拒绝下载，
+/// 避免异常的章节数据占满磁盘与任务队列
+fn ensure_page_limit(chapter_id: i64, page_count: usize, limit: usize) -> ApiResult<()> {
+    if limit > 0 && page_count > limit {
+        return Err(AppError::TooLarge(format!(
+            "章节 {} 有 {} 张图片，超过单章节上限 {}",
+            chapter_id, page_count, limit
+        )));
     }
+    Ok(())
+}
 
-    let response_data = ChapterDownloadData {
-        comic_id,
-        comic_title: comic.name,
-        chapters: all_chapters_data,
-    };
+/// 章节的 scramble_id；按打乱规则不需要真实值时不请求 JM
+async fn chapter_scramble_id(config: &Config, global_client: &GlobalJmClient, chapter_id: i64) -> ApiResult<i64> {
+    match known_scramble_id(
+        &config.scramble_rules,
+        &config.scramble_overrides,
+        config.scramble_id_skip_from,
+        chapter_id,
+    ) {
+        Some(scramble_id) => Ok(scramble_id),
+        None => global_client.get_scramble_id(chapter_id).await,
+    }
+}
 
-    Ok(R::success(response_data))
+/// 普通漫画下载的图片处理选项
+fn comic_process_options(config: &Config, request: &DownloadComicRequest) -> ProcessOptions {
+    ProcessOptions {
+        split_spreads: request.split_spreads.then_some(request.spread_order),
+        eink: request.eink.then_some(config.eink_long_edge),
+        preserve_filenames: request.preserve_filenames,
+        keep_original: request.keep_original,
+        trim_borders: request.trim_borders,
+        upscale: config.upscale_cmd.is_some(),
+        // 已在 validate 中校验
+        post_process: PostProcessSteps::parse(&request.post_process).unwrap_or_default(),
+    }
 }
 
-/// # 下载普通漫画
-/// 仅支持无章节漫画，merge为true时会合并为PDF，encrypt传入则启用加密，支持过期自动清理。
-#[openapi]
-#[post("/api/comic/downloadComic", data = "<request>")]
-pub async fn download_comic(
-    config: &State<Config>,
-    global_client: &State<GlobalJmClient>,
-    request: Json<DownloadComicRequest>,
-) -> ApiResult<R<ComicDownloadData>> {
+/// 普通漫画的产物：合并 PDF 时由处理选项与 PDF 输出选项决定变体，否则只由处理选项决定
+fn comic_artifact(config: &Config, request: &DownloadComicRequest) -> ArtifactKey {
+    let process = comic_process_options(config, request);
+    let comic_id = request.comic_id;
+    if request.merge {
+        let encrypted = pdf_password(request).is_some();
+        ArtifactKey::pdf(
+            comic_id,
+            comic_id,
+            &process,
+            request.pdf_quality,
+            request.pdf_dpi,
+            encrypted,
+            request.title_page,
+        )
+    } else {
+        ArtifactKey::pages(comic_id, comic_id, &process)
+    }
+}
+
+/// 请求的 PDF 密码，去掉首尾空白后为空视为不加密
+fn pdf_password(request: &DownloadComicRequest) -> Option<&str> {
+    request.encrypt.as_deref().map(str::trim).filter(|value| !value.is_empty())
+}
+
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(name = "download_comic", skip_all, fields(comic_id = request.comic_id))]
+async fn run_download_comic(
+    config: &Config,
+    global_client: &GlobalJmClient,
+    storage: &Storage,
+    leases: &DirLeases,
+    jobs: &Jobs,
+    artifacts: &ArtifactLocks,
+    request: &DownloadComicRequest,
+) -> ApiResult<ComicDownloadData> {
     let comic_id = request.comic_id;
     let merge = request.merge;
-    let pdf_password = request
-        .encrypt
-        .as_deref()
-        .map(str::trim)
-        .filter(|value| !value.is_empty());
+    let pdf_password = pdf_password(request);
     let expire_seconds = request.expire_seconds;
     let total_start = Instant::now();
 
     info!("开始下载普通漫画: comic_id={}", comic_id);
-    if expire_seconds < -1 {
-        return Err(AppError::BadRequest("过期时间必须为-1或非负数".to_string()));
+    request.validate(config)?;
+    let selection = PageSelection::new(request.page_range, &request.pages, &request.exclude_pages)?;
+    let library_mode = request.library_mode || config.library_mode;
+    if library_mode {
+        library::library_root(config)?;
+        ensure_all_pages(&selection)?;
     }
+    let email_to = request
+        .email_to
+        .as_deref()
+        .map(|to| mailer::parse_recipient(config, to))
+        .transpose()?;
+    let auto_buy = AutoBuy::new(request.auto_buy, request.max_coins, config.auto_buy_max_coins)?;
 
     // 使用全局客户端获取漫画信息（带自动重试）
+    let metadata_start = Instant::now();
     let comic = match global_client.get_comic(comic_id).await {
         Ok(comic) => comic,
         Err(e) => {
@@ -360,6 +1526,8 @@ pub async fn download_comic(
             return Err(e);
         }
     };
+    let comic = auto_buy.comic(global_client, comic_id, comic).await?;
+    ensure_comic_purchased(comic_id, &comic)?;
 
     // 检查是否为普通漫画
     if !comic.series.is_empty() {
@@ -379,17 +1547,25 @@ pub async fn download_comic(
             return Err(e);
         }
     };
+    let chapter = auto_buy.chapter(global_client, comic_id, chapter_id, chapter).await?;
+    ensure_chapter_readable(chapter_id, &chapter)?;
+    ensure_page_limit(chapter_id, chapter.images.len(), config.max_pages_per_chapter)?;
+    let selected = selection.indices(chapter.images.len())?;
 
-    let scramble_id = match global_client.get_scramble_id(chapter_id).await {
+    let scramble_id = match chapter_scramble_id(config, global_client, chapter_id).await {
         Ok(scramble_id) => scramble_id,
         Err(e) => {
             error!("获取 scramble_id 失败: {}", e);
             return Err(e);
         }
     };
+    let mut timings = PhaseTimings { metadata_ms: elapsed_ms(metadata_start), ..PhaseTimings::default() };
 
-    // 创建下载目录
-    let chapter_dir = match create_download_dir(comic_id, chapter_id) {
+    // 写入期间持有该变体的写锁，创建下载目录
+    let process = comic_process_options(config, request);
+    let artifact = comic_artifact(config, request);
+    let _guard = artifacts.lock(&artifact).await;
+    let chapter_dir = match artifact.create_dir() {
         Ok(chapter_dir) => chapter_dir,
         Err(e) => {
             error!("创建下载目录失败: {}", e);
@@ -397,109 +1573,742 @@ pub async fn download_comic(
         }
     };
 
-    if merge {
-        let pdf_filename = "merged.pdf";
-        let pdf_full_path = chapter_dir.join(pdf_filename);
+    if selection.is_all() {
+        let chapter_meta = ChapterMeta { chapter_id, title: &comic.name, page_count: chapter.images.len() };
+        metadata::write(config, comic_id, &comic, &[chapter_meta]).await;
+    }
+
+    // 书库模式需要单页图片打包 CBZ，不走 PDF 已存在的捷径；
+    // 加密变体中已有的 PDF 可能使用了其他密码，同样重新生成
+    if merge && !library_mode && pdf_password.is_none() {
+        let pdf_filename = merged_pdf_name(&selection, request.rtl);
+        let pdf_full_path = chapter_dir.join(&pdf_filename);
+        if tokio::fs::metadata(&pdf_full_path).await.is_ok() && !is_complete_pdf(&pdf_full_path).await {
+            warn!("PDF {} 不完整，删除后重新生成", pdf_full_path.display());
+            if let Err(e) = tokio::fs::remove_file(&pdf_full_path).await {
+                warn!("删除不完整的 PDF {} 失败: {}", pdf_full_path.display(), e);
+            }
+        }
         if tokio::fs::metadata(&pdf_full_path).await.is_ok() {
             info!("PDF已存在，跳过下载与合并: {}", pdf_full_path.display());
-            schedule_delete_dir(chapter_dir, expire_seconds);
+            // 拆分跨页后 PDF 页数多于所选页数，以 PDF 实际页数为准
+            let pdf_page_count = match pdf_page_count(&pdf_full_path).await {
+                Ok(count) => count,
+                Err(e) => {
+                    warn!("读取 PDF {} 页数失败，按所选页数估算: {}", pdf_full_path.display(), e);
+                    selected.len() + front_matter_pages(request)
+                }
+            };
+            let pdf_paths = split_volumes(
+                request,
+                &pdf_full_path,
+                pdf_page_count,
+                pdf_password,
+                &artifact,
+            )
+            .await?;
+            let emails_sent = send_email(
+                config,
+                email_to,
+                &comic.name,
+                &pdf_full_path,
+                pdf_page_count,
+                pdf_password,
+            )
+            .await?;
+            let pdf_path = storage
+                .publish(&pdf_file(
+                    &artifact,
+                    artifact.relative_path(&pdf_filename),
+                    format!("{}.pdf", comic.name),
+                    &comic.name,
+                    pdf_tag(&pdf_filename),
+                ))
+                .await?;
+            let produced = produced_pdfs(&pdf_filename, pdf_paths.as_deref());
+            let checksums = if request.checksums {
+                let files = produced.clone();
+                let folder = artifact.remote_folder(vec![comic.name.clone()]);
+                Some(publish_checksums(storage, &artifact, files, folder, &comic.name).await?)
+            } else {
+                None
+            };
+            let torrent = if request.torrent {
+                Some(publish_torrent(config, storage, &artifact, produced, request.checksums, &comic.name).await?)
+            } else {
+                None
+            };
+            let pdf_paths = match pdf_paths {
+                Some(paths) => {
+                    let tag = pdf_tag(&merged_pdf_name(&selection, request.rtl)).to_string();
+                    Some(publish_volumes(storage, &artifact, paths, &comic.name, &tag).await?)
+                }
+                None => None,
+            };
+            leases.schedule_delete(artifact.chapter_dir(), expire_seconds);
+            timings.total_ms = elapsed_ms(total_start);
             let response_data = ComicDownloadData {
+                job_id: None,
                 comic_id,
-                comic_title: comic.name,
+                comic_title: comic.name.clone(),
                 images: None,
-                pdf_path: Some(format!("download/{}/{}/{}", comic_id, chapter_id, pdf_filename)),
+                pdf_path: Some(pdf_path),
+                pdf_paths,
+                emails_sent,
+                library_path: None,
+                page_count: pdf_page_count.saturating_sub(front_matter_pages(request)),
+                retried_pages: 0,
+                max_retries_used: 0,
+                timings: request.include_timings.then_some(timings),
+                checksums,
+                torrent,
+                coins_spent: auto_buy.spent(),
             };
-            info!("downloadComic完成，总耗时: {}ms", total_start.elapsed().as_millis());
-            return Ok(R::success(response_data));
+            info!("downloadComic完成，总耗时: {}ms", timings.total_ms);
+            return Ok(response_data);
         }
     }
 
+    let job = jobs
+        .start("downloadComic", comic_id, request.priority, JobLimits::from(config))
+        .await?;
+    job.job().progress().set_log_level(request.log_level.unwrap_or(config.job_log_level));
+    let detail = job.job().progress().detail_level();
+    let reporter = job.job().progress().start_reporter(Duration::from_secs(config.progress_log_seconds));
+
     // 创建用于下载图片的HTTP客户端，带重试机制
-    let reqwest_client = match reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(60))
-        .build()
-    {
-        Ok(client) => client,
-        Err(e) => {
-            return Err(AppError::Internal(format!("创建HTTP客户端失败: {}", e)));
+    let http_client = build_image_http_client(config.max_retries, job.job().progress())?;
+
+    let img_concurrency = config.img_concurrency;
+    let image_urls = global_client.image_urls().await;
+
+    log::log!(detail, "开始并发下载 {}/{} 张图片，并发数 {}",
+        selected.len(), chapter.images.len(), img_concurrency);
+
+    // 创建信号量控制并发数
+    let semaphore = Arc::new(Semaphore::new(img_concurrency));
+
+    // 合并 PDF 时把拼接后的图像直接交给 PDF 构建，keep_images 为 false 时不再落盘单页图片。
+    // 页数超过一个 PDF 分段时不在内存中保留图像，改为落盘后逐批读取，以限制峰值内存
+    let output = if merge {
+        let in_memory = selected.len() <= config.pdf_batch_pages;
+        PageOutput {
+            persist: request.keep_images || !in_memory || library_mode,
+            keep_rgb: in_memory,
+            dedupe: request.dedupe,
+            sidecar: config.page_sidecar,
+            process,
         }
+    } else {
+        PageOutput { dedupe: request.dedupe, sidecar: config.page_sidecar, process, ..PageOutput::DISK }
+    };
+
+    // 并发下载所有图片
+    let download_start = Instant::now();
+    let block_nums = block_nums(
+        &config.scramble_rules,
+        &config.scramble_overrides,
+        scramble_id,
+        chapter_id,
+        &chapter.images,
+    );
+    let (pages, process_stats) = download_pages(
+        &http_client,
+        &semaphore,
+        job.job(),
+        &image_urls,
+        &artifact,
+        block_nums,
+        &chapter.images,
+        &selected,
+        output,
+    )
+    .await?;
+    reporter.finish();
+    let image_files: Vec<PublishFile> = pages
+        .iter()
+        .flat_map(DownloadedPage::relative_paths)
+        .map(|relative_path| PublishFile {
+            relative_path: relative_path.clone(),
+            name: format!("{} - {}", comic.name, file_name(relative_path)),
+            folder: artifact.remote_folder(vec![comic.name.clone()]),
+            file_name: file_name(relative_path).to_string(),
+        })
+        .collect();
+    let image_count = image_files.len();
+    let library_path = if library_mode {
+        let relative_paths: Vec<String> =
+            image_files.iter().map(|file| file.relative_path.clone()).collect();
+        let meta = ChapterMeta { chapter_id, title: &comic.name, page_count: image_count };
+        Some(library::export_chapter(config, comic_id, &comic, &meta, &relative_paths, request.rtl).await?)
+    } else {
+        None
+    };
+
+    log::log!(detail, "完成下载普通漫画 {} 的 {} 张图片", comic_id, image_count);
+    timings.download_ms = elapsed_ms(download_start);
+    timings.processing_ms = process_stats.cpu_time.as_millis() as u64;
+    log::log!(detail, "downloadComic图片下载耗时: {}ms", timings.download_ms);
+
+    // 落盘的单页图片与合并的 PDF 计入校验清单
+    let mut produced: Vec<String> = if output.persist {
+        image_files.iter().map(|file| file.file_name.clone()).collect()
+    } else {
+        Vec::new()
+    };
+    let mut pdf_paths = None;
+    let mut emails_sent = None;
+    let pdf_path = if merge {
+        let pdf_filename = merged_pdf_name(&selection, request.rtl);
+        let pdf_full_path = chapter_dir.join(&pdf_filename);
+        let merge_start = Instant::now();
+        let has_duplicates = pages.iter().any(|page| page.duplicate);
+        let mut pdf_pages: Vec<PdfPage> = pages.into_iter().flat_map(DownloadedPage::into_pdf_pages).collect();
+        // 右到左阅读时倒序排列漫画页面，标题页与封面仍在最前
+        if request.rtl {
+            pdf_pages.reverse();
+        }
+        let title = if request.title_page {
+            let font = config.pdf_font.as_deref().ok_or_else(|| {
+                AppError::BadRequest("服务未配置标题页字体 JM_PDF_FONT".to_string())
+            })?;
+            let title = TitlePage::new(font, comic_id, &comic).await?;
+            let cover = download_image(&http_client, &[image_urls.cover(comic_id)], job.job().progress()).await?;
+            pdf_pages.insert(0, PdfPage::Encoded(cover));
+            Some(title)
+        } else {
+            None
+        };
+        let pdf_parts =
+            merge_images_to_pdf(pdf_pages, &pdf_full_path, config.pdf_batch_pages, title).await?;
+        let pdf_merge_ms = elapsed_ms(merge_start);
+        timings.pdf_merge_ms = Some(pdf_merge_ms);
+        log::log!(detail, "downloadComic合并PDF耗时: {}ms", pdf_merge_ms);
+        let gs_options = GsOptions {
+            quality: request.pdf_quality,
+            dpi: request.pdf_dpi,
+            password: pdf_password,
+            dedupe_images: has_duplicates,
+            subset_fonts: request.title_page,
+        };
+        if gs_options.can_skip(&pdf_parts) {
+            log::log!(detail, "PDF压缩档位为none，跳过GhostScript");
+        } else {
+            let compress_start = Instant::now();
+            compress_pdf_with_gs(&pdf_parts, &pdf_full_path, gs_options).await?;
+            let compress_ms = elapsed_ms(compress_start);
+            timings.compress_ms = Some(compress_ms);
+            log::log!(detail, "downloadComic压缩PDF耗时: {}ms", compress_ms);
+        }
+        let pdf_page_count = image_count + front_matter_pages(request);
+        pdf_paths = split_volumes(
+            request,
+            &pdf_full_path,
+            pdf_page_count,
+            pdf_password,
+            &artifact,
+        )
+        .await?;
+        emails_sent = send_email(
+            config,
+            email_to,
+            &comic.name,
+            &pdf_full_path,
+            pdf_page_count,
+            pdf_password,
+        )
+        .await?;
+        produced.extend(produced_pdfs(&pdf_filename, pdf_paths.as_deref()));
+        Some(
+            storage
+                .publish(&pdf_file(
+                    &artifact,
+                    artifact.relative_path(&pdf_filename),
+                    format!("{}.pdf", comic.name),
+                    &comic.name,
+                    pdf_tag(&pdf_filename),
+                ))
+                .await?,
+        )
+    } else {
+        None
+    };
+    let checksums = if request.checksums {
+        let files = produced.clone();
+        let folder = artifact.remote_folder(vec![comic.name.clone()]);
+        Some(publish_checksums(storage, &artifact, files, folder, &comic.name).await?)
+    } else {
+        None
+    };
+    let torrent = if request.torrent {
+        Some(publish_torrent(config, storage, &artifact, produced, request.checksums, &comic.name).await?)
+    } else {
+        None
+    };
+    let pdf_paths = match pdf_paths {
+        Some(paths) => {
+            let tag = pdf_tag(&merged_pdf_name(&selection, request.rtl)).to_string();
+            Some(publish_volumes(storage, &artifact, paths, &comic.name, &tag).await?)
+        }
+        None => None,
+    };
+    // 合并 PDF 时不返回单页图片，也就无需发布（keep_images 为 false 时单页并未落盘）
+    let images = if merge { None } else { Some(storage.publish_all(image_files).await?) };
+
+    leases.schedule_delete(artifact.chapter_dir(), expire_seconds);
+
+    let progress = job.job().progress().snapshot();
+    timings.total_ms = elapsed_ms(total_start);
+    let response_data = ComicDownloadData {
+        job_id: Some(job.job().id()),
+        comic_id,
+        comic_title: comic.name.clone(),
+        images,
+        pdf_path,
+        pdf_paths,
+        emails_sent,
+        library_path,
+        page_count: image_count,
+        retried_pages: progress.retried_pages,
+        max_retries_used: progress.max_page_retries,
+        timings: request.include_timings.then_some(timings),
+        checksums,
+        torrent,
+        coins_spent: auto_buy.spent(),
+    };
+
+    info!("downloadComic完成，总耗时: {}ms", timings.total_ms);
+    job.succeed(&response_data.comic_title, &response_data);
+    Ok(response_data)
+}
+
+/// 设置了收件人时把合并后的 PDF 发送过去，返回发送的邮件数
+async fn send_email(
+    config: &Config,
+    to: Option<Mailbox>,
+    title: &str,
+    pdf_full_path: &Path,
+    total_pages: usize,
+    password: Option<&str>,
+) -> ApiResult<Option<usize>> {
+    let Some(to) = to else {
+        return Ok(None);
+    };
+    let send_start = Instant::now();
+    let sent = mailer::send_pdf(config, to, title, pdf_full_path, total_pages, password).await?;
+    info!("downloadComic发送邮件耗时: {}ms", send_start.elapsed().as_millis());
+    Ok(Some(sent))
+}
+
+/// 按请求的分卷选项拆分合并后的 PDF，未设置分卷选项时返回 None
+async fn split_volumes(
+    request: &DownloadComicRequest,
+    pdf_full_path: &Path,
+    total_pages: usize,
+    password: Option<&str>,
+    artifact: &ArtifactKey,
+) -> ApiResult<Option<Vec<String>>> {
+    if request.pdf_max_pages.is_none() && request.pdf_max_size_mb.is_none() {
+        return Ok(None);
+    }
+
+    let split_start = Instant::now();
+    let volumes = split_pdf(
+        pdf_full_path,
+        total_pages,
+        request.pdf_max_pages,
+        request.pdf_max_size_mb,
+        password,
+    )
+    .await?;
+    info!("downloadComic拆分PDF耗时: {}ms", split_start.elapsed().as_millis());
+
+    let paths = volumes
+        .iter()
+        .filter_map(|path| path.file_name().and_then(|name| name.to_str()))
+        .map(|name| artifact.relative_path(name))
+        .collect();
+    Ok(Some(paths))
+}
+
+/// 合并的 PDF 中插在漫画页面之前的页数
+fn front_matter_pages(request: &DownloadComicRequest) -> usize {
+    if request.title_page {
+        title_page::FRONT_MATTER_PAGES
+    } else {
+        0
+    }
+}
+
+/// 合并 PDF 的文件名，不同所选页与页序的 PDF 在同一变体目录中共存
+fn merged_pdf_name(selection: &PageSelection, rtl: bool) -> String {
+    let order = if rtl { ".rtl" } else { "" };
+    format!("merged{}{}.pdf", selection.file_suffix(), order)
+}
+
+/// 取相对路径中的文件名部分
+fn file_name(relative_path: &str) -> &str {
+    relative_path.rsplit('/').next().unwrap_or(relative_path)
+}
+
+/// 合并的 PDF 及其分卷在章节目录中的文件名
+fn produced_pdfs(pdf_filename: &str, volumes: Option<&[String]>) -> Vec<String> {
+    std::iter::once(pdf_filename.to_string())
+        .chain(volumes.unwrap_or_default().iter().map(|path| file_name(path).to_string()))
+        .collect()
+}
+
+/// 写入章节目录的校验清单并发布清单文件，`name` 为浏览器保存名的前缀
+async fn publish_checksums(
+    storage: &Storage,
+    artifact: &ArtifactKey,
+    files: Vec<String>,
+    folder: Vec<String>,
+    name: &str,
+) -> ApiResult<ChecksumData> {
+    let files = checksums::write_manifest(&artifact.dir(), files).await?;
+    let manifest = storage
+        .publish(&PublishFile {
+            relative_path: artifact.relative_path(checksums::MANIFEST_FILE),
+            name: format!("{} - {}", name, checksums::MANIFEST_FILE),
+            folder,
+            file_name: checksums::MANIFEST_FILE.to_string(),
+        })
+        .await?;
+    Ok(ChecksumData { manifest, files })
+}
+
+/// 为产出的文件（`include_manifest` 时连同校验清单）生成种子并发布，配置了做种命令时随后调用
+async fn publish_torrent(
+    config: &Config,
+    storage: &Storage,
+    artifact: &ArtifactKey,
+    mut files: Vec<String>,
+    include_manifest: bool,
+    title: &str,
+) -> ApiResult<TorrentData> {
+    if include_manifest {
+        files.push(checksums::MANIFEST_FILE.to_string());
+    }
+    if files.is_empty() {
+        return Err(AppError::BadRequest("没有落盘的文件可以生成种子，请设置 merge 或 keep_images".to_string()));
+    }
+    let dir = artifact.dir();
+    let torrent = torrent::write_torrent(config, &dir, files, title).await?;
+    let seeding = torrent::spawn_seed_hook(config, &torrent.path, &dir);
+    let torrent_path = storage
+        .publish(&PublishFile {
+            relative_path: artifact.relative_path(torrent::TORRENT_FILE),
+            name: format!("{}.torrent", title),
+            folder: artifact.remote_folder(vec![title.to_string()]),
+            file_name: torrent::TORRENT_FILE.to_string(),
+        })
+        .await?;
+    info!("已生成种子 {}，info_hash {}", torrent.path.display(), torrent.info_hash);
+    Ok(TorrentData {
+        torrent_path,
+        info_hash: torrent.info_hash,
+        piece_length: torrent.piece_length,
+        piece_count: torrent.piece_count,
+        total_bytes: torrent.total_bytes,
+        seeding,
+    })
+}
+
+/// 从 `start` 起经过的毫秒数
+fn elapsed_ms(start: Instant) -> u64 {
+    start.elapsed().as_millis() as u64
+}
+
+/// 发布 PDF 分卷，浏览器保存名为 `标题_part1.pdf` 等
+async fn publish_volumes(
+    storage: &Storage,
+    artifact: &ArtifactKey,
+    paths: Vec<String>,
+    title: &str,
+    tag: &str,
+) -> ApiResult<Vec<String>> {
+    let files = if paths.len() == 1 {
+        vec![pdf_file(artifact, paths[0].clone(), format!("{}.pdf", title), title, tag)]
+    } else {
+        paths
+            .into_iter()
+            .enumerate()
+            .map(|(index, path)| pdf_file(artifact, path, format!("{}_part{}.pdf", title, index + 1), title, tag))
+            .collect()
     };
+    storage.publish_all(files).await
+}
+
+/// 普通漫画的 PDF 放在以标题命名的目录下（非默认变体再加一层选项哈希），
+/// 文件名为浏览器保存名加上 `tag`，同一变体中不同所选页与页序的 PDF 互不覆盖
+fn pdf_file(artifact: &ArtifactKey, relative_path: String, name: String, title: &str, tag: &str) -> PublishFile {
+    PublishFile {
+        relative_path,
+        file_name: tagged_name(&name, tag),
+        name,
+        folder: artifact.remote_folder(vec![title.to_string()]),
+    }
+}
+
+/// 合并 PDF 文件名中区分所选页与页序的部分，如 `merged.p1-5.rtl.pdf` -> `.p1-5.rtl`，全部页正序时为空
+fn pdf_tag(pdf_filename: &str) -> &str {
+    pdf_filename
+        .strip_prefix("merged")
+        .and_then(|name| name.strip_suffix(".pdf"))
+        .unwrap_or_default()
+}
 
-    // 配置指数退避重试策略：最多重试3次
+/// 在文件名的扩展名前插入 `tag`，如 `标题.pdf` + `.rtl` -> `标题.rtl.pdf`
+fn tagged_name(name: &str, tag: &str) -> String {
+    match name.rsplit_once('.') {
+        Some((stem, extension)) => format!("{}{}.{}", stem, tag, extension),
+        None => format!("{}{}", name, tag),
+    }
+}
+
+/// 创建用于下载图片的HTTP客户端，带重试机制
+fn build_image_http_client(max_retries: u32, progress: &Arc<Progress>) -> ApiResult<ClientWithMiddleware> {
+    let reqwest_client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(60))
+        .build()
+        .map_err(|e| AppError::Internal(format!("创建HTTP客户端失败: {}", e)))?;
+
+    // 配置指数退避重试策略
     let retry_policy = ExponentialBackoff::builder()
-        .build_with_max_retries(3);
+        .build_with_max_retries(max_retries);
 
     let http_client = ClientBuilder::new(reqwest_client)
         .with(RetryTransientMiddleware::new_with_policy_and_strategy(
             retry_policy,
-            CustomRetryStrategy,
+            CustomRetryStrategy { progress: progress.clone() },
         ))
         .build();
 
-    info!("已配置图片下载重试策略：最多重试3次，使用指数退避");
+    log::log!(progress.detail_level(), "已配置图片下载重试策略：最多重试{}次，使用指数退避", max_retries);
 
-    let img_concurrency = config.img_concurrency;
-    let image_domain = global_client.image_domain().to_string();
+    Ok(http_client)
+}
 
-    info!("开始并发下载 {} 张图片，并发数 {}",
-        chapter.images.len(), img_concurrency);
+/// 单张源图片的下载结果，跨页拆分后包含两个输出文件
+struct DownloadedPage {
+    /// 按阅读顺序排列的输出文件
+    parts: Vec<PagePart>,
+    /// 处理后图像的内容哈希（仅 dedupe 且本次实际下载时存在）
+    hash: Option<[u8; 32]>,
+    /// 与前面某一页内容相同
+    duplicate: bool,
+}
 
-    // 创建信号量控制并发数
-    let semaphore = Arc::new(Semaphore::new(img_concurrency));
+/// 一个输出页面文件
+struct PagePart {
+    /// 返回给调用方的相对路径
+    relative_path: String,
+    /// 本地保存路径（persist 为 false 且本地不存在时文件不会写入）
+    save_path: PathBuf,
+    /// 处理后的内存图像（仅 keep_rgb 时存在）
+    image: Option<PdfPage>,
+}
+
+impl DownloadedPage {
+    /// 由保存路径与可选的内存图像构造，相对路径为 `download/{comic_id}/{chapter_id}/{文件名}`
+    fn new(relative_dir: &str, save_paths: Vec<PathBuf>, images: Vec<PdfPage>) -> Self {
+        let mut images = images.into_iter();
+        let parts = save_paths
+            .into_iter()
+            .map(|save_path| PagePart {
+                relative_path: format!(
+                    "{}/{}",
+                    relative_dir,
+                    save_path.file_name().unwrap_or_default().to_string_lossy()
+                ),
+                save_path,
+                image: images.next(),
+            })
+            .collect();
+        Self { parts, hash: None, duplicate: false }
+    }
+
+    fn relative_paths(&self) -> impl Iterator<Item = &String> {
+        self.parts.iter().map(|part| &part.relative_path)
+    }
+
+    /// 转换为 PDF 页面来源：优先使用内存图像，避免重新解码
+    fn into_pdf_pages(self) -> impl Iterator<Item = PdfPage> {
+        self.parts
+            .into_iter()
+            .map(|part| part.image.unwrap_or(PdfPage::File(part.save_path)))
+    }
+}
+
+/// 单页输出方式
+#[derive(Clone, Copy)]
+struct PageOutput {
+    /// 是否把处理后的图片保存到磁盘
+    persist: bool,
+    /// 是否在内存中保留拼接后的图像（用于直接合并 PDF）
+    keep_rgb: bool,
+    /// 是否按内容去重，重复页面在磁盘上以硬链接共用一份文件
+    dedupe: bool,
+    /// 是否用 pages.json 记录源文件名与内容哈希，复用本地页面前核对
+    sidecar: bool,
+    /// 单页处理选项
+    process: ProcessOptions,
+}
+
+impl PageOutput {
+    /// 仅保存到磁盘（默认行为）
+    const DISK: PageOutput = PageOutput {
+        persist: true,
+        keep_rgb: false,
+        dedupe: false,
+        sidecar: false,
+        process: ProcessOptions {
+            split_spreads: None,
+            eink: None,
+            preserve_filenames: false,
+            keep_original: false,
+            trim_borders: false,
+            upscale: false,
+            post_process: PostProcessSteps::EMPTY,
+        },
+    };
+}
 
+/// 并发下载并处理一个章节中 `selected` 下标对应的图片，按原顺序返回，同时返回累计的处理统计
+#[allow(clippy::too_many_arguments)]
+async fn download_pages(
+    http_client: &ClientWithMiddleware,
+    semaphore: &Arc<Semaphore>,
+    job: &Arc<Job>,
+    image_urls: &ImageUrlBuilder,
+    artifact: &ArtifactKey,
+    block_nums: Vec<u32>,
+    filenames: &[String],
+    selected: &[usize],
+    output: PageOutput,
+) -> ApiResult<(Vec<DownloadedPage>, ProcessStats)> {
     // 创建 JoinSet 用于并发下载
     let mut join_set = JoinSet::new();
 
-    let total_images = chapter.images.len();
-
-    for (index, filename) in chapter.images.iter().enumerate() {
-        let url = format!(
-            "https://{}/media/photos/{}/{}",
-            image_domain, chapter_id, filename
-        );
-        let block_num = calculate_block_num(scramble_id, chapter_id, filename);
-        let save_filename = format!("{:04}.png", index + 1);
+    let total_images = selected.len();
+    let start = Instant::now();
+    job.progress().add_total(total_images);
+    let detail = job.progress().detail_level();
+
+    let chapter_id = artifact.chapter_id;
+    let chapter_dir = artifact.dir();
+    // 文件名按整个章节编号，部分页下载与完整下载共用同一批文件
+    let save_filenames = page_file_names(filenames, &output.process);
+    let total_pages = filenames.len();
+    // 启用页面记录时先读取，复用本地页面前核对是否因 JM 重新上传或本地修改而过期
+    let sidecar = if output.sidecar {
+        Some(Arc::new(PageSidecar::load(&chapter_dir).await))
+    } else {
+        None
+    };
+    for (index, (filename, save_filename)) in filenames.iter().zip(save_filenames).enumerate() {
+        if selected.binary_search(&index).is_err() {
+            continue;
+        }
+        let urls = image_urls.urls(chapter_id, filename);
+        let block_num = block_nums[index];
+        let save_filename = if output.process.keeps_original(block_num) {
+            original_file_name(&save_filename, filename)
+        } else {
+            save_filename
+        };
         let save_path = chapter_dir.join(&save_filename);
-        let relative_path = format!("download/{}/{}/{}", comic_id, chapter_id, save_filename);
+        let spool_path = chapter_dir.join(format!(".{}.spool", save_filename));
+        let relative_dir = artifact.relative_dir();
 
         // 克隆用于异步任务
         let http_client = http_client.clone();
         let filename = filename.clone();
         let semaphore = semaphore.clone();
+        let job = job.clone();
+        let sidecar = sidecar.clone();
 
-        // 启动并发下载任务
+        // 启动并发下载任务，挂在当前章节的 span 下
         join_set.spawn(async move {
+            // 任务暂停时在获取许可前等待，已开始的图片不受影响
+            job.wait_resumed().await;
             // 获取信号量许可
             let _permit = semaphore.acquire().await.unwrap();
+            let progress = job.progress();
 
-            if tokio::fs::metadata(&save_path).await.is_ok() {
-                info!("图片已存在，跳过下载: {}", save_path.display());
-                return Ok::<(usize, String, std::path::PathBuf), AppError>((index, relative_path, save_path));
+            if let Some(sidecar) = &sidecar {
+                discard_stale_pages(sidecar, &save_path, output.process, &filename).await?;
+            }
+            if let Some(save_paths) = existing_page(&save_path, output.process).await {
+                debug!("图片已存在，跳过下载: {}", save_path.display());
+                progress.page_done(0);
+                let page = DownloadedPage::new(&relative_dir, save_paths, Vec::new());
+                return Ok::<_, AppError>((index, page, None));
             }
 
-            info!("下载图片 {}/{}: {}", index + 1, total_images, url);
+            let (img_data, block_num) = if tokio::fs::metadata(&save_path).await.is_ok() {
+                // 已有未拆分的跨页文件：从本地重新处理，无需重新下载
+                let img_data = tokio::fs::read(&save_path).await.map_err(|e| {
+                    AppError::Internal(format!("读取图片 {} 失败: {}", save_path.display(), e))
+                })?;
+                (ImageBody::Memory(Bytes::from(img_data)), 0)
+            } else {
+                debug!("下载图片 {}/{}: {}", index + 1, total_pages, urls[0]);
+                (download_image_body(&http_client, &urls, progress, Some(&spool_path)).await?, block_num)
+            };
+            let img_bytes = img_data.len();
 
-            // 下载图片
-            let img_data = download_image(&http_client, &url).await?;
+            // 解码前申请内存预算，处理完成后释放
+            let _reservation = memory_budget::reserve(img_data.decoded_size().await).await;
+            let img_data = img_data.into_bytes().await?;
 
             // 处理并保存图片
-            info!("处理图片: {} (block_num: {})", filename, block_num);
-            process_and_save_image(img_data, block_num, &save_path).await?;
-
-            // 返回图片路径和保存路径
-            Ok::<(usize, String, std::path::PathBuf), AppError>((index, relative_path, save_path))
-        });
+            debug!("处理图片: {} (block_num: {})", filename, block_num);
+            let processed = process_image(
+                img_data,
+                block_num,
+                output.persist.then_some(save_path.as_path()),
+                output.keep_rgb,
+                output.dedupe,
+                output.process,
+            )
+            .await?;
+            progress.page_done(img_bytes);
+
+            let save_paths = match output.process.split_spreads.filter(|_| processed.split) {
+                Some(order) => spread_part_paths(&save_path, order).to_vec(),
+                None => vec![save_path],
+            };
+            let mut page = DownloadedPage::new(&relative_dir, save_paths, processed.images);
+            page.hash = processed.hash;
+            Ok::<_, AppError>((index, page, Some(processed.stats)))
+        }.in_current_span());
     }
 
     // 等待所有下载完成并收集结果
-    let download_start = Instant::now();
-    let mut images = Vec::new();
-    let mut image_files = Vec::new();
-    while let Some(result) = join_set.join_next().await {
+    let mut pages = Vec::with_capacity(total_images);
+    let mut process_stats = ProcessStats::default();
+    let mut processed = 0usize;
+    loop {
+        // 任务被取消时返回，JoinSet 释放时中止尚未完成的图片下载
+        let result = tokio::select! {
+            biased;
+            () = job.cancelled() => {
+                return Err(AppError::Cancelled(format!("任务 {} 已被取消", job.id())));
+            }
+            result = join_set.join_next() => result,
+        };
+        let Some(result) = result else {
+            break;
+        };
         match result {
-            Ok(Ok((index, file_path, save_path))) => {
-                images.push((index, file_path));
-                image_files.push((index, save_path));
+            Ok(Ok((index, page, stats))) => {
+                if let Some(stats) = stats {
+                    process_stats.merge(stats);
+                    processed += 1;
+                }
+                pages.push((index, page, stats.is_some()));
             }
             Ok(Err(e)) => {
                 error!("下载图片失败: {}", e);
@@ -512,58 +2321,284 @@ pub async fn download_comic(
         }
     }
 
-    // 按索引排序以保持顺序
-    images.sort_by_key(|(index, _)| *index);
-    let images: Vec<String> = images.into_iter().map(|(_, path)| path).collect();
-
-    image_files.sort_by_key(|(index, _)| *index);
-    let image_files: Vec<std::path::PathBuf> = image_files.into_iter().map(|(_, path)| path).collect();
-
-    info!("完成下载普通漫画 {} 的 {} 张图片", comic_id, images.len());
-    info!("downloadComic图片下载耗时: {}ms", download_start.elapsed().as_millis());
+    if processed > 0 {
+        let cpu_secs = process_stats.cpu_time.as_secs_f64().max(f64::EPSILON);
+        log::log!(
+            detail,
+            "章节 {} 图片处理统计: {} 张，墙钟耗时 {}ms，CPU 累计 {}ms，吞吐 {:.1} 张/s、{:.1} MP/s（单线程）",
+            chapter_id,
+            processed,
+            start.elapsed().as_millis(),
+            process_stats.cpu_time.as_millis(),
+            processed as f64 / cpu_secs,
+            process_stats.pixels as f64 / 1_000_000.0 / cpu_secs
+        );
+    }
 
-    let pdf_path = if merge {
-        let pdf_filename = "merged.pdf";
-        let pdf_full_path = chapter_dir.join(pdf_filename);
-        let merge_start = Instant::now();
-        merge_images_to_pdf(&image_files, &pdf_full_path).await?;
-        info!("downloadComic合并PDF耗时: {}ms", merge_start.elapsed().as_millis());
-        let compress_start = Instant::now();
-        compress_pdf_with_gs(&pdf_full_path, pdf_password).await?;
-        info!("downloadComic压缩PDF耗时: {}ms", compress_start.elapsed().as_millis());
-        Some(format!("download/{}/{}/{}", comic_id, chapter_id, pdf_filename))
-    } else {
-        None
-    };
+    // 按索引排序以保持顺序
+    pages.sort_by_key(|(index, _, _)| *index);
+    // 所有任务已结束，页面记录不再被共享
+    if let Some(mut sidecar) = sidecar.and_then(Arc::into_inner) {
+        for (index, page, rewritten) in &pages {
+            for part in &page.parts {
+                if tokio::fs::metadata(&part.save_path).await.is_ok() {
+                    sidecar.record(&part.save_path, &filenames[*index], *rewritten).await?;
+                }
+            }
+        }
+        sidecar.save().await;
+    }
+    let mut pages: Vec<DownloadedPage> = pages.into_iter().map(|(_, page, _)| page).collect();
 
-    schedule_delete_dir(chapter_dir, expire_seconds);
+    if output.dedupe {
+        let duplicates = link_duplicate_pages(&mut pages, output.persist).await?;
+        if duplicates > 0 {
+            log::log!(detail, "章节 {} 发现 {} 张重复页面，已共用同一份文件", chapter_id, duplicates);
+        }
+    }
+    Ok((pages, process_stats))
+}
 
-    let response_data = ComicDownloadData {
-        comic_id,
-        comic_title: comic.name,
-        images: if merge { None } else { Some(images) },
-        pdf_path,
-    };
+/// 本地已有可复用的页面文件时返回其路径（按阅读顺序）
+///
+/// 要求拆分跨页时优先复用已拆分的两半；未拆分的旧文件本身是跨页时不可直接复用，需重新处理。
+/// 不完整的文件（如崩溃时写了一半）会被删除，该页随后重新下载
+async fn existing_page(save_path: &Path, process: ProcessOptions) -> Option<Vec<PathBuf>> {
+    if let Some(order) = process.split_spreads {
+        let halves = spread_part_paths(save_path, order);
+        if complete_page_file(&halves[0]).await && complete_page_file(&halves[1]).await {
+            return Some(halves.to_vec());
+        }
+    }
+    if !complete_page_file(save_path).await {
+        return None;
+    }
+    if process.split_spreads.is_some() {
+        let spread = image_dimensions(save_path)
+            .await
+            .is_some_and(|(width, height)| is_spread(width, height));
+        if spread {
+            return None;
+        }
+    }
+    Some(vec![save_path.to_path_buf()])
+}
 
-    info!("downloadComic完成，总耗时: {}ms", total_start.elapsed().as_millis());
-    Ok(R::success(response_data))
+/// 删除页面记录判定为过期的本地文件（含已拆分的两半），该页随后重新下载
+async fn discard_stale_pages(
+    sidecar: &PageSidecar,
+    save_path: &Path,
+    process: ProcessOptions,
+    source: &str,
+) -> ApiResult<()> {
+    let mut candidates = vec![save_path.to_path_buf()];
+    if let Some(order) = process.split_spreads {
+        candidates.extend(spread_part_paths(save_path, order));
+    }
+    for path in candidates {
+        if tokio::fs::metadata(&path).await.is_err() || !sidecar.is_stale(&path, source).await? {
+            continue;
+        }
+        warn!("页面 {} 与记录不符（源图片已更新或本地文件被修改），删除后重新下载", path.display());
+        tokio::fs::remove_file(&path)
+            .await
+            .map_err(|e| AppError::Internal(format!("删除过期页面 {} 失败: {}", path.display(), e)))?;
+    }
+    Ok(())
 }
 
-fn schedule_delete_dir(path: PathBuf, expire_seconds: i64) {
-    if expire_seconds < 0 {
-        return;
+/// 页面文件存在且完整；存在但不完整时删除并返回 false
+async fn complete_page_file(path: &Path) -> bool {
+    if tokio::fs::metadata(path).await.is_err() {
+        return false;
+    }
+    if is_complete_image(path).await {
+        return true;
     }
+    warn!("图片 {} 不完整，删除后重新下载", path.display());
+    if let Err(e) = tokio::fs::remove_file(path).await {
+        warn!("删除不完整的图片 {} 失败: {}", path.display(), e);
+    }
+    false
+}
 
-    tokio::spawn(async move {
-        if expire_seconds > 0 {
-            sleep(Duration::from_secs(expire_seconds as u64)).await;
+/// 标记内容重复的页面；已落盘的重复页面替换为指向首次出现页面的硬链接，返回重复页数
+///
+/// 文件系统不支持硬链接时保留原文件，仅输出警告
+async fn link_duplicate_pages(pages: &mut [DownloadedPage], persist: bool) -> ApiResult<usize> {
+    // 哈希相同的页面拆分结果也相同，各部分按位置一一对应
+    let mut first_seen: HashMap<[u8; 32], Vec<PathBuf>> = HashMap::new();
+    let mut duplicates = 0;
+    for page in pages.iter_mut() {
+        let Some(hash) = page.hash else {
+            continue;
+        };
+        let Some(originals) = first_seen.get(&hash) else {
+            first_seen.insert(hash, page.parts.iter().map(|part| part.save_path.clone()).collect());
+            continue;
+        };
+        page.duplicate = true;
+        duplicates += 1;
+        if !persist {
+            continue;
         }
-        let path_for_delete = path.clone();
-        let result = tokio::task::spawn_blocking(move || std::fs::remove_dir_all(&path_for_delete)).await;
-        match result {
-            Ok(Ok(())) => info!("已删除目录: {}", path.display()),
-            Ok(Err(e)) => warn!("删除目录 {} 失败: {}", path.display(), e),
-            Err(e) => warn!("删除目录 {} 失败: {}", path.display(), e),
+        for (original, part) in originals.iter().zip(&page.parts) {
+            // 先链接到临时路径再覆盖，避免中途失败导致页面缺失
+            let tmp_path = part.save_path.with_extension("dedupe.tmp");
+            let _ = tokio::fs::remove_file(&tmp_path).await;
+            let linked = match tokio::fs::hard_link(original, &tmp_path).await {
+                Ok(()) => tokio::fs::rename(&tmp_path, &part.save_path).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = linked {
+                warn!(
+                    "以硬链接替换重复页面 {} 失败，保留原文件: {}",
+                    part.save_path.display(),
+                    e
+                );
+            }
         }
-    });
+    }
+    Ok(duplicates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_first_new_chapter() {
+        let chapters: Vec<ChapterItem> = [(10, 1), (12, 2), (15, 3)]
+            .into_iter()
+            .map(|(chapter_id, sort)| ChapterItem { chapter_id, name: String::new(), sort })
+            .collect();
+        assert_eq!(first_new_chapter(&chapters, None, None), 0);
+        assert_eq!(first_new_chapter(&chapters, Some(12), None), 2);
+        // 上次的章节已被删除时按 ID 继续
+        assert_eq!(first_new_chapter(&chapters, Some(11), None), 1);
+        assert_eq!(first_new_chapter(&chapters, Some(15), None), 3);
+        assert_eq!(first_new_chapter(&chapters, None, Some(1)), 1);
+        assert_eq!(first_new_chapter(&chapters, None, Some(5)), 3);
+    }
+
+    #[test]
+    fn huge_timeout_means_no_deadline() {
+        let config: Config = toml::from_str("").unwrap();
+        assert!(Deadline::new(&config, Some(u64::MAX)).unwrap().is_none());
+        assert_eq!(Deadline::new(&config, Some(60)).unwrap().unwrap().seconds, 60);
+        assert!(Deadline::new(&config, Some(0)).is_err());
+    }
+
+    #[tokio::test]
+    async fn like_and_comment_require_admin_key() {
+        use rocket::http::Header;
+        use rocket::local::asynchronous::Client;
+
+        let config: Config = toml::from_str("admin_api_key = \"secret\"").unwrap();
+        let global_client = GlobalJmClient::new(&config).await.unwrap();
+        let rocket = rocket::build()
+            .manage(LiveConfig::new(config))
+            .manage(global_client)
+            .mount("/", routes![like_comic, comment_comic]);
+        let client = Client::untracked(rocket).await.unwrap();
+
+        // 信封格式下错误以 HTTP 200 返回，业务码在 code 中
+        let like = client.post("/api/comic/1/like").dispatch().await;
+        let body: serde_json::Value = like.into_json().await.unwrap();
+        assert_eq!(body["code"], "10002");
+        let comment = client
+            .post("/api/comic/1/comment")
+            .header(Header::new("X-Admin-Key", "wrong"))
+            .json(&serde_json::json!({ "comment": "spam" }))
+            .dispatch()
+            .await;
+        let body: serde_json::Value = comment.into_json().await.unwrap();
+        assert_eq!(body["code"], "10002");
+    }
+
+    #[test]
+    fn local_chapter_includes_variants() {
+        let dir = std::env::temp_dir().join(format!("jm-check-local-{}", std::process::id()));
+        let variant = dir.join(VARIANTS_DIR).join("0a1b2c3d4e5f");
+        std::fs::create_dir_all(&variant).unwrap();
+        std::fs::write(dir.join("0001.png"), b"12").unwrap();
+        std::fs::write(variant.join("0001.png"), b"34").unwrap();
+        std::fs::write(variant.join("0002-1R.png"), b"5").unwrap();
+        std::fs::write(variant.join("merged.pdf"), b"678").unwrap();
+
+        let chapter = local_chapter(7, &dir);
+        assert!(chapter.exists);
+        assert_eq!(chapter.page_count, 2);
+        assert_eq!(chapter.bytes, 8);
+        assert_eq!(chapter.variants, ["0a1b2c3d4e5f"]);
+        let pdfs: Vec<&str> = chapter.pdfs.iter().map(|pdf| pdf.name.as_str()).collect();
+        assert_eq!(pdfs, ["variants/0a1b2c3d4e5f/merged.pdf"]);
+        assert!(!local_chapter(7, &dir.join("missing")).exists);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_chapters_over_the_page_limit() {
+        assert!(ensure_page_limit(1, 2000, 2000).is_ok());
+        assert!(ensure_page_limit(1, 100_000, 0).is_ok());
+        assert_eq!(ensure_page_limit(1, 2001, 2000).unwrap_err().code(), "10016");
+    }
+
+    #[test]
+    fn names_merged_pdfs_by_selection_and_order() {
+        let all = PageSelection::default();
+        assert_eq!(merged_pdf_name(&all, false), "merged.pdf");
+        assert_eq!(merged_pdf_name(&all, true), "merged.rtl.pdf");
+        let excluded = PageSelection::new(None, &[], &[3]).unwrap();
+        assert_eq!(merged_pdf_name(&excluded, true), "merged.x3.rtl.pdf");
+    }
+
+    #[test]
+    fn remote_pdf_names_keep_variants_apart() {
+        let plain = ArtifactKey::pages(1, 1, &ProcessOptions::default());
+        let name = "标题.pdf".to_string();
+        let file = pdf_file(&plain, plain.relative_path("merged.pdf"), name.clone(), "标题", pdf_tag("merged.pdf"));
+        assert_eq!((file.folder, file.file_name), (vec!["标题".to_string()], "标题.pdf".to_string()));
+
+        // 所选页与页序不同的 PDF 在同一目录下以不同文件名发布，保存名不变
+        let excluded = PageSelection::new(None, &[], &[3]).unwrap();
+        let rtl = merged_pdf_name(&excluded, true);
+        let file = pdf_file(&plain, plain.relative_path(&rtl), name, "标题", pdf_tag(&rtl));
+        assert_eq!((file.name, file.file_name), ("标题.pdf".to_string(), "标题.x3.rtl.pdf".to_string()));
+
+        // 其他变体放在选项哈希子目录中
+        let eink = ArtifactKey::pages(1, 1, &ProcessOptions { eink: Some(1600), ..Default::default() });
+        let file = pdf_file(&eink, eink.relative_path("merged.pdf"), "标题_part1.pdf".to_string(), "标题", "");
+        assert_eq!(file.folder.len(), 2);
+        assert_eq!(file.file_name, "标题_part1.pdf");
+    }
+
+    #[tokio::test]
+    async fn cancellation_aborts_page_tasks_and_frees_permits() {
+        let semaphore = Arc::new(Semaphore::new(1));
+        let download = {
+            let semaphore = semaphore.clone();
+            async move {
+                let mut join_set = JoinSet::new();
+                join_set.spawn(async move {
+                    let _permit = semaphore.acquire_owned().await.unwrap();
+                    std::future::pending::<()>().await;
+                });
+                join_set.join_next().await;
+            }
+        };
+        let (tx, rx) = mpsc::unbounded_channel::<()>();
+        let cancelled = tokio::spawn(async move { until_cancelled(tx.closed(), download).await });
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(semaphore.available_permits(), 0, "图片任务应持有许可");
+        // 模拟客户端断开：响应流释放接收端
+        drop(rx);
+        assert!(cancelled.await.unwrap().is_none());
+        let _permit = tokio::time::timeout(Duration::from_millis(50), semaphore.acquire())
+            .await
+            .expect("取消后许可应立即释放")
+            .unwrap();
+    }
 }
@@ -0,0 +1,94 @@
+// 下载历史
+// 每个下载任务结束时与每次自动购买后向 JM_HISTORY_FILE（默认 `{download_dir}/.history.jsonl`）追加一行 JSON 记录，
+// 供用量报表按时间段统计；文件只追加，格式错误的行读取时跳过
+
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use jm_downloader_rs::AppError;
+use serde::{Deserialize, Serialize};
+
+type Result<T> = std::result::Result<T, AppError>;
+
+/// 历史文件路径；写入时持锁，保证并发任务的记录按行完整追加
+static HISTORY_FILE: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// 一次下载任务的记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// 任务结束时间（Unix 时间戳，秒）
+    pub time: i64,
+    /// 发起任务的接口名
+    pub kind: String,
+    pub comic_id: i64,
+    /// 漫画标题，任务在获取漫画信息前失败时为空
+    #[serde(default)]
+    pub title: Option<String>,
+    /// 下载完成的页数
+    pub pages: usize,
+    /// 下载的字节数
+    pub bytes: u64,
+    pub success: bool,
+    /// 任务耗时（秒）
+    pub seconds: u64,
+    /// 自动购买花费的 JM 币，仅购买记录有值
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub coins: u64,
+}
+
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
+/// 设置历史文件路径；启动与重新加载配置时调用
+pub fn configure(path: PathBuf) {
+    *HISTORY_FILE.lock().unwrap() = Some(path);
+}
+
+/// 追加一条记录，写入失败只记录日志
+pub fn record(entry: &HistoryEntry) {
+    let guard = HISTORY_FILE.lock().unwrap();
+    let Some(path) = guard.as_ref() else {
+        return;
+    };
+    let result = serde_json::to_string(entry)
+        .map_err(std::io::Error::other)
+        .and_then(|mut line| {
+            line.push('\n');
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)?
+                .write_all(line.as_bytes())
+        });
+    if let Err(e) = result {
+        warn!("写入下载历史 {} 失败: {}", path.display(), e);
+    }
+}
+
+/// 读取 `since`（含）之后结束的记录；历史文件不存在时返回空列表
+pub fn load_since(since: i64) -> Result<Vec<HistoryEntry>> {
+    let Some(path) = HISTORY_FILE.lock().unwrap().clone() else {
+        return Ok(Vec::new());
+    };
+    let file = match std::fs::File::open(&path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(AppError::Internal(format!("读取下载历史 {} 失败: {}", path.display(), e)));
+        }
+    };
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| AppError::Internal(format!("读取下载历史 {} 失败: {}", path.display(), e)))?;
+        match serde_json::from_str::<HistoryEntry>(&line) {
+            Ok(entry) if entry.time >= since => entries.push(entry),
+            Ok(_) => {}
+            Err(e) if !line.trim().is_empty() => warn!("跳过无法解析的下载历史记录: {}: {}", line, e),
+            Err(_) => {}
+        }
+    }
+    Ok(entries)
+}
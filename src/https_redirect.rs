@@ -0,0 +1,73 @@
+// HTTP → HTTPS 重定向
+// 启用 HTTPS（JM_TLS_CERT/JM_TLS_KEY）并设置 JM_HTTPS_REDIRECT_PORT 时，另起一个只做重定向的 Rocket 实例
+// 监听该 HTTP 端口，任何方法、任何路径都以 308 重定向到 HTTPS 端口上的同一地址（308 保留请求方法与请求体）
+
+use std::net::{IpAddr, SocketAddr};
+
+use rocket::http::{Method, Status};
+use rocket::response::Redirect;
+use rocket::route::{Handler, Outcome, Route};
+use rocket::{Data, Request};
+
+#[derive(Clone)]
+struct RedirectToHttps {
+    https_port: u16,
+}
+
+#[rocket::async_trait]
+impl Handler for RedirectToHttps {
+    async fn handle<'r>(&self, req: &'r Request<'_>, _data: Data<'r>) -> Outcome<'r> {
+        // 没有 Host 请求头时无法确定重定向目标
+        let Some(host) = req.host() else {
+            return Outcome::Error(Status::BadRequest);
+        };
+        let url = https_url(host.domain().as_str(), self.https_port, &req.uri().to_string());
+        Outcome::from(req, Redirect::permanent(url))
+    }
+}
+
+/// 重定向目标：`example.com` + 8443 + `/api/health` -> `https://example.com:8443/api/health`，443 端口省略
+fn https_url(domain: &str, https_port: u16, origin: &str) -> String {
+    match https_port {
+        443 => format!("https://{}{}", domain, origin),
+        port => format!("https://{}:{}{}", domain, port, origin),
+    }
+}
+
+/// 在后台监听 `address:port`，把请求重定向到 `https_port`
+pub fn spawn(address: IpAddr, port: u16, https_port: u16) {
+    let handler = RedirectToHttps { https_port };
+    let routes: Vec<Route> = [
+        Method::Get,
+        Method::Head,
+        Method::Post,
+        Method::Put,
+        Method::Delete,
+        Method::Patch,
+        Method::Options,
+    ]
+    .into_iter()
+    .map(|method| Route::new(method, "/<path..>", handler.clone()))
+    .collect();
+    let config = rocket::Config { address, port, ..rocket::Config::default() };
+    tokio::spawn(async move {
+        info!("HTTP 重定向监听 {}，重定向到 HTTPS 端口 {}", SocketAddr::new(address, port), https_port);
+        if let Err(e) = rocket::custom(config).mount("/", routes).launch().await {
+            error!("HTTP 重定向服务异常退出: {}", e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_host_path_and_query() {
+        assert_eq!(
+            https_url("example.com", 8443, "/api/health?x=1"),
+            "https://example.com:8443/api/health?x=1"
+        );
+        assert_eq!(https_url("example.com", 443, "/"), "https://example.com/");
+    }
+}
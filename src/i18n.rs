@@ -0,0 +1,95 @@
+// 错误信息多语言
+// 按业务错误码维护中文（zh-CN）与英文（en）说明，根据请求头 `Accept-Language` 选择语言；
+// `code` 字段保持不变，客户端仍应以 code 判断错误类型。各处 `format!` 生成的具体错误详情仍为中文，
+// 英文时在详情前加上该错误码的英文说明
+
+/// 错误信息语言，未协商出支持的语言时为中文
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Lang {
+    #[default]
+    Zh,
+    En,
+}
+
+impl Lang {
+    /// 按 `Accept-Language`（如 `en-US,en;q=0.9,zh-CN;q=0.8`）选择 q 值最高的支持语言，相同时取靠前的
+    pub fn negotiate(accept_language: Option<&str>) -> Self {
+        let Some(header) = accept_language else {
+            return Lang::default();
+        };
+        let mut best: Option<(f32, Lang)> = None;
+        for item in header.split(',') {
+            let mut parts = item.split(';');
+            let tag = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+            let quality = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .and_then(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            let primary = tag.split('-').next().unwrap_or_default();
+            let lang = match primary {
+                "zh" => Lang::Zh,
+                "en" => Lang::En,
+                _ => continue,
+            };
+            if quality > 0.0 && best.is_none_or(|(best_quality, _)| quality > best_quality) {
+                best = Some((quality, lang));
+            }
+        }
+        best.map(|(_, lang)| lang).unwrap_or_default()
+    }
+}
+
+/// 业务错误码的说明，未知错误码按内部错误处理
+pub fn error_title(code: &str, lang: Lang) -> &'static str {
+    let (zh, en) = match code {
+        "10001" => ("请求参数错误", "Invalid request parameters"),
+        "10002" => ("未认证或 JM 会话失效", "Not authenticated or the JM session has expired"),
+        "10003" => ("禁止访问", "Access denied"),
+        "10004" => ("资源不存在", "Resource not found"),
+        "10005" => ("JM 账号或密码错误", "Invalid JM username or password"),
+        "10006" => ("需要 JM 币或 VIP", "JM coins or VIP required"),
+        "10007" => ("漫画已被下架或删除", "The comic has been removed"),
+        "10008" => ("请求被 JM 拦截", "Request blocked by JM"),
+        "10009" => ("下载任务队列已满，请稍后重试", "Download queue is full, please retry later"),
+        "10010" => ("下载超过截止时间", "Download exceeded its deadline"),
+        "10011" => ("该操作需要登录 JM 账号", "This operation requires a JM account"),
+        "10012" => ("任务已被取消", "The job was cancelled"),
+        "10013" => ("JM 接口暂时不可用，请稍后重试", "The JM API is temporarily unavailable, please retry later"),
+        "10014" => ("服务处于只读模式，暂不接受下载", "The service is read-only and not accepting downloads"),
+        "10015" => ("服务维护中", "The service is under maintenance"),
+        "10016" => ("超过大小限制", "Size limit exceeded"),
+        _ => ("内部错误", "Internal error"),
+    };
+    match lang {
+        Lang::Zh => zh,
+        Lang::En => en,
+    }
+}
+
+/// 按语言生成错误信息：中文为原始详情，英文为 `英文说明: 详情`。只有说明部分是英文，详情原样保留（仍为中文），
+/// 需要按语言处理错误的客户端应以错误码为准
+pub fn localize_message(code: &str, detail: &str, lang: Lang) -> String {
+    match lang {
+        Lang::Zh => detail.to_string(),
+        Lang::En => format!("{}: {}", error_title(code, lang), detail),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negotiates_language_by_quality() {
+        assert_eq!(Lang::negotiate(None), Lang::Zh);
+        assert_eq!(Lang::negotiate(Some("en-US,en;q=0.9,zh-CN;q=0.8")), Lang::En);
+        assert_eq!(Lang::negotiate(Some("fr, zh-TW;q=0.5, en;q=0.4")), Lang::Zh);
+        assert_eq!(Lang::negotiate(Some("zh;q=0.3, EN-gb;q=0.7")), Lang::En);
+        assert_eq!(Lang::negotiate(Some("en;q=0, de")), Lang::Zh);
+
+        assert_eq!(localize_message("10004", "章节 1 不存在", Lang::Zh), "章节 1 不存在");
+        // 只翻译错误码的说明，详情保持原文
+        assert_eq!(localize_message("10004", "章节 1 不存在", Lang::En), "Resource not found: 章节 1 不存在");
+        assert_eq!(error_title("99999", Lang::En), "Internal error");
+    }
+}
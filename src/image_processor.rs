@@ -1,23 +1,351 @@
-use bytes::Bytes;
-use image::{ImageFormat, RgbImage};
+use bytes::{Bytes, BytesMut};
+use image::{DynamicImage, GrayImage, ImageFormat, RgbImage};
+use jm_downloader_rs::png::{self, PngSettings};
+use jm_downloader_rs::stitch::stitch_img;
 use jm_downloader_rs::AppError;
-use printpdf::{Image as PdfImage, ImageTransform, Mm, PdfDocument};
+use printpdf::{ColorBits, ColorSpace, Image as PdfImage, ImageTransform, ImageXObject, Mm, PdfDocument, Px};
+use std::collections::HashSet;
 use std::fs::File;
-use std::io::BufWriter;
+use std::io::{self, BufWriter, Write};
+use std::panic::AssertUnwindSafe;
 use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::time::Duration;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use sha2::{Digest, Sha256};
 use reqwest_middleware::ClientWithMiddleware;
 
+use crate::adaptive_concurrency;
+use crate::atomic_file;
+use crate::file_server::sanitize_filename;
+use crate::memory_budget;
+use crate::progress::Progress;
+use crate::post_process::PostProcessSteps;
+use crate::throttle;
+use crate::title_page::TitlePage;
+use crate::upscale;
+use crate::scramble;
+use crate::models::{PdfQuality, SpreadOrder};
+use std::io::SeekFrom;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::oneshot;
+
 type Result<T> = std::result::Result<T, AppError>;
 
 const IMG_BODY_READ_MAX_RETRIES: usize = 3;
 const IMG_BODY_READ_BACKOFF_MS: u64 = 200;
 const IMG_BODY_READ_MAX_BACKOFF_MS: u64 = 2_000;
 const PDF_DPI: f32 = 300.0;
+/// 读取图片宽高时读取的文件头字节数，PNG/GIF 的尺寸都在文件开头
+const IMAGE_HEADER_BYTES: usize = 64 * 1024;
+/// PNG 文件末尾的 IEND 块：长度 0、类型 `IEND` 与固定的 CRC
+const PNG_TRAILER: [u8; 12] = [0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82];
+/// JPEG 文件末尾的 EOI 标记
+const JPEG_TRAILER: [u8; 2] = [0xFF, 0xD9];
+/// 检查 PDF 结尾 `%%EOF` 时读取的末尾字节数，允许其后有少量空白
+const PDF_TAIL_BYTES: usize = 1024;
+
+/// 图片解码、拼接、编码专用的 CPU 线程池
+static CPU_POOL: OnceLock<ThreadPool> = OnceLock::new();
+
+/// 下载文件的存储根目录
+static DOWNLOAD_ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+/// 已知屏蔽占位图的 MD5（JM_IMAGE_BLOCKED_MD5）
+static BLOCKED_IMAGE_MD5: Mutex<Vec<String>> = Mutex::new(Vec::new());
+/// 保存页面时的 PNG 编码参数（JM_PNG_COMPRESSION / JM_PNG_FILTER）
+static PNG_SETTINGS: Mutex<PngSettings> = Mutex::new(PngSettings {
+    compression: png::PngCompression::Fast,
+    filter: png::PngFilter::Adaptive,
+});
+/// 单张图片响应体的字节数上限（JM_MAX_IMAGE_MB），0 表示不限制
+static MAX_IMAGE_BYTES: AtomicU64 = AtomicU64::new(0);
+/// 小于该字节数且无法解码的响应视为屏蔽占位图（截断的响应、错误文本）；能正常解码的小图片照常使用
+const BLOCKED_IMAGE_MAX_BYTES: usize = 1024;
+
+/// 单张图片的处理统计，用于计算解码吞吐
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ProcessStats {
+    /// 处理的像素数（GIF 直接保存，不计入）
+    pub pixels: u64,
+    /// 在 CPU 线程池中实际花费的时间
+    pub cpu_time: Duration,
+}
+
+impl ProcessStats {
+    pub fn merge(&mut self, other: ProcessStats) {
+        self.pixels += other.pixels;
+        self.cpu_time += other.cpu_time;
+    }
+}
+
+/// 初始化 CPU 线程池，应在启动时调用一次
+pub fn init_cpu_pool(threads: usize) -> Result<()> {
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .thread_name(|i| format!("img-cpu-{}", i))
+        .build()
+        .map_err(|e| AppError::Internal(format!("创建图片处理线程池失败: {}", e)))?;
+    if CPU_POOL.set(pool).is_err() {
+        warn!("图片处理线程池已初始化，忽略重复初始化");
+    }
+    Ok(())
+}
+
+/// 设置下载存储根目录并创建，应在启动时调用一次
+pub fn init_download_root(dir: &str) -> Result<()> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| AppError::Internal(format!("创建下载目录 {} 失败: {}", dir, e)))?;
+    if DOWNLOAD_ROOT.set(PathBuf::from(dir)).is_err() {
+        warn!("下载目录已初始化，忽略重复初始化");
+    }
+    Ok(())
+}
+
+/// 下载存储根目录，未初始化时为 `./download`
+pub fn download_root() -> &'static Path {
+    DOWNLOAD_ROOT.get_or_init(|| PathBuf::from("./download"))
+}
+
+/// 设置已知屏蔽占位图的 MD5 列表；启动与重新加载配置时调用
+pub fn set_blocked_image_md5(hashes: Vec<String>) {
+    *BLOCKED_IMAGE_MD5.lock().unwrap() = hashes;
+}
+
+/// 设置单张图片的大小上限（MB），0 为不限制；启动与重新加载配置时调用
+pub fn set_max_image_mb(mb: u64) {
+    MAX_IMAGE_BYTES.store(mb.saturating_mul(1024 * 1024), Ordering::Relaxed);
+}
+
+/// 响应体超过单张图片上限时返回错误，不再继续读取
+fn check_image_size(url: &str, len: u64) -> std::result::Result<(), ReadError> {
+    let max = MAX_IMAGE_BYTES.load(Ordering::Relaxed);
+    if max > 0 && len > max {
+        return Err(ReadError::Fatal(AppError::TooLarge(format!(
+            "图片 {} 超过大小上限 {} MB",
+            url,
+            max / 1024 / 1024
+        ))));
+    }
+    Ok(())
+}
+
+/// 设置保存页面时的 PNG 编码参数；启动与重新加载配置时调用
+pub fn set_png_settings(settings: PngSettings) {
+    *PNG_SETTINGS.lock().unwrap() = settings;
+}
+
+/// 响应体是屏蔽占位图时返回原因：命中配置的 MD5，或过小且无法解码；落盘的响应体超过阈值，不可能是占位图
+fn blocked_placeholder(body: &ImageBody) -> Option<String> {
+    let ImageBody::Memory(bytes) = body else {
+        return None;
+    };
+    if bytes.len() < BLOCKED_IMAGE_MAX_BYTES && image::load_from_memory(bytes).is_err() {
+        return Some(format!("响应仅 {} 字节且无法解码", bytes.len()));
+    }
+    let hashes = BLOCKED_IMAGE_MD5.lock().unwrap();
+    if hashes.is_empty() {
+        return None;
+    }
+    let digest = format!("{:x}", md5::compute(bytes));
+    hashes.contains(&digest).then(|| format!("命中已知占位图 MD5 {}", digest))
+}
+
+/// 在 CPU 线程池中执行计算密集型任务并等待结果
+async fn run_on_cpu_pool<T, F>(f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let (tx, rx) = oneshot::channel();
+    let pool = CPU_POOL.get_or_init(|| {
+        ThreadPoolBuilder::new()
+            .build()
+            .expect("创建默认图片处理线程池失败")
+    });
+    // 任务内的 panic 在这里捕获，rayon 对未处理的 panic 会直接中止整个进程
+    pool.spawn(move || {
+        let _ = tx.send(std::panic::catch_unwind(AssertUnwindSafe(f)));
+    });
+    match rx.await {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(payload)) => Err(AppError::Internal(format!(
+            "图片处理任务崩溃: {}",
+            panic_message(payload.as_ref())
+        ))),
+        Err(e) => Err(AppError::Internal(format!("图片处理任务崩溃: {}", e))),
+    }
+}
+
+/// 取出 panic 携带的消息，非字符串时返回占位说明
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "未知错误".to_string()
+    }
+}
+
+/// 下载得到的图片数据：默认在内存中，超过 JM_SPOOL_THRESHOLD_MB 时写入临时文件
+pub enum ImageBody {
+    Memory(Bytes),
+    Spooled(SpoolFile),
+}
+
+/// 落盘的响应体，释放时删除
+pub struct SpoolFile {
+    path: PathBuf,
+    len: u64,
+}
+
+impl Drop for SpoolFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+impl ImageBody {
+    pub fn len(&self) -> u64 {
+        match self {
+            ImageBody::Memory(bytes) => bytes.len() as u64,
+            ImageBody::Spooled(file) => file.len,
+        }
+    }
+
+    /// 预估解码与拼接所需的内存，落盘时只读取文件头
+    pub async fn decoded_size(&self) -> u64 {
+        const HEADER_BYTES: u64 = 64 * 1024;
+        match self {
+            ImageBody::Memory(bytes) => memory_budget::decoded_size(bytes, self.len()),
+            ImageBody::Spooled(file) => {
+                let mut header = Vec::new();
+                if let Ok(handle) = tokio::fs::File::open(&file.path).await {
+                    let _ = handle.take(HEADER_BYTES).read_to_end(&mut header).await;
+                }
+                memory_budget::decoded_size(&header, file.len)
+            }
+        }
+    }
+
+    /// 读入内存用于解码
+    pub async fn into_bytes(self) -> Result<Bytes> {
+        match self {
+            ImageBody::Memory(bytes) => Ok(bytes),
+            ImageBody::Spooled(file) => tokio::fs::read(&file.path)
+                .await
+                .map(Bytes::from)
+                .map_err(|e| AppError::Internal(format!("读取临时文件 {} 失败: {}", file.path.display(), e))),
+        }
+    }
+}
+
+/// 读取响应体失败：网络错误可重试，写临时文件失败或超过大小上限直接返回
+enum ReadError {
+    Http(reqwest::Error),
+    Fatal(AppError),
+}
 
-/// 从URL下载图片
-pub async fn download_image(client: &ClientWithMiddleware, url: &str) -> Result<Bytes> {
+/// 分块读取响应体，每块计入全局下载限速；设置了 `spool_path` 且超过落盘阈值时改为写入该文件。
+/// 声明的或已读取的长度超过单张图片上限时中止
+async fn read_throttled(
+    mut response: reqwest::Response,
+    spool_path: Option<&Path>,
+) -> std::result::Result<ImageBody, ReadError> {
+    let url = response.url().to_string();
+    let threshold = memory_budget::spool_threshold();
+    let spool_path = spool_path.filter(|_| threshold > 0);
+    let mut capacity = response.content_length().unwrap_or(0);
+    check_image_size(&url, capacity)?;
+    let mut received = 0u64;
+    if spool_path.is_some() {
+        capacity = capacity.min(threshold);
+    }
+    let mut body = BytesMut::with_capacity(capacity as usize);
+    let mut spooled: Option<(tokio::fs::File, SpoolFile)> = None;
+    while let Some(chunk) = response.chunk().await.map_err(ReadError::Http)? {
+        received += chunk.len() as u64;
+        check_image_size(&url, received)?;
+        throttle::consume(chunk.len() as u64).await;
+        if spooled.is_none() {
+            body.extend_from_slice(&chunk);
+            match spool_path {
+                Some(path) if body.len() as u64 > threshold => {
+                    let io_error = |e: std::io::Error| {
+                        ReadError::Fatal(AppError::Internal(format!("写入临时文件 {} 失败: {}", path.display(), e)))
+                    };
+                    let spool = SpoolFile { path: path.to_path_buf(), len: body.len() as u64 };
+                    let mut file = tokio::fs::File::create(path).await.map_err(io_error)?;
+                    file.write_all(&body).await.map_err(io_error)?;
+                    body = BytesMut::new();
+                    spooled = Some((file, spool));
+                }
+                _ => {}
+            }
+        } else if let Some((file, spool)) = spooled.as_mut() {
+            file.write_all(&chunk).await.map_err(|e| {
+                ReadError::Fatal(AppError::Internal(format!("写入临时文件 {} 失败: {}", spool.path.display(), e)))
+            })?;
+            spool.len += chunk.len() as u64;
+        }
+    }
+    match spooled {
+        Some((mut file, spool)) => {
+            file.flush().await.map_err(|e| {
+                ReadError::Fatal(AppError::Internal(format!("写入临时文件 {} 失败: {}", spool.path.display(), e)))
+            })?;
+            Ok(ImageBody::Spooled(spool))
+        }
+        None => Ok(ImageBody::Memory(body.freeze())),
+    }
+}
+
+/// 下载图片，`urls` 为各镜像上的地址（主域名在前），重试次数计入该页的统计
+pub async fn download_image(client: &ClientWithMiddleware, urls: &[String], progress: &Progress) -> Result<Bytes> {
+    download_image_body(client, urls, progress, None).await?.into_bytes().await
+}
+
+/// 下载图片，响应体超过落盘阈值时写入 `spool_path`
+///
+/// 图片被 CDN 拒绝（403）或返回屏蔽占位图时改用下一个镜像，所有镜像都被屏蔽后返回 [`AppError::Blocked`]
+#[tracing::instrument(name = "download_image", skip_all, fields(url = urls.first().map(String::as_str)))]
+pub async fn download_image_body(
+    client: &ClientWithMiddleware,
+    urls: &[String],
+    progress: &Progress,
+    spool_path: Option<&Path>,
+) -> Result<ImageBody> {
+    // 启用自适应并发时占用窗口中的一个位置，直到图片读取完成
+    let _permit = adaptive_concurrency::acquire().await;
+    progress
+        .track_page(async {
+            let mut blocked = Vec::new();
+            for (index, url) in urls.iter().enumerate() {
+                match fetch_image(client, url, progress, spool_path).await {
+                    Err(AppError::Blocked(reason)) => {
+                        if let Some(next) = urls.get(index + 1) {
+                            warn!("{}，改用镜像 {}", reason, next);
+                            progress.record_retry();
+                        }
+                        blocked.push(reason);
+                    }
+                    result => return result,
+                }
+            }
+            Err(AppError::Blocked(format!("所有图片镜像均被屏蔽: {}", blocked.join("; "))))
+        })
+        .await
+}
+
+async fn fetch_image(
+    client: &ClientWithMiddleware,
+    url: &str,
+    progress: &Progress,
+    spool_path: Option<&Path>,
+) -> Result<ImageBody> {
     let mut retries = 0;
     let mut backoff = Duration::from_millis(IMG_BODY_READ_BACKOFF_MS);
 
@@ -38,8 +366,11 @@ pub async fn download_image(client: &ClientWithMiddleware, url: &str) -> Result<
                 e.is_decode()
             )))?;
 
-        // 检查HTTP状态码
+        // 检查HTTP状态码，403 表示该镜像屏蔽了图片
         let status = response.status();
+        if status == reqwest::StatusCode::FORBIDDEN {
+            return Err(AppError::Blocked(format!("图片 CDN 拒绝访问 {}: HTTP 403", url)));
+        }
         if !status.is_success() {
             return Err(AppError::Internal(format!(
                 "从 {} 下载图片失败: HTTP状态码 {} ({})",
@@ -49,9 +380,15 @@ pub async fn download_image(client: &ClientWithMiddleware, url: &str) -> Result<
             )));
         }
 
-        match response.bytes().await {
-            Ok(bytes) => return Ok(bytes),
-            Err(e) => {
+        match read_throttled(response, spool_path).await {
+            Ok(body) => {
+                return match blocked_placeholder(&body) {
+                    Some(reason) => Err(AppError::Blocked(format!("{} 返回屏蔽占位图: {}", url, reason))),
+                    None => Ok(body),
+                };
+            }
+            Err(ReadError::Fatal(e)) => return Err(e),
+            Err(ReadError::Http(e)) => {
                 let err_msg = format!(
                     "从 {} 读取响应字节失败: {} (is_timeout: {}, is_connect: {}, is_body: {}, is_decode: {})",
                     url,
@@ -70,6 +407,7 @@ pub async fn download_image(client: &ClientWithMiddleware, url: &str) -> Result<
                 }
 
                 retries += 1;
+                progress.record_retry();
                 warn!(
                     "{}，将在 {}ms 后重试 ({}/{})",
                     err_msg,
@@ -85,130 +423,651 @@ pub async fn download_image(client: &ClientWithMiddleware, url: &str) -> Result<
     }
 }
 
-/// 将图片块拼接回原图
-/// 这会还原JMComic应用的打乱效果
-fn stitch_img(src_img: &mut RgbImage, block_num: u32) -> RgbImage {
-    let (width, height) = src_img.dimensions();
-    let mut stitched_img = image::ImageBuffer::new(width, height);
-    let remainder_height = height % block_num;
+/// 宽高比超过该值的图片视为跨页
+const SPREAD_RATIO: f64 = 1.2;
 
-    for i in 0..block_num {
-        let mut block_height = height / block_num;
-        let src_img_y_start = height - (block_height * (i + 1)) - remainder_height;
-        let mut dst_img_y_start = block_height * i;
+/// 单页处理选项，同一章节以不同选项下载时产物写入不同的变体目录（见 `ArtifactKey`）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ProcessOptions {
+    /// 把跨页拆分为两页并按该顺序排列，None 为不拆分
+    pub split_spreads: Option<SpreadOrder>,
+    /// 电子墨水屏优化：转为 8 位灰度、拉伸对比度并把长边缩小到该像素数，None 为不处理
+    pub eink: Option<u32>,
+    /// 保存时沿用 JM 的原始文件名而不是按顺序编号
+    pub preserve_filenames: bool,
+    /// 原图直存：无需拼接的页面保存 JM 原图，不转码为 PNG（拆分跨页、电子墨水屏优化或裁剪白边时不生效）
+    pub keep_original: bool,
+    /// 裁剪页面四周纯白或纯黑的边框
+    pub trim_borders: bool,
+    /// 交给 JM_UPSCALE_CMD 放大（由服务配置决定，不来自请求）
+    pub upscale: bool,
+    /// 拆分跨页之后按顺序执行的后处理步骤
+    pub post_process: PostProcessSteps,
+}
 
-        if i == 0 {
-            block_height += remainder_height;
-        } else {
-            dst_img_y_start += remainder_height;
+impl ProcessOptions {
+    /// 该页是否原样保存 JM 原图：要求原图直存、无需拼接，且没有要求拆分跨页、电子墨水屏优化、裁剪边框、放大或后处理
+    pub fn keeps_original(&self, block_num: u32) -> bool {
+        self.keep_original
+            && block_num == 0
+            && self.split_spreads.is_none()
+            && self.eink.is_none()
+            && !self.trim_borders
+            && !self.upscale
+            && self.post_process.is_empty()
+    }
+}
+
+/// 判定为边框颜色时每个通道与纯白/纯黑的最大差值，容忍 JPEG 压缩噪点
+const TRIM_TOLERANCE: u8 = 24;
+/// 一行（列）中允许偏离边框颜色的像素比例，超过则视为内容
+const TRIM_NOISE_RATIO: f64 = 0.005;
+/// 裁剪后在内容四周保留的安全边距，取短边的 1%，至少 4 像素
+const TRIM_MARGIN_RATIO: u32 = 100;
+const TRIM_MIN_MARGIN: u32 = 4;
+
+#[derive(Clone, Copy)]
+enum BorderColor {
+    White,
+    Black,
+}
+
+impl BorderColor {
+    fn matches(self, pixel: &image::Rgb<u8>) -> bool {
+        match self {
+            BorderColor::White => pixel.0.iter().all(|&c| c >= u8::MAX - TRIM_TOLERANCE),
+            BorderColor::Black => pixel.0.iter().all(|&c| c <= TRIM_TOLERANCE),
+        }
+    }
+}
+
+/// 裁剪四周纯白或纯黑的边框（各边独立判断颜色），并保留安全边距；
+/// 空白页或裁剪后宽高不足原图一半（如只有几行字的页面）时原样返回，避免阅读器把少量内容放大
+pub fn trim_borders(image: RgbImage) -> RgbImage {
+    match content_bounds(&image) {
+        Some((x, y, width, height)) => image::imageops::crop_imm(&image, x, y, width, height).to_image(),
+        None => image,
+    }
+}
+
+/// 去掉边框后的内容区域 `(x, y, width, height)`，没有可裁剪的边框时返回 None
+fn content_bounds(image: &RgbImage) -> Option<(u32, u32, u32, u32)> {
+    let (width, height) = image.dimensions();
+    if width == 0 || height == 0 {
+        return None;
+    }
+    let is_border = |pixels: &mut dyn Iterator<Item = &image::Rgb<u8>>, len: u32, color: BorderColor| {
+        let off = pixels.filter(|pixel| !color.matches(pixel)).count();
+        off as f64 <= f64::from(len) * TRIM_NOISE_RATIO
+    };
+    let row = |y: u32, color| is_border(&mut (0..width).map(|x| image.get_pixel(x, y)), width, color);
+    let col = |x: u32, color| is_border(&mut (0..height).map(|y| image.get_pixel(x, y)), height, color);
+    // 从外向内数连续的边框行（列），白色与黑色边框取较宽者
+    let border = |count: u32, line: &dyn Fn(u32, BorderColor) -> bool| {
+        [BorderColor::White, BorderColor::Black]
+            .into_iter()
+            .map(|color| (0..count).take_while(|&i| line(i, color)).count() as u32)
+            .max()
+            .unwrap_or(0)
+    };
+    let top = border(height, &|i, color| row(i, color));
+    if top == height {
+        return None;
+    }
+    let bottom = border(height, &|i, color| row(height - 1 - i, color));
+    let left = border(width, &|i, color| col(i, color));
+    let right = border(width, &|i, color| col(width - 1 - i, color));
+
+    let margin = (width.min(height) / TRIM_MARGIN_RATIO).max(TRIM_MIN_MARGIN);
+    let x = left.saturating_sub(margin);
+    let y = top.saturating_sub(margin);
+    let x_end = (width - right + margin).min(width);
+    let y_end = (height - bottom + margin).min(height);
+    let (trimmed_width, trimmed_height) = (x_end - x, y_end - y);
+    let unchanged = trimmed_width == width && trimmed_height == height;
+    if unchanged || trimmed_width * 2 < width || trimmed_height * 2 < height {
+        return None;
+    }
+    Some((x, y, trimmed_width, trimmed_height))
+}
+
+/// 对比度拉伸时两端各忽略的像素比例，避免个别噪点决定拉伸范围
+const EINK_CLIP_RATIO: f64 = 0.005;
+
+/// 电子墨水屏优化：灰度化、对比度拉伸，长边超过 `long_edge` 时缩小
+fn eink_page(image: DynamicImage, long_edge: u32) -> GrayImage {
+    let mut gray = image.into_luma8();
+    normalize_contrast(&mut gray);
+
+    let (width, height) = gray.dimensions();
+    let longest = width.max(height);
+    if long_edge == 0 || longest <= long_edge {
+        return gray;
+    }
+    let scale = f64::from(long_edge) / f64::from(longest);
+    let new_width = ((f64::from(width) * scale).round() as u32).max(1);
+    let new_height = ((f64::from(height) * scale).round() as u32).max(1);
+    image::imageops::resize(&gray, new_width, new_height, image::imageops::FilterType::Triangle)
+}
+
+/// 把灰度直方图两端（各去掉 [`EINK_CLIP_RATIO`]）线性拉伸到 0~255
+fn normalize_contrast(image: &mut GrayImage) {
+    let mut histogram = [0u64; 256];
+    for pixel in image.pixels() {
+        histogram[pixel.0[0] as usize] += 1;
+    }
+    let total: u64 = histogram.iter().sum();
+    let clip = (total as f64 * EINK_CLIP_RATIO) as u64;
+
+    let percentile = |levels: &mut dyn Iterator<Item = usize>| {
+        let mut seen = 0;
+        for level in levels {
+            seen += histogram[level];
+            if seen > clip {
+                return level;
+            }
         }
+        0
+    };
+    let low = percentile(&mut (0..256));
+    let high = percentile(&mut (0..256).rev());
+    if high <= low {
+        return;
+    }
+
+    let range = (high - low) as f32;
+    let lut: Vec<u8> = (0..256)
+        .map(|level| {
+            let stretched = (level as f32 - low as f32) / range * 255.0;
+            stretched.round().clamp(0.0, 255.0) as u8
+        })
+        .collect();
+    for pixel in image.pixels_mut() {
+        pixel.0[0] = lut[pixel.0[0] as usize];
+    }
+}
+
+/// 是否为需要拆分的跨页（宽明显大于高）
+pub fn is_spread(width: u32, height: u32) -> bool {
+    height > 0 && f64::from(width) / f64::from(height) > SPREAD_RATIO
+}
+
+/// 章节中每张图片的保存文件名，与 `filenames` 顺序一致
+///
+/// 默认按顺序编号为 `0001.png`；`preserve_filenames` 时沿用原始文件名（扩展名统一为 png），
+/// 去除路径分隔符等字符，清理后重名的追加 `_2`、`_3`。页面顺序由返回列表的顺序决定，与文件名无关
+pub fn page_file_names(filenames: &[String], process: &ProcessOptions) -> Vec<String> {
+    if !process.preserve_filenames {
+        return (1..=filenames.len())
+            .map(|page| format!("{:04}.png", page))
+            .collect();
+    }
 
-        for y in 0..block_height {
-            let src_y = src_img_y_start + y;
-            let dst_y = dst_img_y_start + y;
-            for x in 0..width {
-                stitched_img.put_pixel(x, dst_y, *src_img.get_pixel(x, src_y));
+    let mut used = HashSet::new();
+    filenames
+        .iter()
+        .enumerate()
+        .map(|(index, filename)| {
+            let stem = filename.rsplit_once('.').map(|(stem, _)| stem).unwrap_or(filename);
+            // `-` 用于标记跨页拆分，`.` 用于扩展名与 `.spool` 等临时文件，原始文件名中的替换为 `_`
+            let mut stem: String = sanitize_filename(stem)
+                .chars()
+                .map(|c| if matches!(c, '.' | '-') { '_' } else { c })
+                .collect();
+            if stem.is_empty() {
+                stem = format!("{:04}", index + 1);
             }
+            let mut candidate = stem.clone();
+            let mut n = 2;
+            while !used.insert(candidate.clone()) {
+                candidate = format!("{}_{}", stem, n);
+                n += 1;
+            }
+            format!("{}.png", candidate)
+        })
+        .collect()
+}
+
+/// 原样保存的页面沿用源图片的扩展名（jpg/jpeg/png/webp），如 `0001.png` + `00001.webp` -> `0001.webp`；
+/// 其他扩展名（如 GIF）保持不变
+pub fn original_file_name(save_filename: &str, source: &str) -> String {
+    let ext = source.rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase());
+    match (ext, save_filename.rsplit_once('.')) {
+        (Some(ext), Some((stem, _))) if matches!(ext.as_str(), "jpg" | "jpeg" | "png" | "webp") => {
+            format!("{}.{}", stem, ext)
         }
+        _ => save_filename.to_string(),
+    }
+}
+
+/// 跨页拆分后两半的保存路径，按阅读顺序排列并标明左右半页，两种顺序的文件可以共存：
+/// 右到左为 0005-1R.png、0005-2L.png，左到右为 0005-1L.png、0005-2R.png
+pub fn spread_part_paths(save_path: &Path, order: SpreadOrder) -> [PathBuf; 2] {
+    let stem = save_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("page");
+    let ext = save_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("png");
+    let sides = match order {
+        SpreadOrder::Rtl => ["1R", "2L"],
+        SpreadOrder::Ltr => ["1L", "2R"],
+    };
+    sides.map(|side| save_path.with_file_name(format!("{}-{}.{}", stem, side, ext)))
+}
+
+/// 把跨页从中间拆为两页，按阅读顺序返回（右到左时右半页在前）
+fn split_spread(image: &RgbImage, order: SpreadOrder) -> [RgbImage; 2] {
+    let (width, height) = image.dimensions();
+    let left_width = width / 2;
+    let left = image::imageops::crop_imm(image, 0, 0, left_width, height).to_image();
+    let right = image::imageops::crop_imm(image, left_width, 0, width - left_width, height).to_image();
+    match order {
+        SpreadOrder::Rtl => [right, left],
+        SpreadOrder::Ltr => [left, right],
     }
+}
 
-    stitched_img
+/// 单张图片的处理结果
+pub struct ProcessedImage {
+    pub stats: ProcessStats,
+    /// 是否已按 `options.split_spreads` 拆分为两页（保存路径见 [`spread_part_paths`]）
+    pub split: bool,
+    /// 处理后的内存图像，拆分时为两页（仅在要求保留时返回，用于直接合并 PDF）
+    pub images: Vec<PdfPage>,
+    /// 处理后图像内容的 SHA-256（仅在要求计算时返回，用于去重）
+    pub hash: Option<[u8; 32]>,
 }
 
-/// 处理并保存图片（可选拼接）
-pub async fn process_and_save_image(
+/// 计算图像内容哈希：尺寸与像素数据一致的图片视为重复
+fn image_hash(image: &RgbImage) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(image.width().to_le_bytes());
+    hasher.update(image.height().to_le_bytes());
+    hasher.update(image.as_raw());
+    hasher.finalize().into()
+}
+
+/// 处理图片（可选拼接）
+///
+/// - `save_path` 为 Some 时保存到磁盘（GIF 与 [`ProcessOptions::keeps_original`] 的 JPEG/PNG/WebP 原样保存，
+///   其他转为 PNG）
+/// - `keep_rgb` 为 true 时在结果中返回拼接后的 RGB 图像，避免合并 PDF 时重新从磁盘解码
+/// - `hash` 为 true 时计算处理后图像的内容哈希（原样保存时按文件内容计算）
+/// - `options.split_spreads` 为 Some 时跨页拆分为两页，保存到 [`spread_part_paths`]（GIF 不拆分）
+/// - `options.trim_borders` 为 true 时在拼接后裁剪四周边框（原样保存的 GIF 不处理）
+/// - `options.post_process` 在拆分跨页之后对每页依次执行（原样保存的 GIF 不处理）
+/// - `options.eink` 为 Some 时转为灰度并缩小（原样保存的 GIF 不处理）
+/// - `options.upscale` 为 true 时在保存前交给外部放大命令，失败时保留原页面（见 [`upscale`]）
+#[tracing::instrument(skip_all, fields(block_num = block_num, bytes = img_data.len()))]
+pub async fn process_image(
     img_data: Bytes,
     block_num: u32,
-    save_path: &Path,
-) -> Result<()> {
+    save_path: Option<&Path>,
+    keep_rgb: bool,
+    hash: bool,
+    options: ProcessOptions,
+) -> Result<ProcessedImage> {
     // 检测图片格式
     let format = image::guess_format(&img_data)
         .map_err(|e| AppError::Internal(format!("检测图片格式失败: {}", e)))?;
 
-    // GIF图片不需要拼接，直接保存
-    if format == ImageFormat::Gif {
-        std::fs::write(save_path, img_data)
-            .map_err(|e| AppError::Internal(format!(
-                "保存GIF图片到 {} 失败: {}",
-                save_path.display(),
-                e
-            )))?;
-        return Ok(());
+    // GIF 图片不需要拼接，原图直存且无需拼接的页面也不转码，直接保存
+    let original = format == ImageFormat::Gif
+        || (options.keeps_original(block_num)
+            && matches!(format, ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP));
+    if original {
+        if let Some(save_path) = save_path {
+            atomic_file::write(save_path, &img_data)
+                .await
+                .map_err(|e| AppError::Internal(format!(
+                    "保存原图到 {} 失败: {}",
+                    save_path.display(),
+                    e
+                )))?;
+            if !keep_rgb {
+                return Ok(ProcessedImage {
+                    stats: ProcessStats::default(),
+                    split: false,
+                    images: Vec::new(),
+                    hash: hash.then(|| Sha256::digest(&img_data).into()),
+                });
+            }
+        }
     }
 
-    // 在阻塞任务中处理图片（CPU密集型）
-    let save_path = save_path.to_path_buf();
-    tokio::task::spawn_blocking(move || -> Result<()> {
-        let mut src_img = image::load_from_memory(&img_data)
+    // 在 CPU 线程池中处理图片并编码为 PNG（CPU密集型），编码结果回到异步任务中写盘，
+    // 线程池不因等待磁盘而空转
+    let save_path = save_path.map(Path::to_path_buf);
+    // 线程池中没有当前 span，显式挂到本次处理的 span 下
+    let span = tracing::Span::current();
+    let upscale_pages = options.upscale && !original;
+    let (mut processed, mut encoded) = run_on_cpu_pool(move || -> Result<(ProcessedImage, Vec<EncodedFile>)> {
+        let start = Instant::now();
+        let src_img = image::load_from_memory(&img_data)
             .map_err(|e| AppError::Internal(format!("解码图片失败: {}", e)))?
             .to_rgb8();
+        let pixels = u64::from(src_img.width()) * u64::from(src_img.height());
 
-        // 如果 block_num > 0 则拼接图片
-        let dst_img = if block_num == 0 {
+        // 如果 block_num > 0 则拼接图片（GIF 不拼接）
+        let dst_img = if block_num == 0 || format == ImageFormat::Gif {
             src_img
         } else {
-            stitch_img(&mut src_img, block_num)
+            let _span = tracing::info_span!(parent: &span, "stitch", block_num).entered();
+            let stitched = stitch_img(&src_img, block_num);
+            scramble::check_stitched(&stitched, block_num);
+            stitched
         };
+        let dst_img = if options.trim_borders && !original { trim_borders(dst_img) } else { dst_img };
+
+        let hash = hash.then(|| image_hash(&dst_img));
+        let split_order = options
+            .split_spreads
+            .filter(|_| !original && is_spread(dst_img.width(), dst_img.height()));
+        let parts = match split_order {
+            Some(order) => split_spread(&dst_img, order).to_vec(),
+            None => vec![dst_img],
+        };
+        let split = split_order.is_some();
+        let pages: Vec<PdfPage> = parts
+            .into_iter()
+            .map(|part| {
+                let page = options.post_process.apply(DynamicImage::ImageRgb8(part));
+                match (options.eink, page) {
+                    (Some(long_edge), page) => PdfPage::Gray(eink_page(page, long_edge)),
+                    (None, DynamicImage::ImageLuma8(gray)) => PdfPage::Gray(gray),
+                    (None, page) => PdfPage::Rgb(page.into_rgb8()),
+                }
+            })
+            .collect();
 
-        // 保存为PNG格式
-        dst_img
-            .save_with_format(&save_path, ImageFormat::Png)
+        // 编码为PNG格式（GIF 与原图直存的页面已在上面原样保存）；需要放大时不落盘的页面也先编码，交给放大命令
+        let paths: Vec<Option<PathBuf>> = match save_path.filter(|_| !original) {
+            Some(save_path) => match split_order {
+                Some(order) => spread_part_paths(&save_path, order).into_iter().map(Some).collect(),
+                None => vec![Some(save_path)],
+            },
+            None if upscale_pages => vec![None; pages.len()],
+            None => Vec::new(),
+        };
+        let mut encoded = Vec::new();
+        let settings = *PNG_SETTINGS.lock().unwrap();
+        for (page, path) in pages.iter().zip(paths) {
+            let png = page.encode_png(settings).map_err(|e| AppError::Internal(format!(
+                "编码图片 {} 失败: {}",
+                path.as_deref().unwrap_or(Path::new("")).display(),
+                e
+            )))?;
+            encoded.push((path, Bytes::from(png)));
+        }
+
+        let processed = ProcessedImage {
+            stats: ProcessStats {
+                pixels,
+                cpu_time: start.elapsed(),
+            },
+            split,
+            hash,
+            images: if keep_rgb { pages } else { Vec::new() },
+        };
+        Ok((processed, encoded))
+    })
+    .await??;
+
+    if upscale_pages {
+        for (index, (_, png)) in encoded.iter_mut().enumerate() {
+            if let Some(upscaled) = upscale::upscale(png).await {
+                if keep_rgb {
+                    processed.images[index] = PdfPage::Encoded(upscaled.clone());
+                }
+                *png = upscaled;
+            }
+        }
+    }
+    for (path, png) in encoded {
+        let Some(path) = path else { continue };
+        atomic_file::write(&path, png)
+            .await
             .map_err(|e| AppError::Internal(format!(
                 "保存图片到 {} 失败: {}",
-                save_path.display(),
+                path.display(),
                 e
             )))?;
+    }
+    Ok(processed)
+}
 
-        Ok(())
-    })
-    .await
-    .map_err(|e| AppError::Internal(format!("图片处理任务崩溃: {}", e)))??;
+/// 编码后的 PNG：(保存路径，不落盘时为 None, 编码后的内容)
+type EncodedFile = (Option<PathBuf>, Bytes);
 
-    Ok(())
+/// 读取图片文件头得到宽高，无需解码整张图片；读取失败或格式无法识别时返回 None
+pub async fn image_dimensions(path: &Path) -> Option<(u32, u32)> {
+    let mut header = Vec::with_capacity(IMAGE_HEADER_BYTES);
+    tokio::fs::File::open(path)
+        .await
+        .ok()?
+        .take(IMAGE_HEADER_BYTES as u64)
+        .read_to_end(&mut header)
+        .await
+        .ok()?;
+    image::ImageReader::new(std::io::Cursor::new(header))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+/// 已保存的图片是否完整：文件头可以解析出尺寸，且 PNG 以 IEND 块、JPEG 以 EOI 标记结尾；
+/// 跳过已存在的页面前调用，排除崩溃时只写了一半的文件
+pub async fn is_complete_image(path: &Path) -> bool {
+    let Ok(mut file) = tokio::fs::File::open(path).await else {
+        return false;
+    };
+    let mut header = Vec::with_capacity(IMAGE_HEADER_BYTES);
+    if (&mut file).take(IMAGE_HEADER_BYTES as u64).read_to_end(&mut header).await.is_err() {
+        return false;
+    }
+    let Ok(reader) = image::ImageReader::new(std::io::Cursor::new(header)).with_guessed_format() else {
+        return false;
+    };
+    let Some(format) = reader.format() else {
+        return false;
+    };
+    if reader.into_dimensions().is_err() {
+        return false;
+    }
+    let mut tail = [0u8; PNG_TRAILER.len()];
+    if file.seek(SeekFrom::End(-(tail.len() as i64))).await.is_err() || file.read_exact(&mut tail).await.is_err() {
+        return false;
+    }
+    has_image_trailer(format, &tail)
+}
+
+/// 图片末尾是否为格式规定的结束标记，其他格式只依赖文件头检查
+fn has_image_trailer(format: ImageFormat, tail: &[u8]) -> bool {
+    match format {
+        ImageFormat::Png => tail.ends_with(&PNG_TRAILER),
+        ImageFormat::Jpeg => tail.ends_with(&JPEG_TRAILER),
+        _ => true,
+    }
+}
+
+/// 已生成的 PDF 是否完整：以 `%PDF-` 开头，末尾 [`PDF_TAIL_BYTES`] 字节内有 `%%EOF`
+pub async fn is_complete_pdf(path: &Path) -> bool {
+    let Ok(mut file) = tokio::fs::File::open(path).await else {
+        return false;
+    };
+    let mut header = [0u8; 5];
+    if file.read_exact(&mut header).await.is_err() || &header != b"%PDF-" {
+        return false;
+    }
+    let len = file.metadata().await.map(|meta| meta.len()).unwrap_or(0);
+    let tail_len = len.min(PDF_TAIL_BYTES as u64);
+    let mut tail = Vec::with_capacity(tail_len as usize);
+    if file.seek(SeekFrom::Start(len - tail_len)).await.is_err() || file.read_to_end(&mut tail).await.is_err() {
+        return false;
+    }
+    tail.windows(5).any(|window| window == b"%%EOF")
 }
 
-/// 创建下载目录结构
-pub fn create_download_dir(comic_id: i64, chapter_id: i64) -> Result<PathBuf> {
-    let base_dir = PathBuf::from("./download");
-    let comic_dir = base_dir.join(comic_id.to_string());
-    let chapter_dir = comic_dir.join(chapter_id.to_string());
+/// 章节下载目录路径 `{download_root}/{comic_id}/{chapter_id}`
+pub fn chapter_dir_path(comic_id: i64, chapter_id: i64) -> PathBuf {
+    download_root()
+        .join(comic_id.to_string())
+        .join(chapter_id.to_string())
+}
+
+/// PDF 页面来源
+pub enum PdfPage {
+    /// 磁盘上的图片文件（需重新解码）
+    File(PathBuf),
+    /// 拼接后仍在内存中的 RGB 图像
+    Rgb(RgbImage),
+    /// 电子墨水屏优化后的灰度图像
+    Gray(GrayImage),
+    /// 下载后尚未解码的图片（如标题页后的封面）
+    Encoded(Bytes),
+}
+
+impl PdfPage {
+    /// 按给定参数把内存图像编码为 PNG；磁盘文件无需保存，返回空内容
+    fn encode_png(&self, settings: PngSettings) -> image::ImageResult<Vec<u8>> {
+        match self {
+            PdfPage::File(_) => Ok(Vec::new()),
+            PdfPage::Rgb(image) => png::encode_png(image, settings),
+            PdfPage::Gray(image) => png::encode_png(image, settings),
+            PdfPage::Encoded(data) => Ok(data.to_vec()),
+        }
+    }
 
-    std::fs::create_dir_all(&chapter_dir).map_err(|e| AppError::Internal(format!(
-        "创建目录 {} 失败: {}",
-        chapter_dir.display(),
-        e
-    )))?;
+    /// 用项目自身的 image 解码为内存像素页面；printpdf 自带的 image 版本不支持 WebP，原样保存的 WebP 页面无法交给它解码
+    fn decoded(image: DynamicImage) -> PdfPage {
+        match image {
+            DynamicImage::ImageLuma8(gray) => PdfPage::Gray(gray),
+            image => PdfPage::Rgb(image.into_rgb8()),
+        }
+    }
 
-    Ok(chapter_dir)
+    /// 转换为 printpdf 的图片对象
+    fn into_pdf_image(self) -> Result<PdfImage> {
+        match self {
+            PdfPage::File(path) => {
+                let image = image::open(&path)
+                    .map_err(|e| AppError::Internal(format!(
+                        "读取图片失败: {}: {}",
+                        path.display(),
+                        e
+                    )))?;
+                PdfPage::decoded(image).into_pdf_image()
+            }
+            PdfPage::Encoded(data) => {
+                let image = image::load_from_memory(&data)
+                    .map_err(|e| AppError::Internal(format!("解码图片失败: {}", e)))?;
+                PdfPage::decoded(image).into_pdf_image()
+            }
+            // 直接使用原始 RGB 像素，无需编码/解码
+            PdfPage::Rgb(image) => Ok(PdfImage::from(ImageXObject {
+                width: Px(image.width() as usize),
+                height: Px(image.height() as usize),
+                color_space: ColorSpace::Rgb,
+                bits_per_component: ColorBits::Bit8,
+                interpolate: true,
+                image_data: image.into_raw(),
+                image_filter: None,
+                smask: None,
+                clipping_bbox: None,
+            })),
+            PdfPage::Gray(image) => Ok(PdfImage::from(ImageXObject {
+                width: Px(image.width() as usize),
+                height: Px(image.height() as usize),
+                color_space: ColorSpace::Greyscale,
+                bits_per_component: ColorBits::Bit8,
+                interpolate: true,
+                image_data: image.into_raw(),
+                image_filter: None,
+                smask: None,
+                clipping_bbox: None,
+            })),
+        }
+    }
 }
 
 /// 合并图片为PDF
-pub async fn merge_images_to_pdf(image_paths: &[PathBuf], output_path: &Path) -> Result<()> {
-    let image_paths = image_paths.to_vec();
+///
+/// 每 `batch_pages` 页写出一个分段 PDF 并立即释放内存，避免超大漫画一次性占满内存。
+/// 页数不超过一批时直接写到 `output_path`；否则写为 `xxx.part1.pdf`、`xxx.part2.pdf`...，
+/// 由 [`compress_pdf_with_gs`] 合并为最终文件。返回实际写出的 PDF 文件列表（按顺序）。
+/// 传入 `title` 时在第一个分段开头插入标题页。
+#[tracing::instrument(skip_all, fields(pages = pages.len(), path = %output_path.display()))]
+pub async fn merge_images_to_pdf(
+    pages: Vec<PdfPage>,
+    output_path: &Path,
+    batch_pages: usize,
+    title: Option<TitlePage>,
+) -> Result<Vec<PathBuf>> {
     let output_path = output_path.to_path_buf();
+    let batch_pages = batch_pages.max(1);
 
-    tokio::task::spawn_blocking(move || -> Result<()> {
-        if image_paths.is_empty() {
+    tokio::task::spawn_blocking(move || -> Result<Vec<PathBuf>> {
+        if pages.is_empty() {
             return Err(AppError::Internal("没有可合并的图片".to_string()));
         }
 
-        let first_image = printpdf::image_crate::open(&image_paths[0])
-            .map_err(|e| AppError::Internal(format!(
-                "读取图片失败: {}: {}",
-                image_paths[0].display(),
-                e
-            )))?;
-        let (width, height) = (first_image.width(), first_image.height());
+        if pages.len() <= batch_pages {
+            write_pdf(pages, &output_path, title.as_ref())?;
+            return Ok(vec![output_path]);
+        }
 
-        let (doc, page1, layer1) = PdfDocument::new(
-            "jm-downloader-rs",
-            px_to_mm(width),
-            px_to_mm(height),
-            "Layer 1",
-        );
-        let current_layer = doc.get_page(page1).get_layer(layer1);
-        PdfImage::from_dynamic_image(&first_image).add_to_layer(
-            current_layer,
+        let total_batches = pages.len().div_ceil(batch_pages);
+        let mut parts = Vec::with_capacity(total_batches);
+        let mut pages = pages.into_iter();
+        loop {
+            let batch: Vec<PdfPage> = pages.by_ref().take(batch_pages).collect();
+            if batch.is_empty() {
+                break;
+            }
+            let part_path = part_path(&output_path, parts.len() + 1);
+            info!(
+                "写入PDF分段 {}/{}（{} 页）: {}",
+                parts.len() + 1,
+                total_batches,
+                batch.len(),
+                part_path.display()
+            );
+            let title = if parts.is_empty() { title.as_ref() } else { None };
+            write_pdf(batch, &part_path, title)?;
+            parts.push(part_path);
+        }
+        Ok(parts)
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("合并PDF任务崩溃: {}", e)))?
+}
+
+/// 分段 PDF 路径：merged.pdf -> merged.part1.pdf
+fn part_path(output_path: &Path, index: usize) -> PathBuf {
+    let stem = output_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("merged");
+    output_path.with_file_name(format!("{}.part{}.pdf", stem, index))
+}
+
+/// 将一批页面写成单个 PDF 文件，有标题页时以漫画标题作为文档标题
+fn write_pdf(pages: Vec<PdfPage>, output_path: &Path, title: Option<&TitlePage>) -> Result<()> {
+    let doc = PdfDocument::empty(title.map_or("jm-downloader-rs", |title| title.title.as_str()));
+    if let Some(title) = title {
+        title.add_to(&doc)?;
+    }
+    for page in pages {
+        let image = page.into_pdf_image()?;
+        let (width, height) = (image.image.width.0 as u32, image.image.height.0 as u32);
+        let (page, layer) = doc.add_page(px_to_mm(width), px_to_mm(height), "Layer 1");
+        let layer_ref = doc.get_page(page).get_layer(layer);
+        image.add_to_layer(
+            layer_ref,
             ImageTransform {
                 translate_x: Some(Mm(0.0)),
                 translate_y: Some(Mm(0.0)),
@@ -218,94 +1077,436 @@ pub async fn merge_images_to_pdf(image_paths: &[PathBuf], output_path: &Path) ->
                 dpi: Some(PDF_DPI),
             },
         );
+    }
 
-        for path in image_paths.iter().skip(1) {
-            let image = printpdf::image_crate::open(path)
-                .map_err(|e| AppError::Internal(format!(
-                    "读取图片失败: {}: {}",
-                    path.display(),
-                    e
-                )))?;
-            let (width, height) = (image.width(), image.height());
-            let (page, layer) = doc.add_page(px_to_mm(width), px_to_mm(height), "Layer 1");
-            let layer_ref = doc.get_page(page).get_layer(layer);
-            PdfImage::from_dynamic_image(&image).add_to_layer(
-                layer_ref,
-                ImageTransform {
-                    translate_x: Some(Mm(0.0)),
-                    translate_y: Some(Mm(0.0)),
-                    rotate: None,
-                    scale_x: Some(1.0),
-                    scale_y: Some(1.0),
-                    dpi: Some(PDF_DPI),
-                },
-            );
+    atomic_file::persist_with(output_path, |part| {
+        let mut writer = BufWriter::new(File::create(part)?);
+        doc.save(&mut writer).map_err(io::Error::other)?;
+        writer.flush()
+    })
+    .map_err(|e| AppError::Internal(format!("写入PDF文件 {} 失败: {}", output_path.display(), e)))
+}
+
+fn px_to_mm(px: u32) -> Mm {
+    Mm(px as f32 * (25.4 / PDF_DPI))
+}
+
+/// GhostScript 处理选项
+pub struct GsOptions<'a> {
+    pub quality: PdfQuality,
+    /// 图片降采样目标 DPI
+    pub dpi: Option<u32>,
+    /// 加密密码
+    pub password: Option<&'a str>,
+    /// 存在重复页面，需由 GhostScript 合并重复图片以缩小体积
+    pub dedupe_images: bool,
+    /// 标题页嵌入了完整字体，需由 GhostScript 只保留用到的字形
+    pub subset_fonts: bool,
+}
+
+impl GsOptions<'_> {
+    /// 是否可以完全跳过 GhostScript（无需压缩、加密与分段合并）
+    pub fn can_skip(&self, inputs: &[PathBuf]) -> bool {
+        self.quality == PdfQuality::None
+            && self.dpi.is_none()
+            && self.password.is_none()
+            && !self.dedupe_images
+            && !self.subset_fonts
+            && inputs.len() == 1
+    }
+}
+
+/// 使用GhostScript压缩PDF并可选加密
+///
+/// `inputs` 为多个分段时按顺序合并为 `pdf_path`，合并后删除分段文件
+#[tracing::instrument(skip_all, fields(inputs = inputs.len(), path = %pdf_path.display()))]
+pub async fn compress_pdf_with_gs(
+    inputs: &[PathBuf],
+    pdf_path: &Path,
+    options: GsOptions<'_>,
+) -> Result<()> {
+    let inputs = inputs.to_vec();
+    let pdf_path = pdf_path.to_path_buf();
+    let password = options.password.map(|value| value.to_string());
+    let quality = options.quality;
+    let dpi = options.dpi;
+    let dedupe_images = options.dedupe_images;
+
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let tmp_path = tmp_pdf_path(&pdf_path);
+
+        info!("开始压缩PDF: {}（{} 个输入文件）", pdf_path.display(), inputs.len());
+        let mut cmd = gs_command(password.as_deref());
+        match quality.gs_setting() {
+            Some(setting) => {
+                cmd.arg(format!("-dPDFSETTINGS={}", setting));
+            }
+            // 不压缩：关闭图片降采样与有损重编码，仅做加密/合并
+            None => add_lossless_args(&mut cmd),
+        }
+        if let Some(dpi) = dpi {
+            cmd.arg("-dDownsampleColorImages=true")
+                .arg("-dDownsampleGrayImages=true")
+                .arg("-dColorImageDownsampleThreshold=1.0")
+                .arg("-dGrayImageDownsampleThreshold=1.0")
+                .arg(format!("-dColorImageResolution={}", dpi))
+                .arg(format!("-dGrayImageResolution={}", dpi));
         }
+        if dedupe_images {
+            cmd.arg("-dDetectDuplicateImages=true");
+        }
+        cmd.arg("-o").arg(&tmp_path).args(&inputs);
+        run_gs(cmd)?;
 
-        let mut writer = BufWriter::new(File::create(&output_path).map_err(|e| {
-            AppError::Internal(format!("创建PDF文件失败: {}: {}", output_path.display(), e))
-        })?);
-        doc.save(&mut writer)
-            .map_err(|e| AppError::Internal(format!("写入PDF失败: {}", e)))?;
+        std::fs::rename(&tmp_path, &pdf_path).map_err(|e| {
+            AppError::Internal(format!("替换PDF文件失败: {}: {}", pdf_path.display(), e))
+        })?;
+        for input in inputs.iter().filter(|input| **input != pdf_path) {
+            if let Err(e) = std::fs::remove_file(input) {
+                warn!("删除PDF分段 {} 失败: {}", input.display(), e);
+            }
+        }
+        info!("PDF压缩完成: {}", pdf_path.display());
         Ok(())
     })
     .await
-    .map_err(|e| AppError::Internal(format!("合并PDF任务崩溃: {}", e)))??;
+    .map_err(|e| AppError::Internal(format!("PDF压缩任务崩溃: {}", e)))??;
 
     Ok(())
 }
 
-fn px_to_mm(px: u32) -> Mm {
-    Mm(px as f32 * (25.4 / PDF_DPI))
-}
-
-/// 使用GhostScript压缩PDF并可选加密
-pub async fn compress_pdf_with_gs(pdf_path: &Path, password: Option<&str>) -> Result<()> {
+/// 按页数和/或文件大小把 PDF 拆分为多个分卷（merged_part1.pdf、merged_part2.pdf...）
+///
+/// 先按页数上限和文件大小估算每卷页数，拆分后若某卷仍超出大小上限则继续对半拆分，
+/// 单页即超限时保留并输出警告。无需拆分时返回原文件。
+#[tracing::instrument(skip_all, fields(total_pages = total_pages, path = %pdf_path.display()))]
+pub async fn split_pdf(
+    pdf_path: &Path,
+    total_pages: usize,
+    max_pages: Option<usize>,
+    max_size_mb: Option<u64>,
+    password: Option<&str>,
+) -> Result<Vec<PathBuf>> {
     let pdf_path = pdf_path.to_path_buf();
     let password = password.map(|value| value.to_string());
 
-    tokio::task::spawn_blocking(move || -> Result<()> {
-        let file_name = pdf_path
-            .file_name()
-            .and_then(|name| name.to_str())
-            .unwrap_or("merged.pdf");
-        let tmp_path = pdf_path.with_file_name(format!("{}.tmp", file_name));
-
-        info!("开始压缩PDF: {}", pdf_path.display());
-        let mut cmd = Command::new("gs");
-        cmd.arg("-q")
+    tokio::task::spawn_blocking(move || -> Result<Vec<PathBuf>> {
+        let max_bytes = max_size_mb.map(|mb| mb.saturating_mul(1024 * 1024));
+        let file_size = std::fs::metadata(&pdf_path)
+            .map_err(|e| AppError::Internal(format!("读取PDF大小失败: {}: {}", pdf_path.display(), e)))?
+            .len();
+
+        let mut pages_per_volume = max_pages.unwrap_or(total_pages).max(1);
+        if let Some(max_bytes) = max_bytes.filter(|max| file_size > *max) {
+            // 按平均每页大小估算，预留 10% 余量
+            let estimate = (total_pages as f64 * max_bytes as f64 / file_size as f64 * 0.9) as usize;
+            pages_per_volume = pages_per_volume.min(estimate.max(1));
+        }
+        if pages_per_volume >= total_pages {
+            return Ok(vec![pdf_path]);
+        }
+
+        // 待处理的页码范围（1 起始，闭区间），按顺序处理
+        let mut pending: Vec<(usize, usize)> = (0..total_pages)
+            .step_by(pages_per_volume)
+            .map(|start| (start + 1, (start + pages_per_volume).min(total_pages)))
+            .rev()
+            .collect();
+        let mut volumes = Vec::new();
+        while let Some((first, last)) = pending.pop() {
+            let volume_path = pdf_path.with_file_name(format!("volume_{}-{}.pdf.tmp", first, last));
+            let mut cmd = gs_command(password.as_deref());
+            if let Some(pwd) = password.as_deref() {
+                cmd.arg(format!("-sPDFPassword={}", pwd));
+            }
+            add_lossless_args(&mut cmd);
+            cmd.arg(format!("-dFirstPage={}", first))
+                .arg(format!("-dLastPage={}", last))
+                .arg("-o")
+                .arg(&volume_path)
+                .arg(&pdf_path);
+            run_gs(cmd)?;
+
+            let size = std::fs::metadata(&volume_path).map(|meta| meta.len()).unwrap_or(0);
+            if let Some(max_bytes) = max_bytes.filter(|max| size > *max) {
+                if last > first {
+                    let mid = first + (last - first) / 2;
+                    let _ = std::fs::remove_file(&volume_path);
+                    pending.push((mid + 1, last));
+                    pending.push((first, mid));
+                    continue;
+                }
+                warn!("第 {} 页单独成卷仍超过大小上限 {} 字节，保留该分卷", first, max_bytes);
+            }
+            volumes.push(volume_path);
+        }
+
+        let stem = pdf_path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("merged")
+            .to_string();
+        let mut outputs = Vec::with_capacity(volumes.len());
+        for (index, volume_path) in volumes.into_iter().enumerate() {
+            let output = pdf_path.with_file_name(format!("{}_part{}.pdf", stem, index + 1));
+            std::fs::rename(&volume_path, &output).map_err(|e| {
+                AppError::Internal(format!("重命名PDF分卷失败: {}: {}", output.display(), e))
+            })?;
+            outputs.push(output);
+        }
+        info!("PDF已拆分为 {} 个分卷: {}", outputs.len(), pdf_path.display());
+        Ok(outputs)
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("PDF拆分任务崩溃: {}", e)))?
+}
+
+/// 用 GhostScript 读取 PDF 的实际页数（含拆分跨页后多出的页与标题页）
+pub async fn pdf_page_count(pdf_path: &Path) -> Result<usize> {
+    let pdf_path = pdf_path.to_path_buf();
+    tokio::task::spawn_blocking(move || -> Result<usize> {
+        let path = pdf_path.to_string_lossy();
+        // PostScript 字符串中的反斜杠与括号需要转义
+        let ps_path = path.replace('\\', "\\\\").replace('(', "\\(").replace(')', "\\)");
+        let output = Command::new("gs")
+            .arg("-q")
+            .arg("-dNODISPLAY")
             .arg("-dNOPAUSE")
             .arg("-dBATCH")
-            .arg("-sDEVICE=pdfwrite");
-        if let Some(pwd) = password.as_deref() {
-            cmd.arg(format!("-sUserPassword={}", pwd))
-                .arg(format!("-sOwnerPassword={}", pwd));
-        }
-        cmd.arg("-dPDFSETTINGS=/printer")
             .arg("-dSAFER")
-            .arg("-o")
-            .arg(&tmp_path)
-            .arg(&pdf_path);
-
-        let output = cmd
+            .arg(format!("--permit-file-read={}", path))
+            .arg("-c")
+            .arg(format!("({}) (r) file runpdfbegin pdfpagecount = quit", ps_path))
             .output()
             .map_err(|e| AppError::Internal(format!("执行GhostScript失败: {}", e)))?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
         if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
             return Err(AppError::Internal(format!(
-                "GhostScript处理失败: {}",
-                stderr.trim()
+                "GhostScript读取PDF页数失败: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
             )));
         }
-
-        std::fs::rename(&tmp_path, &pdf_path).map_err(|e| {
-            AppError::Internal(format!("替换PDF文件失败: {}: {}", pdf_path.display(), e))
-        })?;
-        info!("PDF压缩完成: {}", pdf_path.display());
-        Ok(())
+        stdout
+            .lines()
+            .rev()
+            .find_map(|line| line.trim().parse().ok())
+            .ok_or_else(|| AppError::Internal(format!("无法解析PDF页数: {}", stdout.trim())))
     })
     .await
-    .map_err(|e| AppError::Internal(format!("PDF压缩任务崩溃: {}", e)))??;
+    .map_err(|e| AppError::Internal(format!("读取PDF页数任务崩溃: {}", e)))?
+}
+
+fn tmp_pdf_path(pdf_path: &Path) -> PathBuf {
+    let file_name = pdf_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("merged.pdf");
+    pdf_path.with_file_name(format!("{}.tmp", file_name))
+}
 
+/// 构造 GhostScript pdfwrite 命令（含可选加密参数）
+fn gs_command(password: Option<&str>) -> Command {
+    let mut cmd = Command::new("gs");
+    cmd.arg("-q")
+        .arg("-dNOPAUSE")
+        .arg("-dBATCH")
+        .arg("-sDEVICE=pdfwrite")
+        .arg("-dSAFER");
+    if let Some(pwd) = password {
+        cmd.arg(format!("-sUserPassword={}", pwd))
+            .arg(format!("-sOwnerPassword={}", pwd));
+    }
+    cmd
+}
+
+/// 关闭图片降采样与有损重编码
+fn add_lossless_args(cmd: &mut Command) {
+    cmd.arg("-dAutoFilterColorImages=false")
+        .arg("-dAutoFilterGrayImages=false")
+        .arg("-dColorImageFilter=/FlateEncode")
+        .arg("-dGrayImageFilter=/FlateEncode")
+        .arg("-dDownsampleColorImages=false")
+        .arg("-dDownsampleGrayImages=false");
+}
+
+fn run_gs(mut cmd: Command) -> Result<()> {
+    let output = cmd
+        .output()
+        .map_err(|e| AppError::Internal(format!("执行GhostScript失败: {}", e)))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::Internal(format!(
+            "GhostScript处理失败: {}",
+            stderr.trim()
+        )));
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_blocked_placeholders() {
+        assert!(blocked_placeholder(&ImageBody::Memory(Bytes::from_static(b"GIF89a"))).is_some());
+        // 能解码的小图片（如空白页）不是占位图，除非命中配置的 MD5
+        let small = png::encode_png(&RgbImage::new(8, 8), PngSettings::default()).unwrap();
+        assert!(small.len() < BLOCKED_IMAGE_MAX_BYTES);
+        let small = ImageBody::Memory(Bytes::from(small));
+        assert!(blocked_placeholder(&small).is_none());
+        let page = ImageBody::Memory(Bytes::from(vec![7u8; 4096]));
+        assert!(blocked_placeholder(&page).is_none());
+        set_blocked_image_md5(vec![format!("{:x}", md5::compute(vec![7u8; 4096]))]);
+        assert!(blocked_placeholder(&page).is_some());
+        set_blocked_image_md5(Vec::new());
+    }
+
+    #[tokio::test]
+    async fn cpu_pool_panic_becomes_internal_error() {
+        let err = run_on_cpu_pool(|| -> u32 { panic!("解码失败") }).await.unwrap_err();
+        match err {
+            AppError::Internal(msg) => assert!(msg.contains("解码失败"), "{}", msg),
+            other => panic!("unexpected error: {:?}", other),
+        }
+        // 线程池在 panic 后仍可继续执行任务
+        assert_eq!(run_on_cpu_pool(|| 7).await.unwrap(), 7);
+    }
+
+    #[test]
+    fn recognizes_truncated_images() {
+        let png = png::encode_png(&RgbImage::new(2, 2), PngSettings::default()).unwrap();
+        let tail = &png[png.len() - PNG_TRAILER.len()..];
+        assert!(has_image_trailer(ImageFormat::Png, tail));
+        assert!(!has_image_trailer(ImageFormat::Png, &png[png.len() - 20..png.len() - 8]));
+        assert!(has_image_trailer(ImageFormat::Jpeg, &[0x00, 0xFF, 0xD9]));
+        assert!(!has_image_trailer(ImageFormat::Jpeg, &[0xFF, 0xD9, 0x00]));
+        assert!(has_image_trailer(ImageFormat::WebP, &[]));
+    }
+
+    #[test]
+    fn spread_splits_in_reading_order() {
+        // 左半红、右半蓝的 4x2 跨页
+        let spread = RgbImage::from_fn(4, 2, |x, _| {
+            if x < 2 { image::Rgb([255, 0, 0]) } else { image::Rgb([0, 0, 255]) }
+        });
+        assert!(is_spread(4, 2));
+        assert!(!is_spread(2, 3));
+
+        let [first, second] = split_spread(&spread, SpreadOrder::Rtl);
+        assert_eq!(first.get_pixel(0, 0), &image::Rgb([0, 0, 255]));
+        assert_eq!(second.get_pixel(0, 0), &image::Rgb([255, 0, 0]));
+
+        let [first, _] = split_spread(&spread, SpreadOrder::Ltr);
+        assert_eq!(first.get_pixel(0, 0), &image::Rgb([255, 0, 0]));
+    }
+
+    #[test]
+    fn page_file_names_keep_order_and_avoid_collisions() {
+        let names: Vec<String> = ["00002.webp", "a/b.jpg", "a_b.webp", "x-1R.webp", ".webp"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let numbered = ProcessOptions::default();
+        assert_eq!(page_file_names(&names[..2], &numbered), ["0001.png", "0002.png"]);
+
+        let preserve = ProcessOptions { preserve_filenames: true, eink: Some(1600), ..Default::default() };
+        assert_eq!(
+            page_file_names(&names, &preserve),
+            ["00002.png", "a_b.png", "a_b_2.png", "x_1R.png", "0005.png"]
+        );
+        assert_eq!(original_file_name("0001.png", "00001.WEBP"), "0001.webp");
+        assert_eq!(original_file_name("0001.png", "00001.gif"), "0001.png");
+    }
+
+    #[tokio::test]
+    async fn keep_original_saves_source_bytes_only_without_stitching() {
+        let dir = std::env::temp_dir().join(format!("jm-keep-original-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut jpeg = Vec::new();
+        image::DynamicImage::ImageRgb8(RgbImage::from_pixel(40, 60, image::Rgb([200, 100, 50])))
+            .write_to(&mut std::io::Cursor::new(&mut jpeg), ImageFormat::Jpeg)
+            .unwrap();
+        let options = ProcessOptions { keep_original: true, ..Default::default() };
+
+        let kept = dir.join("0001.jpg");
+        process_image(Bytes::from(jpeg.clone()), 0, Some(&kept), false, false, options).await.unwrap();
+        assert_eq!(std::fs::read(&kept).unwrap(), jpeg);
+
+        // 需要拼接的页面仍转码为 PNG
+        let stitched = dir.join("0002.png");
+        process_image(Bytes::from(jpeg), 10, Some(&stitched), false, false, options).await.unwrap();
+        assert!(std::fs::read(&stitched).unwrap().starts_with(b"\x89PNG"));
+        assert!(!options.keeps_original(10));
+        assert!(!ProcessOptions { eink: Some(1600), ..options }.keeps_original(0));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn merges_original_webp_pages() {
+        let dir = std::env::temp_dir().join(format!("jm-merge-webp-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut webp = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::from_pixel(40, 60, image::Rgb([200, 100, 50])))
+            .write_to(&mut std::io::Cursor::new(&mut webp), ImageFormat::WebP)
+            .unwrap();
+        // 原样保存的 WebP 页面以文件形式合并，封面等未解码页面以内存形式合并
+        let kept = dir.join("0001.webp");
+        std::fs::write(&kept, &webp).unwrap();
+        let pages = vec![PdfPage::File(kept), PdfPage::Encoded(Bytes::from(webp))];
+
+        let output = dir.join("merged.pdf");
+        let written = merge_images_to_pdf(pages, &output, 10, None).await.unwrap();
+        assert_eq!(written, std::slice::from_ref(&output));
+        assert!(is_complete_pdf(&output).await);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn trims_uniform_borders_with_safety_margin() {
+        // 200x300 的内容四周加 50 像素白边，白边上带少量噪点
+        let content = image::Rgb([90, 60, 30]);
+        let mut bordered = RgbImage::from_fn(300, 400, |x, y| {
+            let inside = (50..250).contains(&x) && (50..350).contains(&y);
+            if inside { content } else { image::Rgb([250, 252, 247]) }
+        });
+        bordered.put_pixel(10, 10, image::Rgb([0, 0, 0]));
+        let trimmed = trim_borders(bordered);
+        // 安全边距为短边的 1%（3 像素）与 4 像素中的较大者
+        assert_eq!(trimmed.dimensions(), (208, 308));
+        assert_eq!(*trimmed.get_pixel(4, 4), content);
+        assert_ne!(*trimmed.get_pixel(3, 3), content);
+
+        // 黑边只在左右两侧
+        let letterboxed = RgbImage::from_fn(400, 300, |x, _| {
+            if (40..360).contains(&x) { content } else { image::Rgb([5, 5, 5]) }
+        });
+        assert_eq!(trim_borders(letterboxed).dimensions(), (328, 300));
+
+        // 空白页、无边框页与大面积留白的页面不裁剪
+        let blank = RgbImage::from_pixel(100, 100, image::Rgb([255, 255, 255]));
+        assert_eq!(trim_borders(blank).dimensions(), (100, 100));
+        let full = RgbImage::from_pixel(100, 100, content);
+        assert_eq!(trim_borders(full).dimensions(), (100, 100));
+        let sparse = RgbImage::from_fn(300, 400, |x, y| {
+            if (100..200).contains(&x) && (180..220).contains(&y) { content } else { image::Rgb([255, 255, 255]) }
+        });
+        assert_eq!(trim_borders(sparse).dimensions(), (300, 400));
+        assert!(!ProcessOptions { keep_original: true, trim_borders: true, ..Default::default() }.keeps_original(0));
+    }
+
+    #[test]
+    fn eink_stretches_contrast_and_limits_long_edge() {
+        // 灰度只在 100~150 之间的低对比度图片
+        let flat = RgbImage::from_fn(400, 200, |x, _| {
+            let level = if x < 200 { 100 } else { 150 };
+            image::Rgb([level, level, level])
+        });
+        let page = eink_page(DynamicImage::ImageRgb8(flat), 100);
+        assert_eq!(page.dimensions(), (100, 50));
+        assert_eq!(page.get_pixel(0, 0).0[0], 0);
+        assert_eq!(page.get_pixel(99, 0).0[0], 255);
+    }
+}
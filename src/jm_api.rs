@@ -0,0 +1,90 @@
+// JMComic 接口抽象
+// 移动端 API 客户端与网页端客户端共同实现该 trait，便于互为备用
+
+use std::future::Future;
+
+use jm_downloader_rs::AppError;
+
+use crate::models::{CheckinData, CommentData, ComicSummary, GetChapterRespData, GetComicRespData, LikeData, UserProfile};
+
+type Result<T> = std::result::Result<T, AppError>;
+
+/// 搜索结果的一页，已去掉重复条目
+#[derive(Debug, Default)]
+pub struct SearchPage {
+    /// 结果总数
+    pub total: usize,
+    pub comics: Vec<ComicSummary>,
+    /// 查询为车号时 JM 不返回列表，只返回要跳转到的漫画 ID
+    pub redirect_aid: Option<i64>,
+}
+
+/// JMComic 数据来源的统一接口
+pub trait JmApi: Send + Sync {
+    /// 使用账号密码登录，登录态保存在客户端内部的 cookie 中
+    fn login(&self, username: &str, password: &str) -> impl Future<Output = Result<()>> + Send;
+
+    /// 获取漫画（本子）信息
+    fn get_comic(&self, aid: i64) -> impl Future<Output = Result<GetComicRespData>> + Send;
+
+    /// 获取章节信息（图片文件名列表）
+    fn get_chapter(&self, id: i64) -> impl Future<Output = Result<GetChapterRespData>> + Send;
+
+    /// 获取章节的 scramble_id
+    fn get_scramble_id(&self, id: i64) -> impl Future<Output = Result<i64>> + Send;
+
+    /// 最新上架列表，`page` 从 1 开始，默认不支持
+    fn latest(&self, _page: u32) -> impl Future<Output = Result<Vec<ComicSummary>>> + Send {
+        async { Err(AppError::BadRequest("当前数据源不支持最新上架列表".to_string())) }
+    }
+
+    /// 搜索漫画，`page` 从 1 开始，默认不支持
+    fn search(&self, _query: &str, _page: u32) -> impl Future<Output = Result<SearchPage>> + Send {
+        async { Err(AppError::BadRequest("当前数据源不支持搜索".to_string())) }
+    }
+
+    /// 用 JM 币购买漫画或章节，默认不支持
+    fn buy(&self, _id: i64) -> impl Future<Output = Result<()>> + Send {
+        async { Err(AppError::BadRequest("当前数据源不支持购买".to_string())) }
+    }
+
+    /// 本周推荐列表，`category` 为 manga/hanman/another，默认不支持
+    fn week_best(&self, _category: &str) -> impl Future<Output = Result<Vec<ComicSummary>>> + Send {
+        async { Err(AppError::BadRequest("当前数据源不支持每周推荐".to_string())) }
+    }
+
+    /// 漫画接口解密后的完整 JSON，不经模型过滤，用于排查解析失败；默认不支持
+    fn raw_album(&self, _aid: i64) -> impl Future<Output = Result<serde_json::Value>> + Send {
+        async { Err(AppError::BadRequest("当前数据源不支持获取原始数据".to_string())) }
+    }
+
+    /// 章节接口解密后的完整 JSON，默认不支持
+    fn raw_chapter(&self, _id: i64) -> impl Future<Output = Result<serde_json::Value>> + Send {
+        async { Err(AppError::BadRequest("当前数据源不支持获取原始数据".to_string())) }
+    }
+
+    /// 请求一个需要登录态的轻量接口以保持会话活跃，默认不做任何事
+    fn keep_alive(&self) -> impl Future<Output = Result<()>> + Send {
+        async { Ok(()) }
+    }
+
+    /// 获取当前登录账号的资料，默认不支持
+    fn user_profile(&self) -> impl Future<Output = Result<UserProfile>> + Send {
+        async { Err(AppError::BadRequest("当前数据源不支持获取账号资料".to_string())) }
+    }
+
+    /// 每日签到，默认不支持
+    fn checkin(&self) -> impl Future<Output = Result<CheckinData>> + Send {
+        async { Err(AppError::BadRequest("当前数据源不支持每日签到".to_string())) }
+    }
+
+    /// 给漫画点赞，默认不支持
+    fn like(&self, _aid: i64) -> impl Future<Output = Result<LikeData>> + Send {
+        async { Err(AppError::BadRequest("当前数据源不支持点赞".to_string())) }
+    }
+
+    /// 在漫画下发表评论，`reply_to` 为要回复的评论 ID，默认不支持
+    fn comment(&self, _aid: i64, _text: &str, _reply_to: Option<i64>) -> impl Future<Output = Result<CommentData>> + Send {
+        async { Err(AppError::BadRequest("当前数据源不支持发表评论".to_string())) }
+    }
+}
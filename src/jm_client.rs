@@ -1,4 +1,6 @@
-use std::sync::Arc;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use aes::cipher::generic_array::GenericArray;
@@ -11,17 +13,108 @@ use reqwest::cookie::Jar;
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
 use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware, Retryable, RetryableStrategy};
 use serde_json::{json, Value};
+use thiserror::Error;
 
-use crate::models::{GetChapterRespData, GetComicRespData, JmResp};
+use crate::jm_api::{JmApi, SearchPage};
+use crate::models::{
+    CheckinData, CommentData, ComicSummary, GetChapterRespData, GetComicRespData, JmResp, LikeData, UserProfile,
+};
 
 const APP_TOKEN_SECRET: &str = "18comicAPP";
 const APP_TOKEN_SECRET_2: &str = "18comicAPPContent";
-const APP_DATA_SECRET: &str = "185Hcomic3PAPP7R";
 const APP_VERSION: &str = "2.0.13";
+const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/128.0.0.0 Safari/537.36";
 
 type AppResult<T> = std::result::Result<T, AppError>;
 
-struct JmRetryStrategy;
+/// 默认的图片地址模板
+pub const DEFAULT_IMAGE_URL_TEMPLATE: &str = "https://{domain}/media/photos/{chapter_id}/{filename}";
+/// 图片地址模板支持的占位符
+/// 用 JM 币购买漫画或章节的接口
+const BUY_PATH: &str = "/coin_buy_comics";
+
+/// JM 搜索接口每页返回的条目数
+pub const SEARCH_PAGE_SIZE: usize = 80;
+
+const IMAGE_URL_PLACEHOLDERS: &[&str] = &["domain", "chapter_id", "filename", "ts"];
+
+/// 按模板拼接章节图片地址
+///
+/// 占位符：`{domain}` 图片域名、`{chapter_id}` 章节 ID、`{filename}` 图片文件名、`{ts}` 当前 Unix 时间戳（秒），
+/// 用于需要 `?v=` 等查询参数的 CDN 镜像
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageUrlBuilder {
+    template: String,
+    /// 图片域名，第一个为主域名，其余为图片被屏蔽时依次尝试的镜像
+    domains: Vec<String>,
+}
+
+impl ImageUrlBuilder {
+    /// 模板应已通过 [`check_image_url_template`] 校验
+    pub fn new(template: String, domains: Vec<String>) -> Self {
+        Self { template, domains }
+    }
+
+    /// 章节 `chapter_id` 中图片 `filename` 在各个图片域名上的下载地址，主域名在前
+    pub fn urls(&self, chapter_id: i64, filename: &str) -> Vec<String> {
+        let ts = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        let url = self
+            .template
+            .replace("{chapter_id}", &chapter_id.to_string())
+            .replace("{filename}", filename)
+            .replace("{ts}", &ts.to_string());
+        self.domains.iter().map(|domain| url.replace("{domain}", domain)).collect()
+    }
+
+    /// 图片域名，按使用顺序
+    pub fn domains(&self) -> &[String] {
+        &self.domains
+    }
+
+    /// 改用 `domains` 的顺序生成地址（按探测结果排序后调用）
+    pub fn with_domains(self, domains: Vec<String>) -> Self {
+        Self { domains, ..self }
+    }
+
+    /// 漫画封面地址（主图片域名）
+    pub fn cover(&self, comic_id: i64) -> String {
+        cover_url(self.domains.first().map_or("", String::as_str), comic_id)
+    }
+}
+
+/// 校验图片地址模板：必须包含 `{chapter_id}` 与 `{filename}`，且不能有未知占位符
+pub fn check_image_url_template(template: &str) -> Result<(), String> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| "占位符缺少右花括号".to_string())?;
+        let name = &rest[start + 1..start + end];
+        if !IMAGE_URL_PLACEHOLDERS.contains(&name) {
+            return Err(format!(
+                "未知占位符 {{{}}}，可用 {}",
+                name,
+                IMAGE_URL_PLACEHOLDERS.iter().map(|p| format!("{{{}}}", p)).collect::<Vec<_>>().join("、")
+            ));
+        }
+        rest = &rest[start + end + 1..];
+    }
+    for required in ["{chapter_id}", "{filename}"] {
+        if !template.contains(required) {
+            return Err(format!("缺少 {}", required));
+        }
+    }
+    if !template.starts_with("https://") && !template.starts_with("http://") {
+        return Err("必须以 http:// 或 https:// 开头".to_string());
+    }
+    Ok(())
+}
+
+/// JM 请求重试策略：网络错误、5xx 与 429 均视为可重试
+pub struct JmRetryStrategy;
 
 impl RetryableStrategy for JmRetryStrategy {
     fn handle(&self, res: &std::result::Result<reqwest::Response, reqwest_middleware::Error>) -> Option<Retryable> {
@@ -44,20 +137,35 @@ pub struct JmClient {
     client: ClientWithMiddleware,
     #[allow(dead_code)]
     cookie_jar: Arc<Jar>,
-    api_domain: String,
-    #[allow(dead_code)]
+    /// 移动端 API 域名，第一个为主域名，其余为被拦截时依次切换的备用域名
+    api_domains: Vec<String>,
+    /// 当前使用的域名下标
+    current_domain: AtomicUsize,
+    /// 主图片域名，用于封面与头像地址
     pub image_domain: String,
+    /// 章节图片地址模板与镜像域名
+    image_urls: ImageUrlBuilder,
+    /// 候选的数据解密密钥，按顺序尝试
+    data_secrets: Vec<String>,
+    /// 最近一次登录返回的账号资料
+    profile: Mutex<Option<UserProfile>>,
 }
 
 impl JmClient {
-    pub fn new(api_domain: String, image_domain: String) -> Self {
+    pub fn new(
+        api_domains: Vec<String>,
+        image_domains: Vec<String>,
+        image_url_template: String,
+        max_retries: u32,
+        data_secrets: Vec<String>,
+    ) -> Self {
         let cookie_jar = Arc::new(Jar::default());
         let reqwest_client = reqwest::Client::builder()
             .cookie_provider(cookie_jar.clone())
             .timeout(std::time::Duration::from_secs(30))
             .build()
             .unwrap();
-        let retry_policy = ExponentialBackoff::builder().build_with_max_retries(3);
+        let retry_policy = ExponentialBackoff::builder().build_with_max_retries(max_retries);
         let client = ClientBuilder::new(reqwest_client)
             .with(RetryTransientMiddleware::new_with_policy_and_strategy(
                 retry_policy,
@@ -68,12 +176,146 @@ impl JmClient {
         Self {
             client,
             cookie_jar,
-            api_domain,
-            image_domain,
+            api_domains,
+            current_domain: AtomicUsize::new(0),
+            image_domain: image_domains[0].clone(),
+            image_urls: ImageUrlBuilder::new(image_url_template, image_domains),
+            data_secrets,
+            profile: Mutex::new(None),
+        }
+    }
+
+    /// 当前使用的移动端 API 域名
+    pub fn api_domain(&self) -> &str {
+        &self.api_domains[self.current_domain.load(Ordering::Relaxed)]
+    }
+
+    /// 章节图片地址生成器
+    pub fn image_urls(&self) -> &ImageUrlBuilder {
+        &self.image_urls
+    }
+
+    /// 请求被拦截（`AppError::Blocked`）且配置了备用域名时切换到下一个域名重试，每个域名最多尝试一次
+    ///
+    /// 切换后新域名上没有登录态，需要登录的请求返回未登录错误后由 `GlobalJmClient` 重新登录
+    async fn with_failover<T, F, Fut>(&self, request: F) -> AppResult<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = AppResult<T>>,
+    {
+        let mut attempts = 1;
+        loop {
+            let index = self.current_domain.load(Ordering::Relaxed);
+            match request().await {
+                Err(AppError::Blocked(msg)) if attempts < self.api_domains.len() => {
+                    attempts += 1;
+                    self.switch_domain(index, &msg);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// 从下标 `from` 切换到下一个域名；并发请求同时被拦截时只切换一次
+    fn switch_domain(&self, from: usize, reason: &str) {
+        let next = (from + 1) % self.api_domains.len();
+        if self
+            .current_domain
+            .compare_exchange(from, next, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+        {
+            warn!(
+                "移动端 API 域名 {} 被拦截，切换到 {}: {}",
+                self.api_domains[from],
+                self.api_domains[next],
+                reason
+            );
         }
     }
 
-    pub async fn login(&self, username: &str, password: &str) -> AppResult<()> {
+    /// 请求需要登录态的 API 并返回解密后的 JSON 数据
+    #[tracing::instrument(name = "jm_api", skip_all, fields(path = path))]
+    async fn fetch_data(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        form: Option<&[(&str, String)]>,
+        what: &str,
+    ) -> AppResult<Value> {
+        self.with_failover(|| self.fetch_data_once(method.clone(), path, form, what))
+            .await
+    }
+
+    async fn fetch_data_once(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        form: Option<&[(&str, String)]>,
+        what: &str,
+    ) -> AppResult<Value> {
+        let ts = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| AppError::Internal(format!("系统时间异常: {}", e)))?
+            .as_secs();
+        let token = generate_token(ts, APP_TOKEN_SECRET);
+        let tokenparam = format!("{},{}", ts, APP_VERSION);
+
+        let url = format!("https://{}{}", self.api_domain(), path);
+        let mut request = self
+            .client
+            .request(method, &url)
+            .header("token", token)
+            .header("tokenparam", tokenparam)
+            .header("user-agent", USER_AGENT);
+        if let Some(form) = form {
+            request = request.form(form);
+        }
+        let http_resp = request
+            .send()
+            .await
+            .map_err(|e| AppError::Internal(format!("{}请求失败: {}", what, e)))?;
+
+        let status = http_resp.status();
+        let body = http_resp
+            .text()
+            .await
+            .map_err(|e| AppError::Internal(format!("读取{}响应失败: {}", what, e)))?;
+
+        if let Some(blocked) = self.blocked_response(status, &body) {
+            return Err(blocked);
+        }
+
+        if status != reqwest::StatusCode::OK {
+            return Err(classify_http_status(
+                status,
+                format!("{}失败，status {}: {}", what, status, body_snippet(&body)),
+            ));
+        }
+
+        let jm_resp: JmResp = serde_json::from_str(&body).map_err(|e| {
+            AppError::Internal(format!("解析{}响应失败: {}: {}", what, body_snippet(&body), e))
+        })?;
+
+        if jm_resp.code != 200 {
+            return Err(classify_jm_error(
+                jm_resp.code,
+                &jm_resp.error_msg,
+                format!("{}失败，code {}: {}", what, jm_resp.code, jm_resp.error_msg),
+            ));
+        }
+
+        let data = jm_resp
+            .data
+            .as_str()
+            .ok_or_else(|| AppError::Internal(format!("{}数据不是字符串", what)))?;
+
+        let decrypted_data = decrypt_data(ts, data, &self.data_secrets)?;
+        serde_json::from_str(&decrypted_data).map_err(|e| {
+            AppError::Internal(format!("解析{}解密数据失败: {}: {}", what, decrypted_data, e))
+        })
+    }
+
+    async fn login_once(&self, username: &str, password: &str) -> AppResult<()> {
         let ts = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .map_err(|e| AppError::Internal(format!("系统时间异常: {}", e)))?
@@ -86,13 +328,13 @@ impl JmClient {
             "password": password,
         });
 
-        let url = format!("https://{}/login", self.api_domain);
+        let url = format!("https://{}/login", self.api_domain());
         let http_resp = self
             .client
             .post(&url)
             .header("token", token)
             .header("tokenparam", tokenparam)
-            .header("user-agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/128.0.0.0 Safari/537.36")
+            .header("user-agent", USER_AGENT)
             .form(&form)
             .send()
             .await
@@ -104,28 +346,49 @@ impl JmClient {
             .await
             .map_err(|e| AppError::Internal(format!("读取登录响应失败: {}", e)))?;
 
+        if let Some(blocked) = self.blocked_response(status, &body) {
+            return Err(blocked);
+        }
+
         if status != reqwest::StatusCode::OK {
-            return Err(AppError::Internal(format!(
-                "Login failed with status {}: {}",
-                status, body
-            )));
+            return Err(classify_http_status(
+                status,
+                format!("Login failed with status {}: {}", status, body_snippet(&body)),
+            ));
         }
 
         let jm_resp: JmResp = serde_json::from_str(&body).map_err(|e| {
-            AppError::Internal(format!("Failed to parse login response: {}: {}", body, e))
+            AppError::Internal(format!("Failed to parse login response: {}: {}", body_snippet(&body), e))
         })?;
 
         if jm_resp.code != 200 {
-            return Err(AppError::Internal(format!(
-                "Login failed with code {}: {}",
-                jm_resp.code, jm_resp.error_msg
-            )));
+            return Err(classify_jm_error(
+                jm_resp.code,
+                &jm_resp.error_msg,
+                format!("Login failed with code {}: {}", jm_resp.code, jm_resp.error_msg),
+            ));
+        }
+
+        // 账号资料解析失败不影响登录本身
+        let profile = jm_resp
+            .data
+            .as_str()
+            .ok_or_else(|| AppError::Internal("Login data is not a string".to_string()))
+            .and_then(|data| decrypt_data(ts, data, &self.data_secrets))
+            .and_then(|data| {
+                serde_json::from_str::<Value>(&data)
+                    .map_err(|e| AppError::Internal(format!("解析登录数据失败: {}", e)))
+            })
+            .map(|value| parse_user_profile(&value, &self.image_domain));
+        match profile {
+            Ok(profile) => *self.profile.lock().unwrap() = Some(profile),
+            Err(e) => warn!("解析登录返回的账号资料失败: {}", e),
         }
 
         Ok(())
     }
 
-    pub async fn get_comic(&self, aid: i64) -> AppResult<GetComicRespData> {
+    async fn get_comic_once(&self, aid: i64) -> AppResult<GetComicRespData> {
         let ts = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .map_err(|e| AppError::Internal(format!("系统时间异常: {}", e)))?
@@ -133,13 +396,13 @@ impl JmClient {
         let token = generate_token(ts, APP_TOKEN_SECRET);
         let tokenparam = format!("{},{}", ts, APP_VERSION);
 
-        let url = format!("https://{}/album?id={}", self.api_domain, aid);
+        let url = format!("https://{}/album?id={}", self.api_domain(), aid);
         let http_resp = self
             .client
             .get(&url)
             .header("token", token)
             .header("tokenparam", tokenparam)
-            .header("user-agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/128.0.0.0 Safari/537.36")
+            .header("user-agent", USER_AGENT)
             .send()
             .await
             .map_err(|e| AppError::Internal(format!("获取漫画请求失败: {}", e)))?;
@@ -150,18 +413,22 @@ impl JmClient {
             .await
             .map_err(|e| AppError::Internal(format!("读取漫画响应失败: {}", e)))?;
 
+        if let Some(blocked) = self.blocked_response(status, &body) {
+            return Err(blocked);
+        }
+
         if status == reqwest::StatusCode::NOT_FOUND {
             return Err(AppError::NotFound(format!("漫画 {} 未找到", aid)));
         }
         if status != reqwest::StatusCode::OK {
-            return Err(AppError::Internal(format!(
-                "Get comic failed with status {}: {}",
-                status, body
-            )));
+            return Err(classify_http_status(
+                status,
+                format!("Get comic failed with status {}: {}", status, body_snippet(&body)),
+            ));
         }
 
         let jm_resp: JmResp = serde_json::from_str(&body).map_err(|e| {
-            AppError::Internal(format!("Failed to parse comic response: {}: {}", body, e))
+            AppError::Internal(format!("Failed to parse comic response: {}: {}", body_snippet(&body), e))
         })?;
 
         if jm_resp.code != 200 {
@@ -169,10 +436,11 @@ impl JmClient {
             if jm_resp.code == 404 || error_msg_lower.contains("not found") {
                 return Err(AppError::NotFound(format!("漫画 {} 未找到", aid)));
             }
-            return Err(AppError::Internal(format!(
-                "Get comic failed with code {}: {}",
-                jm_resp.code, jm_resp.error_msg
-            )));
+            return Err(classify_jm_error(
+                jm_resp.code,
+                &jm_resp.error_msg,
+                format!("Get comic failed with code {}: {}", jm_resp.code, jm_resp.error_msg),
+            ));
         }
 
         let data = jm_resp
@@ -180,7 +448,7 @@ impl JmClient {
             .as_str()
             .ok_or_else(|| AppError::Internal("Comic data is not a string".to_string()))?;
 
-        let decrypted_data = decrypt_data(ts, data)?;
+        let decrypted_data = decrypt_data(ts, data, &self.data_secrets)?;
         if raw_missing_comic(&decrypted_data) {
             return Err(AppError::NotFound(format!("漫画 {} 未找到", aid)));
         }
@@ -205,7 +473,7 @@ impl JmClient {
         Ok(comic)
     }
 
-    pub async fn get_chapter(&self, id: i64) -> AppResult<GetChapterRespData> {
+    async fn get_chapter_once(&self, id: i64) -> AppResult<GetChapterRespData> {
         let ts = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .map_err(|e| AppError::Internal(format!("系统时间异常: {}", e)))?
@@ -213,13 +481,13 @@ impl JmClient {
         let token = generate_token(ts, APP_TOKEN_SECRET);
         let tokenparam = format!("{},{}", ts, APP_VERSION);
 
-        let url = format!("https://{}/chapter?id={}", self.api_domain, id);
+        let url = format!("https://{}/chapter?id={}", self.api_domain(), id);
         let http_resp = self
             .client
             .get(&url)
             .header("token", token)
             .header("tokenparam", tokenparam)
-            .header("user-agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/128.0.0.0 Safari/537.36")
+            .header("user-agent", USER_AGENT)
             .send()
             .await
             .map_err(|e| AppError::Internal(format!("获取章节请求失败: {}", e)))?;
@@ -230,22 +498,27 @@ impl JmClient {
             .await
             .map_err(|e| AppError::Internal(format!("读取章节响应失败: {}", e)))?;
 
+        if let Some(blocked) = self.blocked_response(status, &body) {
+            return Err(blocked);
+        }
+
         if status != reqwest::StatusCode::OK {
-            return Err(AppError::Internal(format!(
-                "Get chapter failed with status {}: {}",
-                status, body
-            )));
+            return Err(classify_http_status(
+                status,
+                format!("Get chapter failed with status {}: {}", status, body_snippet(&body)),
+            ));
         }
 
         let jm_resp: JmResp = serde_json::from_str(&body).map_err(|e| {
-            AppError::Internal(format!("Failed to parse chapter response: {}: {}", body, e))
+            AppError::Internal(format!("Failed to parse chapter response: {}: {}", body_snippet(&body), e))
         })?;
 
         if jm_resp.code != 200 {
-            return Err(AppError::Internal(format!(
-                "Get chapter failed with code {}: {}",
-                jm_resp.code, jm_resp.error_msg
-            )));
+            return Err(classify_jm_error(
+                jm_resp.code,
+                &jm_resp.error_msg,
+                format!("Get chapter failed with code {}: {}", jm_resp.code, jm_resp.error_msg),
+            ));
         }
 
         let data = jm_resp
@@ -253,7 +526,7 @@ impl JmClient {
             .as_str()
             .ok_or_else(|| AppError::Internal("Chapter data is not a string".to_string()))?;
 
-        let decrypted_data = decrypt_data(ts, data)?;
+        let decrypted_data = decrypt_data(ts, data, &self.data_secrets)?;
         let chapter: GetChapterRespData = serde_json::from_str(&decrypted_data)
             .map_err(|e| {
                 AppError::Internal(format!(
@@ -265,7 +538,7 @@ impl JmClient {
         Ok(chapter)
     }
 
-    pub async fn get_scramble_id(&self, id: i64) -> AppResult<i64> {
+    async fn get_scramble_id_once(&self, id: i64) -> AppResult<i64> {
         let ts = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .map_err(|e| AppError::Internal(format!("系统时间异常: {}", e)))?
@@ -275,14 +548,14 @@ impl JmClient {
 
         let url = format!(
             "https://{}/chapter_view_template?id={}&v={}&mode=vertical&page=0&app_img_shunt=1&express=off",
-            self.api_domain, id, ts
+            self.api_domain(), id, ts
         );
         let http_resp = self
             .client
             .get(&url)
             .header("token", token)
             .header("tokenparam", tokenparam)
-            .header("user-agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/128.0.0.0 Safari/537.36")
+            .header("user-agent", USER_AGENT)
             .send()
             .await
             .map_err(|e| AppError::Internal(format!("获取 scramble_id 请求失败: {}", e)))?;
@@ -293,11 +566,15 @@ impl JmClient {
             .await
             .map_err(|e| AppError::Internal(format!("读取 scramble_id 响应失败: {}", e)))?;
 
+        if let Some(blocked) = self.blocked_response(status, &body) {
+            return Err(blocked);
+        }
+
         if status != reqwest::StatusCode::OK {
-            return Err(AppError::Internal(format!(
-                "Get scramble_id failed with status {}: {}",
-                status, body
-            )));
+            return Err(classify_http_status(
+                status,
+                format!("Get scramble_id failed with status {}: {}", status, body_snippet(&body)),
+            ));
         }
 
         // 从 HTML 响应中提取 scramble_id
@@ -310,6 +587,192 @@ impl JmClient {
 
         Ok(scramble_id)
     }
+
+    /// 响应是 Cloudflare/JM 的 HTML 拦截页面（人机验证、IP 封禁）时返回 `AppError::Blocked`，只保留简短说明
+    fn blocked_response(&self, status: reqwest::StatusCode, body: &str) -> Option<AppError> {
+        let page = challenge_page(status, body)?;
+        Some(AppError::Blocked(format!(
+            "请求被拦截：{} 返回了{}（HTTP {}），可能是 IP 被封或触发了人机验证",
+            self.api_domain(),
+            page,
+            status.as_u16()
+        )))
+    }
+}
+
+impl JmApi for JmClient {
+    #[tracing::instrument(name = "jm_api.login", skip_all)]
+    async fn login(&self, username: &str, password: &str) -> AppResult<()> {
+        self.with_failover(|| self.login_once(username, password)).await
+    }
+
+    #[tracing::instrument(name = "jm_api.get_comic", skip(self))]
+    async fn get_comic(&self, aid: i64) -> AppResult<GetComicRespData> {
+        self.with_failover(|| self.get_comic_once(aid)).await
+    }
+
+    #[tracing::instrument(name = "jm_api.get_chapter", skip(self))]
+    async fn get_chapter(&self, id: i64) -> AppResult<GetChapterRespData> {
+        self.with_failover(|| self.get_chapter_once(id)).await
+    }
+
+    #[tracing::instrument(name = "jm_api.get_scramble_id", skip(self))]
+    async fn get_scramble_id(&self, id: i64) -> AppResult<i64> {
+        self.with_failover(|| self.get_scramble_id_once(id)).await
+    }
+
+    async fn latest(&self, page: u32) -> AppResult<Vec<ComicSummary>> {
+        // JM 的最新上架列表页码从 0 开始
+        let data = self
+            .fetch_data(
+                reqwest::Method::GET,
+                &format!("/latest?page={}", page.saturating_sub(1)),
+                None,
+                "获取最新上架",
+            )
+            .await?;
+        Ok(parse_comic_list(&data, &self.image_domain))
+    }
+
+    async fn search(&self, query: &str, page: u32) -> AppResult<SearchPage> {
+        let url = reqwest::Url::parse_with_params(
+            "https://localhost/search",
+            &[("search_query", query), ("page", &page.to_string()), ("o", "mr")],
+        )
+        .map_err(|e| AppError::Internal(format!("构造搜索地址失败: {}", e)))?;
+        let data = self
+            .fetch_data(
+                reqwest::Method::GET,
+                &format!("/search?{}", url.query().unwrap_or_default()),
+                None,
+                "搜索漫画",
+            )
+            .await?;
+        Ok(parse_search(&data, &self.image_domain))
+    }
+
+    async fn buy(&self, id: i64) -> AppResult<()> {
+        let result = self
+            .fetch_data(reqwest::Method::POST, BUY_PATH, Some(&[("id", id.to_string())]), "购买漫画")
+            .await?;
+        debug!("购买 {} 返回: {}", id, result);
+        Ok(())
+    }
+
+    async fn week_best(&self, category: &str) -> AppResult<Vec<ComicSummary>> {
+        // 先取最近一期的期号，再按类别取该期的推荐列表
+        let weeks = self
+            .fetch_data(reqwest::Method::GET, "/week", None, "获取每周推荐期数")
+            .await?;
+        let week_id = weeks["categories"]
+            .as_array()
+            .and_then(|categories| categories.first())
+            .and_then(|week| json_i64(&week["id"]))
+            .ok_or_else(|| AppError::Internal(format!("每周推荐期数数据异常: {}", weeks)))?;
+
+        let data = self
+            .fetch_data(
+                reqwest::Method::GET,
+                &format!("/week/filter?id={}&type={}", week_id, category),
+                None,
+                "获取每周推荐",
+            )
+            .await?;
+        Ok(parse_comic_list(&data["list"], &self.image_domain))
+    }
+
+    async fn raw_album(&self, aid: i64) -> AppResult<Value> {
+        self.fetch_data(reqwest::Method::GET, &format!("/album?id={}", aid), None, "获取漫画原始数据")
+            .await
+    }
+
+    async fn raw_chapter(&self, id: i64) -> AppResult<Value> {
+        self.fetch_data(reqwest::Method::GET, &format!("/chapter?id={}", id), None, "获取章节原始数据")
+            .await
+    }
+
+    async fn keep_alive(&self) -> AppResult<()> {
+        // 收藏夹第一页需要登录态，且数据量小
+        self.fetch_data(
+            reqwest::Method::GET,
+            "/favorite?page=1&folder_id=0&o=mr",
+            None,
+            "会话保活",
+        )
+        .await
+        .map(|_| ())
+    }
+
+    async fn user_profile(&self) -> AppResult<UserProfile> {
+        self.profile
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| AppError::Internal("登录数据中没有账号资料".to_string()))
+    }
+
+    async fn checkin(&self) -> AppResult<CheckinData> {
+        let uid = self
+            .profile
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|profile| profile.uid)
+            .ok_or_else(|| AppError::Internal("尚未获取到账号 uid，请重新登录后再签到".to_string()))?;
+
+        let daily = self
+            .fetch_data(
+                reqwest::Method::GET,
+                &format!("/daily?user_id={}", uid),
+                None,
+                "获取签到活动",
+            )
+            .await?;
+        let daily_id = json_i64(&daily["daily_id"])
+            .ok_or_else(|| AppError::Internal(format!("签到活动数据中没有 daily_id: {}", daily)))?;
+
+        let result = self
+            .fetch_data(
+                reqwest::Method::POST,
+                "/daily_chk",
+                Some(&[("user_id", uid.to_string()), ("daily_id", daily_id.to_string())]),
+                "每日签到",
+            )
+            .await?;
+
+        Ok(CheckinData {
+            message: json_string(&result["msg"]),
+        })
+    }
+
+    async fn like(&self, aid: i64) -> AppResult<LikeData> {
+        let result = self
+            .fetch_data(reqwest::Method::POST, "/like", Some(&[("id", aid.to_string())]), "点赞漫画")
+            .await?;
+        debug!("点赞 {} 返回: {}", aid, result);
+        Ok(LikeData {
+            message: json_string(&result["msg"]),
+        })
+    }
+
+    async fn comment(&self, aid: i64, text: &str, reply_to: Option<i64>) -> AppResult<CommentData> {
+        let mut form = vec![
+            ("video_id", aid.to_string()),
+            ("comment", text.to_string()),
+            ("originator", String::new()),
+            ("status", "true".to_string()),
+        ];
+        if let Some(comment_id) = reply_to {
+            form.push(("comment_id", comment_id.to_string()));
+        }
+        let result = self
+            .fetch_data(reqwest::Method::POST, "/comment", Some(&form), "发表评论")
+            .await?;
+        debug!("评论 {} 返回: {}", aid, result);
+        Ok(CommentData {
+            message: json_string(&result["msg"]),
+        })
+    }
 }
 
 fn generate_token(ts: u64, secret: &str) -> String {
@@ -317,6 +780,166 @@ fn generate_token(ts: u64, secret: &str) -> String {
     format!("{:x}", md5::compute(data))
 }
 
+/// 将 JM 业务错误归类为对应的 `AppError`，无法识别时返回 `fallback` 内部错误
+fn classify_jm_error(code: i64, error_msg: &str, fallback: String) -> AppError {
+    let msg = error_msg.to_lowercase();
+    let has = |keywords: &[&str]| keywords.iter().any(|k| msg.contains(k));
+
+    if has(&["密码错误", "密碼錯誤", "帐号或密码", "帳號或密碼", "账号或密码", "password"]) {
+        AppError::InvalidCredentials(format!("JM 账号或密码错误: {}", error_msg))
+    } else if has(&["jcoin", "金币", "金幣", "购买", "購買", "vip", "余额不足", "餘額不足"]) {
+        AppError::PaymentRequired(format!("需要 JM 币或 VIP 才能查看: {}", error_msg))
+    } else if has(&["下架", "已删除", "已刪除", "removed", "deleted"]) {
+        AppError::AlbumRemoved(format!("漫画已被下架或删除: {}", error_msg))
+    } else if has(&["ip被", "ip 被", "ip blocked", "ip banned", "封锁", "封鎖", "禁止访问", "禁止訪問"]) {
+        AppError::Blocked(format!("请求被 JM 拦截: {}", error_msg))
+    } else if code == 401 {
+        AppError::Unauthorized(fallback)
+    } else {
+        AppError::Internal(fallback)
+    }
+}
+
+/// 将非 200 的 HTTP 状态归类为对应的 `AppError`
+fn classify_http_status(status: reqwest::StatusCode, fallback: String) -> AppError {
+    match status.as_u16() {
+        401 => AppError::Unauthorized(fallback),
+        403 => AppError::Blocked(format!("请求被 JM 拦截（HTTP {}），可能是 IP 被封或触发了人机验证", status)),
+        _ => AppError::Internal(fallback),
+    }
+}
+
+/// Cloudflare 人机验证/拦截页面的特征
+const CHALLENGE_MARKERS: &[&str] = &[
+    "cf-chl",
+    "cf_chl_opt",
+    "challenge-platform",
+    "cf-browser-verification",
+    "cf-error-details",
+    "<title>just a moment",
+    "<title>attention required",
+];
+
+/// 识别 HTML 拦截页面，返回用于错误信息的简短描述；正常的 JSON 响应与章节模板页返回 None
+fn challenge_page(status: reqwest::StatusCode, body: &str) -> Option<String> {
+    if !is_html(body) {
+        return None;
+    }
+    let lower = body.to_lowercase();
+    if CHALLENGE_MARKERS.iter().any(|marker| lower.contains(marker)) {
+        Some("Cloudflare 人机验证页面".to_string())
+    } else if status.as_u16() == 403 {
+        Some(format!("拦截页面 {}", body_snippet(body)))
+    } else {
+        None
+    }
+}
+
+fn is_html(body: &str) -> bool {
+    let head: String = body.trim_start().chars().take(100).collect::<String>().to_lowercase();
+    head.starts_with("<!doctype html") || head.starts_with("<html") || head.contains("<head")
+}
+
+/// 错误信息中的响应内容：HTML 页面只取标题，其余截断到 200 个字符，避免整页 HTML 出现在错误里
+fn body_snippet(body: &str) -> String {
+    const MAX_CHARS: usize = 200;
+    if is_html(body) {
+        let lower = body.to_lowercase();
+        let title = lower
+            .find("<title")
+            .and_then(|start| Some(start + lower[start..].find('>')? + 1))
+            .and_then(|start| Some((start, start + lower[start..].find("</title")?)))
+            .map(|(start, end)| body[start..end].trim())
+            .filter(|title| !title.is_empty());
+        return match title {
+            Some(title) => format!("HTML 页面「{}」", title.chars().take(MAX_CHARS).collect::<String>()),
+            None => "HTML 页面".to_string(),
+        };
+    }
+    let body = body.trim();
+    if body.chars().count() > MAX_CHARS {
+        format!("{}...", body.chars().take(MAX_CHARS).collect::<String>())
+    } else {
+        body.to_string()
+    }
+}
+
+/// 从登录返回的数据中提取账号资料；JM 的数值字段有时是字符串，统一宽松解析
+fn parse_user_profile(value: &Value, image_domain: &str) -> UserProfile {
+    let photo = json_string(&value["photo"]);
+    UserProfile {
+        uid: json_i64(&value["uid"]).unwrap_or_default(),
+        username: json_string(&value["username"]),
+        coin: json_i64(&value["coin"]).unwrap_or_default(),
+        level: json_i64(&value["level"]).unwrap_or_default(),
+        level_name: json_string(&value["level_name"]),
+        exp: json_i64(&value["exp"]).unwrap_or_default(),
+        next_level_exp: json_i64(&value["nextLevelExp"]).unwrap_or_default(),
+        avatar: if photo.is_empty() {
+            String::new()
+        } else {
+            format!("https://{}/media/users/{}", image_domain, photo)
+        },
+    }
+}
+
+/// 解析漫画列表（最新上架、每周推荐等接口共用的条目格式）
+fn parse_comic_list(value: &Value, image_domain: &str) -> Vec<ComicSummary> {
+    value
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| {
+                    let comic_id = json_i64(&item["id"])?;
+                    let category = json_string(&item["category"]["title"]);
+                    Some(ComicSummary {
+                        comic_id,
+                        title: json_string(&item["name"]),
+                        author: json_string(&item["author"]),
+                        cover: cover_url(image_domain, comic_id),
+                        category: (!category.is_empty()).then_some(category),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 归一化搜索结果：`content` 中同一漫画可能出现多次，按首次出现保留；
+/// 查询为车号时没有 `content`，只有 `redirect_aid`
+fn parse_search(data: &Value, image_domain: &str) -> SearchPage {
+    let mut comics = parse_comic_list(&data["content"], image_domain);
+    let mut seen = std::collections::HashSet::new();
+    comics.retain(|comic| seen.insert(comic.comic_id));
+    let redirect_aid = json_i64(&data["redirect_aid"]).filter(|&aid| aid > 0);
+    SearchPage {
+        total: json_i64(&data["total"]).map_or(comics.len(), |total| total.max(0) as usize),
+        comics,
+        redirect_aid,
+    }
+}
+
+fn cover_url(image_domain: &str, comic_id: i64) -> String {
+    format!("https://{}/media/albums/{}_3x4.jpg", image_domain, comic_id)
+}
+
+fn json_i64(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(n) => n.as_i64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn json_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
 fn is_missing_comic(value: &Value) -> bool {
     match value.get("name") {
         None | Some(Value::Null) => true,
@@ -332,58 +955,221 @@ fn raw_missing_comic(data: &str) -> bool {
         || data.contains("\"name\": \"\"")
 }
 
-fn decrypt_data(ts: u64, data: &str) -> AppResult<String> {
-    // Base64解码加密数据
-    let aes256_ecb_encrypted_data = general_purpose::STANDARD
-        .decode(data)
-        .map_err(|e| AppError::Internal(format!("Base64解码失败: {}", e)))?;
+/// 响应数据解密失败的原因
+#[derive(Debug, Error, PartialEq)]
+enum DecryptError {
+    #[error("数据为空")]
+    Empty,
+    #[error("Base64解码失败: {0}")]
+    Base64(#[from] base64::DecodeError),
+    #[error("密文长度 {0} 不是16的倍数")]
+    BlockLength(usize),
+    #[error("PKCS#7填充无效（末字节 {0}）")]
+    Padding(u8),
+    #[error("解密数据不是有效的UTF-8: {0}")]
+    Utf8(#[from] std::string::FromUtf8Error),
+    #[error("未配置解密密钥")]
+    NoSecret,
+}
 
+/// 依次用候选密钥解密响应数据，返回第一个得到有效填充与UTF-8文本的结果
+///
+/// 密钥错误时解出的是随机字节，几乎不可能同时满足 PKCS#7 填充与 UTF-8 校验，
+/// 因此可以据此判断密钥是否匹配，而不必等到 JSON 解析失败
+fn decrypt_data(ts: u64, data: &str, secrets: &[String]) -> AppResult<String> {
+    let ciphertext = decode_ciphertext(data).map_err(|e| AppError::Internal(format!("解密数据失败: {}", e)))?;
+    let mut last_error = DecryptError::NoSecret;
+    for (index, secret) in secrets.iter().enumerate() {
+        match decrypt_with_secret(ts, &ciphertext, secret) {
+            Ok(plaintext) => {
+                if index > 0 {
+                    debug!("第 {} 个候选密钥解密成功", index + 1);
+                }
+                return Ok(plaintext);
+            }
+            Err(e) => last_error = e,
+        }
+    }
+    Err(AppError::Internal(format!(
+        "解密数据失败（已尝试 {} 个密钥，JM 可能更换了密钥，可通过 JM_DATA_SECRETS 配置新密钥）: {}",
+        secrets.len(),
+        last_error
+    )))
+}
+
+/// Base64解码并校验密文长度
+fn decode_ciphertext(data: &str) -> Result<Vec<u8>, DecryptError> {
+    let ciphertext = general_purpose::STANDARD.decode(data.trim())?;
+    if ciphertext.is_empty() {
+        return Err(DecryptError::Empty);
+    }
+    if ciphertext.len() % 16 != 0 {
+        return Err(DecryptError::BlockLength(ciphertext.len()));
+    }
+    Ok(ciphertext)
+}
+
+fn decrypt_with_secret(ts: u64, ciphertext: &[u8], secret: &str) -> Result<String, DecryptError> {
     // 使用MD5生成密钥
-    let key_str = format!("{}{}", ts, APP_DATA_SECRET);
-    let key = format!("{:x}", md5::compute(&key_str));
+    let key = format!("{:x}", md5::compute(format!("{}{}", ts, secret)));
 
     // 使用AES-256-ECB解密
     let cipher = Aes256::new(GenericArray::from_slice(key.as_bytes()));
-    let decrypted_data_with_padding: Vec<u8> = aes256_ecb_encrypted_data
-        .chunks(16)
-        .map(GenericArray::clone_from_slice)
-        .flat_map(|mut block| {
-            cipher.decrypt_block(&mut block);
-            block.to_vec()
-        })
-        .collect();
+    let mut plaintext = ciphertext.to_vec();
+    for block in plaintext.chunks_exact_mut(16) {
+        cipher.decrypt_block(GenericArray::from_mut_slice(block));
+    }
 
-    // 移除PKCS#7填充
-    let padding_length = decrypted_data_with_padding.last().copied().unwrap() as usize;
-    let decrypted_data_without_padding =
-        decrypted_data_with_padding[..decrypted_data_with_padding.len() - padding_length].to_vec();
+    // 校验并移除PKCS#7填充
+    let padding = *plaintext.last().ok_or(DecryptError::Empty)?;
+    let padding_length = padding as usize;
+    if padding_length == 0
+        || padding_length > 16
+        || !plaintext[plaintext.len() - padding_length..].iter().all(|&b| b == padding)
+    {
+        return Err(DecryptError::Padding(padding));
+    }
+    plaintext.truncate(plaintext.len() - padding_length);
 
-    // 转换为UTF-8字符串
-    let decrypted_data = String::from_utf8(decrypted_data_without_padding)
-        .map_err(|e| AppError::Internal(format!("解密数据转UTF-8失败: {}", e)))?;
-    Ok(decrypted_data)
+    Ok(String::from_utf8(plaintext)?)
 }
 
-pub fn calculate_block_num(scramble_id: i64, chapter_id: i64, filename: &str) -> u32 {
-    if chapter_id < scramble_id {
-        0
-    } else if chapter_id < 268_850 {
-        10
-    } else {
-        let x = if chapter_id < 421_926 { 10 } else { 8 };
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TS: u64 = 1700000000;
+    const SECRET: &str = "185Hcomic3PAPP7R";
+    /// 以 TS 与 SECRET 加密的 `{"id":350234,"name":"测试漫画","images":["00001.webp","00002.webp"]}`
+    const COMIC_FIXTURE: &str =
+        "MHY7kxt/Vglh2D31I0IJSx7+AWPjfM6sZPd0NxZ/oqg7Q77ISWOT/WLIodWUksNM5PzAylPIkZC/nxMsQ3DEB0m39YAS4vZM7jGnL8bswVY=";
+    /// 末字节为 5 但前 4 字节为 0 的单个分组
+    const BAD_PADDING_FIXTURE: &str = "2FENoPE3yLboFOdbXyLzsw==";
+    /// 填充正确但内容不是 UTF-8
+    const BAD_UTF8_FIXTURE: &str = "tiPjp3LC3kj1fa4bzpXR0A==";
+
+    fn secrets(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn decrypts_captured_fixture() {
+        let plaintext = decrypt_data(TS, COMIC_FIXTURE, &secrets(&[SECRET])).unwrap();
+        assert_eq!(
+            plaintext,
+            r#"{"id":350234,"name":"测试漫画","images":["00001.webp","00002.webp"]}"#
+        );
+    }
+
+    #[test]
+    fn falls_back_to_later_secret() {
+        let plaintext = decrypt_data(TS, COMIC_FIXTURE, &secrets(&["新密钥", SECRET])).unwrap();
+        assert!(plaintext.starts_with(r#"{"id":350234"#));
+        assert!(decrypt_data(TS, COMIC_FIXTURE, &secrets(&["新密钥"])).is_err());
+        assert!(decrypt_data(TS + 1, COMIC_FIXTURE, &secrets(&[SECRET])).is_err());
+    }
+
+    #[test]
+    fn classifies_challenge_pages() {
+        let ok = reqwest::StatusCode::OK;
+        let cloudflare = "<!DOCTYPE html><html><head><title>Just a moment...</title></head>\
+                          <body><script src=\"/cdn-cgi/challenge-platform/h/b/orchestrate\"></script></body></html>";
+        assert_eq!(challenge_page(ok, cloudflare).as_deref(), Some("Cloudflare 人机验证页面"));
+        assert_eq!(body_snippet(cloudflare), "HTML 页面「Just a moment...」");
+
+        let denied = "<html><head><title>403 Forbidden</title></head><body>nginx</body></html>";
+        assert_eq!(
+            challenge_page(reqwest::StatusCode::FORBIDDEN, denied).as_deref(),
+            Some("拦截页面 HTML 页面「403 Forbidden」")
+        );
+
+        // 章节模板页本身就是 HTML，不应被当作拦截
+        let template = "<!DOCTYPE html><html><head><title>章节</title></head><script>var scramble_id = 220980;</script></html>";
+        assert_eq!(challenge_page(ok, template), None);
+        assert_eq!(challenge_page(ok, r#"{"code":200,"data":""}"#), None);
+        assert_eq!(body_snippet(&"x".repeat(300)).chars().count(), 203);
+    }
+
+    #[test]
+    fn failover_switches_domain_once() {
+        let client = JmClient::new(
+            vec!["a.test".to_string(), "b.test".to_string()],
+            vec![String::new()],
+            DEFAULT_IMAGE_URL_TEMPLATE.to_string(),
+            0,
+            Vec::new(),
+        );
+        assert_eq!(client.api_domain(), "a.test");
+        // 两个并发请求都在 a.test 上被拦截时只切换一次
+        client.switch_domain(0, "blocked");
+        client.switch_domain(0, "blocked");
+        assert_eq!(client.api_domain(), "b.test");
+        client.switch_domain(1, "blocked");
+        assert_eq!(client.api_domain(), "a.test");
+    }
 
-        // 从文件名中移除文件扩展名
-        let filename_without_ext = filename
-            .rsplit_once('.')
-            .map(|(name, _)| name)
-            .unwrap_or(filename);
+    #[test]
+    fn builds_image_urls_from_template() {
+        let default = ImageUrlBuilder::new(
+            DEFAULT_IMAGE_URL_TEMPLATE.to_string(),
+            vec!["cdn.test".to_string(), "mirror.test".to_string()],
+        );
+        assert_eq!(
+            default.urls(42, "00001.webp"),
+            [
+                "https://cdn.test/media/photos/42/00001.webp",
+                "https://mirror.test/media/photos/42/00001.webp"
+            ]
+        );
+        let shunt = ImageUrlBuilder::new(
+            "https://{domain}/media/photos/{chapter_id}/{filename}?v={ts}&shunt=2".to_string(),
+            vec!["cdn.test".to_string()],
+        );
+        let url = &shunt.urls(42, "00001.webp")[0];
+        let ts: u64 = url.split("?v=").nth(1).unwrap().trim_end_matches("&shunt=2").parse().unwrap();
+        assert!(ts > 0);
+
+        assert!(check_image_url_template(DEFAULT_IMAGE_URL_TEMPLATE).is_ok());
+        assert!(check_image_url_template("https://{domain}/{chapter_id}/{filename}?v={ts}").is_ok());
+        assert!(check_image_url_template("https://{domain}/{chapter_id}").is_err());
+        assert!(check_image_url_template("https://{domain}/{chapter}/{filename}").is_err());
+        assert!(check_image_url_template("https://{domain}/{chapter_id}/{filename").is_err());
+        assert!(check_image_url_template("{domain}/{chapter_id}/{filename}").is_err());
+    }
+
+    #[test]
+    fn normalizes_search_results() {
+        let data = json!({
+            "search_query": "测试",
+            "total": "162",
+            "content": [
+                { "id": "101", "name": "甲", "author": "作者", "category": { "title": "同人" } },
+                { "id": 102, "name": "乙", "author": "" },
+                { "id": "101", "name": "甲", "author": "作者" },
+                { "name": "缺少ID" }
+            ]
+        });
+        let page = parse_search(&data, "cdn.test");
+        assert_eq!(page.total, 162);
+        assert_eq!(page.comics.iter().map(|comic| comic.comic_id).collect::<Vec<_>>(), [101, 102]);
+        assert_eq!(page.comics[0].category.as_deref(), Some("同人"));
+        assert_eq!(page.comics[1].cover, "https://cdn.test/media/albums/102_3x4.jpg");
+        assert_eq!(page.redirect_aid, None);
+
+        let redirect = parse_search(&json!({ "search_query": "350234", "total": 0, "redirect_aid": "350234" }), "cdn.test");
+        assert_eq!((redirect.redirect_aid, redirect.comics.len()), (Some(350234), 0));
+    }
 
-        let s = format!("{}{}", chapter_id, filename_without_ext);
-        let md5_hash = format!("{:x}", md5::compute(&s));
+    #[test]
+    fn rejects_malformed_ciphertext() {
+        assert_eq!(decode_ciphertext(""), Err(DecryptError::Empty));
+        assert_eq!(decode_ciphertext("AAAA"), Err(DecryptError::BlockLength(3)));
+        assert!(matches!(decode_ciphertext("不是base64"), Err(DecryptError::Base64(_))));
+        assert!(decrypt_data(TS, "", &secrets(&[SECRET])).is_err());
 
-        let mut block_num = md5_hash.chars().last().unwrap() as u32;
-        block_num %= x;
-        block_num = block_num * 2 + 2;
-        block_num
+        let block = decode_ciphertext(BAD_PADDING_FIXTURE).unwrap();
+        assert_eq!(decrypt_with_secret(TS, &block, SECRET), Err(DecryptError::Padding(5)));
+        let block = decode_ciphertext(BAD_UTF8_FIXTURE).unwrap();
+        assert!(matches!(decrypt_with_secret(TS, &block, SECRET), Err(DecryptError::Utf8(_))));
     }
 }
@@ -0,0 +1,664 @@
+// 下载任务模块
+// 每个下载请求在执行期间登记为一个任务，可查询进度，并可暂停/恢复图片下载；
+// 同时执行的任务数达到 JM_MAX_CONCURRENT_JOBS 时按优先级排队，队列长度受 JM_MAX_QUEUED_JOBS 限制；
+// 成功完成的任务结果保留 JM_JOB_RESULT_RETENTION_SECONDS 秒，响应丢失的客户端可取回结果而无需重新下载；
+// 管理员可取消任务，最近结束的任务与执行中的任务一起通过事件流推送给 /ui 仪表盘
+
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::{BTreeMap, BinaryHeap, VecDeque};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use jm_downloader_rs::{ApiResult, AppError, R};
+use rocket::futures::{stream, Stream, StreamExt};
+use rocket::response::stream::{Event, EventStream};
+use rocket::{Shutdown, State};
+use rocket_okapi::openapi;
+use serde::Serialize;
+use tokio::sync::{oneshot, watch};
+use tokio::time::Instant;
+
+use crate::admin::AdminKey;
+use crate::config::{Config, LiveConfig};
+use crate::history::{self, HistoryEntry};
+use crate::models::{FinishedJob, JobEvents, JobInfo, JobPriority, JobResult};
+use crate::progress::Progress;
+use crate::service_mode;
+
+/// 下载任务登记表（含排队中的任务），克隆后共享同一份状态
+#[derive(Clone, Default)]
+pub struct Jobs {
+    running: Arc<Mutex<BTreeMap<u64, Arc<Job>>>>,
+    next_id: Arc<AtomicU64>,
+    scheduler: Arc<Mutex<Scheduler>>,
+    /// 已成功完成、仍在保留期内的任务结果
+    results: Arc<Mutex<BTreeMap<u64, RetainedResult>>>,
+    /// 最近结束的任务，从新到旧
+    recent: Arc<Mutex<VecDeque<FinishedJob>>>,
+}
+
+/// 最多保留的任务结果数，超出时丢弃最早的
+const MAX_RETAINED_RESULTS: usize = 1000;
+/// 事件流中最近结束任务的条数
+const RECENT_JOBS: usize = 20;
+/// 任务事件流的推送间隔
+const EVENT_INTERVAL: Duration = Duration::from_secs(1);
+
+struct RetainedResult {
+    result: JobResult,
+    /// 保留期大到无法表示时为 None，不过期（仍受 MAX_RETAINED_RESULTS 限制）
+    expires_at: Option<Instant>,
+}
+
+/// 执行名额分配：`active` 为占用名额的任务数，`queue` 为等待名额的任务
+#[derive(Default)]
+struct Scheduler {
+    active: usize,
+    /// 最近一次登记任务时的并发上限，0 表示不限制
+    max_active: usize,
+    queue: BinaryHeap<Waiter>,
+}
+
+/// 排队中的任务，优先级高者先出队，同优先级先到先得
+struct Waiter {
+    priority: JobPriority,
+    job_id: u64,
+    slot: oneshot::Sender<()>,
+}
+
+impl PartialEq for Waiter {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == CmpOrdering::Equal
+    }
+}
+
+impl Eq for Waiter {}
+
+impl PartialOrd for Waiter {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Waiter {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.job_id.cmp(&self.job_id))
+    }
+}
+
+impl Scheduler {
+    fn has_room(&self) -> bool {
+        self.max_active == 0 || self.active < self.max_active
+    }
+
+    /// 有空余名额时依次交给队首任务；等待方已放弃（接收端已释放）时跳过
+    fn fill(&mut self) {
+        while self.has_room() {
+            let Some(waiter) = self.queue.pop() else {
+                return;
+            };
+            if waiter.slot.send(()).is_ok() {
+                self.active += 1;
+            }
+        }
+    }
+}
+
+/// 任务并发与排队上限，取自登记任务时的配置快照
+#[derive(Debug, Clone, Copy)]
+pub struct JobLimits {
+    /// 同时执行的任务数上限，0 表示不限制
+    pub max_concurrent: usize,
+    pub max_queued: usize,
+    /// 成功完成后结果的保留时长，为 0 时不保留
+    pub result_retention: Duration,
+}
+
+impl From<&Config> for JobLimits {
+    fn from(config: &Config) -> Self {
+        Self {
+            max_concurrent: config.max_concurrent_jobs,
+            max_queued: config.max_queued_jobs,
+            result_retention: Duration::from_secs(config.job_result_retention_seconds),
+        }
+    }
+}
+
+/// 一个已登记的下载任务
+pub struct Job {
+    id: u64,
+    kind: &'static str,
+    comic_id: i64,
+    priority: JobPriority,
+    queued: AtomicBool,
+    progress: Arc<Progress>,
+    paused: watch::Sender<bool>,
+    cancelled: watch::Sender<bool>,
+    /// 成功完成时的漫画标题，结束时仍为 None 的任务记为失败
+    completed: Mutex<Option<String>>,
+    /// 成功完成时下载接口返回的 data
+    result: Mutex<Option<serde_json::Value>>,
+    result_retention: Duration,
+}
+
+/// 任务登记句柄，释放时从登记表中移除
+pub struct JobHandle {
+    jobs: Jobs,
+    job: Arc<Job>,
+}
+
+impl Jobs {
+    /// 登记一个新任务并等待执行名额，`kind` 为发起任务的接口名
+    ///
+    /// 名额已满时按优先级排队；队列也已满时返回 [`AppError::QueueFull`]
+    pub async fn start(
+        &self,
+        kind: &'static str,
+        comic_id: i64,
+        priority: JobPriority,
+        limits: JobLimits,
+    ) -> ApiResult<JobHandle> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let slot = {
+            let mut scheduler = self.scheduler.lock().unwrap();
+            scheduler.max_active = limits.max_concurrent;
+            // 上限调大后，先让已排队的任务出队
+            scheduler.fill();
+            if scheduler.has_room() {
+                scheduler.active += 1;
+                None
+            } else if scheduler.queue.len() >= limits.max_queued {
+                return Err(AppError::QueueFull(format!(
+                    "下载任务队列已满（执行中 {}，排队 {}），请稍后重试",
+                    scheduler.active,
+                    scheduler.queue.len()
+                )));
+            } else {
+                let (sender, receiver) = oneshot::channel();
+                scheduler.queue.push(Waiter {
+                    priority,
+                    job_id: id,
+                    slot: sender,
+                });
+                Some(receiver)
+            }
+        };
+
+        let job = Arc::new(Job {
+            id,
+            kind,
+            comic_id,
+            priority,
+            queued: AtomicBool::new(slot.is_some()),
+            progress: Progress::new(format!("任务 {} {} comic_id={}", id, kind, comic_id)),
+            paused: watch::channel(false).0,
+            cancelled: watch::channel(false).0,
+            completed: Mutex::new(None),
+            result: Mutex::new(None),
+            result_retention: limits.result_retention,
+        });
+        self.running.lock().unwrap().insert(id, job.clone());
+        // 句柄先于等待创建：请求在排队时被取消也能从队列中移除
+        let handle = JobHandle {
+            jobs: self.clone(),
+            job,
+        };
+
+        if let Some(slot) = slot {
+            info!("任务 {} 排队中: {} comic_id={} 优先级 {:?}", id, kind, comic_id, priority);
+            // 发送端只会在交出名额时使用，从队列移除时任务句柄也已释放
+            tokio::select! {
+                _ = slot => {}
+                () = handle.job.cancelled() => {
+                    return Err(AppError::Cancelled(format!("任务 {} 在排队时被取消", id)));
+                }
+            }
+            handle.job.queued.store(false, Ordering::Relaxed);
+        }
+        info!("任务 {} 开始: {} comic_id={}", id, kind, comic_id);
+        Ok(handle)
+    }
+
+    /// 按给定上限还能登记而不被拒绝的任务数（空闲名额加队列空位），不限并发时为 `usize::MAX`
+    pub fn room(&self, limits: JobLimits) -> usize {
+        if limits.max_concurrent == 0 {
+            return usize::MAX;
+        }
+        let scheduler = self.scheduler.lock().unwrap();
+        limits.max_concurrent.saturating_sub(scheduler.active)
+            + limits.max_queued.saturating_sub(scheduler.queue.len())
+    }
+
+    pub fn get(&self, id: u64) -> Option<Arc<Job>> {
+        self.running.lock().unwrap().get(&id).cloned()
+    }
+
+    /// 按任务 ID 顺序列出所有进行中的任务
+    pub fn list(&self) -> Vec<Arc<Job>> {
+        self.running.lock().unwrap().values().cloned().collect()
+    }
+
+    /// 最近结束的任务，从新到旧
+    pub fn recent(&self) -> Vec<FinishedJob> {
+        self.recent.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// 事件流推送的任务快照
+    pub fn events(&self) -> JobEvents {
+        JobEvents {
+            active: self.list().iter().map(|job| job.info()).collect(),
+            recent: self.recent(),
+        }
+    }
+
+    /// 保留期内的任务结果
+    pub fn result(&self, id: u64) -> Option<JobResult> {
+        let mut results = self.results.lock().unwrap();
+        let now = Instant::now();
+        results.retain(|_, retained| retained.expires_at.is_none_or(|at| at > now));
+        results.get(&id).map(|retained| retained.result.clone())
+    }
+
+    fn retain_result(&self, job: &Job) {
+        let Some(data) = job.result.lock().unwrap().take() else {
+            return;
+        };
+        if job.result_retention.is_zero() {
+            return;
+        }
+        let result = JobResult {
+            job_id: job.id,
+            kind: job.kind.to_string(),
+            comic_id: job.comic_id,
+            finished_at: chrono::Utc::now().with_timezone(&chrono_tz::Asia::Shanghai).to_rfc3339(),
+            data,
+        };
+        let mut results = self.results.lock().unwrap();
+        let now = Instant::now();
+        results.retain(|_, retained| retained.expires_at.is_none_or(|at| at > now));
+        while results.len() >= MAX_RETAINED_RESULTS {
+            results.pop_first();
+        }
+        results.insert(
+            job.id,
+            RetainedResult { result, expires_at: now.checked_add(job.result_retention) },
+        );
+    }
+}
+
+impl JobHandle {
+    pub fn job(&self) -> &Arc<Job> {
+        &self.job
+    }
+
+    /// 标记任务成功完成，释放句柄时写入的下载历史记为成功，`data` 在保留期内可通过任务结果接口取回
+    pub fn succeed(&self, title: &str, data: &impl Serialize) {
+        *self.job.completed.lock().unwrap() = Some(title.to_string());
+        match serde_json::to_value(data) {
+            Ok(data) => *self.job.result.lock().unwrap() = Some(data),
+            Err(e) => warn!("任务 {} 的结果序列化失败，不保留: {}", self.job.id, e),
+        }
+    }
+}
+
+impl Drop for JobHandle {
+    fn drop(&mut self) {
+        self.jobs.running.lock().unwrap().remove(&self.job.id);
+        self.jobs.retain_result(&self.job);
+        {
+            let mut recent = self.jobs.recent.lock().unwrap();
+            recent.push_front(self.job.finished());
+            recent.truncate(RECENT_JOBS);
+        }
+        // 排队期间被取消的任务没有实际下载，不计入历史
+        if !self.job.queued.load(Ordering::Relaxed) {
+            self.job.record_history();
+        }
+        let mut scheduler = self.jobs.scheduler.lock().unwrap();
+        let queued = scheduler.queue.len();
+        scheduler.queue.retain(|waiter| waiter.job_id != self.job.id);
+        // 不在队列中说明已占用名额，释放后交给下一个任务
+        if scheduler.queue.len() == queued {
+            scheduler.active -= 1;
+            scheduler.fill();
+        }
+    }
+}
+
+impl Job {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn progress(&self) -> &Arc<Progress> {
+        &self.progress
+    }
+
+    pub fn pause(&self) {
+        self.paused.send_replace(true);
+    }
+
+    pub fn resume(&self) {
+        self.paused.send_replace(false);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    /// 请求取消任务：排队中的任务直接退出，执行中的任务停止尚未完成的图片下载
+    pub fn cancel(&self) {
+        self.cancelled.send_replace(true);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancelled.borrow()
+    }
+
+    /// 等待任务被取消
+    pub async fn cancelled(&self) {
+        let mut cancelled = self.cancelled.subscribe();
+        // 发送端与任务同生命周期，等待期间不会关闭
+        let _ = cancelled.wait_for(|cancelled| *cancelled).await;
+    }
+
+    /// 任务暂停时等待恢复，未暂停时立即返回
+    pub async fn wait_resumed(&self) {
+        let mut paused = self.paused.subscribe();
+        // 发送端与任务同生命周期，等待期间不会关闭
+        let _ = paused.wait_for(|paused| !*paused).await;
+    }
+
+    fn record_history(&self) {
+        let snapshot = self.progress.snapshot();
+        let title = self.completed.lock().unwrap().clone();
+        history::record(&HistoryEntry {
+            time: chrono::Utc::now().timestamp(),
+            kind: self.kind.to_string(),
+            comic_id: self.comic_id,
+            success: title.is_some(),
+            title,
+            pages: snapshot.completed,
+            bytes: snapshot.bytes,
+            seconds: snapshot.elapsed.as_secs(),
+            coins: 0,
+        });
+    }
+
+    fn finished(&self) -> FinishedJob {
+        let snapshot = self.progress.snapshot();
+        let title = self.completed.lock().unwrap().clone();
+        FinishedJob {
+            job_id: self.id,
+            kind: self.kind.to_string(),
+            comic_id: self.comic_id,
+            success: title.is_some(),
+            title,
+            cancelled: self.is_cancelled(),
+            completed_pages: snapshot.completed,
+            downloaded_bytes: snapshot.bytes,
+            elapsed_seconds: snapshot.elapsed.as_secs(),
+            finished_at: chrono::Utc::now().with_timezone(&chrono_tz::Asia::Shanghai).to_rfc3339(),
+        }
+    }
+
+    pub fn info(&self) -> JobInfo {
+        let snapshot = self.progress.snapshot();
+        JobInfo {
+            job_id: self.id,
+            kind: self.kind.to_string(),
+            comic_id: self.comic_id,
+            priority: self.priority,
+            queued: self.queued.load(Ordering::Relaxed),
+            paused: self.is_paused(),
+            cancelled: self.is_cancelled(),
+            completed_pages: snapshot.completed,
+            total_pages: snapshot.total,
+            downloaded_bytes: snapshot.bytes,
+            retries: snapshot.retries,
+            speed_mbps: snapshot.speed_mbps(),
+            eta_seconds: snapshot.eta().map(|eta| eta.as_secs()),
+            elapsed_seconds: snapshot.elapsed.as_secs(),
+        }
+    }
+}
+
+/// # 任务列表
+/// 列出执行中与排队中的下载任务及其进度（完成页数、速度、预计剩余时间、重试次数）。
+#[openapi]
+#[get("/api/job")]
+pub async fn list_jobs(jobs: &State<Jobs>) -> ApiResult<R<Vec<JobInfo>>> {
+    service_mode::ensure_available()?;
+    Ok(R::success(jobs.list().iter().map(|job| job.info()).collect()))
+}
+
+/// # 暂停任务
+/// 暂停任务的图片下载：已开始的图片会继续完成，尚未开始的图片等待恢复。
+#[openapi]
+#[post("/api/job/<id>/pause")]
+pub async fn pause_job(
+    config: &State<LiveConfig>,
+    jobs: &State<Jobs>,
+    admin: AdminKey,
+    id: u64,
+) -> ApiResult<R<JobInfo>> {
+    admin.verify(&config.load())?;
+    let job = find_job(jobs, id)?;
+    job.pause();
+    info!("任务 {} 已暂停", id);
+    Ok(R::success(job.info()))
+}
+
+/// # 恢复任务
+/// 恢复已暂停任务的图片下载。
+#[openapi]
+#[post("/api/job/<id>/resume")]
+pub async fn resume_job(
+    config: &State<LiveConfig>,
+    jobs: &State<Jobs>,
+    admin: AdminKey,
+    id: u64,
+) -> ApiResult<R<JobInfo>> {
+    admin.verify(&config.load())?;
+    let job = find_job(jobs, id)?;
+    job.resume();
+    info!("任务 {} 已恢复", id);
+    Ok(R::success(job.info()))
+}
+
+/// # 取消任务
+/// 取消排队中或执行中的任务：排队中的任务直接退出，执行中的任务停止尚未完成的图片下载，
+/// 发起下载的请求返回 10012（`downloadChapter` 仍返回已完成的章节）。已进入 PDF 合并等后续阶段的任务会执行完当前阶段。
+#[openapi]
+#[post("/api/job/<id>/cancel")]
+pub async fn cancel_job(
+    config: &State<LiveConfig>,
+    jobs: &State<Jobs>,
+    admin: AdminKey,
+    id: u64,
+) -> ApiResult<R<JobInfo>> {
+    admin.verify(&config.load())?;
+    let job = find_job(jobs, id)?;
+    job.cancel();
+    info!("任务 {} 已请求取消", id);
+    Ok(R::success(job.info()))
+}
+
+type JobEventStream = EventStream<Pin<Box<dyn Stream<Item = Event> + Send>>>;
+
+/// # 任务事件流
+/// 以 Server-Sent Events 每秒推送一次 `jobs` 事件，data 为 `JobEvents`：执行中与排队中的任务（同任务列表）
+/// 以及最近结束的 20 个任务。/ui 仪表盘据此刷新进度条。
+#[openapi]
+#[get("/api/job/events")]
+pub fn job_events(jobs: &State<Jobs>, shutdown: Shutdown) -> ApiResult<JobEventStream> {
+    service_mode::ensure_available()?;
+    let ticker = tokio::time::interval(EVENT_INTERVAL);
+    let events = stream::unfold((jobs.inner().clone(), ticker), |(jobs, mut ticker)| async move {
+        ticker.tick().await;
+        let event = Event::json(&jobs.events()).event("jobs");
+        Some((event, (jobs, ticker)))
+    })
+    // 服务关闭时结束事件流，不拖延关闭
+    .take_until(shutdown);
+    Ok(EventStream::from(Box::pin(events) as Pin<Box<dyn Stream<Item = Event> + Send>>))
+}
+
+/// # 任务结果
+/// 返回已成功完成任务的结果，`data` 与当时下载接口成功响应的 data 相同（含 `job_id` 与文件链接）；
+/// 保留 JM_JOB_RESULT_RETENTION_SECONDS 秒（默认 1 小时），响应丢失时据此取回，无需重新下载。
+/// 任务仍在执行时返回 10001，不存在、失败或已过保留期时返回 10004。结果含签名下载链接且任务 ID 连续，需要 `X-Admin-Key`。
+#[openapi]
+#[get("/api/job/<id>/result")]
+pub async fn job_result(
+    config: &State<LiveConfig>,
+    jobs: &State<Jobs>,
+    admin: AdminKey,
+    id: u64,
+) -> ApiResult<R<JobResult>> {
+    service_mode::ensure_available()?;
+    admin.verify(&config.load())?;
+    if jobs.get(id).is_some() {
+        return Err(AppError::BadRequest(format!("任务 {} 尚未完成", id)));
+    }
+    jobs.result(id)
+        .map(R::success)
+        .ok_or_else(|| AppError::NotFound(format!("任务 {} 不存在、未成功完成或结果已过保留期", id)))
+}
+
+fn find_job(jobs: &Jobs, id: u64) -> ApiResult<Arc<Job>> {
+    jobs.get(id)
+        .ok_or_else(|| AppError::NotFound(format!("任务 {} 不存在或已结束", id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn paused_job_blocks_until_resumed() {
+        let jobs = Jobs::default();
+        let limits = JobLimits { max_concurrent: 0, max_queued: 0, result_retention: Duration::ZERO };
+        let handle = jobs.start("downloadComic", 1, JobPriority::Normal, limits).await.unwrap();
+        let job = handle.job().clone();
+
+        job.pause();
+        let waiting = tokio::time::timeout(Duration::from_millis(50), job.wait_resumed()).await;
+        assert!(waiting.is_err(), "暂停期间应等待");
+
+        job.resume();
+        tokio::time::timeout(Duration::from_millis(50), job.wait_resumed())
+            .await
+            .expect("恢复后应立即返回");
+
+        drop(handle);
+        assert!(jobs.get(job.id).is_none(), "句柄释放后任务应移除");
+    }
+
+    #[tokio::test]
+    async fn huge_retention_keeps_result() {
+        let jobs = Jobs::default();
+        let limits = JobLimits { max_concurrent: 1, max_queued: 0, result_retention: Duration::MAX };
+        let handle = jobs.start("downloadComic", 1, JobPriority::Normal, limits).await.unwrap();
+        let id = handle.job().id;
+        handle.succeed("标题", &serde_json::json!({ "ok": true }));
+        drop(handle);
+
+        assert_eq!(jobs.result(id).unwrap().data["ok"], true);
+        assert_eq!(jobs.room(limits), 1, "释放句柄后应归还名额");
+    }
+
+    #[tokio::test]
+    async fn queued_jobs_start_by_priority() {
+        let jobs = Jobs::default();
+        let limits = JobLimits { max_concurrent: 1, max_queued: 2, result_retention: Duration::ZERO };
+        assert_eq!(jobs.room(limits), 3);
+        let first = jobs.start("downloadComic", 1, JobPriority::Low, limits).await.unwrap();
+        assert_eq!(jobs.room(limits), 2);
+
+        let low = tokio::spawn({
+            let jobs = jobs.clone();
+            async move { jobs.start("downloadComic", 2, JobPriority::Low, limits).await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let high = tokio::spawn({
+            let jobs = jobs.clone();
+            async move { jobs.start("downloadComic", 3, JobPriority::High, limits).await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        assert_eq!(jobs.room(limits), 0);
+        let full = jobs.start("downloadComic", 4, JobPriority::High, limits).await;
+        assert!(matches!(full, Err(AppError::QueueFull(_))), "队列满时应拒绝");
+
+        drop(first);
+        let high = tokio::time::timeout(Duration::from_millis(50), high)
+            .await
+            .expect("高优先级任务应先出队")
+            .unwrap()
+            .unwrap();
+        assert!(!low.is_finished(), "低优先级任务应继续排队");
+        drop(high);
+        tokio::time::timeout(Duration::from_millis(50), low)
+            .await
+            .expect("名额释放后低优先级任务出队")
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn cancelling_queued_job_returns_error_and_lists_it_as_recent() {
+        let jobs = Jobs::default();
+        let limits = JobLimits { max_concurrent: 1, max_queued: 1, result_retention: Duration::ZERO };
+        let running = jobs.start("downloadComic", 1, JobPriority::Normal, limits).await.unwrap();
+        let queued = tokio::spawn({
+            let jobs = jobs.clone();
+            async move { jobs.start("downloadComic", 2, JobPriority::Normal, limits).await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let events = jobs.events();
+        assert_eq!(events.active.len(), 2);
+        assert!(events.active[1].queued);
+        jobs.get(events.active[1].job_id).unwrap().cancel();
+        let cancelled = tokio::time::timeout(Duration::from_millis(50), queued).await.unwrap().unwrap();
+        assert!(matches!(cancelled, Err(AppError::Cancelled(_))), "排队中的任务应立即退出");
+
+        drop(running);
+        let recent = jobs.events().recent;
+        assert_eq!(recent.iter().map(|job| job.comic_id).collect::<Vec<_>>(), vec![1, 2], "从新到旧排列");
+        assert!(recent[1].cancelled && !recent[1].success);
+        assert!(jobs.events().active.is_empty());
+    }
+
+    #[tokio::test]
+    async fn keeps_successful_results_for_retention_window() {
+        let jobs = Jobs::default();
+        let limits = JobLimits { max_concurrent: 0, max_queued: 0, result_retention: Duration::from_secs(60) };
+        let handle = jobs.start("downloadComic", 7, JobPriority::Normal, limits).await.unwrap();
+        let id = handle.job().id();
+        handle.succeed("标题", &serde_json::json!({ "job_id": id, "pdf_path": "download/7/7/merged.pdf" }));
+        assert!(jobs.result(id).is_none(), "执行中的任务没有结果");
+        drop(handle);
+
+        let result = jobs.result(id).expect("完成后应保留结果");
+        assert_eq!(result.comic_id, 7);
+        assert_eq!(result.data["pdf_path"], "download/7/7/merged.pdf");
+
+        // 失败的任务与不保留结果时都取不到
+        let failed = jobs.start("downloadComic", 8, JobPriority::Normal, limits).await.unwrap();
+        let failed_id = failed.job().id();
+        drop(failed);
+        assert!(jobs.result(failed_id).is_none());
+        let limits = JobLimits { result_retention: Duration::ZERO, ..limits };
+        let unretained = jobs.start("downloadComic", 9, JobPriority::Normal, limits).await.unwrap();
+        let unretained_id = unretained.job().id();
+        unretained.succeed("标题", &serde_json::json!({}));
+        drop(unretained);
+        assert!(jobs.result(unretained_id).is_none());
+    }
+}
@@ -1,8 +1,17 @@
+pub mod i18n;
+pub mod png;
+pub mod stitch;
+
+use std::borrow::Cow;
+use std::pin::Pin;
+
 use chrono::Utc;
 use chrono_tz::Asia::Shanghai;
 use rocket::{
+    futures::{Stream, StreamExt},
+    http::{ContentType, Status},
     request::Request,
-    response::{Responder, Result as RocketResult},
+    response::{stream::TextStream, Responder, Result as RocketResult},
     serde::json::Json,
 };
 use rocket_okapi::{
@@ -11,13 +20,18 @@ use rocket_okapi::{
     response::OpenApiResponderInner,
     util::add_schema_response,
 };
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Metadata, ObjectValidation, Schema, SchemaObject, SubschemaValidation};
 use schemars::JsonSchema;
 use serde::Serialize;
 use thiserror::Error;
 
+use crate::i18n::Lang;
+
 /// 统一响应结构：code / success / data / message / time
-#[derive(Debug, Serialize, JsonSchema)]
-#[schemars(bound = "T: JsonSchema")]
+///
+/// JSON Schema 为以 `success` 区分的 `oneOf`（见下方 `JsonSchema` 实现），便于生成客户端时得到成功/失败两种类型
+#[derive(Debug, Serialize)]
 #[serde(bound(serialize = "T: Serialize"))]
 pub struct R<T> {
     pub code: String, // 成功："0"；失败：由 AppError::code() 决定
@@ -39,6 +53,24 @@ impl<T: Serialize> R<T> {
         }
     }
 
+    /// 业务失败但附带已完成的部分结果（如下载超过截止时间）
+    pub fn partial(error: AppError, data: T) -> Self {
+        Self {
+            data: Some(data),
+            ..Self::fail(error.code(), error.message())
+        }
+    }
+
+    /// 按语言改写失败响应的 message，成功响应不变
+    pub fn localize(mut self, lang: Lang) -> Self {
+        if !self.success {
+            if let Some(message) = self.message.take() {
+                self.message = Some(i18n::localize_message(&self.code, &message, lang));
+            }
+        }
+        self
+    }
+
     /// 业务失败（HTTP 统一 200；通常由 `AppError` 使用）
     fn fail(code: impl Into<String>, msg: impl Into<String>) -> Self {
         Self {
@@ -58,15 +90,99 @@ fn beijing_now() -> String {
         .to_string()
 }
 
-/// 让 `R<T>` 可以直接作为 Responder，序列化为 JSON；状态码保持 200
+/// `R<T>` 的 Schema：成功时 `success` 为 true、`code` 为 "0"、`data` 必有；
+/// 失败时 `success` 为 false、`message` 必有，`data` 只在返回部分结果时存在（如下载超时）
+impl<T: JsonSchema> JsonSchema for R<T> {
+    fn schema_name() -> String {
+        format!("R_for_{}", T::schema_name())
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        Cow::Owned(format!("jm_downloader_rs::R<{}>", T::schema_id()))
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        let string = gen.subschema_for::<String>();
+        let success = envelope_variant(
+            "成功响应",
+            true,
+            enum_schema(InstanceType::String, serde_json::json!("0")),
+            gen.subschema_for::<T>(),
+            gen.subschema_for::<Option<String>>(),
+            string.clone(),
+            &["code", "success", "data", "time"],
+        );
+        let failure = envelope_variant(
+            "失败响应，code 为业务错误码",
+            false,
+            string.clone(),
+            gen.subschema_for::<Option<T>>(),
+            string.clone(),
+            string,
+            &["code", "success", "message", "time"],
+        );
+        SchemaObject {
+            metadata: Some(Box::new(Metadata {
+                description: Some("统一响应信封，按 success 区分成功与失败".to_string()),
+                ..Default::default()
+            })),
+            subschemas: Some(Box::new(SubschemaValidation {
+                one_of: Some(vec![success, failure]),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+/// 只允许一个取值的 Schema；OpenAPI 3.0 不支持 `const`，用单元素 `enum` 表示
+fn enum_schema(instance_type: InstanceType, value: serde_json::Value) -> Schema {
+    SchemaObject {
+        instance_type: Some(instance_type.into()),
+        enum_values: Some(vec![value]),
+        ..Default::default()
+    }
+    .into()
+}
+
+fn envelope_variant(
+    title: &str,
+    success: bool,
+    code: Schema,
+    data: Schema,
+    message: Schema,
+    time: Schema,
+    required: &[&str],
+) -> Schema {
+    let mut object = ObjectValidation::default();
+    object.properties.insert("code".to_string(), code);
+    object
+        .properties
+        .insert("success".to_string(), enum_schema(InstanceType::Boolean, serde_json::json!(success)));
+    object.properties.insert("data".to_string(), data);
+    object.properties.insert("message".to_string(), message);
+    object.properties.insert("time".to_string(), time);
+    object.required = required.iter().map(|field| field.to_string()).collect();
+    SchemaObject {
+        metadata: Some(Box::new(Metadata { title: Some(title.to_string()), ..Default::default() })),
+        instance_type: Some(InstanceType::Object.into()),
+        object: Some(Box::new(object)),
+        ..Default::default()
+    }
+    .into()
+}
+
+/// 让 `R<T>` 可以直接作为 Responder，序列化为 JSON；状态码保持 200，失败信息按请求语言改写
 impl<'r, T: Serialize> Responder<'r, 'static> for R<T> {
     fn respond_to(self, req: &'r Request<'_>) -> RocketResult<'static> {
-        Json(self).respond_to(req) // Rocket 的 Json 默认 200 OK
+        let lang = *req.local_cache(Lang::default);
+        Json(self.localize(lang)).respond_to(req) // Rocket 的 Json 默认 200 OK
     }
 }
 
 /// 你的应用错误类型：既支持业务错误，也可承载内部错误
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub enum AppError {
     #[error("{0}")]
     BadRequest(String),
@@ -77,6 +193,43 @@ pub enum AppError {
     #[error("{0}")]
     Forbidden(String),
 
+    /// JM 账号或密码错误（重新登录无法恢复）
+    #[error("{0}")]
+    InvalidCredentials(String),
+    /// 需要 JM 币购买或 VIP 才能查看
+    #[error("{0}")]
+    PaymentRequired(String),
+    /// 漫画已被 JM 下架或删除
+    #[error("{0}")]
+    AlbumRemoved(String),
+    /// 请求被 JM 或 Cloudflare 拦截（IP 被封、人机验证等）
+    #[error("{0}")]
+    Blocked(String),
+    /// 当前以匿名模式运行（未配置 JM 账号），该操作需要登录
+    #[error("{0}")]
+    LoginRequired(String),
+    /// 下载任务队列已满，稍后重试
+    #[error("{0}")]
+    QueueFull(String),
+    /// 下载超过截止时间，未完成的部分已取消
+    #[error("{0}")]
+    Timeout(String),
+    /// 任务被管理员取消，未完成的部分已停止
+    #[error("{0}")]
+    Cancelled(String),
+    /// JM API 连续失败已熔断，`retry_after_seconds` 秒后再试
+    #[error("{message}")]
+    ServiceUnavailable { message: String, retry_after_seconds: u64 },
+    /// 服务处于只读模式，不接受新的下载
+    #[error("{0}")]
+    ReadOnly(String),
+    /// 服务处于维护模式，暂停对外服务
+    #[error("{0}")]
+    Maintenance(String),
+    /// 请求体、章节页数或单页图片超过配置的上限
+    #[error("{0}")]
+    TooLarge(String),
+
     /// 未分类/内部错误
     #[error("{0}")]
     Internal(String),
@@ -90,6 +243,18 @@ impl AppError {
             AppError::Unauthorized(_) => "10002",
             AppError::Forbidden(_) => "10003",
             AppError::NotFound(_) => "10004",
+            AppError::InvalidCredentials(_) => "10005",
+            AppError::PaymentRequired(_) => "10006",
+            AppError::AlbumRemoved(_) => "10007",
+            AppError::Blocked(_) => "10008",
+            AppError::QueueFull(_) => "10009",
+            AppError::Timeout(_) => "10010",
+            AppError::LoginRequired(_) => "10011",
+            AppError::Cancelled(_) => "10012",
+            AppError::ServiceUnavailable { .. } => "10013",
+            AppError::ReadOnly(_) => "10014",
+            AppError::Maintenance(_) => "10015",
+            AppError::TooLarge(_) => "10016",
             AppError::Internal(_) => "20000",
         }
     }
@@ -97,12 +262,124 @@ impl AppError {
     pub fn message(&self) -> String {
         self.to_string()
     }
+
+    /// problem+json 模式下使用的 HTTP 状态码
+    pub fn status(&self) -> Status {
+        match self {
+            AppError::BadRequest(_) => Status::BadRequest,
+            AppError::Unauthorized(_) | AppError::LoginRequired(_) => Status::Unauthorized,
+            AppError::Forbidden(_) => Status::Forbidden,
+            AppError::NotFound(_) => Status::NotFound,
+            AppError::PaymentRequired(_) => Status::PaymentRequired,
+            AppError::AlbumRemoved(_) => Status::Gone,
+            // 服务端的 JM 账号或 IP 出了问题，调用方无法自行修复
+            AppError::InvalidCredentials(_) | AppError::Blocked(_) => Status::BadGateway,
+            AppError::QueueFull(_)
+            | AppError::ServiceUnavailable { .. }
+            | AppError::ReadOnly(_)
+            | AppError::Maintenance(_) => Status::ServiceUnavailable,
+            AppError::Timeout(_) => Status::GatewayTimeout,
+            AppError::Cancelled(_) => Status::Conflict,
+            AppError::TooLarge(_) => Status::PayloadTooLarge,
+            AppError::Internal(_) => Status::InternalServerError,
+        }
+    }
+
+    /// 建议调用方等待的秒数，只有熔断错误提供
+    pub fn retry_after_seconds(&self) -> Option<u64> {
+        match self {
+            AppError::ServiceUnavailable { retry_after_seconds, .. } => Some(*retry_after_seconds),
+            _ => None,
+        }
+    }
 }
 
+/// 错误响应格式：为 true 时 `AppError` 以 RFC 7807 `application/problem+json` 和对应的 HTTP 状态码返回，
+/// 否则保持 HTTP 200 + `R` 信封。服务端在收到请求时按配置与请求头写入请求本地缓存
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProblemJson(pub bool);
+
+/// RFC 7807 错误响应体，`code`/`time` 为扩展字段，与 `R` 的同名字段一致
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    pub code: String,
+    pub time: String,
+    /// 建议的重试等待秒数，只在熔断（10013）时返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after_seconds: Option<u64>,
+}
+
+impl ProblemDetails {
+    /// `title` 为错误码在 `lang` 下的说明，`detail` 为按语言改写的错误信息
+    pub fn new(error: &AppError, lang: Lang) -> Self {
+        let code = error.code();
+        Self {
+            problem_type: "about:blank".to_string(),
+            title: i18n::error_title(code, lang).to_string(),
+            status: error.status().code,
+            detail: i18n::localize_message(code, &error.message(), lang),
+            code: code.to_string(),
+            time: beijing_now(),
+            retry_after_seconds: error.retry_after_seconds(),
+        }
+    }
+}
+
+/// 熔断错误同时带上 `Retry-After` 响应头，信封模式下 `data` 为 `{"retry_after_seconds": N}`
 impl<'r> Responder<'r, 'static> for AppError {
     fn respond_to(self, req: &'r Request<'_>) -> RocketResult<'static> {
-        let body: R<serde_json::Value> = R::fail(self.code(), self.message());
-        Json(body).respond_to(req)
+        let retry_after = self.retry_after_seconds();
+        let mut response = if req.local_cache(ProblemJson::default).0 {
+            let lang = *req.local_cache(Lang::default);
+            let mut response = Json(ProblemDetails::new(&self, lang)).respond_to(req)?;
+            response.set_status(self.status());
+            response.set_header(ContentType::new("application", "problem+json"));
+            response
+        } else {
+            let body: R<serde_json::Value> = match retry_after {
+                Some(seconds) => R::partial(self, serde_json::json!({ "retry_after_seconds": seconds })),
+                None => R::from(self),
+            };
+            body.respond_to(req)?
+        };
+        if let Some(seconds) = retry_after {
+            response.set_raw_header("Retry-After", seconds.to_string());
+        }
+        Ok(response)
+    }
+}
+
+impl<T: Serialize> From<AppError> for R<T> {
+    fn from(error: AppError) -> Self {
+        R::fail(error.code(), error.message())
+    }
+}
+
+/// NDJSON 流式响应（`application/x-ndjson`）：每个元素序列化为一行 `R<T>`，客户端可边接收边处理
+pub struct NdJson<T>(Pin<Box<dyn Stream<Item = R<T>> + Send>>);
+
+impl<T> NdJson<T> {
+    pub fn new(stream: impl Stream<Item = R<T>> + Send + 'static) -> Self {
+        Self(Box::pin(stream))
+    }
+}
+
+impl<'r, T: Serialize + 'r> Responder<'r, 'r> for NdJson<T> {
+    fn respond_to(self, req: &'r Request<'_>) -> RocketResult<'r> {
+        let lang = *req.local_cache(Lang::default);
+        let lines = self.0.map(move |item| {
+            let mut line = serde_json::to_string(&item.localize(lang)).unwrap_or_default();
+            line.push('\n');
+            line
+        });
+        let mut response = TextStream(lines).respond_to(req)?;
+        response.set_header(ContentType::new("application", "x-ndjson"));
+        Ok(response)
     }
 }
 
@@ -118,13 +395,52 @@ where
     }
 }
 
+impl<T> OpenApiResponderInner for NdJson<T>
+where
+    T: Serialize + JsonSchema,
+{
+    fn responses(gen: &mut OpenApiGenerator) -> rocket_okapi::Result<Responses> {
+        let mut responses = Responses::default();
+        let schema = gen.json_schema::<R<T>>();
+        add_schema_response(&mut responses, 200, "application/x-ndjson", schema)?;
+        Ok(responses)
+    }
+}
+
 impl OpenApiResponderInner for AppError {
     fn responses(gen: &mut OpenApiGenerator) -> rocket_okapi::Result<Responses> {
         let mut responses = Responses::default();
         let schema = gen.json_schema::<R<serde_json::Value>>();
         add_schema_response(&mut responses, 200, "application/json", schema)?;
+        // 开启 problem+json 模式时的错误响应
+        let problem = gen.json_schema::<ProblemDetails>();
+        add_schema_response(&mut responses, 400, "application/problem+json", problem.clone())?;
+        add_schema_response(&mut responses, 500, "application/problem+json", problem)?;
         Ok(responses)
     }
 }
 
 pub type ApiResult<T> = Result<T, AppError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schemars::gen::SchemaSettings;
+
+    #[test]
+    fn envelope_schema_is_one_of_success_and_failure() {
+        let mut gen = SchemaSettings::openapi3().into_generator();
+        let schema = serde_json::to_value(gen.subschema_for::<R<Vec<i64>>>()).unwrap();
+        assert_eq!(schema["$ref"], "#/components/schemas/R_for_Array_of_int64");
+
+        let definitions = serde_json::to_value(gen.definitions()).unwrap();
+        let variants = definitions["R_for_Array_of_int64"]["oneOf"].as_array().unwrap();
+        assert_eq!(variants.len(), 2);
+        assert_eq!(variants[0]["properties"]["success"]["enum"], serde_json::json!([true]));
+        assert_eq!(variants[0]["properties"]["code"]["enum"], serde_json::json!(["0"]));
+        assert_eq!(variants[0]["properties"]["data"]["type"], "array");
+        assert_eq!(variants[1]["properties"]["success"]["enum"], serde_json::json!([false]));
+        assert_eq!(variants[1]["properties"]["data"]["nullable"], true);
+        assert!(variants[1]["required"].as_array().unwrap().contains(&serde_json::json!("message")));
+    }
+}
@@ -0,0 +1,221 @@
+// 书库导出模式
+// 下载完成后把每个章节打包为 CBZ（内含 ComicInfo.xml），按 `{漫画标题} [JM{ID}]/{漫画标题} - {章节}.cbz`
+// 写入 JM_LIBRARY_DIR，目录结构与 Komga/Kavita 的 Series/Book 一致；目录名带 JM ID，同名的不同漫画互不覆盖，
+// 从右到左的导出另存为 `... [RTL].cbz`，与正序版本共存。导出的文件不在下载目录中，不会被过期删除。
+// 标题按 JM_FILENAME_STYLE 使用原文、转写为拼音或只用 ID，并替换 Windows/SMB 共享不允许的字符与保留名，
+// 书库目录同步到 NAS 或 Windows 共享时也能原样使用
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use jm_downloader_rs::AppError;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::config::{Config, FilenameStyle};
+use crate::image_processor::download_root;
+use crate::metadata::{comic_info_xml, ChapterMeta, COMIC_INFO_FILE};
+use crate::models::GetComicRespData;
+
+type Result<T> = std::result::Result<T, AppError>;
+
+/// Windows/SMB 共享不允许出现在文件名中的字符
+const RESERVED_CHARS: [char; 9] = ['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+/// Windows 保留的设备名，不区分大小写，带扩展名（如 `CON.txt`）时同样不可用
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "LPT1",
+    "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+/// 目录名或章节名的最大字节数，使 `漫画 - 章节.cbz` 不超过常见文件系统 255 字节的文件名上限
+const MAX_SEGMENT_BYTES: usize = 100;
+
+/// 书库根目录；请求开启书库模式但未配置时返回错误
+pub fn library_root(config: &Config) -> Result<PathBuf> {
+    config
+        .library_dir
+        .as_deref()
+        .map(PathBuf::from)
+        .ok_or_else(|| AppError::BadRequest("书库模式需要服务端配置 JM_LIBRARY_DIR".to_string()))
+}
+
+/// 把章节页面打包为 CBZ 写入书库，返回相对书库根目录的路径
+///
+/// `relative_paths` 为按阅读顺序排列的 `download/...` 页面路径，`rtl` 为 true 时倒序打包
+pub async fn export_chapter(
+    config: &Config,
+    comic_id: i64,
+    comic: &GetComicRespData,
+    chapter: &ChapterMeta<'_>,
+    relative_paths: &[String],
+    rtl: bool,
+) -> Result<String> {
+    let root = library_root(config)?;
+    let chapter_meta = (!comic.series.is_empty()).then_some(chapter);
+    let relative = cbz_path(comic_id, &comic.name, chapter_meta, rtl, config.filename_style);
+    let target = root.join(&relative);
+    let xml = comic_info_xml(&config.web_domain, comic_id, comic, Some(chapter));
+    let mut pages: Vec<PathBuf> = relative_paths
+        .iter()
+        .map(|path| download_root().join(path.strip_prefix("download/").unwrap_or(path)))
+        .collect();
+    if rtl {
+        pages.reverse();
+    }
+
+    tokio::task::spawn_blocking(move || write_cbz(&target, &pages, &xml))
+        .await
+        .map_err(|e| AppError::Internal(format!("打包 CBZ 任务执行失败: {}", e)))??;
+    info!("已导出到书库: {}", relative);
+    Ok(relative)
+}
+
+/// CBZ 相对书库根目录的路径：`{标题} [JM{ID}]/{标题} - {章节}.cbz`，普通漫画（`chapter` 为 None）为 `{标题} [JM{ID}]/{标题}.cbz`，
+/// 从右到左时文件名加 ` [RTL]`；只用 ID 命名或标题为空时目录名就是 `JM{ID}`
+fn cbz_path(comic_id: i64, title: &str, chapter: Option<&ChapterMeta<'_>>, rtl: bool, style: FilenameStyle) -> String {
+    let id = format!("JM{}", comic_id);
+    let title = path_segment(title, &id, style);
+    let series = if title == id { id } else { format!("{} [{}]", title, id) };
+    let mut book = match chapter {
+        Some(chapter) => format!("{} - {}", title, path_segment(chapter.title, &chapter.chapter_id.to_string(), style)),
+        None => title,
+    };
+    if rtl {
+        book.push_str(" [RTL]");
+    }
+    format!("{}/{}.cbz", series, book)
+}
+
+/// 写入 CBZ：页面按顺序编号为 `0001.png` 等，保证阅读器按文件名排序时顺序正确
+fn write_cbz(target: &Path, pages: &[PathBuf], comic_info: &str) -> Result<()> {
+    let parent = target.parent().unwrap_or(Path::new("."));
+    std::fs::create_dir_all(parent)
+        .map_err(|e| AppError::Internal(format!("创建书库目录 {} 失败: {}", parent.display(), e)))?;
+    let tmp = target.with_extension("cbz.tmp");
+    let file = File::create(&tmp)
+        .map_err(|e| AppError::Internal(format!("创建 {} 失败: {}", tmp.display(), e)))?;
+
+    let zip_error = |e: zip::result::ZipError| AppError::Internal(format!("写入 {} 失败: {}", tmp.display(), e));
+    let io_error = |e: std::io::Error| AppError::Internal(format!("写入 {} 失败: {}", tmp.display(), e));
+    // PNG 已经压缩过，直接存储
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let mut zip = ZipWriter::new(BufWriter::new(file));
+    let width = pages.len().to_string().len().max(4);
+    for (index, page) in pages.iter().enumerate() {
+        let ext = page.extension().and_then(|ext| ext.to_str()).unwrap_or("png");
+        zip.start_file(format!("{:0width$}.{}", index + 1, ext, width = width), options)
+            .map_err(zip_error)?;
+        let content = std::fs::read(page)
+            .map_err(|e| AppError::Internal(format!("读取图片 {} 失败: {}", page.display(), e)))?;
+        zip.write_all(&content).map_err(io_error)?;
+    }
+    zip.start_file(COMIC_INFO_FILE, options)
+        .map_err(zip_error)?;
+    zip.write_all(comic_info.as_bytes()).map_err(io_error)?;
+    zip.finish()
+        .map_err(zip_error)?
+        .flush()
+        .map_err(io_error)?;
+
+    std::fs::rename(&tmp, target)
+        .map_err(|e| AppError::Internal(format!("重命名 {} 失败: {}", target.display(), e)))
+}
+
+/// 标题用作目录名或文件名：按命名方式转换后替换不允许的字符，空标题或只用 ID 时使用 `fallback`
+fn path_segment(name: &str, fallback: &str, style: FilenameStyle) -> String {
+    let name = match style {
+        FilenameStyle::Original => name.to_string(),
+        // 无法转写的字符以 `_` 代替
+        FilenameStyle::Pinyin => deunicode::deunicode_with_tofu(name, "_"),
+        FilenameStyle::Id => return fallback.to_string(),
+    };
+    let name = windows_safe(&name);
+    if name.is_empty() {
+        fallback.to_string()
+    } else {
+        name
+    }
+}
+
+/// 在 Windows/SMB 共享上也合法的文件名：去除控制字符，替换保留字符，合并连续空白，
+/// 截断到 `MAX_SEGMENT_BYTES`，去掉 Windows 会自动删除的结尾点与空格，保留设备名后追加 `_`
+fn windows_safe(name: &str) -> String {
+    let replaced: String = name
+        .chars()
+        .filter(|c| !c.is_control())
+        .map(|c| if RESERVED_CHARS.contains(&c) { '_' } else { c })
+        .collect();
+    let collapsed = replaced.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut end = collapsed.len().min(MAX_SEGMENT_BYTES);
+    while !collapsed.is_char_boundary(end) {
+        end -= 1;
+    }
+    let name = collapsed[..end].trim_end_matches(['.', ' ']).to_string();
+    let stem = name.split('.').next().unwrap_or_default();
+    if RESERVED_NAMES.iter().any(|reserved| reserved.eq_ignore_ascii_case(stem)) {
+        format!("{}_", name)
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cbz_numbers_pages_in_order() {
+        let dir = std::env::temp_dir().join(format!("jm-library-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let pages: Vec<PathBuf> = ["00002.png", "00010.webp"]
+            .iter()
+            .map(|name| {
+                let path = dir.join(name);
+                std::fs::write(&path, name.as_bytes()).unwrap();
+                path
+            })
+            .collect();
+        let target = dir.join("系列").join("系列 - 第1话.cbz");
+        write_cbz(&target, &pages, "<ComicInfo/>").unwrap();
+
+        let mut archive = zip::ZipArchive::new(File::open(&target).unwrap()).unwrap();
+        let names: Vec<&str> = archive.file_names().collect();
+        assert_eq!(names, ["0001.png", "0002.webp", COMIC_INFO_FILE]);
+        let mut content = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name("0002.webp").unwrap(), &mut content).unwrap();
+        assert_eq!(content, "00010.webp");
+        assert!(!target.with_extension("cbz.tmp").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn cbz_paths_include_id_and_order() {
+        let chapter = ChapterMeta { chapter_id: 456, title: "第1话", page_count: 10 };
+        let original = FilenameStyle::Original;
+        assert_eq!(cbz_path(123, "漫画", Some(&chapter), false, original), "漫画 [JM123]/漫画 - 第1话.cbz");
+        assert_eq!(cbz_path(123, "漫画", None, false, original), "漫画 [JM123]/漫画.cbz");
+        // 同名的不同漫画、同一章节的两种页序各自独立
+        assert_ne!(cbz_path(123, "漫画", None, false, original), cbz_path(124, "漫画", None, false, original));
+        assert_eq!(cbz_path(123, "漫画", None, true, original), "漫画 [JM123]/漫画 [RTL].cbz");
+        assert_eq!(cbz_path(123, "漫画", Some(&chapter), false, FilenameStyle::Id), "JM123/JM123 - 456.cbz");
+        assert_eq!(cbz_path(123, "", None, false, original), "JM123/JM123.cbz");
+    }
+
+    #[test]
+    fn names_segments_safely_for_each_style() {
+        let original = |name| path_segment(name, "JM1", FilenameStyle::Original);
+        assert_eq!(original("漫画: 第1话?"), "漫画_ 第1话_");
+        assert_eq!(original("  a/b\\c  \t d... "), "a_b_c d");
+        assert_eq!(original("con"), "con_");
+        assert_eq!(original("Aux.part1"), "Aux.part1_");
+        assert_eq!(original(".."), "JM1");
+        assert_eq!(original(""), "JM1");
+        // 按字符边界截断，不超过上限
+        let long = original(&"漫".repeat(100));
+        assert_eq!(long.len(), MAX_SEGMENT_BYTES / 3 * 3);
+
+        assert_eq!(path_segment("北京 漫画", "JM1", FilenameStyle::Pinyin), "Bei Jing Man Hua");
+        assert!(path_segment("はじめて", "JM1", FilenameStyle::Pinyin).is_ascii());
+        assert_eq!(path_segment("北京", "JM1", FilenameStyle::Id), "JM1");
+    }
+}
@@ -0,0 +1,125 @@
+// 日志初始化
+// 工作目录下存在 log4rs.yaml 时按其配置（支持热加载）；否则使用内置配置：控制台输出，
+// 加上按大小（可选再按天）滚动的文件日志，最多保留 JM_LOG_MAX_FILES 个归档。
+// 文件日志无法创建（如只读文件系统）时只输出到控制台，不因日志配置导致启动失败
+
+use std::path::Path;
+
+use log4rs::append::console::ConsoleAppender;
+use log4rs::append::rolling_file::policy::compound::roll::fixed_window::FixedWindowRoller;
+use log4rs::append::rolling_file::policy::compound::trigger::size::SizeTrigger;
+use log4rs::append::rolling_file::policy::compound::trigger::time::{
+    TimeTrigger, TimeTriggerConfig, TimeTriggerInterval,
+};
+use log4rs::append::rolling_file::policy::compound::trigger::Trigger;
+use log4rs::append::rolling_file::policy::compound::CompoundPolicy;
+use log4rs::append::rolling_file::{LogFile, RollingFileAppender};
+use log4rs::config::{Appender, Root};
+use log4rs::encode::pattern::PatternEncoder;
+
+use crate::config::Config;
+
+pub const LOG_CONFIG_FILE: &str = "log4rs.yaml";
+
+const CONSOLE_PATTERN: &str = "{d(%Y-%m-%dT%H:%M:%S%.3f%:z)(local)}  {h({l}):<5} {f:-24.24}:{L:<5}: {m}{n}";
+const FILE_PATTERN: &str = "{d(%Y-%m-%dT%H:%M:%S%.3f%:z)(local)}  {l:<5.5} {f}:{L}: {m}{n}";
+
+/// 初始化全局日志，启动时在加载配置后调用一次
+pub fn init(config: &Config) {
+    if Path::new(LOG_CONFIG_FILE).is_file() {
+        match log4rs::init_file(LOG_CONFIG_FILE, Default::default()) {
+            Ok(()) => return,
+            Err(e) => eprintln!("加载 {} 失败，改用内置日志配置: {}", LOG_CONFIG_FILE, e),
+        }
+    }
+
+    let console = ConsoleAppender::builder()
+        .encoder(Box::new(PatternEncoder::new(CONSOLE_PATTERN)))
+        .build();
+    let mut builder = log4rs::Config::builder()
+        .appender(Appender::builder().build("stdout", Box::new(console)));
+    let mut root = Root::builder().appender("stdout");
+    let mut file_error = None;
+    if let Some(path) = &config.log_file {
+        match rolling_file(config, path) {
+            Ok(appender) => {
+                builder = builder.appender(Appender::builder().build("rolling_file", Box::new(appender)));
+                root = root.appender("rolling_file");
+            }
+            Err(e) => file_error = Some(format!("创建日志文件 {} 失败，仅输出到控制台: {}", path, e)),
+        }
+    }
+    let log_config = builder.build(root.build(config.log_level)).expect("内置日志配置无效");
+    log4rs::init_config(log_config).expect("init log4rs");
+
+    match file_error {
+        Some(e) => warn!("{}", e),
+        None => info!("未找到 {}，使用内置日志配置", LOG_CONFIG_FILE),
+    }
+}
+
+/// 按大小（可选再按天）滚动的文件日志，归档为 `app.1.log`、`app.2.log`……
+fn rolling_file(config: &Config, path: &str) -> anyhow::Result<RollingFileAppender> {
+    let trigger = RollTrigger {
+        size: SizeTrigger::new(config.log_max_size_mb * 1024 * 1024),
+        daily: config.log_rotate_daily.then(|| {
+            TimeTrigger::new(TimeTriggerConfig {
+                interval: TimeTriggerInterval::Day(1),
+                modulate: false,
+                max_random_delay: 0,
+            })
+        }),
+    };
+    let roller = FixedWindowRoller::builder()
+        .base(1)
+        .build(&archive_pattern(path), config.log_max_files)?;
+    let appender = RollingFileAppender::builder()
+        .encoder(Box::new(PatternEncoder::new(FILE_PATTERN)))
+        .build(path, Box::new(CompoundPolicy::new(Box::new(trigger), Box::new(roller))))?;
+    Ok(appender)
+}
+
+/// 归档文件名模板：`logs/app.log` -> `logs/app.{}.log`
+fn archive_pattern(path: &str) -> String {
+    let path = Path::new(path);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{}.{{}}.{}", stem, ext.to_string_lossy()),
+        None => format!("{}.{{}}", stem),
+    };
+    path.with_file_name(name).to_string_lossy().to_string()
+}
+
+/// 文件超过大小上限或（启用按天滚动时）跨过零点时滚动
+#[derive(Debug)]
+struct RollTrigger {
+    size: SizeTrigger,
+    daily: Option<TimeTrigger>,
+}
+
+impl Trigger for RollTrigger {
+    fn trigger(&self, file: &LogFile) -> anyhow::Result<bool> {
+        if let Some(daily) = &self.daily {
+            if daily.trigger(file)? {
+                return Ok(true);
+            }
+        }
+        self.size.trigger(file)
+    }
+
+    /// 按天滚动需要在写入前判断，否则跨天后的第一条日志会留在前一天的文件中
+    fn is_pre_process(&self) -> bool {
+        self.daily.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archives_next_to_the_active_log() {
+        assert_eq!(archive_pattern("logs/app.log"), "logs/app.{}.log");
+        assert_eq!(archive_pattern("/var/log/jm"), "/var/log/jm.{}");
+    }
+}
@@ -0,0 +1,197 @@
+// 邮件发送模块
+// 合并后的 PDF 通过 SMTP 作为附件发送（如发送到 Kindle 邮箱），超过附件上限时拆分为多卷逐封发送。
+// 收件人必须在 JM_SMTP_ALLOWED_RECIPIENTS 中，避免任何能访问下载接口的调用方借服务的 SMTP 账号向任意地址发信
+
+use std::path::{Path, PathBuf};
+
+use jm_downloader_rs::AppError;
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use crate::config::{Config, SmtpSecurity};
+use crate::file_server::sanitize_filename;
+use crate::image_processor::split_pdf;
+
+type Result<T> = std::result::Result<T, AppError>;
+
+/// 校验收件人地址，服务未配置 SMTP 或收件人不在允许列表中时返回错误；在开始下载前调用，避免下载完成后才发现无法发送
+pub fn parse_recipient(config: &Config, to: &str) -> Result<Mailbox> {
+    if config.smtp_host.is_none() {
+        return Err(AppError::BadRequest("服务未配置 SMTP，无法发送邮件".to_string()));
+    }
+    let mailbox: Mailbox = to
+        .trim()
+        .parse()
+        .map_err(|e| AppError::BadRequest(format!("收件人邮箱地址无效: {}: {}", to, e)))?;
+    if config.smtp_allowed_recipients.is_empty() {
+        return Err(AppError::Forbidden("服务未配置 JM_SMTP_ALLOWED_RECIPIENTS，不允许发送邮件".to_string()));
+    }
+    if !recipient_allowed(&config.smtp_allowed_recipients, mailbox.email.as_ref()) {
+        return Err(AppError::Forbidden(format!("收件人 {} 不在 JM_SMTP_ALLOWED_RECIPIENTS 中", mailbox.email)));
+    }
+    Ok(mailbox)
+}
+
+/// 允许列表中的完整地址或域名（可带 `@` 前缀）匹配收件人，不区分大小写
+fn recipient_allowed(allowed: &[String], email: &str) -> bool {
+    let email = email.to_ascii_lowercase();
+    let domain = email.rsplit_once('@').map_or("", |(_, domain)| domain);
+    allowed.iter().any(|entry| {
+        let entry = entry.to_ascii_lowercase();
+        if entry.contains('@') && !entry.starts_with('@') {
+            entry == email
+        } else {
+            entry.trim_start_matches('@') == domain
+        }
+    })
+}
+
+/// 把 PDF 作为附件发送给 `to`，返回发送的邮件数
+///
+/// 文件超过 `smtp_max_attachment_mb` 时按体积拆分，每卷一封邮件，发送完成后删除拆分出的临时分卷
+pub async fn send_pdf(
+    config: &Config,
+    to: Mailbox,
+    title: &str,
+    pdf_path: &Path,
+    total_pages: usize,
+    password: Option<&str>,
+) -> Result<usize> {
+    let transport = build_transport(config)?;
+    let from: Mailbox = config
+        .smtp_from
+        .as_deref()
+        .unwrap_or_default()
+        .parse()
+        .map_err(|e| AppError::Internal(format!("发件人邮箱地址无效: {}", e)))?;
+
+    let volumes = split_for_mail(config, pdf_path, total_pages, password).await?;
+    let count = volumes.len();
+    let result = async {
+        for (index, volume) in volumes.iter().enumerate() {
+            let (subject, filename) = if count == 1 {
+                (title.to_string(), format!("{}.pdf", title))
+            } else {
+                (
+                    format!("{} ({}/{})", title, index + 1, count),
+                    format!("{}_part{}.pdf", title, index + 1),
+                )
+            };
+            let body = tokio::fs::read(volume).await.map_err(|e| {
+                AppError::Internal(format!("读取PDF失败: {}: {}", volume.display(), e))
+            })?;
+            let message = Message::builder()
+                .from(from.clone())
+                .to(to.clone())
+                .subject(subject)
+                .multipart(
+                    MultiPart::mixed()
+                        .singlepart(SinglePart::plain(format!("{}\n由 jm-downloader-rs 发送", title)))
+                        .singlepart(
+                            Attachment::new(sanitize_filename(&filename))
+                                .body(body, ContentType::parse("application/pdf").unwrap()),
+                        ),
+                )
+                .map_err(|e| AppError::Internal(format!("构造邮件失败: {}", e)))?;
+            transport
+                .send(message)
+                .await
+                .map_err(|e| AppError::Internal(format!("发送邮件到 {} 失败: {}", to, e)))?;
+            info!("已发送邮件 {}/{} 到 {}", index + 1, count, to);
+        }
+        Ok(count)
+    }
+    .await;
+
+    // 仅删除为发送而拆分出的分卷，原 PDF 保留
+    for volume in volumes.iter().filter(|volume| volume.as_path() != pdf_path) {
+        let _ = tokio::fs::remove_file(volume).await;
+    }
+    result
+}
+
+fn build_transport(config: &Config) -> Result<AsyncSmtpTransport<Tokio1Executor>> {
+    let host = config
+        .smtp_host
+        .as_deref()
+        .ok_or_else(|| AppError::BadRequest("服务未配置 SMTP，无法发送邮件".to_string()))?;
+    let builder = match config.smtp_security {
+        SmtpSecurity::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host),
+        SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host),
+        SmtpSecurity::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)),
+    }
+    .map_err(|e| AppError::Internal(format!("创建 SMTP 连接失败: {}: {}", host, e)))?;
+
+    let builder = builder.port(config.smtp_port);
+    let builder = match (&config.smtp_username, &config.smtp_password) {
+        (Some(username), Some(password)) => {
+            builder.credentials(Credentials::new(username.clone(), password.clone()))
+        }
+        _ => builder,
+    };
+    Ok(builder.build())
+}
+
+/// PDF 未超过附件上限时直接返回原文件，否则拆分为多个分卷
+///
+/// 拆分前先链接为 `*.mail.pdf`，避免与请求中 pdf_max_size_mb 生成的分卷同名
+async fn split_for_mail(
+    config: &Config,
+    pdf_path: &Path,
+    total_pages: usize,
+    password: Option<&str>,
+) -> Result<Vec<PathBuf>> {
+    let size = tokio::fs::metadata(pdf_path)
+        .await
+        .map_err(|e| AppError::Internal(format!("读取PDF大小失败: {}: {}", pdf_path.display(), e)))?
+        .len();
+    if size <= config.smtp_max_attachment_mb.saturating_mul(1024 * 1024) {
+        return Ok(vec![pdf_path.to_path_buf()]);
+    }
+
+    let mail_path = pdf_path.with_extension("mail.pdf");
+    let _ = tokio::fs::remove_file(&mail_path).await;
+    if tokio::fs::hard_link(pdf_path, &mail_path).await.is_err() {
+        tokio::fs::copy(pdf_path, &mail_path).await.map_err(|e| {
+            AppError::Internal(format!("复制PDF失败: {}: {}", mail_path.display(), e))
+        })?;
+    }
+    let volumes = split_pdf(
+        &mail_path,
+        total_pages,
+        None,
+        Some(config.smtp_max_attachment_mb),
+        password,
+    )
+    .await;
+    if !matches!(&volumes, Ok(paths) if paths.contains(&mail_path)) {
+        let _ = tokio::fs::remove_file(&mail_path).await;
+    }
+    volumes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_mails_allowed_recipients() {
+        let config: Config = toml::from_str(
+            "smtp_host = \"smtp.example.com\"\nsmtp_allowed_recipients = [\"Me@Example.com\", \"@kindle.com\", \"free.kindle.com\"]",
+        )
+        .unwrap();
+        for to in ["me@example.com", "someone@KINDLE.com", "Reader <a@free.kindle.com>"] {
+            assert!(parse_recipient(&config, to).is_ok(), "{}", to);
+        }
+        for to in ["other@example.com", "a@evil-kindle.com", "a@sub.kindle.com"] {
+            assert!(matches!(parse_recipient(&config, to), Err(AppError::Forbidden(_))), "{}", to);
+        }
+        assert!(matches!(parse_recipient(&config, "not an address"), Err(AppError::BadRequest(_))));
+
+        // 未配置允许列表时一律拒绝
+        let config: Config = toml::from_str("smtp_host = \"smtp.example.com\"").unwrap();
+        assert!(matches!(parse_recipient(&config, "me@example.com"), Err(AppError::Forbidden(_))));
+    }
+}
@@ -1,20 +1,82 @@
 #[macro_use]
 extern crate rocket;
 
+mod account_pool;
+mod adaptive_concurrency;
+mod admin;
+mod admin_listener;
+mod artifact;
+mod atomic_file;
+mod checksums;
+mod circuit_breaker;
+mod coalesce;
+mod comic_ref;
 mod config;
+mod dashboard;
+mod dir_lease;
+mod domain_probe;
+mod doctor;
 mod models;
+mod throttle;
+mod torrent;
+mod progress;
+mod jobs;
+mod mailer;
+mod memory_budget;
+mod metadata;
+mod library;
+mod logging;
+mod notifier;
+mod pacing;
+mod page_selection;
+mod page_sidecar;
+mod post_process;
+mod preview;
+mod purchase;
+mod reports;
+mod jm_api;
 mod jm_client;
 mod handlers;
 mod image_processor;
+mod scramble;
+mod scramble_cache;
+mod service_mode;
+mod spec_export;
 mod global_client;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod history;
+mod https_redirect;
+mod file_server;
+mod storage;
+mod telemetry;
+mod title_page;
+mod upscale;
+mod url_signer;
+mod validation;
+mod watch_dir;
+mod web_client;
+#[cfg(test)]
+mod mock_client;
 
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use rocket::fairing::AdHoc;
+use rocket::figment::Figment;
 use rocket::http::Method;
-use rocket::fs::FileServer;
+use rocket::{Build, Request, Rocket};
 use rocket_cors::{AllowedHeaders, AllowedOrigins, CorsOptions};
-use rocket_okapi::{openapi, openapi_get_routes};
+use rocket_okapi::okapi::openapi3::OpenApi;
+use rocket_okapi::settings::OpenApiSettings;
+use rocket_okapi::{get_openapi_route, openapi, openapi_get_routes_spec};
+use rocket_okapi::rapidoc::{make_rapidoc, GeneralConfig, RapiDocConfig};
+use rocket_okapi::settings::UrlObject;
 use rocket_okapi::swagger_ui::{make_swagger_ui, SwaggerUIConfig};
-use jm_downloader_rs::{ApiResult, R};
+use jm_downloader_rs::i18n::Lang;
+use jm_downloader_rs::{ApiResult, AppError, ProblemJson, R};
 use global_client::GlobalJmClient;
+use config::LiveConfig;
+use url_signer::UrlSigner;
 
 /// # 健康检查
 /// 返回服务运行状态。
@@ -24,20 +86,199 @@ async fn health() -> ApiResult<R<String>> {
     Ok(R::success("ok".to_string()))
 }
 
+/// 按请求头 `Accept-Problem: true/false` 或 `Accept: application/problem+json` 决定错误响应格式，
+/// 请求未指定时使用 JM_PROBLEM_JSON
+fn problem_json_requested(req: &Request<'_>) -> bool {
+    let headers = req.headers();
+    if let Some(value) = headers.get_one("Accept-Problem") {
+        return value.trim().eq_ignore_ascii_case("true") || value.trim() == "1";
+    }
+    if headers.get("Accept").any(|accept| accept.contains("application/problem+json")) {
+        return true;
+    }
+    req.rocket()
+        .state::<LiveConfig>()
+        .is_some_and(|config| config.load().problem_json)
+}
+
+/// 收到 SIGHUP 时重新加载配置
+#[cfg(unix)]
+fn spawn_sighup_reload(config: LiveConfig, global_client: GlobalJmClient, url_signer: UrlSigner) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(e) => {
+            warn!("注册 SIGHUP 处理失败，只能通过管理接口重新加载配置: {}", e);
+            return;
+        }
+    };
+    tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            info!("收到 SIGHUP，重新加载配置");
+            if let Err(e) = admin::reload(&config, &global_client, &url_signer).await {
+                error!("重新加载配置失败，继续使用原配置: {}", e);
+            }
+        }
+    });
+}
+
+/// 请求体超过 JM_MAX_BODY_KB 时 Rocket 以 413 拒绝，按统一错误格式返回 10016
+#[catch(413)]
+fn payload_too_large() -> AppError {
+    AppError::TooLarge("请求体超过大小上限（JM_MAX_BODY_KB）".to_string())
+}
+
+/// Rocket 配置：设置了 JM_BIND_ADDR/JM_PORT 时覆盖 Rocket.toml 与 ROCKET_ADDRESS/ROCKET_PORT，
+/// 设置了 JM_TLS_CERT/JM_TLS_KEY 时启用 HTTPS；JSON 请求体上限取 JM_MAX_BODY_KB
+fn rocket_figment(config: &config::Config) -> Figment {
+    let mut figment = rocket::Config::figment().merge(("limits.json", config.max_body_bytes()));
+    if let Some(addr) = config.bind_addr {
+        figment = figment.merge(("address", addr));
+    }
+    if let Some(port) = config.port {
+        figment = figment.merge(("port", port));
+    }
+    if let (Some(cert), Some(key)) = (&config.tls_cert, &config.tls_key) {
+        figment = figment.merge(("tls.certs", cert)).merge(("tls.key", key));
+    }
+    figment
+}
+
+/// 错误响应格式（信封或 problem+json）与语言协商，公开端口与管理端口共用
+fn with_error_format(rocket: Rocket<Build>) -> Rocket<Build> {
+    rocket
+        .attach(AdHoc::on_request("problem+json", |req, _| {
+            Box::pin(async move {
+                let enabled = problem_json_requested(req);
+                req.local_cache(|| ProblemJson(enabled));
+            })
+        }))
+        .attach(AdHoc::on_request("Accept-Language", |req, _| {
+            Box::pin(async move {
+                let lang = Lang::negotiate(req.headers().get_one("Accept-Language"));
+                req.local_cache(|| lang);
+            })
+        }))
+        .register("/", catchers![payload_too_large])
+}
+
+/// 日志中展示的访问地址，监听所有地址时改用本机回环地址
+fn local_url(scheme: &str, addr: IpAddr, port: u16) -> String {
+    let host = match addr {
+        IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+        ip => ip,
+    };
+    format!("{}://{}", scheme, SocketAddr::new(host, port))
+}
+
+/// 带 OpenAPI 文档的全部接口及生成的文档
+fn api_routes() -> (Vec<rocket::Route>, OpenApi) {
+    openapi_get_routes_spec![
+        health,
+        handlers::download_chapter,
+        handlers::download_chapter_stream,
+        handlers::sync_new_chapters,
+        handlers::download_comic,
+        handlers::get_comic_info,
+        handlers::resolve,
+        handlers::preview_comic,
+        handlers::get_comic_chapters,
+        handlers::check_local,
+        handlers::get_latest,
+        handlers::search_comics,
+        handlers::get_week_best,
+        handlers::get_user_profile,
+        handlers::user_checkin,
+        handlers::like_comic,
+        handlers::comment_comic,
+        jobs::list_jobs,
+        jobs::pause_job,
+        jobs::resume_job,
+        jobs::cancel_job,
+        jobs::job_events,
+        jobs::job_result,
+        admin::cleanup,
+        admin::storage,
+        admin::domains,
+        admin::get_mode,
+        admin::set_mode,
+        admin::reload_config,
+        admin::raw_album,
+        admin::raw_chapter,
+        reports::usage
+    ]
+}
+
 #[launch]
 async fn rocket() -> _ {
-    log4rs::init_file("log4rs.yaml", Default::default()).expect("init log4rs");
+    // 导出 OpenAPI 文档后直接退出，不需要配置与 JM 账号
+    if let Some(path) = spec_export::requested() {
+        let (_, spec) = api_routes();
+        match spec_export::write(&path, &spec) {
+            Ok(()) => {
+                println!("已导出 OpenAPI 文档到 {}", path.display());
+                std::process::exit(0);
+            }
+            Err(e) => {
+                eprintln!("导出 OpenAPI 文档失败: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // 自检模式：打印诊断报告后直接退出，不启动服务
+    if let Some(comic_id) = doctor::requested() {
+        let passed = doctor::run(comic_id).await;
+        std::process::exit(if passed { 0 } else { 1 });
+    }
 
     // 加载配置
     let config = config::load_config().expect("Failed to load config");
+    logging::init(&config);
+    telemetry::init(&config);
+    image_processor::init_cpu_pool(config.cpu_threads).expect("初始化图片处理线程池失败");
+    info!("图片处理线程池已创建，线程数 {}", config.cpu_threads);
 
     // 创建全局 JmClient 实例并登录
     let global_client = GlobalJmClient::new(&config)
         .await
         .expect("Failed to initialize global JmClient");
 
-    info!("全局 JmClient 已创建并完成初始登录");
-    std::fs::create_dir_all("download").expect("创建下载目录失败");
+    if global_client.is_anonymous() {
+        info!("全局 JmClient 已创建（匿名模式）");
+    } else {
+        info!("全局 JmClient 已创建并完成初始登录");
+    }
+    if config.keep_alive_minutes > 0 && !global_client.is_anonymous() {
+        global_client.spawn_keep_alive(std::time::Duration::from_secs(config.keep_alive_minutes * 60));
+        info!("已启用会话保活，间隔约 {} 分钟", config.keep_alive_minutes);
+    }
+    if config.image_probe_seconds > 0 {
+        global_client.spawn_domain_probe(std::time::Duration::from_secs(config.image_probe_seconds));
+        info!("已启用图片域名探测，间隔 {} 秒", config.image_probe_seconds);
+    }
+    image_processor::init_download_root(&config.download_dir).expect("创建下载目录失败");
+    throttle::set_max_download_mbps(config.max_download_mbps);
+    image_processor::set_blocked_image_md5(config.image_blocked_md5.clone());
+    image_processor::set_png_settings(config.png_settings());
+    image_processor::set_max_image_mb(config.max_image_mb);
+    upscale::configure(&config);
+    adaptive_concurrency::configure(config.adaptive_concurrency, config.img_concurrency);
+    if config.max_download_mbps > 0.0 {
+        info!("已启用图片下载限速，上限 {} MB/s", config.max_download_mbps);
+    }
+    memory_budget::configure(config.memory_budget_mb, config.spool_threshold_mb);
+    history::configure(config.history_path());
+    if config.memory_budget_mb > 0 {
+        info!("已启用图片解码内存预算 {} MB", config.memory_budget_mb);
+    }
+
+    if config.service_mode != service_mode::ServiceMode::Normal {
+        service_mode::set(config.service_mode);
+        warn!("服务以 {:?} 模式启动", config.service_mode);
+    }
 
     let cors = CorsOptions::default()
         .allowed_origins(AllowedOrigins::all())
@@ -49,22 +290,101 @@ async fn rocket() -> _ {
                 .collect(),
         )
         .allow_credentials(true);
-    info!("健康检查地址 http://127.0.0.1:8000/api/health");
-    info!("在线调试 http://127.0.0.1:8000/docs");
-    rocket::build()
+    let figment = rocket_figment(&config);
+    let rocket_config: rocket::Config = figment.extract().expect("Rocket 配置无效");
+    let scheme = if rocket_config.tls_enabled() { "https" } else { "http" };
+    let base_url = local_url(scheme, rocket_config.address, rocket_config.port);
+    info!("{} 服务监听 {}", scheme.to_uppercase(), SocketAddr::new(rocket_config.address, rocket_config.port));
+    info!("健康检查地址 {}/api/health", base_url);
+    info!("在线调试 {}/docs（RapiDoc: /rapidoc）", base_url);
+    match config.https_redirect_port {
+        Some(port) if rocket_config.tls_enabled() => {
+            https_redirect::spawn(rocket_config.address, port, rocket_config.port)
+        }
+        Some(_) => warn!("已设置 JM_HTTPS_REDIRECT_PORT，但未启用 HTTPS，不监听重定向端口"),
+        None => {}
+    }
+    let url_signer = UrlSigner::from_config(&config);
+    let storage = storage::Storage::from_config(&config, &url_signer).expect("初始化存储后端失败");
+    let config = LiveConfig::new(config);
+    #[cfg(unix)]
+    spawn_sighup_reload(config.clone(), global_client.clone(), url_signer.clone());
+
+    let inflight = handlers::InFlightDownloads::default();
+    let leases = dir_lease::DirLeases::default();
+    let jobs = jobs::Jobs::default();
+    match config.load().grpc_addr {
+        #[cfg(feature = "grpc")]
+        Some(addr) => grpc::spawn(
+            addr,
+            grpc::GrpcService {
+                config: config.clone(),
+                global_client: global_client.clone(),
+                storage: storage.clone(),
+                inflight: inflight.clone(),
+                leases: leases.clone(),
+                jobs: jobs.clone(),
+            },
+        ),
+        #[cfg(not(feature = "grpc"))]
+        Some(_) => warn!("已设置 JM_GRPC_ADDR，但程序未以 --features grpc 编译，不启动 gRPC 接口"),
+        None => {}
+    }
+    if let Some(dir) = &config.load().watch_dir {
+        watch_dir::WatchDir {
+            dir: std::path::PathBuf::from(dir),
+            interval: std::time::Duration::from_secs(config.load().watch_interval_seconds),
+            config: config.clone(),
+            global_client: global_client.clone(),
+            storage: storage.clone(),
+            inflight: inflight.clone(),
+            leases: leases.clone(),
+            jobs: jobs.clone(),
+        }
+        .spawn();
+    }
+
+    let (mut routes, mut spec) = api_routes();
+    let admin_addr = config.load().admin_addr;
+    // 仪表盘调用的存储统计、清理与任务控制只在管理端口提供，仪表盘随之移过去
+    let public_pages = match admin_addr {
+        Some(_) => routes![file_server::serve_download],
+        None => routes![file_server::serve_download, dashboard::ui],
+    };
+    if let Some(addr) = admin_addr {
+        let (admin_routes, admin_spec) = admin_listener::split_off(&mut routes, &mut spec);
+        // 沿用公开端口的 TLS 与请求体上限，只替换监听地址
+        let admin_figment = figment.clone().merge(("address", addr.ip())).merge(("port", addr.port()));
+        let admin = with_error_format(rocket::custom(admin_figment))
+            .manage(config.clone())
+            .manage(global_client.clone())
+            .manage(url_signer.clone())
+            .manage(storage.clone())
+            .manage(inflight.clone())
+            .manage(leases.clone())
+            .manage(jobs.clone())
+            .mount("/", admin_routes)
+            .mount("/", vec![get_openapi_route(admin_spec, &OpenApiSettings::new())])
+            .mount("/", routes![dashboard::ui]);
+        admin_listener::spawn(admin, addr);
+    }
+    with_error_format(rocket::custom(figment))
         .attach(cors.to_cors().unwrap())
+        .attach(AdHoc::on_shutdown("OpenTelemetry", |_| {
+            Box::pin(async {
+                let _ = tokio::task::spawn_blocking(telemetry::shutdown).await;
+            })
+        }))
         .manage(config)
         .manage(global_client)
-        .mount(
-            "/",
-            openapi_get_routes![
-                health,
-                handlers::download_chapter,
-                handlers::download_comic,
-                handlers::get_comic_info
-            ],
-        )
-        .mount("/download", FileServer::from("download"))
+        .manage(url_signer)
+        .manage(storage)
+        .manage(inflight)
+        .manage(leases)
+        .manage(jobs)
+        .mount("/", routes)
+        .mount("/", vec![get_openapi_route(spec, &OpenApiSettings::new())])
+        .mount("/", public_pages)
         .mount(
             "/docs",
             make_swagger_ui(&SwaggerUIConfig {
@@ -72,4 +392,14 @@ async fn rocket() -> _ {
                 ..Default::default()
             }),
         )
+        .mount(
+            "/rapidoc",
+            make_rapidoc(&RapiDocConfig {
+                general: GeneralConfig {
+                    spec_urls: vec![UrlObject::new("jm-downloader-rs", "/openapi.json")],
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+        )
 }
@@ -0,0 +1,122 @@
+// 内存预算模块
+// 图片解码前按预估的解码后字节数申请全局预算（JM_MEMORY_BUDGET_MB），超出时等待其他图片处理完成，
+// 使峰值内存不随并发数增长；超过 JM_SPOOL_THRESHOLD_MB 的响应体先写入临时文件，等到获得预算时再读入
+
+use std::io::Cursor;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use tokio::sync::Notify;
+
+static BUDGET: Mutex<Budget> = Mutex::new(Budget { limit: 0, used: 0 });
+static RELEASED: Notify = Notify::const_new();
+/// 响应体超过该字节数时写入临时文件，0 表示始终保存在内存中
+static SPOOL_THRESHOLD: AtomicU64 = AtomicU64::new(0);
+
+struct Budget {
+    /// 预算上限（字节），0 表示不限制
+    limit: u64,
+    /// 已被占用的字节数
+    used: u64,
+}
+
+impl Budget {
+    /// 预算足够时占用 `bytes` 并返回 true；没有其他占用时总是允许，避免单张超大图片永远等待
+    fn try_take(&mut self, bytes: u64) -> bool {
+        if self.limit == 0 {
+            return true;
+        }
+        if self.used == 0 || self.used + bytes <= self.limit {
+            self.used += bytes;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// 设置内存预算与落盘阈值（MB），0 表示不限制/不落盘；启动与重新加载配置时调用
+pub fn configure(budget_mb: u64, spool_threshold_mb: u64) {
+    BUDGET.lock().unwrap().limit = budget_mb * 1024 * 1024;
+    SPOOL_THRESHOLD.store(spool_threshold_mb * 1024 * 1024, Ordering::Relaxed);
+    // 预算变大时唤醒等待者重新检查
+    RELEASED.notify_waiters();
+}
+
+/// 响应体落盘阈值（字节），0 表示不落盘
+pub fn spool_threshold() -> u64 {
+    SPOOL_THRESHOLD.load(Ordering::Relaxed)
+}
+
+/// 预算占用，释放时归还
+pub struct Reservation {
+    bytes: u64,
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        if self.bytes > 0 {
+            let mut budget = BUDGET.lock().unwrap();
+            budget.used = budget.used.saturating_sub(self.bytes);
+            drop(budget);
+            RELEASED.notify_waiters();
+        }
+    }
+}
+
+/// 申请 `bytes` 字节的预算，不足时等待；未设置预算时立即返回
+pub async fn reserve(bytes: u64) -> Reservation {
+    loop {
+        // 先登记等待再检查，避免检查与等待之间的释放通知丢失
+        let released = RELEASED.notified();
+        tokio::pin!(released);
+        released.as_mut().enable();
+        {
+            let mut budget = BUDGET.lock().unwrap();
+            if budget.try_take(bytes) {
+                let bytes = if budget.limit == 0 { 0 } else { bytes };
+                return Reservation { bytes };
+            }
+        }
+        released.await;
+    }
+}
+
+/// 预估图片解码与拼接所需的内存：源图与拼接结果各一份 RGB 缓冲；无法读取尺寸时按压缩体积的 10 倍估算
+pub fn decoded_size(header: &[u8], compressed_len: u64) -> u64 {
+    image::ImageReader::new(Cursor::new(header))
+        .with_guessed_format()
+        .ok()
+        .and_then(|reader| reader.into_dimensions().ok())
+        .map(|(width, height)| u64::from(width) * u64::from(height) * 3 * 2)
+        .unwrap_or(compressed_len * 10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_admits_until_full() {
+        let mut budget = Budget { limit: 100, used: 0 };
+        // 没有其他占用时即使超出预算也允许
+        assert!(budget.try_take(150));
+        assert!(!budget.try_take(1));
+        budget.used = 60;
+        assert!(budget.try_take(40));
+        assert!(!budget.try_take(1));
+
+        let mut unlimited = Budget { limit: 0, used: 0 };
+        assert!(unlimited.try_take(u64::MAX));
+    }
+
+    #[test]
+    fn estimates_decoded_size_from_header() {
+        let mut png = Vec::new();
+        image::RgbImage::new(20, 10)
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        assert_eq!(decoded_size(&png, png.len() as u64), 20 * 10 * 3 * 2);
+        assert_eq!(decoded_size(b"not an image", 12), 120);
+    }
+}
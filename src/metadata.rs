@@ -0,0 +1,271 @@
+// 元数据附属文件
+// 下载时在漫画目录写入 ComicInfo.xml 与 metadata.json，在章节目录写入该章节的 ComicInfo.xml，
+// 供 Komga/Kavita/Calibre 等书库识别标题、作者、标签与页数
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use jm_downloader_rs::AppError;
+use serde::{Deserialize, Serialize};
+
+use crate::atomic_file;
+use crate::config::Config;
+use crate::image_processor::{chapter_dir_path, download_root};
+use crate::models::GetComicRespData;
+
+type Result<T> = std::result::Result<T, AppError>;
+
+pub const COMIC_INFO_FILE: &str = "ComicInfo.xml";
+pub const METADATA_FILE: &str = "metadata.json";
+
+/// 本次下载的章节
+pub struct ChapterMeta<'a> {
+    pub chapter_id: i64,
+    pub title: &'a str,
+    pub page_count: usize,
+}
+
+/// metadata.json 的内容
+#[derive(Debug, Serialize, Deserialize)]
+struct ComicMetadata {
+    source: String,
+    comic_id: i64,
+    title: String,
+    authors: Vec<String>,
+    tags: Vec<String>,
+    works: Vec<String>,
+    actors: Vec<String>,
+    description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    add_time: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    update_time: Option<i64>,
+    chapters: Vec<ChapterMetadata>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ChapterMetadata {
+    chapter_id: i64,
+    title: String,
+    number: usize,
+    /// 已下载过的章节的页数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    page_count: Option<usize>,
+}
+
+/// 写入漫画目录与各章节目录的元数据，失败只记录日志，不影响下载结果
+pub async fn write(config: &Config, comic_id: i64, comic: &GetComicRespData, chapters: &[ChapterMeta<'_>]) {
+    if !config.write_metadata {
+        return;
+    }
+    if let Err(e) = try_write(&config.web_domain, comic_id, comic, chapters).await {
+        warn!("写入漫画 {} 的元数据失败: {}", comic_id, e);
+    }
+}
+
+async fn try_write(
+    web_domain: &str,
+    comic_id: i64,
+    comic: &GetComicRespData,
+    chapters: &[ChapterMeta<'_>],
+) -> Result<()> {
+    for chapter in chapters {
+        let xml = comic_info_xml(web_domain, comic_id, comic, Some(chapter));
+        let path = chapter_dir_path(comic_id, chapter.chapter_id).join(COMIC_INFO_FILE);
+        write_file(&path, xml.into_bytes()).await?;
+    }
+
+    let comic_dir = download_root().join(comic_id.to_string());
+    let xml = comic_info_xml(web_domain, comic_id, comic, None);
+    write_file(&comic_dir.join(COMIC_INFO_FILE), xml.into_bytes()).await?;
+
+    // 保留之前下载过的章节页数
+    let mut page_counts: BTreeMap<i64, usize> = match tokio::fs::read(comic_dir.join(METADATA_FILE)).await {
+        Ok(content) => serde_json::from_slice::<ComicMetadata>(&content)
+            .map(|old| {
+                old.chapters
+                    .into_iter()
+                    .filter_map(|chapter| Some((chapter.chapter_id, chapter.page_count?)))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        Err(_) => BTreeMap::new(),
+    };
+    for chapter in chapters {
+        page_counts.insert(chapter.chapter_id, chapter.page_count);
+    }
+    let metadata = ComicMetadata {
+        source: format!("jmcomic:{}", comic_id),
+        comic_id,
+        title: comic.name.clone(),
+        authors: comic.author.clone(),
+        tags: comic.tags.clone(),
+        works: comic.works.clone(),
+        actors: comic.actors.clone(),
+        description: comic.description.clone(),
+        add_time: comic.add_time,
+        update_time: comic.update_time,
+        chapters: chapter_list(comic_id, comic)
+            .into_iter()
+            .enumerate()
+            .map(|(index, (chapter_id, title))| ChapterMetadata {
+                chapter_id,
+                title,
+                number: index + 1,
+                page_count: page_counts.get(&chapter_id).copied(),
+            })
+            .collect(),
+    };
+    let json = serde_json::to_vec_pretty(&metadata)
+        .map_err(|e| AppError::Internal(format!("序列化元数据失败: {}", e)))?;
+    write_file(&comic_dir.join(METADATA_FILE), json).await
+}
+
+/// 漫画的章节列表，普通漫画为章节ID等于漫画ID的单个章节
+fn chapter_list(comic_id: i64, comic: &GetComicRespData) -> Vec<(i64, String)> {
+    if comic.series.is_empty() {
+        return vec![(comic_id, comic.name.clone())];
+    }
+    comic
+        .series
+        .iter()
+        .filter_map(|series| Some((series.id.parse().ok()?, series.name.clone())))
+        .collect()
+}
+
+/// 生成 ComicInfo.xml（v2.0 schema）；`chapter` 为 None 时描述整部漫画
+pub fn comic_info_xml(
+    web_domain: &str,
+    comic_id: i64,
+    comic: &GetComicRespData,
+    chapter: Option<&ChapterMeta<'_>>,
+) -> String {
+    let chapters = chapter_list(comic_id, comic);
+    let mut fields: Vec<(&str, String)> = Vec::new();
+    match chapter {
+        Some(chapter) => {
+            let title = if comic.series.is_empty() { comic.name.clone() } else { chapter.title.to_string() };
+            fields.push(("Title", title));
+            fields.push(("Series", comic.name.clone()));
+            if let Some(index) = chapters.iter().position(|(id, _)| *id == chapter.chapter_id) {
+                fields.push(("Number", (index + 1).to_string()));
+            }
+            fields.push(("Count", chapters.len().to_string()));
+        }
+        None => {
+            fields.push(("Title", comic.name.clone()));
+            fields.push(("Series", comic.name.clone()));
+            fields.push(("Count", chapters.len().to_string()));
+        }
+    }
+    fields.push(("Summary", comic.description.clone()));
+    if let Some(year) = comic
+        .add_time
+        .and_then(|time| chrono::DateTime::from_timestamp(time, 0))
+    {
+        use chrono::Datelike;
+        fields.push(("Year", year.year().to_string()));
+        fields.push(("Month", year.month().to_string()));
+        fields.push(("Day", year.day().to_string()));
+    }
+    fields.push(("Writer", comic.author.join(", ")));
+    fields.push(("Tags", comic.tags.join(", ")));
+    fields.push(("Characters", comic.actors.join(", ")));
+    fields.push(("SeriesGroup", comic.works.join(", ")));
+    fields.push(("Web", format!("https://{}/album/{}", web_domain, comic_id)));
+    if let Some(chapter) = chapter {
+        fields.push(("PageCount", chapter.page_count.to_string()));
+    }
+    fields.push(("LanguageISO", "zh".to_string()));
+    fields.push(("Notes", format!("jmcomic:{}", comic_id)));
+
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <ComicInfo xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" \
+         xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\">\n",
+    );
+    for (name, value) in fields.into_iter().filter(|(_, value)| !value.is_empty()) {
+        xml.push_str(&format!("  <{0}>{1}</{0}>\n", name, xml_escape(&value)));
+    }
+    xml.push_str("</ComicInfo>\n");
+    xml
+}
+
+fn xml_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // XML 1.0 不允许的控制字符
+            c if c.is_control() && !matches!(c, '\t' | '\n' | '\r') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// 先写临时文件再重命名，避免书库读到写了一半的文件
+async fn write_file(path: &Path, content: Vec<u8>) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| AppError::Internal(format!("创建目录 {} 失败: {}", parent.display(), e)))?;
+    }
+    atomic_file::write(path, content)
+        .await
+        .map_err(|e| AppError::Internal(format!("写入 {} 失败: {}", path.display(), e)))
+}
+
+/// 漫画目录下已没有章节目录时删除元数据文件
+pub fn remove_if_orphaned(comic_dir: &Path) {
+    let has_chapters = std::fs::read_dir(comic_dir)
+        .map(|entries| entries.flatten().any(|entry| entry.path().is_dir()))
+        .unwrap_or(true);
+    if !has_chapters {
+        let _ = std::fs::remove_file(comic_dir.join(COMIC_INFO_FILE));
+        let _ = std::fs::remove_file(comic_dir.join(METADATA_FILE));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SeriesRespData;
+
+    #[test]
+    fn comic_info_escapes_and_numbers_chapters() {
+        let comic = GetComicRespData {
+            name: "标题 <A&B>".to_string(),
+            series: vec![
+                SeriesRespData { id: "11".to_string(), name: "第1话".to_string() },
+                SeriesRespData { id: "12".to_string(), name: "第2话".to_string() },
+            ],
+            total_views: String::new(),
+            likes: String::new(),
+            author: vec!["作者甲".to_string(), "作者乙".to_string()],
+            description: String::new(),
+            tags: vec!["标签".to_string()],
+            works: Vec::new(),
+            actors: Vec::new(),
+            add_time: Some(1_700_000_000),
+            update_time: None,
+            is_favorite: false,
+            price: None,
+            purchased: false,
+        };
+        let chapter = ChapterMeta { chapter_id: 12, title: "第2话", page_count: 30 };
+        let xml = comic_info_xml("18comic.vip", 10, &comic, Some(&chapter));
+        assert!(xml.contains("<Series>标题 &lt;A&amp;B&gt;</Series>"));
+        assert!(xml.contains("<Title>第2话</Title>"));
+        assert!(xml.contains("<Number>2</Number>"));
+        assert!(xml.contains("<Count>2</Count>"));
+        assert!(xml.contains("<PageCount>30</PageCount>"));
+        assert!(xml.contains("<Writer>作者甲, 作者乙</Writer>"));
+        assert!(xml.contains("<Year>2023</Year>"));
+        assert!(!xml.contains("<Summary>"));
+    }
+}
@@ -0,0 +1,187 @@
+// 测试用 Mock 客户端
+// 实现 JmApi，返回预置数据，可注入认证失败或任意错误，便于在不访问 JM 服务器的情况下测试
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use jm_downloader_rs::AppError;
+
+use crate::jm_api::JmApi;
+use crate::models::{GetChapterRespData, GetComicRespData, SeriesRespData};
+
+type Result<T> = std::result::Result<T, AppError>;
+
+/// Mock 调用统计，测试代码持有其克隆以便在客户端被移交后检查
+#[derive(Debug, Default)]
+pub struct MockStats {
+    pub logins: AtomicUsize,
+    pub calls: AtomicUsize,
+    /// 调用购买接口的次数（含失败）
+    pub purchases: AtomicUsize,
+}
+
+#[derive(Default)]
+pub struct MockJmClient {
+    comics: HashMap<i64, GetComicRespData>,
+    chapters: HashMap<i64, GetChapterRespData>,
+    scramble_ids: HashMap<i64, i64>,
+    /// 接下来多少次数据请求返回认证错误（模拟会话过期）
+    auth_failures: AtomicUsize,
+    /// 设置后所有数据请求都返回该内部错误
+    failure: Option<String>,
+    /// 为 true 时所有数据请求都返回拦截错误（模拟账号触发风控）
+    blocked: bool,
+    /// 为 true 时购买接口返回错误
+    buy_fails: bool,
+    /// 已购买的漫画与章节 ID
+    purchased: Mutex<HashSet<i64>>,
+    pub stats: Arc<MockStats>,
+}
+
+impl MockJmClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 预置一本漫画，`series` 为空表示普通漫画
+    pub fn with_comic(mut self, aid: i64, name: &str, series: &[(i64, &str)]) -> Self {
+        self.comics.insert(
+            aid,
+            GetComicRespData {
+                name: name.to_string(),
+                series: series
+                    .iter()
+                    .map(|(id, name)| SeriesRespData {
+                        id: id.to_string(),
+                        name: name.to_string(),
+                    })
+                    .collect(),
+                total_views: String::new(),
+                likes: String::new(),
+                author: Vec::new(),
+                description: String::new(),
+                tags: Vec::new(),
+                works: Vec::new(),
+                actors: Vec::new(),
+                add_time: None,
+                update_time: None,
+                is_favorite: false,
+                price: None,
+                purchased: false,
+            },
+        );
+        self
+    }
+
+    /// 预置一个章节的图片文件名列表与 scramble_id
+    pub fn with_chapter(mut self, id: i64, images: &[&str], scramble_id: i64) -> Self {
+        self.chapters.insert(
+            id,
+            GetChapterRespData {
+                images: images.iter().map(|name| name.to_string()).collect(),
+                ..Default::default()
+            },
+        );
+        self.scramble_ids.insert(id, scramble_id);
+        self
+    }
+
+    /// 设置漫画或章节 `id` 的价格（JM 币），购买前 `requires_purchase` 为 true
+    pub fn with_price(mut self, id: i64, price: i64) -> Self {
+        if let Some(comic) = self.comics.get_mut(&id) {
+            comic.price = Some(price);
+        }
+        if let Some(chapter) = self.chapters.get_mut(&id) {
+            chapter.price = Some(price);
+        }
+        self
+    }
+
+    pub fn fail_buy(mut self) -> Self {
+        self.buy_fails = true;
+        self
+    }
+
+    pub fn fail_auth(self, times: usize) -> Self {
+        self.auth_failures.store(times, Ordering::SeqCst);
+        self
+    }
+
+    pub fn fail_always(mut self, message: &str) -> Self {
+        self.failure = Some(message.to_string());
+        self
+    }
+
+    pub fn block(mut self) -> Self {
+        self.blocked = true;
+        self
+    }
+
+    /// 记录一次数据请求，并按注入规则返回错误
+    fn check_call(&self) -> Result<()> {
+        self.stats.calls.fetch_add(1, Ordering::SeqCst);
+        if let Some(message) = &self.failure {
+            return Err(AppError::Internal(message.clone()));
+        }
+        if self.blocked {
+            return Err(AppError::Blocked("请求被 JM 拦截: IP被封锁".to_string()));
+        }
+        let injected = self
+            .auth_failures
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok();
+        if injected {
+            return Err(AppError::Internal("Get comic failed with code 401: 请先登录".to_string()));
+        }
+        Ok(())
+    }
+}
+
+impl JmApi for MockJmClient {
+    async fn login(&self, _username: &str, _password: &str) -> Result<()> {
+        self.stats.logins.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    async fn get_comic(&self, aid: i64) -> Result<GetComicRespData> {
+        self.check_call()?;
+        let mut comic = self
+            .comics
+            .get(&aid)
+            .cloned()
+            .ok_or_else(|| AppError::NotFound(format!("漫画 {} 未找到", aid)))?;
+        comic.purchased = self.purchased.lock().unwrap().contains(&aid);
+        Ok(comic)
+    }
+
+    async fn get_chapter(&self, id: i64) -> Result<GetChapterRespData> {
+        self.check_call()?;
+        let mut chapter = self
+            .chapters
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| AppError::Internal(format!("章节 {} 不存在", id)))?;
+        chapter.purchased = self.purchased.lock().unwrap().contains(&id);
+        Ok(chapter)
+    }
+
+    async fn keep_alive(&self) -> Result<()> {
+        self.check_call()
+    }
+
+    async fn buy(&self, id: i64) -> Result<()> {
+        self.check_call()?;
+        self.stats.purchases.fetch_add(1, Ordering::SeqCst);
+        if self.buy_fails {
+            return Err(AppError::PaymentRequired("JM 币余额不足".to_string()));
+        }
+        self.purchased.lock().unwrap().insert(id);
+        Ok(())
+    }
+
+    async fn get_scramble_id(&self, id: i64) -> Result<i64> {
+        self.check_call()?;
+        Ok(self.scramble_ids.get(&id).copied().unwrap_or(220_980))
+    }
+}
@@ -1,16 +1,83 @@
 use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::json;
+
+use crate::progress::JobLogLevel;
+use crate::service_mode::ServiceMode;
 
 fn default_expire_seconds() -> i64 {
     600
 }
 
+fn default_true() -> bool {
+    true
+}
+
+fn default_chapter_concurrency() -> usize {
+    1
+}
+
+/// 宽松解析 Unix 时间戳（秒），兼容字符串与数字两种形式，无法解析时为 None
+fn de_opt_timestamp<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<i64>, D::Error> {
+    Ok(de_opt_i64(deserializer)?.filter(|ts| *ts > 0))
+}
+
+/// 宽松解析整数，兼容字符串与数字两种形式，空字符串或无法解析时为 None
+fn de_opt_i64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<i64>, D::Error> {
+    let value = Option::<serde_json::Value>::deserialize(deserializer)?;
+    Ok(match value {
+        Some(serde_json::Value::Number(n)) => n.as_i64(),
+        Some(serde_json::Value::String(s)) => s.trim().parse().ok(),
+        _ => None,
+    })
+}
+
+/// 宽松解析布尔值：true、非 0 数字与 "1"/"true" 为 true，其余（含空字符串）为 false
+fn de_lenient_bool<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    let value = Option::<serde_json::Value>::deserialize(deserializer)?;
+    Ok(match value {
+        Some(serde_json::Value::Bool(b)) => b,
+        Some(serde_json::Value::Number(n)) => n.as_i64().is_some_and(|n| n != 0),
+        Some(serde_json::Value::String(s)) => matches!(s.trim(), "1" | "true"),
+        _ => false,
+    })
+}
+
 // 获取漫画信息请求
 #[derive(Debug, Deserialize, JsonSchema)]
+#[schemars(example = "example_get_comic_info")]
 pub struct GetComicInfoRequest {
     pub id: i64,
 }
 
+fn example_get_comic_info() -> serde_json::Value {
+    json!({ "id": 350234 })
+}
+
+// 解析漫画编号请求
+#[derive(Debug, Deserialize, JsonSchema)]
+#[schemars(example = "example_resolve")]
+pub struct ResolveRequest {
+    /// 用户输入的文本，如 `JM123456`、`https://18comic.vip/album/123456/...`，或包含它们的整条消息
+    pub input: String,
+}
+
+fn example_resolve() -> serde_json::Value {
+    json!({ "input": "帮我下载 https://18comic.vip/album/350234/" })
+}
+
+// 解析漫画编号响应
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ResolveData {
+    pub comic_id: i64,
+    /// 输入为章节链接（`/photo/{id}`）时的章节 ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chapter_id: Option<i64>,
+    /// 规范的漫画网页链接
+    pub url: String,
+    pub info: ComicInfo,
+}
+
 // 获取漫画信息响应
 #[derive(Debug, Serialize, JsonSchema)]
 pub struct ComicInfo {
@@ -25,20 +92,331 @@ pub struct ComicInfo {
     pub description: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub total_pages: Option<usize>,
+    /// 标签
+    pub tags: Vec<String>,
+    /// 作品（原作）
+    pub works: Vec<String>,
+    /// 登场人物
+    pub actors: Vec<String>,
+    /// 上架时间（RFC 3339，东八区）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upload_time: Option<String>,
+    /// 更新时间（RFC 3339，东八区）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update_time: Option<String>,
+    /// 当前账号是否已收藏
+    pub is_favorite: bool,
+}
+
+// 预览请求
+#[derive(Debug, Deserialize, JsonSchema)]
+#[schemars(example = "example_preview")]
+pub struct PreviewRequest {
+    pub comic_id: i64,
+    /// 要预览的章节，默认为第一个章节（普通漫画为漫画本身）
+    #[serde(default)]
+    pub chapter_id: Option<i64>,
+    /// 预览页数（1~10），默认 JM_PREVIEW_PAGES
+    #[serde(default)]
+    pub count: Option<usize>,
+    /// 把各页横向拼成一张预览图返回，默认false 时逐页返回
+    #[serde(default)]
+    pub collage: bool,
+}
+
+fn example_preview() -> serde_json::Value {
+    json!({ "comic_id": 350234, "count": 3, "collage": true })
+}
+
+// 预览响应
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PreviewData {
+    pub comic_id: i64,
+    pub chapter_id: i64,
+    pub title: String,
+    /// 章节总页数
+    pub total_pages: usize,
+    /// 逐页预览（collage 为 false 时返回）
+    pub pages: Vec<PreviewPage>,
+    /// 拼接后的预览图 JPEG data URL（collage 为 true 时返回）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collage: Option<String>,
+}
+
+// 单页预览
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PreviewPage {
+    /// 页码，从 1 开始
+    pub page: usize,
+    pub width: u32,
+    pub height: u32,
+    /// 缩小后的 JPEG data URL（`data:image/jpeg;base64,...`）
+    pub image: String,
 }
 
 // 下载章节漫画请求
 #[derive(Debug, Deserialize, JsonSchema)]
+#[schemars(example = "example_download_chapter")]
 pub struct DownloadChapterRequest {
     pub comic_id: i64,
     pub chapter_ids: Vec<i64>,
     /// 下载完成后多少秒自动删除目录，默认600秒，-1为不过期
     #[serde(default = "default_expire_seconds")]
     pub expire_seconds: i64,
+    /// 按内容去重：重复页面在磁盘上以硬链接共用一份文件，默认false
+    #[serde(default)]
+    pub dedupe: bool,
+    /// 把宽明显大于高的跨页拆分为两页（按 spread_order 保存为 0005-1R.png、0005-2L.png（右到左）或 0005-1L.png、0005-2R.png（左到右）），默认false
+    #[serde(default)]
+    pub split_spreads: bool,
+    /// 跨页拆分后的顺序：rtl（默认，右半页在前）/ltr
+    #[serde(default)]
+    pub spread_order: SpreadOrder,
+    /// 电子墨水屏优化：转为灰度、拉伸对比度并缩小到 JM_EINK_LONG_EDGE（写入单独的变体目录），默认false
+    #[serde(default)]
+    pub eink: bool,
+    /// 保留 JM 图片的原始文件名（如 00001.png，重名时追加 _2），默认false 时按顺序命名为 0001.png
+    #[serde(default)]
+    pub preserve_filenames: bool,
+    /// 原图直存：无需拼接且未要求拆分跨页/电子墨水屏优化/裁剪边框的页面直接保存 JM 原图（jpg/png/webp，写入单独的变体目录），不再转码为 PNG，默认false
+    #[serde(default)]
+    pub keep_original: bool,
+    /// 裁剪页面四周纯白或纯黑的边框（保留少量安全边距，写入单独的变体目录），页面更紧凑，适合电子阅读器，默认false
+    #[serde(default)]
+    pub trim_borders: bool,
+    /// 拆分跨页之后按顺序执行的页面后处理步骤，写作 `名称` 或 `名称:参数`：resize:长边像素、greyscale、trim，
+    /// 如 `["trim", "resize:1600", "greyscale"]`（最多 8 步，写入单独的变体目录）
+    #[serde(default)]
+    pub post_process: Vec<String>,
+    /// 书库模式：把章节打包为 CBZ（内含 ComicInfo.xml）写入 JM_LIBRARY_DIR，导出的文件不会过期删除，默认false
+    #[serde(default)]
+    pub library_mode: bool,
+    /// 右到左阅读：按倒序写入合并的 PDF 与书库 CBZ，适合在双页阅读器中阅读日漫，默认false
+    #[serde(default)]
+    pub rtl: bool,
+    /// 只下载该范围内的页（从 1 开始，包含两端），超出章节页数的部分忽略，如预览前 5 页 `{"from": 1, "to": 5}`
+    #[serde(default)]
+    pub page_range: Option<PageRange>,
+    /// 只下载指定页码（从 1 开始），与 page_range 同时设置时取并集
+    #[serde(default)]
+    pub pages: Vec<usize>,
+    /// 排除的页码（从 1 开始），不下载、不写入 PDF 与 CBZ；其余页面仍按整章编号保存，超出章节页数的页码忽略
+    #[serde(default)]
+    pub exclude_pages: Vec<usize>,
+    /// 任务优先级：high/normal（默认）/low，同时进行的任务数达到上限时高优先级先出队
+    #[serde(default)]
+    pub priority: JobPriority,
+    /// 任务日志详细程度：info/quiet（只输出开始与汇总），不传时使用 JM_JOB_LOG_LEVEL
+    #[serde(default)]
+    pub log_level: Option<JobLogLevel>,
+    /// 本次下载的最长耗时（秒），超过后取消未完成的下载，返回错误码 10010 与已完成的部分；与 JM_MAX_JOB_SECONDS 取较小者
+    #[serde(default)]
+    pub timeout_seconds: Option<u64>,
+    /// 在响应中返回各阶段耗时 `timings`（元数据获取、下载、图片处理、PDF合并、压缩），默认false
+    #[serde(default)]
+    pub include_timings: bool,
+    /// 为章节目录中产出的文件写入 SHA-256 校验清单 checksums.sha256，并在响应中返回各文件的哈希，默认false
+    #[serde(default)]
+    pub checksums: bool,
+    /// 漫画或章节需要 JM 币时自动购买后再下载，需同时设置 max_coins，默认false
+    #[serde(default)]
+    pub auto_buy: bool,
+    /// 自动购买时本次请求最多花费的 JM 币，超出时返回错误码 10006
+    #[serde(default)]
+    pub max_coins: Option<u64>,
+    /// 同时下载的章节数，默认1（逐章下载）；所有章节共用 JM_IMG_CONCURRENCY 的图片并发上限，结果仍按 chapter_ids 的顺序返回
+    #[serde(default = "default_chapter_concurrency")]
+    pub chapter_concurrency: usize,
+}
+
+fn example_download_chapter() -> serde_json::Value {
+    json!({
+        "comic_id": 1026275,
+        "chapter_ids": [1026275, 1026276],
+        "expire_seconds": 3600,
+        "split_spreads": true,
+        "spread_order": "rtl",
+        "priority": "high"
+    })
 }
 
-// 下载普通漫画请求
+// 增量同步章节请求
 #[derive(Debug, Deserialize, JsonSchema)]
+#[schemars(example = "example_sync_chapters")]
+pub struct SyncChaptersRequest {
+    pub comic_id: i64,
+    /// 上次同步到的章节 ID，只下载章节列表中位于它之后的章节；该章节已不在列表中时从第一个 ID 更大的章节开始
+    #[serde(default)]
+    pub last_chapter_id: Option<i64>,
+    /// 上次同步到的章节序号（与章节列表的 sort 一致），不能与 last_chapter_id 同时设置；都不设置时同步全部章节
+    #[serde(default)]
+    pub last_sort: Option<usize>,
+    /// 本次最多下载的新章节数，默认为 JM_MAX_CHAPTERS_PER_REQUEST，其余的留到下次同步
+    #[serde(default)]
+    pub max_chapters: Option<usize>,
+    // 以下选项与下载章节漫画相同（不支持 page_range/pages/exclude_pages）
+    /// 下载完成后多少秒自动删除目录，默认600秒，-1为不过期
+    #[serde(default = "default_expire_seconds")]
+    pub expire_seconds: i64,
+    #[serde(default)]
+    pub dedupe: bool,
+    #[serde(default)]
+    pub split_spreads: bool,
+    #[serde(default)]
+    pub spread_order: SpreadOrder,
+    #[serde(default)]
+    pub eink: bool,
+    #[serde(default)]
+    pub preserve_filenames: bool,
+    #[serde(default)]
+    pub keep_original: bool,
+    #[serde(default)]
+    pub trim_borders: bool,
+    #[serde(default)]
+    pub post_process: Vec<String>,
+    #[serde(default)]
+    pub library_mode: bool,
+    #[serde(default)]
+    pub rtl: bool,
+    #[serde(default)]
+    pub priority: JobPriority,
+    #[serde(default)]
+    pub log_level: Option<JobLogLevel>,
+    #[serde(default)]
+    pub timeout_seconds: Option<u64>,
+    #[serde(default)]
+    pub include_timings: bool,
+    #[serde(default)]
+    pub checksums: bool,
+    #[serde(default)]
+    pub auto_buy: bool,
+    #[serde(default)]
+    pub max_coins: Option<u64>,
+    #[serde(default = "default_chapter_concurrency")]
+    pub chapter_concurrency: usize,
+}
+
+fn example_sync_chapters() -> serde_json::Value {
+    json!({
+        "comic_id": 1026275,
+        "last_chapter_id": 1026276,
+        "library_mode": true,
+        "expire_seconds": -1
+    })
+}
+
+impl SyncChaptersRequest {
+    /// 下载指定新章节的请求，选项与本请求相同
+    pub fn download_request(&self, chapter_ids: Vec<i64>) -> DownloadChapterRequest {
+        DownloadChapterRequest {
+            comic_id: self.comic_id,
+            chapter_ids,
+            expire_seconds: self.expire_seconds,
+            dedupe: self.dedupe,
+            split_spreads: self.split_spreads,
+            spread_order: self.spread_order,
+            eink: self.eink,
+            preserve_filenames: self.preserve_filenames,
+            keep_original: self.keep_original,
+            trim_borders: self.trim_borders,
+            post_process: self.post_process.clone(),
+            library_mode: self.library_mode,
+            rtl: self.rtl,
+            page_range: None,
+            pages: Vec::new(),
+            exclude_pages: Vec::new(),
+            priority: self.priority,
+            log_level: self.log_level,
+            timeout_seconds: self.timeout_seconds,
+            include_timings: self.include_timings,
+            checksums: self.checksums,
+            auto_buy: self.auto_buy,
+            max_coins: self.max_coins,
+            chapter_concurrency: self.chapter_concurrency,
+        }
+    }
+}
+
+// 增量同步章节响应
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SyncChaptersData {
+    pub comic_id: i64,
+    pub comic_title: String,
+    /// 漫画当前的章节总数
+    pub total_chapters: usize,
+    /// 本次下载的新章节
+    pub new_chapters: Vec<ChapterItem>,
+    /// 尚未同步的新章节数（超出 max_chapters 或下载被中断），大于 0 时应继续同步
+    pub remaining: usize,
+    /// 新的同步位置：已连续同步到的最后一个章节，下次请求作为 last_chapter_id 传入；还没有同步任何章节时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub high_water: Option<ChapterItem>,
+    /// 下载结果，没有新章节时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download: Option<ChapterDownloadData>,
+}
+
+/// 页码范围（从 1 开始，包含两端）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, JsonSchema)]
+pub struct PageRange {
+    pub from: usize,
+    pub to: usize,
+}
+
+/// 跨页拆分后的页面顺序
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SpreadOrder {
+    /// 右到左（默认，日漫阅读顺序）：右半页在前
+    #[default]
+    Rtl,
+    /// 左到右：左半页在前
+    Ltr,
+}
+
+/// 下载任务优先级，任务数达到 JM_MAX_CONCURRENT_JOBS 时按优先级出队
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum JobPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+/// PDF 压缩档位，对应 GhostScript 的 -dPDFSETTINGS
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PdfQuality {
+    /// 72 DPI，体积最小
+    Screen,
+    /// 150 DPI
+    Ebook,
+    /// 300 DPI（默认）
+    #[default]
+    Printer,
+    /// 300 DPI，保留色彩信息
+    Prepress,
+    /// 不压缩；需要加密或合并分段时以无损方式调用 GhostScript
+    None,
+}
+
+impl PdfQuality {
+    /// GhostScript -dPDFSETTINGS 参数值，None 档位不设置
+    pub fn gs_setting(self) -> Option<&'static str> {
+        match self {
+            PdfQuality::Screen => Some("/screen"),
+            PdfQuality::Ebook => Some("/ebook"),
+            PdfQuality::Printer => Some("/printer"),
+            PdfQuality::Prepress => Some("/prepress"),
+            PdfQuality::None => None,
+        }
+    }
+}
+
+// 下载普通漫画请求（派生 Hash/Eq 用于合并相同的并发请求）
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, JsonSchema)]
+#[schemars(example = "example_download_comic")]
 pub struct DownloadComicRequest {
     pub comic_id: i64,
     /// 是否合并为PDF，默认false
@@ -47,30 +425,273 @@ pub struct DownloadComicRequest {
     /// 合并PDF密码，传入则启用加密
     #[serde(default)]
     pub encrypt: Option<String>,
+    /// PDF压缩档位：screen/ebook/printer/prepress/none，默认printer
+    #[serde(default)]
+    pub pdf_quality: PdfQuality,
+    /// PDF图片目标DPI（36~600），传入则按该分辨率降采样，覆盖压缩档位的默认分辨率
+    #[serde(default)]
+    pub pdf_dpi: Option<u32>,
+    /// PDF分卷：每卷最大页数，传入则拆分为 merged_part1.pdf、merged_part2.pdf...
+    #[serde(default)]
+    pub pdf_max_pages: Option<usize>,
+    /// PDF分卷：每卷最大体积（MB），传入则按体积拆分（如邮件附件上限50MB）
+    #[serde(default)]
+    pub pdf_max_size_mb: Option<u64>,
+    /// 在合并的PDF开头插入生成的标题页（标题、作者、ID、下载日期）与封面图，需 merge 为 true 且服务配置了 JM_PDF_FONT，默认false
+    #[serde(default)]
+    pub title_page: bool,
+    /// merge为true时是否同时保存单页图片，默认true；false时图片仅在内存中直接合并为PDF（页数超过PDF分段大小时仍会落盘）
+    #[serde(default = "default_true")]
+    pub keep_images: bool,
     /// 下载完成后多少秒自动删除目录，默认600秒，-1为不过期
     #[serde(default = "default_expire_seconds")]
     pub expire_seconds: i64,
+    /// 按内容去重：重复页面在磁盘上以硬链接共用一份文件，合并PDF时由GhostScript复用重复图片，默认false
+    #[serde(default)]
+    pub dedupe: bool,
+    /// 把宽明显大于高的跨页拆分为两页后再合并PDF（按 spread_order 保存为 0005-1R.png、0005-2L.png（右到左）或 0005-1L.png、0005-2R.png（左到右）），默认false
+    #[serde(default)]
+    pub split_spreads: bool,
+    /// 跨页拆分后的顺序：rtl（默认，右半页在前）/ltr
+    #[serde(default)]
+    pub spread_order: SpreadOrder,
+    /// 电子墨水屏优化：转为灰度、拉伸对比度并缩小到 JM_EINK_LONG_EDGE（写入单独的变体目录），默认false
+    #[serde(default)]
+    pub eink: bool,
+    /// 保留 JM 图片的原始文件名（如 00001.png，重名时追加 _2），默认false 时按顺序命名为 0001.png
+    #[serde(default)]
+    pub preserve_filenames: bool,
+    /// 原图直存：无需拼接且未要求拆分跨页/电子墨水屏优化/裁剪边框的页面直接保存 JM 原图（jpg/png/webp，写入单独的变体目录），不再转码为 PNG，默认false
+    #[serde(default)]
+    pub keep_original: bool,
+    /// 裁剪页面四周纯白或纯黑的边框（保留少量安全边距，写入单独的变体目录），页面更紧凑，适合电子阅读器，默认false
+    #[serde(default)]
+    pub trim_borders: bool,
+    /// 拆分跨页之后按顺序执行的页面后处理步骤，写作 `名称` 或 `名称:参数`：resize:长边像素、greyscale、trim，
+    /// 如 `["trim", "resize:1600", "greyscale"]`（最多 8 步，写入单独的变体目录）
+    #[serde(default)]
+    pub post_process: Vec<String>,
+    /// 书库模式：把章节打包为 CBZ（内含 ComicInfo.xml）写入 JM_LIBRARY_DIR，导出的文件不会过期删除，默认false
+    #[serde(default)]
+    pub library_mode: bool,
+    /// 右到左阅读：按倒序写入合并的 PDF 与书库 CBZ，适合在双页阅读器中阅读日漫，默认false
+    #[serde(default)]
+    pub rtl: bool,
+    /// 只下载该范围内的页（从 1 开始，包含两端），超出章节页数的部分忽略，如预览前 5 页 `{"from": 1, "to": 5}`
+    #[serde(default)]
+    pub page_range: Option<PageRange>,
+    /// 只下载指定页码（从 1 开始），与 page_range 同时设置时取并集
+    #[serde(default)]
+    pub pages: Vec<usize>,
+    /// 排除的页码（从 1 开始），不下载、不写入 PDF 与 CBZ；其余页面仍按整章编号保存，超出章节页数的页码忽略
+    #[serde(default)]
+    pub exclude_pages: Vec<usize>,
+    /// 任务优先级：high/normal（默认）/low，同时进行的任务数达到上限时高优先级先出队
+    #[serde(default)]
+    pub priority: JobPriority,
+    /// 任务日志详细程度：info/quiet（只输出开始与汇总），不传时使用 JM_JOB_LOG_LEVEL
+    #[serde(default)]
+    pub log_level: Option<JobLogLevel>,
+    /// 本次下载的最长耗时（秒），超过后取消未完成的下载并返回错误码 10010；与 JM_MAX_JOB_SECONDS 取较小者。
+    /// 普通漫画只有一个章节，超时即整体失败、不返回部分结果，已保存的页面留在磁盘上，重试时直接复用
+    #[serde(default)]
+    pub timeout_seconds: Option<u64>,
+    /// 在响应中返回各阶段耗时 `timings`（元数据获取、下载、图片处理、PDF合并、压缩），默认false
+    #[serde(default)]
+    pub include_timings: bool,
+    /// 为章节目录中产出的文件写入 SHA-256 校验清单 checksums.sha256，并在响应中返回各文件的哈希，默认false
+    #[serde(default)]
+    pub checksums: bool,
+    /// 为产出的文件生成 BitTorrent 种子（Tracker 与私有标记见 JM_TORRENT_TRACKERS/JM_TORRENT_PRIVATE），默认false；
+    /// 配置了 JM_TORRENT_SEED_COMMAND 时随后调用做种命令，做种时应同时设置 expire_seconds 为 -1
+    #[serde(default)]
+    pub torrent: bool,
+    /// 漫画或章节需要 JM 币时自动购买后再下载，需同时设置 max_coins，默认false
+    #[serde(default)]
+    pub auto_buy: bool,
+    /// 自动购买时本次请求最多花费的 JM 币，超出时返回错误码 10006
+    #[serde(default)]
+    pub max_coins: Option<u64>,
+    /// PDF合并完成后作为邮件附件发送到该地址（如 Kindle 邮箱），需 merge 为 true 且服务配置了 SMTP；超过附件上限时分卷逐封发送
+    #[serde(default)]
+    pub email_to: Option<String>,
+}
+
+fn example_download_comic() -> serde_json::Value {
+    json!({
+        "comic_id": 350234,
+        "merge": true,
+        "encrypt": "1234",
+        "pdf_quality": "ebook",
+        "pdf_max_size_mb": 50,
+        "keep_images": false,
+        "expire_seconds": 600
+    })
 }
 
 // 单个章节下载数据
-#[derive(Debug, Serialize, JsonSchema)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct SingleChapterData {
     pub chapter_id: i64,
     pub chapter_title: String,
     pub images: Vec<String>,
+    /// 书库模式下导出的 CBZ 路径（相对 JM_LIBRARY_DIR）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub library_path: Option<String>,
+    /// 该章节的各阶段耗时（仅在 include_timings 为 true 时返回）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timings: Option<PhaseTimings>,
+    /// 该章节文件的校验和（仅在 checksums 为 true 时返回）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checksums: Option<ChecksumData>,
+}
+
+/// 生成的 BitTorrent 种子
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct TorrentData {
+    /// 种子文件的下载路径
+    pub torrent_path: String,
+    /// info 字典的 SHA-1（十六进制），即 BT 客户端显示的哈希
+    pub info_hash: String,
+    pub piece_length: u64,
+    pub piece_count: usize,
+    pub total_bytes: u64,
+    /// 是否已调用 JM_TORRENT_SEED_COMMAND 做种
+    pub seeding: bool,
+}
+
+/// SHA-256 校验清单
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ChecksumData {
+    /// 清单文件 checksums.sha256 的路径，可直接用 `sha256sum -c` 校验
+    pub manifest: String,
+    pub files: Vec<FileChecksum>,
+}
+
+/// 单个文件的校验和
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct FileChecksum {
+    /// 章节目录中的文件名
+    pub file: String,
+    /// 十六进制小写 SHA-256
+    pub sha256: String,
+}
+
+/// 下载各阶段耗时（毫秒），与日志中输出的耗时一致
+#[derive(Debug, Clone, Copy, Default, Serialize, JsonSchema)]
+pub struct PhaseTimings {
+    /// 获取漫画、章节信息与 scramble_id
+    pub metadata_ms: u64,
+    /// 并发下载图片（含解码与拼接）的墙钟耗时
+    pub download_ms: u64,
+    /// 图片解码、拼接与编码在 CPU 线程池中的累计耗时，并发处理时可能大于 download_ms
+    pub processing_ms: u64,
+    /// 合并PDF（仅在合并PDF时返回）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pdf_merge_ms: Option<u64>,
+    /// GhostScript 压缩PDF（仅在执行了压缩时返回）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compress_ms: Option<u64>,
+    /// 请求总耗时
+    pub total_ms: u64,
+}
+
+impl PhaseTimings {
+    /// 累加各章节的耗时，总耗时由调用方单独设置
+    pub fn add(&mut self, other: &PhaseTimings) {
+        self.metadata_ms += other.metadata_ms;
+        self.download_ms += other.download_ms;
+        self.processing_ms += other.processing_ms;
+    }
+}
+
+// 查询本地已下载内容请求
+#[derive(Debug, Deserialize, JsonSchema)]
+#[schemars(example = "example_check_local")]
+pub struct CheckLocalRequest {
+    pub comic_id: i64,
+    /// 要查询的章节ID，为空时返回磁盘上该漫画的全部章节；普通漫画的章节ID等于漫画ID
+    #[serde(default)]
+    pub chapter_ids: Vec<i64>,
+}
+
+fn example_check_local() -> serde_json::Value {
+    json!({ "comic_id": 1026275, "chapter_ids": [1026275] })
+}
+
+// 本地已下载内容
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct LocalComicData {
+    pub comic_id: i64,
+    /// 磁盘上是否存在该漫画的任何章节
+    pub exists: bool,
+    pub chapters: Vec<LocalChapterData>,
+}
+
+// 单个章节在磁盘上的状态
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct LocalChapterData {
+    pub chapter_id: i64,
+    pub exists: bool,
+    /// 已下载的页数（同一页的不同处理版本与跨页拆分只计一次）
+    pub page_count: usize,
+    pub bytes: u64,
+    /// 目录中最晚的修改时间（Unix 秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified: Option<i64>,
+    /// 已合并的 PDF（含分卷），变体中的 PDF 名称带 `variants/{选项哈希}/` 前缀
+    pub pdfs: Vec<LocalFileData>,
+    /// 章节目录下已有的变体（电子墨水屏、跨页拆分等非默认选项的产物）的选项哈希
+    pub variants: Vec<String>,
+}
+
+// 磁盘上的文件
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct LocalFileData {
+    pub name: String,
+    pub bytes: u64,
+    /// 修改时间（Unix 秒）
+    pub modified: i64,
 }
 
 // 下载章节漫画响应数据
 #[derive(Debug, Serialize, JsonSchema)]
 pub struct ChapterDownloadData {
+    /// 下载任务 ID，可在结果保留期内通过 `/api/job/<id>/result` 再次取回本响应的 data
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<u64>,
     pub comic_id: i64,
     pub comic_title: String,
     pub chapters: Vec<SingleChapterData>,
+    /// 重试过至少一次的页数，持续增长说明图片 CDN 正在变得不稳定
+    pub retried_pages: usize,
+    /// 单页最多重试的次数
+    pub max_retries_used: u64,
+    /// 所有章节累计的各阶段耗时（仅在 include_timings 为 true 时返回）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timings: Option<PhaseTimings>,
+    /// 自动购买花费的 JM 币（仅在 auto_buy 为 true 时返回）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coins_spent: Option<u64>,
 }
 
-// 下载普通漫画响应数据
+// 流式下载章节时每完成一个章节输出的一行
 #[derive(Debug, Serialize, JsonSchema)]
+pub struct ChapterStreamItem {
+    pub comic_id: i64,
+    pub comic_title: String,
+    /// 已完成的章节数，等于 total 时表示全部完成
+    pub completed: usize,
+    pub total: usize,
+    pub chapter: SingleChapterData,
+}
+
+// 下载普通漫画响应数据
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct ComicDownloadData {
+    /// 下载任务 ID，可在结果保留期内通过 `/api/job/<id>/result` 再次取回本响应的 data；直接复用已有 PDF 时不登记任务，为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<u64>,
     pub comic_id: i64,
     pub comic_title: String,
     /// 图片路径列表（merge为false时返回）
@@ -79,6 +700,376 @@ pub struct ComicDownloadData {
     /// 合并PDF文件路径（仅在merge为true时返回）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pdf_path: Option<String>,
+    /// PDF分卷路径列表（仅在设置了分卷选项时返回，无需拆分时仅含完整PDF）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pdf_paths: Option<Vec<String>>,
+    /// 已发送的邮件数（仅在设置了 email_to 时返回）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emails_sent: Option<usize>,
+    /// 书库模式下导出的 CBZ 路径（相对 JM_LIBRARY_DIR）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub library_path: Option<String>,
+    /// 页数
+    pub page_count: usize,
+    /// 重试过至少一次的页数，持续增长说明图片 CDN 正在变得不稳定
+    pub retried_pages: usize,
+    /// 单页最多重试的次数
+    pub max_retries_used: u64,
+    /// 各阶段耗时（仅在 include_timings 为 true 时返回）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timings: Option<PhaseTimings>,
+    /// PDF 与单页图片的校验和（仅在 checksums 为 true 时返回）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checksums: Option<ChecksumData>,
+    /// 生成的种子（仅在 torrent 为 true 时返回）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub torrent: Option<TorrentData>,
+    /// 自动购买花费的 JM 币（仅在 auto_buy 为 true 时返回）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coins_spent: Option<u64>,
+}
+
+// 章节列表条目
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ChapterItem {
+    pub chapter_id: i64,
+    pub name: String,
+    /// 章节序号，从 1 开始，与 JM 章节列表顺序一致
+    pub sort: usize,
+}
+
+// 章节列表响应
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ChapterListData {
+    pub comic_id: i64,
+    pub title: String,
+    pub chapters: Vec<ChapterItem>,
+}
+
+// 漫画列表条目（最新上架、每周推荐等）
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ComicSummary {
+    pub comic_id: i64,
+    pub title: String,
+    pub author: String,
+    /// 封面图片 URL
+    pub cover: String,
+    /// 分类名称
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+}
+
+// 漫画列表响应
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ComicListData {
+    pub page: u32,
+    pub comics: Vec<ComicSummary>,
+}
+
+// 搜索结果响应
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SearchData {
+    pub query: String,
+    /// 结果总数
+    pub total: usize,
+    /// 当前页码，从 1 开始
+    pub page: u32,
+    /// 每页条目数（JM 固定为 80）
+    pub page_size: usize,
+    /// 是否还有下一页
+    pub has_next: bool,
+    /// 查询为车号时 JM 跳转到的漫画 ID，此时 comics 只含该漫画
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redirect_aid: Option<i64>,
+    pub comics: Vec<ComicSummary>,
+}
+
+// 账号资料响应（来自最近一次登录返回的数据）
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct UserProfile {
+    pub uid: i64,
+    pub username: String,
+    /// JM 币余额
+    pub coin: i64,
+    pub level: i64,
+    pub level_name: String,
+    /// 当前经验值
+    pub exp: i64,
+    /// 升级所需经验值
+    pub next_level_exp: i64,
+    /// 头像图片 URL
+    pub avatar: String,
+}
+
+// 每日签到响应
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CheckinData {
+    /// JM 返回的签到结果提示
+    pub message: String,
+}
+
+// 点赞响应
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct LikeData {
+    /// JM 返回的点赞结果提示
+    pub message: String,
+}
+
+// 发表评论请求
+#[derive(Debug, Deserialize, JsonSchema)]
+#[schemars(example = "example_comment")]
+pub struct CommentRequest {
+    /// 评论内容
+    pub comment: String,
+    /// 要回复的评论 ID，为空时直接评论漫画
+    pub reply_to: Option<i64>,
+}
+
+fn example_comment() -> serde_json::Value {
+    json!({ "comment": "感谢分享" })
+}
+
+// 发表评论响应
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CommentData {
+    /// JM 返回的评论结果提示（新评论可能需要审核后才显示）
+    pub message: String,
+}
+
+// 用量报表响应
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct UsageReport {
+    /// 统计周期：day（今日）、week（本周，周一起）、month（本月）
+    pub period: String,
+    /// 周期开始时间（北京时间）
+    pub from: String,
+    /// 报表生成时间（北京时间）
+    pub to: String,
+    /// 下载任务数
+    pub jobs: usize,
+    /// 失败的任务数
+    pub failures: usize,
+    /// 涉及的漫画数
+    pub comics: usize,
+    /// 下载的页数
+    pub pages: usize,
+    /// 下载的字节数
+    pub bytes: u64,
+    /// 自动购买花费的 JM 币
+    pub coins: u64,
+    /// 下载页数最多的漫画
+    pub top_comics: Vec<ComicUsage>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ComicUsage {
+    pub comic_id: i64,
+    pub title: Option<String>,
+    /// 下载任务数
+    pub downloads: usize,
+    /// 失败的任务数
+    pub failures: usize,
+    pub pages: usize,
+    pub bytes: u64,
+    /// 自动购买花费的 JM 币
+    pub coins: u64,
+}
+
+// 调试接口原始数据请求
+#[derive(Debug, Deserialize, JsonSchema)]
+#[schemars(example = "example_raw_data")]
+pub struct RawDataRequest {
+    /// rawAlbum 为漫画 ID，rawChapter 为章节 ID
+    pub id: i64,
+}
+
+fn example_raw_data() -> serde_json::Value {
+    json!({ "id": 350234 })
+}
+
+// 清理下载目录请求，多个条件同时生效
+#[derive(Debug, Deserialize, JsonSchema)]
+#[schemars(example = "example_cleanup")]
+pub struct CleanupRequest {
+    /// 只清理最后修改时间早于 N 小时前的章节目录
+    #[serde(default)]
+    pub older_than_hours: Option<u64>,
+    /// 只清理指定漫画
+    #[serde(default)]
+    pub comic_id: Option<i64>,
+    /// 未指定其他条件时必须为 true，表示清理全部
+    #[serde(default)]
+    pub all: bool,
+}
+
+fn example_cleanup() -> serde_json::Value {
+    json!({ "older_than_hours": 24 })
+}
+
+// 清理下载目录响应
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CleanupData {
+    /// 已删除的章节目录数
+    pub removed_dirs: usize,
+    /// 释放的磁盘空间（字节）
+    pub freed_bytes: u64,
+    /// 正在使用而跳过的章节目录数
+    pub skipped_in_use: usize,
+}
+
+// 切换服务模式请求
+#[derive(Debug, Deserialize, JsonSchema)]
+#[schemars(example = "example_service_mode")]
+pub struct ServiceModeRequest {
+    /// normal：正常；read_only：拒绝新的下载（10014）；maintenance：除健康检查与管理接口外全部拒绝（10015）
+    pub mode: ServiceMode,
+}
+
+fn example_service_mode() -> serde_json::Value {
+    json!({ "mode": "read_only" })
+}
+
+// 服务模式响应
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ServiceModeData {
+    pub mode: ServiceMode,
+    /// 切换前的模式，查询时与 mode 相同
+    pub previous: ServiceMode,
+    /// 执行中与排队中的任务数，只读模式下降为 0 后即可安全停机
+    pub running_jobs: usize,
+}
+
+// 单个漫画的磁盘占用
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ComicStorage {
+    pub comic_id: String,
+    pub bytes: u64,
+    pub chapters: usize,
+    pub files: usize,
+}
+
+// 下载目录磁盘占用响应
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct StorageData {
+    pub total_bytes: u64,
+    /// 按占用从大到小排序
+    pub comics: Vec<ComicStorage>,
+}
+
+// 单个图片域名的探测结果
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DomainHealth {
+    pub domain: String,
+    /// 当前下载时首先使用的域名
+    pub preferred: bool,
+    /// 最近一次探测成功且失败率低于 50%
+    pub healthy: bool,
+    /// 成功探测的延迟指数平均（毫秒），从未成功时为空
+    pub latency_ms: Option<u64>,
+    /// 统计失败率的探测次数（最近 20 次）
+    pub probes: usize,
+    pub error_rate: f64,
+    pub last_error: Option<String>,
+    pub last_probe_at: Option<String>,
+}
+
+// 图片域名探测结果响应
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DomainsData {
+    /// 探测间隔（秒），0 表示未启用探测
+    pub probe_interval_seconds: u64,
+    /// 按下载时的使用顺序排列
+    pub domains: Vec<DomainHealth>,
+    /// 自适应图片下载并发的当前状态，未启用 JM_ADAPTIVE_CONCURRENCY 时为空
+    pub concurrency: Option<ImageConcurrency>,
+}
+
+// 自适应图片下载并发状态
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ImageConcurrency {
+    /// 当前允许的在途下载数
+    pub limit: usize,
+    /// 上限（JM_IMG_CONCURRENCY）
+    pub max: usize,
+    pub in_flight: usize,
+    /// 启动以来收到的 429/503 响应数
+    pub throttled_responses: u64,
+}
+
+// 下载任务信息
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct JobInfo {
+    pub job_id: u64,
+    /// 发起任务的接口（downloadChapter / downloadComic）
+    pub kind: String,
+    pub comic_id: i64,
+    pub priority: JobPriority,
+    /// 是否在队列中等待执行
+    pub queued: bool,
+    pub paused: bool,
+    /// 已请求取消，正在停止
+    pub cancelled: bool,
+    pub completed_pages: usize,
+    pub total_pages: usize,
+    pub downloaded_bytes: u64,
+    pub retries: u64,
+    /// 平均下载速度（MB/s）
+    pub speed_mbps: f64,
+    /// 预计剩余秒数，尚无完成页时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eta_seconds: Option<u64>,
+    pub elapsed_seconds: u64,
+}
+
+// 已成功完成的下载任务结果
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct JobResult {
+    pub job_id: u64,
+    /// 发起任务的接口（downloadChapter / downloadComic）
+    pub kind: String,
+    pub comic_id: i64,
+    /// 完成时间（RFC 3339，东八区）
+    pub finished_at: String,
+    /// 下载接口成功响应的 data（ChapterDownloadData 或 ComicDownloadData）
+    pub data: serde_json::Value,
+}
+
+// 最近结束的下载任务
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct FinishedJob {
+    pub job_id: u64,
+    /// 发起任务的接口（downloadChapter / downloadComic）
+    pub kind: String,
+    pub comic_id: i64,
+    /// 漫画标题，只有成功的任务才有
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub success: bool,
+    /// 是否被取消
+    pub cancelled: bool,
+    pub completed_pages: usize,
+    pub downloaded_bytes: u64,
+    pub elapsed_seconds: u64,
+    /// 结束时间（RFC 3339，东八区）
+    pub finished_at: String,
+}
+
+// 任务事件流每次推送的内容
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct JobEvents {
+    /// 执行中与排队中的任务，同 /api/job
+    pub active: Vec<JobInfo>,
+    /// 最近结束的任务，从新到旧
+    pub recent: Vec<FinishedJob>,
+}
+
+// 重新加载配置响应
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ReloadConfigData {
+    /// 已热更新的字段
+    pub changed: Vec<String>,
+    /// 已修改但需重启服务才能生效的字段
+    pub requires_restart: Vec<String>,
 }
 
 // 内部 API 响应模型（来自 JMComic API）
@@ -90,14 +1081,14 @@ pub struct JmResp {
     pub error_msg: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SeriesRespData {
     pub id: String,
     pub name: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct GetComicRespData {
     pub name: String,
     pub series: Vec<SeriesRespData>,
@@ -106,9 +1097,87 @@ pub struct GetComicRespData {
     pub likes: String,
     pub author: Vec<String>,
     pub description: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub works: Vec<String>,
+    #[serde(default)]
+    pub actors: Vec<String>,
+    /// 上架时间（Unix 秒）
+    #[serde(default, rename = "addtime", deserialize_with = "de_opt_timestamp")]
+    pub add_time: Option<i64>,
+    /// 更新时间（Unix 秒）
+    #[serde(default, rename = "update_at", deserialize_with = "de_opt_timestamp")]
+    pub update_time: Option<i64>,
+    #[serde(default)]
+    pub is_favorite: bool,
+    /// 购买所需 JM 币，免费时为空或 0
+    #[serde(default, deserialize_with = "de_opt_i64")]
+    pub price: Option<i64>,
+    /// 当前账号是否已购买
+    #[serde(default, deserialize_with = "de_lenient_bool")]
+    pub purchased: bool,
 }
 
-#[derive(Debug, Deserialize)]
+impl GetComicRespData {
+    /// 需要购买且当前账号尚未购买
+    pub fn requires_purchase(&self) -> bool {
+        self.price.unwrap_or(0) > 0 && !self.purchased
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct GetChapterRespData {
     pub images: Vec<String>,
+    /// 所属漫画 ID，普通漫画为 0 或缺失
+    #[serde(default, deserialize_with = "de_opt_i64")]
+    pub series_id: Option<i64>,
+    /// 购买所需 JM 币，免费时为空或 0
+    #[serde(default, deserialize_with = "de_opt_i64")]
+    pub price: Option<i64>,
+    /// 当前账号是否已购买
+    #[serde(default, deserialize_with = "de_lenient_bool")]
+    pub purchased: bool,
+}
+
+impl GetChapterRespData {
+    /// 需要购买且当前账号尚未购买
+    pub fn requires_purchase(&self) -> bool {
+        self.price.unwrap_or(0) > 0 && !self.purchased
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn purchase_fields_parse_leniently() {
+        let free: GetChapterRespData =
+            serde_json::from_str(r#"{"images":["1.webp"],"price":"","purchased":""}"#).unwrap();
+        assert!(!free.requires_purchase());
+
+        let locked: GetChapterRespData =
+            serde_json::from_str(r#"{"images":[],"price":"20","purchased":""}"#).unwrap();
+        assert!(locked.requires_purchase());
+
+        let bought: GetChapterRespData =
+            serde_json::from_str(r#"{"images":[],"price":20,"purchased":1}"#).unwrap();
+        assert!(!bought.requires_purchase());
+    }
+
+    #[test]
+    fn openapi_examples_are_valid_requests() {
+        serde_json::from_value::<GetComicInfoRequest>(example_get_comic_info()).unwrap();
+        serde_json::from_value::<ResolveRequest>(example_resolve()).unwrap();
+        serde_json::from_value::<PreviewRequest>(example_preview()).unwrap();
+        serde_json::from_value::<DownloadChapterRequest>(example_download_chapter()).unwrap();
+        serde_json::from_value::<DownloadComicRequest>(example_download_comic()).unwrap();
+        serde_json::from_value::<CheckLocalRequest>(example_check_local()).unwrap();
+        serde_json::from_value::<RawDataRequest>(example_raw_data()).unwrap();
+        serde_json::from_value::<CleanupRequest>(example_cleanup()).unwrap();
+        serde_json::from_value::<ServiceModeRequest>(example_service_mode()).unwrap();
+        serde_json::from_value::<CommentRequest>(example_comment()).unwrap();
+        serde_json::from_value::<SyncChaptersRequest>(example_sync_chapters()).unwrap();
+    }
 }
@@ -0,0 +1,161 @@
+// 任务通知模块
+// 下载任务完成或失败时通过 Telegram Bot 发送消息（标题、页数、下载链接）；
+// 合并的 PDF 不超过 Telegram Bot API 的上传上限时直接作为文件发送
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use jm_downloader_rs::AppError;
+use reqwest::multipart::{Form, Part};
+use serde_json::json;
+
+use crate::config::Config;
+use crate::dir_lease::DirLease;
+use crate::file_server::sanitize_filename;
+
+type Result<T> = std::result::Result<T, AppError>;
+
+/// Telegram Bot API 上传文件的大小上限
+const TELEGRAM_UPLOAD_LIMIT: u64 = 50 * 1024 * 1024;
+/// 文件说明（caption）的长度上限
+const TELEGRAM_CAPTION_CHARS: usize = 1024;
+
+/// 一次下载任务的结果
+pub struct JobEvent {
+    /// 发起任务的接口名
+    pub kind: &'static str,
+    pub comic_id: i64,
+    pub outcome: JobOutcome,
+}
+
+pub enum JobOutcome {
+    Completed {
+        title: String,
+        pages: usize,
+        /// 接口返回的下载链接，相对路径会拼接 JM_PUBLIC_BASE_URL
+        link: Option<String>,
+        /// 合并的 PDF，持有目录租约保证发送完成前不被过期清理
+        pdf: Option<(PathBuf, DirLease)>,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+/// 是否配置了任何通知渠道
+pub fn enabled(config: &Config) -> bool {
+    config.telegram_bot_token.is_some() && config.telegram_chat_id.is_some()
+}
+
+/// 在后台发送任务通知，发送失败只记录日志，不影响接口响应
+pub fn notify(config: &Config, event: JobEvent) {
+    let (Some(token), Some(chat_id)) = (config.telegram_bot_token.clone(), config.telegram_chat_id.clone())
+    else {
+        return;
+    };
+    let text = message_text(config, &event);
+    tokio::spawn(async move {
+        let telegram = Telegram { token, chat_id };
+        let result = match event.outcome {
+            JobOutcome::Completed { pdf: Some((path, _lease)), title, .. } => {
+                telegram.send_document_or_message(&path, &title, &text).await
+            }
+            _ => telegram.send_message(&text).await,
+        };
+        if let Err(e) = result {
+            warn!("发送 Telegram 通知失败: {}", e);
+        }
+    });
+}
+
+fn message_text(config: &Config, event: &JobEvent) -> String {
+    match &event.outcome {
+        JobOutcome::Completed { title, pages, link, .. } => {
+            let mut text = format!("✅ {}\n{} 页 · {} · comic_id={}", title, pages, event.kind, event.comic_id);
+            if let Some(link) = link.as_deref().and_then(|link| absolute_link(config, link)) {
+                text.push('\n');
+                text.push_str(&link);
+            }
+            text
+        }
+        JobOutcome::Failed { error } => {
+            format!("❌ 下载失败 · {} · comic_id={}\n{}", event.kind, event.comic_id, error)
+        }
+    }
+}
+
+/// 存储后端返回的绝对链接原样使用，本地 `/download` 相对链接需配置 JM_PUBLIC_BASE_URL 才能拼出可点击的地址
+fn absolute_link(config: &Config, link: &str) -> Option<String> {
+    if link.starts_with("http://") || link.starts_with("https://") {
+        return Some(link.to_string());
+    }
+    config
+        .public_base_url
+        .as_deref()
+        .map(|base| format!("{}/{}", base, link.trim_start_matches('/')))
+}
+
+struct Telegram {
+    token: String,
+    chat_id: String,
+}
+
+impl Telegram {
+    fn client() -> Result<reqwest::Client> {
+        reqwest::Client::builder()
+            .timeout(Duration::from_secs(300))
+            .build()
+            .map_err(|e| AppError::Internal(format!("创建HTTP客户端失败: {}", e)))
+    }
+
+    fn url(&self, method: &str) -> String {
+        format!("https://api.telegram.org/bot{}/{}", self.token, method)
+    }
+
+    async fn send_message(&self, text: &str) -> Result<()> {
+        let response = Self::client()?
+            .post(self.url("sendMessage"))
+            .json(&json!({ "chat_id": self.chat_id, "text": text }))
+            .send()
+            .await;
+        check_response(response).await
+    }
+
+    /// PDF 不超过上传上限时作为文件发送并附带说明，否则只发送文字消息
+    async fn send_document_or_message(&self, path: &Path, title: &str, text: &str) -> Result<()> {
+        let size = tokio::fs::metadata(path).await.map(|meta| meta.len()).unwrap_or(u64::MAX);
+        if size > TELEGRAM_UPLOAD_LIMIT {
+            info!("PDF 超过 Telegram 上传上限（{} 字节），只发送链接: {}", size, path.display());
+            return self.send_message(text).await;
+        }
+        let body = tokio::fs::read(path)
+            .await
+            .map_err(|e| AppError::Internal(format!("读取PDF失败: {}: {}", path.display(), e)))?;
+        let document = Part::bytes(body)
+            .file_name(format!("{}.pdf", sanitize_filename(title)))
+            .mime_str("application/pdf")
+            .map_err(|e| AppError::Internal(format!("构造 Telegram 请求失败: {}", e)))?;
+        let form = Form::new()
+            .text("chat_id", self.chat_id.clone())
+            .text("caption", text.chars().take(TELEGRAM_CAPTION_CHARS).collect::<String>())
+            .part("document", document);
+        let response = Self::client()?
+            .post(self.url("sendDocument"))
+            .multipart(form)
+            .send()
+            .await;
+        check_response(response).await
+    }
+}
+
+async fn check_response(response: reqwest::Result<reqwest::Response>) -> Result<()> {
+    // 错误信息中的请求地址含 bot token，不写入日志
+    let response = response
+        .map_err(|e| AppError::Internal(format!("请求 Telegram 失败: {}", e.without_url())))?;
+    let status = response.status();
+    if !status.is_success() {
+        let text = response.text().await.unwrap_or_default();
+        return Err(AppError::Internal(format!("Telegram 返回 {}: {}", status, text)));
+    }
+    Ok(())
+}
@@ -0,0 +1,94 @@
+// JM API 请求节流
+// 获取漫画（/album）、章节（/chapter）与 scramble_id（/chapter_view_template）的请求保持最小间隔，
+// 并限制每小时的请求数；超出时排队等待而不是立即发出，使长时间的批量下载保持在 JM 风控阈值以下
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+const HOUR: Duration = Duration::from_secs(3600);
+
+/// 请求节流器，限制为 0 时不等待
+#[derive(Default)]
+pub struct Pacer {
+    /// 相邻两次请求的最小间隔（毫秒）
+    min_interval_ms: AtomicU64,
+    /// 每小时请求数上限
+    hourly_limit: AtomicU64,
+    /// 最近一小时内发出请求的时间；等待期间持有锁，排队的请求按先后顺序发出
+    sent: Mutex<VecDeque<Instant>>,
+}
+
+impl Pacer {
+    /// 更新限制；启动与重新加载配置时调用，对之后的请求生效
+    pub fn configure(&self, min_interval_ms: u64, hourly_limit: u64) {
+        self.min_interval_ms.store(min_interval_ms, Ordering::Relaxed);
+        self.hourly_limit.store(hourly_limit, Ordering::Relaxed);
+    }
+
+    /// 等到允许发出下一次请求，`what` 用于日志
+    pub async fn wait(&self, what: &str) {
+        let min_interval = Duration::from_millis(self.min_interval_ms.load(Ordering::Relaxed));
+        let hourly_limit = self.hourly_limit.load(Ordering::Relaxed) as usize;
+        if min_interval.is_zero() && hourly_limit == 0 {
+            return;
+        }
+
+        let mut sent = self.sent.lock().await;
+        let now = Instant::now();
+        while sent.front().is_some_and(|&time| now.duration_since(time) >= HOUR) {
+            sent.pop_front();
+        }
+        let ready = next_send(&sent, now, min_interval, hourly_limit);
+        if ready > now {
+            let delay = ready - now;
+            if delay >= Duration::from_secs(60) {
+                warn!("JM API 已达每小时 {} 次请求上限，{}在 {} 秒后发出", hourly_limit, what, delay.as_secs());
+            } else {
+                debug!("JM API 节流，{}等待 {}ms", what, delay.as_millis());
+            }
+            tokio::time::sleep_until(ready).await;
+        }
+        sent.push_back(Instant::now());
+        // 未限制每小时次数时只需保留最近一次
+        let keep = hourly_limit.max(1);
+        while sent.len() > keep {
+            sent.pop_front();
+        }
+    }
+}
+
+/// 根据一小时内的发送记录计算下一次请求最早的发出时间
+fn next_send(sent: &VecDeque<Instant>, now: Instant, min_interval: Duration, hourly_limit: usize) -> Instant {
+    let mut ready = now;
+    if let Some(&last) = sent.back() {
+        ready = ready.max(last + min_interval);
+    }
+    if hourly_limit > 0 && sent.len() >= hourly_limit {
+        ready = ready.max(sent[sent.len() - hourly_limit] + HOUR);
+    }
+    ready
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spaces_requests_and_caps_hourly_count() {
+        let start = Instant::now();
+        let second = Duration::from_secs(1);
+        let sent: VecDeque<Instant> = [start, start + second * 10].into();
+
+        // 最小间隔从最近一次请求算起
+        assert_eq!(next_send(&sent, start + second * 11, second * 5, 0), start + second * 15);
+        assert_eq!(next_send(&sent, start + second * 20, second * 5, 0), start + second * 20);
+        // 一小时内已满 2 次时，等到最早的一次满一小时
+        assert_eq!(next_send(&sent, start + second * 20, Duration::ZERO, 2), start + HOUR);
+        assert_eq!(next_send(&sent, start + second * 20, Duration::ZERO, 3), start + second * 20);
+        assert_eq!(next_send(&VecDeque::new(), start, second, 1), start);
+    }
+}
@@ -0,0 +1,157 @@
+// 部分页下载
+// 请求中的 page_range 与 pages 合并为要下载的页集合，用于预览前几页等场景，无需拉取整个章节；
+// exclude_pages 再从中去掉指定的页，保存的文件仍按整章编号，与完整下载共用
+
+use jm_downloader_rs::AppError;
+
+use crate::models::PageRange;
+
+type Result<T> = std::result::Result<T, AppError>;
+
+/// 要下载的页；`page_range` 与 `pages` 都未设置时为全部页，再去掉 `exclude` 中的页
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct PageSelection {
+    range: Option<PageRange>,
+    /// 升序、去重后的页码（从 1 开始）
+    pages: Vec<usize>,
+    /// 升序、去重后的排除页码（从 1 开始）
+    exclude: Vec<usize>,
+}
+
+impl PageSelection {
+    /// 校验请求中的页码，`range` 与 `pages` 同时设置时取并集，再去掉 `exclude`
+    pub fn new(range: Option<PageRange>, pages: &[usize], exclude: &[usize]) -> Result<Self> {
+        if let Some(range) = range {
+            if range.from == 0 || range.to < range.from {
+                return Err(AppError::BadRequest(format!(
+                    "page_range 无效: from={}, to={}，页码从 1 开始且 to 不能小于 from",
+                    range.from, range.to
+                )));
+            }
+        }
+        if pages.contains(&0) {
+            return Err(AppError::BadRequest("pages 中的页码从 1 开始".to_string()));
+        }
+        if exclude.contains(&0) {
+            return Err(AppError::BadRequest("exclude_pages 中的页码从 1 开始".to_string()));
+        }
+        Ok(Self { range, pages: sorted(pages), exclude: sorted(exclude) })
+    }
+
+    /// 是否下载全部页
+    pub fn is_all(&self) -> bool {
+        !self.is_partial() && self.exclude.is_empty()
+    }
+
+    /// 是否只选取了部分页（page_range 或 pages）；只排除个别页面不算
+    pub fn is_partial(&self) -> bool {
+        self.range.is_some() || !self.pages.is_empty()
+    }
+
+    /// 在共 `total` 页的章节中选中的页下标（从 0 开始，升序）
+    ///
+    /// 范围超出章节页数时截断到最后一页；`pages` 中超出页数的页码与最终为空的选择返回错误，
+    /// 超出页数的排除页码忽略
+    pub fn indices(&self, total: usize) -> Result<Vec<usize>> {
+        let mut indices: Vec<usize> = if self.is_partial() {
+            if let Some(&page) = self.pages.iter().find(|&&page| page > total) {
+                return Err(AppError::BadRequest(format!("第 {} 页超出章节页数 {}", page, total)));
+            }
+            let mut indices: Vec<usize> = self.pages.iter().map(|page| page - 1).collect();
+            if let Some(range) = self.range {
+                indices.extend(range.from - 1..range.to.min(total));
+            }
+            indices.sort_unstable();
+            indices.dedup();
+            if indices.is_empty() {
+                return Err(AppError::BadRequest(format!("所选页码超出章节页数 {}", total)));
+            }
+            indices
+        } else {
+            (0..total).collect()
+        };
+        if !self.exclude.is_empty() {
+            indices.retain(|index| self.exclude.binary_search(&(index + 1)).is_err());
+            if indices.is_empty() {
+                return Err(AppError::BadRequest("排除 exclude_pages 后没有要下载的页".to_string()));
+            }
+        }
+        Ok(indices)
+    }
+
+    /// 用于区分部分页 PDF 的文件名后缀，如 `.p1-5.8`、排除页为 `.x3.7`；页码过多时使用摘要，全部页时为空
+    pub fn file_suffix(&self) -> String {
+        let mut suffix = String::new();
+        if self.is_partial() {
+            let runs: Vec<String> = self
+                .range
+                .map(|range| format!("{}-{}", range.from, range.to))
+                .into_iter()
+                .chain(self.pages.iter().map(usize::to_string))
+                .collect();
+            suffix.push_str(&suffix_part('p', &runs));
+        }
+        if !self.exclude.is_empty() {
+            let runs: Vec<String> = self.exclude.iter().map(usize::to_string).collect();
+            suffix.push_str(&suffix_part('x', &runs));
+        }
+        suffix
+    }
+}
+
+fn sorted(pages: &[usize]) -> Vec<usize> {
+    let mut pages = pages.to_vec();
+    pages.sort_unstable();
+    pages.dedup();
+    pages
+}
+
+/// `.p1-5.8` 形式的后缀片段，过长时使用摘要
+fn suffix_part(prefix: char, runs: &[String]) -> String {
+    let joined = runs.join(".");
+    if joined.len() > 40 {
+        format!(".{}{}", prefix, &format!("{:x}", md5::compute(&joined))[..8])
+    } else {
+        format!(".{}{}", prefix, joined)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_range_and_pages() {
+        let selection = PageSelection::new(Some(PageRange { from: 2, to: 4 }), &[8, 3, 1], &[]).unwrap();
+        assert_eq!(selection.indices(10).unwrap(), [0, 1, 2, 3, 7]);
+        assert_eq!(selection.file_suffix(), ".p2-4.1.3.8");
+
+        // 范围超出页数时截断
+        let preview = PageSelection::new(Some(PageRange { from: 1, to: 5 }), &[], &[]).unwrap();
+        assert_eq!(preview.indices(3).unwrap(), [0, 1, 2]);
+
+        assert!(PageSelection::new(None, &[], &[]).unwrap().is_all());
+        assert_eq!(PageSelection::new(None, &[], &[]).unwrap().file_suffix(), "");
+        assert!(PageSelection::new(None, &[0], &[]).is_err());
+        assert!(PageSelection::new(Some(PageRange { from: 5, to: 4 }), &[], &[]).is_err());
+        assert!(PageSelection::new(None, &[11], &[]).unwrap().indices(10).is_err());
+        assert!(PageSelection::new(Some(PageRange { from: 11, to: 20 }), &[], &[]).unwrap().indices(10).is_err());
+    }
+
+    #[test]
+    fn excludes_pages_from_any_selection() {
+        let selection = PageSelection::new(None, &[], &[7, 3, 3, 12]).unwrap();
+        assert!(!selection.is_all());
+        assert!(!selection.is_partial());
+        // 超出页数的排除页码忽略，其余页保持原下标
+        assert_eq!(selection.indices(8).unwrap(), [0, 1, 3, 4, 5, 7]);
+        assert_eq!(selection.file_suffix(), ".x3.7.12");
+
+        let selection = PageSelection::new(Some(PageRange { from: 1, to: 4 }), &[], &[2]).unwrap();
+        assert_eq!(selection.indices(10).unwrap(), [0, 2, 3]);
+        assert_eq!(selection.file_suffix(), ".p1-4.x2");
+
+        assert!(PageSelection::new(None, &[], &[0]).is_err());
+        assert!(PageSelection::new(None, &[2], &[2]).unwrap().indices(10).is_err());
+    }
+}
@@ -0,0 +1,128 @@
+// 页面旁注文件
+// 启用 JM_PAGE_SIDECAR 后在章节（变体）目录写入 pages.json，按输出文件名记录源图片在 CDN 上的文件名
+// 与处理后文件内容的 SHA-256。之后复用本地页面前先核对：源文件名已变化（JM 重新上传了修正的页面）
+// 或本地内容与记录不符的页面被删除后重新下载，其余页面照常跳过。没有记录的旧页面视为有效并补写记录
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use jm_downloader_rs::AppError;
+use serde::{Deserialize, Serialize};
+
+use crate::atomic_file;
+use crate::checksums::hash_file;
+
+type Result<T> = std::result::Result<T, AppError>;
+
+pub const SIDECAR_FILE: &str = "pages.json";
+
+/// 单个输出页面文件的记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageRecord {
+    /// 源图片在 CDN 上的文件名
+    pub source: String,
+    /// 处理后文件内容的 SHA-256
+    pub sha256: String,
+}
+
+/// 一个章节目录的页面记录，键为输出文件名
+pub struct PageSidecar {
+    path: PathBuf,
+    pages: BTreeMap<String, PageRecord>,
+    changed: bool,
+}
+
+impl PageSidecar {
+    /// 读取目录中的 pages.json，不存在或无法解析时从空记录开始
+    pub async fn load(dir: &Path) -> Self {
+        let path = dir.join(SIDECAR_FILE);
+        let pages = match tokio::fs::read(&path).await {
+            Ok(data) => serde_json::from_slice(&data).unwrap_or_else(|e| {
+                warn!("页面记录 {} 无法解析，将重新生成: {}", path.display(), e);
+                BTreeMap::new()
+            }),
+            Err(_) => BTreeMap::new(),
+        };
+        Self { path, pages, changed: false }
+    }
+
+    /// 本地页面文件是否已过期：有记录且源文件名或内容哈希与记录不符
+    pub async fn is_stale(&self, path: &Path, source: &str) -> Result<bool> {
+        let Some(record) = self.pages.get(&file_name(path)) else {
+            return Ok(false);
+        };
+        if record.source != source {
+            return Ok(true);
+        }
+        Ok(file_sha256(path).await? != record.sha256)
+    }
+
+    /// 记录页面文件；已有相同来源的记录且未重新写入时保留原记录，不再计算哈希
+    pub async fn record(&mut self, path: &Path, source: &str, rewritten: bool) -> Result<()> {
+        let name = file_name(path);
+        if !rewritten && self.pages.get(&name).is_some_and(|record| record.source == source) {
+            return Ok(());
+        }
+        let record = PageRecord { source: source.to_string(), sha256: file_sha256(path).await? };
+        if self.pages.get(&name) != Some(&record) {
+            self.pages.insert(name, record);
+            self.changed = true;
+        }
+        Ok(())
+    }
+
+    /// 有变化时写回 pages.json，失败只记录日志
+    pub async fn save(&self) {
+        if !self.changed {
+            return;
+        }
+        let json = match serde_json::to_vec_pretty(&self.pages) {
+            Ok(json) => json,
+            Err(e) => {
+                warn!("序列化页面记录失败: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = atomic_file::write(&self.path, json).await {
+            warn!("写入页面记录 {} 失败: {}", self.path.display(), e);
+        }
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().unwrap_or_default().to_string_lossy().to_string()
+}
+
+async fn file_sha256(path: &Path) -> Result<String> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || hash_file(&path))
+        .await
+        .map_err(|e| AppError::Internal(format!("计算页面哈希任务执行失败: {}", e)))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn detects_reuploaded_and_modified_pages() {
+        let dir = std::env::temp_dir().join(format!("jm-page-sidecar-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let page = dir.join("0001.png");
+        std::fs::write(&page, b"page").unwrap();
+
+        let mut sidecar = PageSidecar::load(&dir).await;
+        // 没有记录的旧页面视为有效
+        assert!(!sidecar.is_stale(&page, "00001.webp").await.unwrap());
+        sidecar.record(&page, "00001.webp", false).await.unwrap();
+        sidecar.save().await;
+
+        let sidecar = PageSidecar::load(&dir).await;
+        assert!(!sidecar.is_stale(&page, "00001.webp").await.unwrap());
+        assert!(sidecar.is_stale(&page, "00001_v2.webp").await.unwrap());
+        std::fs::write(&page, b"edited").unwrap();
+        assert!(sidecar.is_stale(&page, "00001.webp").await.unwrap());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
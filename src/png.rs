@@ -0,0 +1,156 @@
+// PNG 编码参数
+// 保存页面时的压缩级别与行过滤方式（JM_PNG_COMPRESSION / JM_PNG_FILTER），默认与 `image` 的默认编码器一致
+// （fast + adaptive）。放在库 crate 中，供二进制与 `benches/png_encode.rs` 共用
+
+use std::str::FromStr;
+
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::{ImageBuffer, ImageResult, PixelWithColorType};
+use serde::Deserialize;
+
+/// DEFLATE 压缩级别
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum PngCompression {
+    /// 不压缩，编码最快、文件最大
+    None,
+    /// 快速压缩
+    #[default]
+    Fast,
+    /// zlib 默认级别
+    Default,
+    /// 最高压缩，编码最慢
+    Best,
+    /// 指定级别 1-9
+    Level(u8),
+}
+
+impl FromStr for PngCompression {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "fast" => Ok(Self::Fast),
+            "default" => Ok(Self::Default),
+            "best" => Ok(Self::Best),
+            level => match level.parse::<u8>() {
+                Ok(level @ 1..=9) => Ok(Self::Level(level)),
+                _ => Err(format!("{}，应为 none、fast、default、best 或 1-9", value)),
+            },
+        }
+    }
+}
+
+impl TryFrom<String> for PngCompression {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<PngCompression> for CompressionType {
+    fn from(compression: PngCompression) -> Self {
+        match compression {
+            PngCompression::None => CompressionType::Uncompressed,
+            PngCompression::Fast => CompressionType::Fast,
+            PngCompression::Default => CompressionType::Default,
+            PngCompression::Best => CompressionType::Best,
+            PngCompression::Level(level) => CompressionType::Level(level),
+        }
+    }
+}
+
+/// 行过滤方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum PngFilter {
+    /// 不过滤
+    None,
+    Sub,
+    Up,
+    Avg,
+    Paeth,
+    /// 每行选择效果最好的过滤方式
+    #[default]
+    Adaptive,
+}
+
+impl FromStr for PngFilter {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "sub" => Ok(Self::Sub),
+            "up" => Ok(Self::Up),
+            "avg" => Ok(Self::Avg),
+            "paeth" => Ok(Self::Paeth),
+            "adaptive" => Ok(Self::Adaptive),
+            _ => Err(format!("{}，应为 none、sub、up、avg、paeth 或 adaptive", value)),
+        }
+    }
+}
+
+impl TryFrom<String> for PngFilter {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<PngFilter> for FilterType {
+    fn from(filter: PngFilter) -> Self {
+        match filter {
+            PngFilter::None => FilterType::NoFilter,
+            PngFilter::Sub => FilterType::Sub,
+            PngFilter::Up => FilterType::Up,
+            PngFilter::Avg => FilterType::Avg,
+            PngFilter::Paeth => FilterType::Paeth,
+            PngFilter::Adaptive => FilterType::Adaptive,
+        }
+    }
+}
+
+/// PNG 编码参数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PngSettings {
+    pub compression: PngCompression,
+    pub filter: PngFilter,
+}
+
+/// 按给定参数把 RGB/灰度图像编码为 PNG
+pub fn encode_png<P>(image: &ImageBuffer<P, Vec<u8>>, settings: PngSettings) -> ImageResult<Vec<u8>>
+where
+    P: PixelWithColorType<Subpixel = u8>,
+{
+    let mut png = Vec::new();
+    let encoder = PngEncoder::new_with_quality(&mut png, settings.compression.into(), settings.filter.into());
+    image.write_with_encoder(encoder)?;
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+
+    #[test]
+    fn parses_settings_and_round_trips() {
+        assert_eq!("BEST".parse(), Ok(PngCompression::Best));
+        assert_eq!("6".parse(), Ok(PngCompression::Level(6)));
+        assert!("10".parse::<PngCompression>().is_err());
+        assert_eq!("none".parse(), Ok(PngFilter::None));
+        assert!("fastest".parse::<PngFilter>().is_err());
+
+        let image = RgbImage::from_fn(64, 48, |x, y| image::Rgb([x as u8, y as u8, (x ^ y) as u8]));
+        for compression in [PngCompression::None, PngCompression::Fast, PngCompression::Best] {
+            let settings = PngSettings { compression, filter: PngFilter::Paeth };
+            let png = encode_png(&image, settings).unwrap();
+            let decoded = image::load_from_memory(&png).unwrap().to_rgb8();
+            assert_eq!(decoded, image);
+        }
+    }
+}
@@ -0,0 +1,192 @@
+// 页面后处理流水线
+// 请求的 `post_process` 按顺序列出处理步骤（如 `["trim", "resize:1600", "greyscale"]`），每页在还原打乱、
+// 拆分跨页之后、电子墨水屏优化与编码之前依次执行。步骤写作 `名称` 或 `名称:参数`（参数为非负整数），
+// 名称对应实现了 `PagePostProcessor` 的处理器：内置 resize/greyscale/trim，下游分支可在启动时用 `register`
+// 注册自己的处理器，无需修改下载流程。步骤列表计入变体目录，不同流水线的产物互不覆盖
+
+use std::fmt;
+use std::sync::RwLock;
+
+use image::imageops::FilterType;
+use image::DynamicImage;
+
+use crate::image_processor::trim_borders;
+
+/// 单个请求最多的处理步骤数
+pub const MAX_POST_STEPS: usize = 8;
+
+/// 页面后处理器
+pub trait PagePostProcessor: Send + Sync {
+    /// 处理一页，`arg` 为步骤中 `名称:参数` 的参数
+    fn process(&self, image: DynamicImage, arg: Option<u32>) -> DynamicImage;
+
+    /// 校验步骤参数，不合法时返回错误说明
+    fn check_arg(&self, arg: Option<u32>) -> Result<(), String> {
+        match arg {
+            None => Ok(()),
+            Some(_) => Err("不接受参数".to_string()),
+        }
+    }
+}
+
+/// 把长边缩小到参数指定的像素数，已不超过时不处理
+struct Resize;
+
+impl PagePostProcessor for Resize {
+    fn process(&self, image: DynamicImage, arg: Option<u32>) -> DynamicImage {
+        let long_edge = arg.unwrap_or(u32::MAX);
+        if image.width().max(image.height()) <= long_edge {
+            return image;
+        }
+        image.resize(long_edge, long_edge, FilterType::Triangle)
+    }
+
+    fn check_arg(&self, arg: Option<u32>) -> Result<(), String> {
+        match arg {
+            Some(100..=10_000) => Ok(()),
+            _ => Err("需要 100~10000 之间的长边像素数，如 resize:1600".to_string()),
+        }
+    }
+}
+
+/// 转为 8 位灰度，保存的 PNG 与 PDF 中的图片只有一个通道
+struct Greyscale;
+
+impl PagePostProcessor for Greyscale {
+    fn process(&self, image: DynamicImage, _arg: Option<u32>) -> DynamicImage {
+        DynamicImage::ImageLuma8(image.into_luma8())
+    }
+}
+
+/// 裁剪四周纯白或纯黑的边框，与请求的 `trim_borders` 相同，但在拆分跨页之后按流水线顺序执行
+struct Trim;
+
+impl PagePostProcessor for Trim {
+    fn process(&self, image: DynamicImage, _arg: Option<u32>) -> DynamicImage {
+        DynamicImage::ImageRgb8(trim_borders(image.into_rgb8()))
+    }
+}
+
+type Entry = (&'static str, &'static dyn PagePostProcessor);
+
+static BUILTIN: [Entry; 3] = [("resize", &Resize), ("greyscale", &Greyscale), ("trim", &Trim)];
+static CUSTOM: RwLock<Vec<Entry>> = RwLock::new(Vec::new());
+
+/// 注册自定义处理器，需在启动时、处理任何请求之前调用；与内置处理器同名时内置处理器优先
+#[allow(dead_code)] // 供下游分支在 main 中调用
+pub fn register(name: &'static str, processor: &'static dyn PagePostProcessor) {
+    CUSTOM.write().unwrap().push((name, processor));
+}
+
+fn lookup(name: &str) -> Option<Entry> {
+    BUILTIN
+        .iter()
+        .copied()
+        .chain(CUSTOM.read().unwrap().iter().copied())
+        .find(|(registered, _)| *registered == name)
+}
+
+/// 一个处理步骤：已注册的处理器名称与参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct PostStep {
+    name: &'static str,
+    arg: Option<u32>,
+}
+
+/// 按顺序执行的处理步骤，定长存储以便随单页处理选项复制
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct PostProcessSteps {
+    steps: [Option<PostStep>; MAX_POST_STEPS],
+}
+
+impl PostProcessSteps {
+    pub const EMPTY: Self = Self { steps: [None; MAX_POST_STEPS] };
+
+    /// 解析请求中的步骤列表，返回第一个不合法步骤的错误说明
+    pub fn parse(specs: &[String]) -> Result<Self, String> {
+        if specs.len() > MAX_POST_STEPS {
+            return Err(format!("最多 {} 个步骤，当前 {} 个", MAX_POST_STEPS, specs.len()));
+        }
+        let mut steps = [None; MAX_POST_STEPS];
+        for (slot, spec) in steps.iter_mut().zip(specs) {
+            let (name, arg) = match spec.trim().split_once(':') {
+                Some((name, arg)) => {
+                    let arg = arg.trim().parse().map_err(|_| format!("{}: 参数须为非负整数", spec))?;
+                    (name.trim(), Some(arg))
+                }
+                None => (spec.trim(), None),
+            };
+            let (name, processor) = lookup(name).ok_or_else(|| format!("{}: 未知的处理器", spec))?;
+            processor.check_arg(arg).map_err(|e| format!("{}: {}", spec, e))?;
+            *slot = Some(PostStep { name, arg });
+        }
+        Ok(Self { steps })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps[0].is_none()
+    }
+
+    /// 依次执行各步骤
+    pub fn apply(&self, mut image: DynamicImage) -> DynamicImage {
+        for step in self.steps.iter().flatten() {
+            // 步骤只能由 parse 从已注册的处理器构造，处理器不会被移除
+            if let Some((_, processor)) = lookup(step.name) {
+                image = processor.process(image, step.arg);
+            }
+        }
+        image
+    }
+}
+
+/// 以请求中的写法输出，如 `trim,resize:1600`，用于变体目录的选项
+impl fmt::Display for PostProcessSteps {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, step) in self.steps.iter().flatten().enumerate() {
+            if index > 0 {
+                f.write_str(",")?;
+            }
+            f.write_str(step.name)?;
+            if let Some(arg) = step.arg {
+                write!(f, ":{}", arg)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+
+    #[test]
+    fn runs_steps_in_request_order() {
+        let specs = ["resize:100".to_string(), " greyscale ".to_string()];
+        let steps = PostProcessSteps::parse(&specs).unwrap();
+        assert_eq!(steps.to_string(), "resize:100,greyscale");
+        let page = DynamicImage::ImageRgb8(RgbImage::from_pixel(400, 200, image::Rgb([200, 100, 50])));
+        let processed = steps.apply(page);
+        assert_eq!((processed.width(), processed.height()), (100, 50));
+        assert!(matches!(processed, DynamicImage::ImageLuma8(_)));
+        assert!(PostProcessSteps::parse(&[]).unwrap().is_empty());
+
+        for invalid in ["sharpen", "resize", "resize:abc", "greyscale:2"] {
+            assert!(PostProcessSteps::parse(&[invalid.to_string()]).is_err(), "{}", invalid);
+        }
+        assert!(PostProcessSteps::parse(&vec!["trim".to_string(); MAX_POST_STEPS + 1]).is_err());
+
+        // 注册的处理器可以按名称使用
+        struct Invert;
+        impl PagePostProcessor for Invert {
+            fn process(&self, mut image: DynamicImage, _arg: Option<u32>) -> DynamicImage {
+                image.invert();
+                image
+            }
+        }
+        register("invert", &Invert);
+        let steps = PostProcessSteps::parse(&["invert".to_string()]).unwrap();
+        let inverted = steps.apply(DynamicImage::ImageRgb8(RgbImage::from_pixel(1, 1, image::Rgb([0, 0, 0]))));
+        assert_eq!(inverted.into_rgb8().get_pixel(0, 0).0, [255, 255, 255]);
+    }
+}
@@ -0,0 +1,99 @@
+// 预览图生成
+// 把拼接还原后的前几页缩小并编码为 JPEG data URL，或横向拼成一张预览图，直接放在 JSON 响应中返回
+
+use std::io::Cursor;
+
+use base64::engine::general_purpose;
+use base64::Engine;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{Rgb, RgbImage};
+use jm_downloader_rs::AppError;
+
+use crate::models::PreviewPage;
+
+type Result<T> = std::result::Result<T, AppError>;
+
+/// 单页预览图的最长边
+const PAGE_LONG_EDGE: u32 = 800;
+/// 拼图中每页的高度
+const COLLAGE_HEIGHT: u32 = 600;
+/// 拼图中页面之间的间隔
+const COLLAGE_GAP: u32 = 8;
+const JPEG_QUALITY: u8 = 80;
+
+/// 缩小并编码每一页，`pages` 为 (页码, 图像)
+pub fn encode_pages(pages: Vec<(usize, RgbImage)>) -> Result<Vec<PreviewPage>> {
+    pages
+        .into_iter()
+        .map(|(page, image)| {
+            let image = fit_long_edge(image, PAGE_LONG_EDGE);
+            Ok(PreviewPage {
+                page,
+                width: image.width(),
+                height: image.height(),
+                image: jpeg_data_url(&image)?,
+            })
+        })
+        .collect()
+}
+
+/// 把各页缩放到相同高度后从左到右拼成一张图，返回 JPEG data URL
+pub fn collage(pages: &[RgbImage]) -> Result<String> {
+    let scaled: Vec<RgbImage> = pages
+        .iter()
+        .map(|page| {
+            let width = (u64::from(page.width()) * u64::from(COLLAGE_HEIGHT) / u64::from(page.height().max(1))).max(1);
+            image::imageops::resize(page, width as u32, COLLAGE_HEIGHT, FilterType::Triangle)
+        })
+        .collect();
+    let gaps = COLLAGE_GAP * (scaled.len() as u32 + 1);
+    let width = scaled.iter().map(RgbImage::width).sum::<u32>() + gaps;
+    let mut sheet = RgbImage::from_pixel(width, COLLAGE_HEIGHT + COLLAGE_GAP * 2, Rgb([255, 255, 255]));
+    let mut x = COLLAGE_GAP;
+    for page in &scaled {
+        image::imageops::replace(&mut sheet, page, i64::from(x), i64::from(COLLAGE_GAP));
+        x += page.width() + COLLAGE_GAP;
+    }
+    jpeg_data_url(&sheet)
+}
+
+/// 长边超过 `long_edge` 时等比缩小
+fn fit_long_edge(image: RgbImage, long_edge: u32) -> RgbImage {
+    let (width, height) = image.dimensions();
+    if width.max(height) <= long_edge {
+        return image;
+    }
+    let scale = f64::from(long_edge) / f64::from(width.max(height));
+    let new_width = ((f64::from(width) * scale).round() as u32).max(1);
+    let new_height = ((f64::from(height) * scale).round() as u32).max(1);
+    image::imageops::resize(&image, new_width, new_height, FilterType::Triangle)
+}
+
+fn jpeg_data_url(image: &RgbImage) -> Result<String> {
+    let mut jpeg = Cursor::new(Vec::new());
+    JpegEncoder::new_with_quality(&mut jpeg, JPEG_QUALITY)
+        .encode_image(image)
+        .map_err(|e| AppError::Internal(format!("编码预览图失败: {}", e)))?;
+    Ok(format!("data:image/jpeg;base64,{}", general_purpose::STANDARD.encode(jpeg.into_inner())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collage_places_pages_side_by_side() {
+        let pages = [RgbImage::new(300, 1200), RgbImage::new(600, 1200)];
+        let url = collage(&pages).unwrap();
+        let jpeg = general_purpose::STANDARD
+            .decode(url.strip_prefix("data:image/jpeg;base64,").unwrap())
+            .unwrap();
+        let sheet = image::load_from_memory(&jpeg).unwrap();
+        assert_eq!(sheet.width(), 150 + 300 + COLLAGE_GAP * 3);
+        assert_eq!(sheet.height(), COLLAGE_HEIGHT + COLLAGE_GAP * 2);
+
+        let encoded = encode_pages(vec![(1, RgbImage::new(1000, 2000))]).unwrap();
+        assert_eq!((encoded[0].width, encoded[0].height), (400, 800));
+    }
+}
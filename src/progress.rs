@@ -0,0 +1,277 @@
+// 下载进度模块
+// 汇总一次下载请求的完成页数、流量与重试次数，按固定间隔输出一行进度日志，替代逐张图片的日志
+
+use std::cell::Cell;
+use std::fmt;
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::Level;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+tokio::task_local! {
+    /// 当前图片已重试的次数，由 `Progress::track_page` 设置作用域
+    static PAGE_RETRIES: Cell<u64>;
+}
+
+/// 任务日志详细程度，请求未指定时使用 JM_JOB_LOG_LEVEL
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum JobLogLevel {
+    /// 输出逐章节的开始、耗时与定时进度（默认）
+    #[default]
+    Info,
+    /// 只输出任务开始与完成汇总，逐章节与定时进度日志降为 debug，警告与错误不受影响
+    Quiet,
+}
+
+impl FromStr for JobLogLevel {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "info" => Ok(Self::Info),
+            "quiet" => Ok(Self::Quiet),
+            _ => Err(format!("{}，应为 info 或 quiet", value)),
+        }
+    }
+}
+
+/// 一次下载请求的进度计数，可在并发任务间共享
+pub struct Progress {
+    label: String,
+    start: Instant,
+    total: AtomicUsize,
+    completed: AtomicUsize,
+    bytes: AtomicU64,
+    retries: AtomicU64,
+    /// 重试过至少一次的页数
+    retried_pages: AtomicUsize,
+    /// 单页最多重试的次数
+    max_page_retries: AtomicU64,
+    /// 安静模式：逐章节与定时进度日志降为 debug
+    quiet: AtomicBool,
+}
+
+/// 某一时刻的进度快照
+#[derive(Debug, Clone, Copy)]
+pub struct ProgressSnapshot {
+    pub completed: usize,
+    pub total: usize,
+    pub bytes: u64,
+    pub retries: u64,
+    pub retried_pages: usize,
+    pub max_page_retries: u64,
+    pub elapsed: Duration,
+}
+
+impl ProgressSnapshot {
+    /// 平均下载速度（MB/s）
+    pub fn speed_mbps(&self) -> f64 {
+        self.bytes as f64 / 1_000_000.0 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+
+    /// 按已完成页数的平均速度估算剩余时间，尚无完成页时为 None
+    pub fn eta(&self) -> Option<Duration> {
+        if self.completed == 0 {
+            return None;
+        }
+        let remaining = self.total.saturating_sub(self.completed) as f64;
+        Some(Duration::from_secs_f64(
+            self.elapsed.as_secs_f64() / self.completed as f64 * remaining,
+        ))
+    }
+}
+
+impl fmt::Display for ProgressSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{} 页，{:.1} MB，{:.2} MB/s，重试 {} 次",
+            self.completed,
+            self.total,
+            self.bytes as f64 / 1_000_000.0,
+            self.speed_mbps(),
+            self.retries
+        )?;
+        if self.retried_pages > 0 {
+            write!(f, "（{} 页，单页最多 {} 次）", self.retried_pages, self.max_page_retries)?;
+        }
+        match self.eta() {
+            Some(eta) => write!(f, "，预计剩余 {}s", eta.as_secs()),
+            None => write!(f, "，预计剩余未知"),
+        }
+    }
+}
+
+impl Progress {
+    pub fn new(label: impl Into<String>) -> Arc<Self> {
+        Arc::new(Self {
+            label: label.into(),
+            start: Instant::now(),
+            total: AtomicUsize::new(0),
+            completed: AtomicUsize::new(0),
+            bytes: AtomicU64::new(0),
+            retries: AtomicU64::new(0),
+            retried_pages: AtomicUsize::new(0),
+            max_page_retries: AtomicU64::new(0),
+            quiet: AtomicBool::new(false),
+        })
+    }
+
+    /// 设置任务的日志详细程度
+    pub fn set_log_level(&self, level: JobLogLevel) {
+        self.quiet.store(level == JobLogLevel::Quiet, Ordering::Relaxed);
+    }
+
+    /// 逐章节等明细日志的输出级别：安静模式为 debug，否则为 info
+    pub fn detail_level(&self) -> Level {
+        if self.quiet.load(Ordering::Relaxed) {
+            Level::Debug
+        } else {
+            Level::Info
+        }
+    }
+
+    /// 增加待处理页数（多章节下载时逐章累加）
+    pub fn add_total(&self, pages: usize) {
+        self.total.fetch_add(pages, Ordering::Relaxed);
+    }
+
+    /// 记录一页完成，`bytes` 为实际下载的字节数（本地已存在时为 0）
+    pub fn page_done(&self, bytes: u64) {
+        self.completed.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// 记录一次重试，在 `track_page` 内调用时同时计入当前页
+    pub fn record_retry(&self) {
+        self.retries.fetch_add(1, Ordering::Relaxed);
+        let _ = PAGE_RETRIES.try_with(|retries| retries.set(retries.get() + 1));
+    }
+
+    /// 执行单张图片的下载，统计期间（含 HTTP 中间件内）发生的重试次数
+    pub async fn track_page<T>(&self, download: impl Future<Output = T>) -> T {
+        let (result, retries) = PAGE_RETRIES
+            .scope(Cell::new(0), async {
+                let result = download.await;
+                (result, PAGE_RETRIES.with(Cell::get))
+            })
+            .await;
+        if retries > 0 {
+            self.retried_pages.fetch_add(1, Ordering::Relaxed);
+            self.max_page_retries.fetch_max(retries, Ordering::Relaxed);
+        }
+        result
+    }
+
+    pub fn snapshot(&self) -> ProgressSnapshot {
+        ProgressSnapshot {
+            completed: self.completed.load(Ordering::Relaxed),
+            total: self.total.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+            retries: self.retries.load(Ordering::Relaxed),
+            retried_pages: self.retried_pages.load(Ordering::Relaxed),
+            max_page_retries: self.max_page_retries.load(Ordering::Relaxed),
+            elapsed: self.start.elapsed(),
+        }
+    }
+
+    /// 启动后台任务，每隔 `interval` 输出一次进度；`interval` 为 0 时只在结束时输出汇总
+    pub fn start_reporter(self: &Arc<Self>, interval: Duration) -> ProgressReporter {
+        let task = (!interval.is_zero()).then(|| {
+            let progress = self.clone();
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval_at(Instant::now() + interval, interval);
+                loop {
+                    ticker.tick().await;
+                    log::log!(progress.detail_level(), "{} 进度: {}", progress.label, progress.snapshot());
+                }
+            })
+        });
+        ProgressReporter {
+            progress: self.clone(),
+            task,
+        }
+    }
+}
+
+/// 进度日志任务句柄，释放时停止输出
+pub struct ProgressReporter {
+    progress: Arc<Progress>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl ProgressReporter {
+    /// 停止定时输出并记录最终汇总
+    pub fn finish(self) {
+        info!("{} 完成: {}", self.progress.label, self.progress.snapshot());
+    }
+}
+
+impl Drop for ProgressReporter {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eta_scales_with_remaining_pages() {
+        let snapshot = ProgressSnapshot {
+            completed: 25,
+            total: 100,
+            bytes: 50_000_000,
+            retries: 0,
+            retried_pages: 0,
+            max_page_retries: 0,
+            elapsed: Duration::from_secs(10),
+        };
+        assert_eq!(snapshot.eta(), Some(Duration::from_secs(30)));
+        assert!((snapshot.speed_mbps() - 5.0).abs() < 1e-9);
+
+        let empty = ProgressSnapshot { completed: 0, ..snapshot };
+        assert_eq!(empty.eta(), None);
+    }
+
+    #[test]
+    fn quiet_jobs_log_details_at_debug() {
+        let progress = Progress::new("test");
+        assert_eq!(progress.detail_level(), Level::Info);
+        progress.set_log_level("QUIET".parse().unwrap());
+        assert_eq!(progress.detail_level(), Level::Debug);
+        assert!("verbose".parse::<JobLogLevel>().is_err());
+    }
+
+    #[tokio::test]
+    async fn tracks_retries_per_page() {
+        let progress = Progress::new("test");
+        for retries in [0, 2, 1] {
+            progress
+                .track_page(async {
+                    for _ in 0..retries {
+                        progress.record_retry();
+                    }
+                })
+                .await;
+        }
+        // 页面之外的重试只计入总数
+        progress.record_retry();
+
+        let snapshot = progress.snapshot();
+        assert_eq!(snapshot.retries, 4);
+        assert_eq!(snapshot.retried_pages, 2);
+        assert_eq!(snapshot.max_page_retries, 2);
+    }
+}
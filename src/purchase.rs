@@ -0,0 +1,213 @@
+// 自动购买
+// 请求 auto_buy 为 true 时，漫画或章节需要 JM 币才能查看则在下载前调用购买接口，
+// 本次请求累计花费不超过 max_coins 与服务端上限 JM_AUTO_BUY_MAX_COINS 中的较小者（未配置上限时不允许自动购买）；
+// 每次购买记入下载历史，用量报表据此统计花费
+
+use std::sync::Mutex;
+
+use jm_downloader_rs::{ApiResult, AppError};
+
+use crate::global_client::GlobalJmClient;
+use crate::jm_api::JmApi;
+use crate::history::{self, HistoryEntry};
+use crate::models::{GetChapterRespData, GetComicRespData};
+
+/// 下载历史中购买记录的 kind
+pub const PURCHASE_KIND: &str = "purchase";
+
+/// 一次下载请求的自动购买预算
+pub struct AutoBuy {
+    /// 最多花费的 JM 币，未开启自动购买时为 None
+    budget: Option<u64>,
+    /// 已花费（含正在购买）的 JM 币
+    spent: Mutex<u64>,
+}
+
+impl AutoBuy {
+    /// `auto_buy` 为 true 时必须同时设置 `max_coins`，避免意外花光余额；预算不超过服务端上限 `server_cap`
+    /// （JM_AUTO_BUY_MAX_COINS），未配置上限时拒绝自动购买，防止任何能访问下载接口的调用方花费账号的 JM 币
+    pub fn new(auto_buy: bool, max_coins: Option<u64>, server_cap: Option<u64>) -> ApiResult<Self> {
+        let budget = match (auto_buy, max_coins) {
+            (false, _) => None,
+            (true, Some(max_coins)) if max_coins > 0 => {
+                let Some(cap) = server_cap else {
+                    return Err(AppError::Forbidden("服务端未配置 JM_AUTO_BUY_MAX_COINS，自动购买不可用".to_string()));
+                };
+                Some(max_coins.min(cap))
+            }
+            (true, _) => {
+                return Err(AppError::BadRequest("开启 auto_buy 时必须设置大于0的 max_coins".to_string()));
+            }
+        };
+        Ok(Self { budget, spent: Mutex::new(0) })
+    }
+
+    /// 开启自动购买时返回本次请求已花费的 JM 币
+    pub fn spent(&self) -> Option<u64> {
+        self.budget.map(|_| *self.spent.lock().unwrap())
+    }
+
+    /// 漫画需要购买时购买并重新获取漫画信息；未开启或无需购买时原样返回
+    pub async fn comic<C: JmApi, W: JmApi>(
+        &self,
+        client: &GlobalJmClient<C, W>,
+        comic_id: i64,
+        comic: GetComicRespData,
+    ) -> ApiResult<GetComicRespData> {
+        if !comic.requires_purchase() {
+            return Ok(comic);
+        }
+        let Some(price) = self.price(comic_id, comic.price)? else {
+            return Ok(comic);
+        };
+        self.buy(client, comic_id, comic_id, Some(&comic.name), price).await?;
+        client.get_comic(comic_id).await
+    }
+
+    /// 章节需要购买时购买并重新获取章节信息；未开启或无需购买时原样返回
+    pub async fn chapter<C: JmApi, W: JmApi>(
+        &self,
+        client: &GlobalJmClient<C, W>,
+        comic_id: i64,
+        chapter_id: i64,
+        chapter: GetChapterRespData,
+    ) -> ApiResult<GetChapterRespData> {
+        if !chapter.requires_purchase() {
+            return Ok(chapter);
+        }
+        let Some(price) = self.price(chapter_id, chapter.price)? else {
+            return Ok(chapter);
+        };
+        self.buy(client, comic_id, chapter_id, None, price).await?;
+        client.get_chapter(chapter_id).await
+    }
+
+    /// 开启自动购买时 `id` 的价格；价格未知时不购买，避免绕过预算检查
+    fn price(&self, id: i64, price: Option<i64>) -> ApiResult<Option<u64>> {
+        if self.budget.is_none() {
+            return Ok(None);
+        }
+        match price {
+            Some(price) if price >= 0 => Ok(Some(price as u64)),
+            _ => Err(AppError::PaymentRequired(format!("{} 需要购买但价格未知，不自动购买", id))),
+        }
+    }
+
+    /// 在预算内购买 `id`（漫画或章节），购买失败时退回预留的花费
+    async fn buy<C: JmApi, W: JmApi>(
+        &self,
+        client: &GlobalJmClient<C, W>,
+        comic_id: i64,
+        id: i64,
+        title: Option<&str>,
+        price: u64,
+    ) -> ApiResult<()> {
+        let budget = self.budget.unwrap_or_default();
+        {
+            let mut spent = self.spent.lock().unwrap();
+            if *spent + price > budget {
+                return Err(AppError::PaymentRequired(format!(
+                    "{} 需要 {} JM 币购买，超出本次预算（max_coins {}，已花费 {}）",
+                    id, price, budget, *spent
+                )));
+            }
+            *spent += price;
+        }
+
+        info!("自动购买 {}（漫画 {}），花费 {} JM 币", id, comic_id, price);
+        if let Err(e) = client.buy(id).await {
+            *self.spent.lock().unwrap() -= price;
+            error!("自动购买 {} 失败: {}", id, e);
+            return Err(e);
+        }
+        history::record(&HistoryEntry {
+            time: chrono::Utc::now().timestamp(),
+            kind: PURCHASE_KIND.to_string(),
+            comic_id,
+            title: title.map(str::to_string),
+            pages: 0,
+            bytes: 0,
+            success: true,
+            seconds: 0,
+            coins: price,
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use super::*;
+    use crate::mock_client::MockJmClient;
+
+    async fn global(client: MockJmClient) -> GlobalJmClient<MockJmClient, MockJmClient> {
+        GlobalJmClient::with_clients(client, None, Some(("user", "pass"))).await.unwrap()
+    }
+
+    #[test]
+    fn requires_budget_when_enabled() {
+        assert!(AutoBuy::new(true, None, Some(100)).is_err());
+        assert!(AutoBuy::new(true, Some(0), Some(100)).is_err());
+        assert_eq!(AutoBuy::new(false, None, None).unwrap().spent(), None);
+        assert_eq!(AutoBuy::new(false, None, None).unwrap().price(1, Some(20)).unwrap(), None);
+
+        let auto_buy = AutoBuy::new(true, Some(50), Some(100)).unwrap();
+        assert_eq!(auto_buy.spent(), Some(0));
+        assert_eq!(auto_buy.price(1, Some(20)).unwrap(), Some(20));
+    }
+
+    #[test]
+    fn server_cap_limits_the_budget() {
+        // 未配置服务端上限时不允许自动购买
+        assert!(matches!(AutoBuy::new(true, Some(50), None), Err(AppError::Forbidden(_))));
+        assert_eq!(AutoBuy::new(true, Some(500), Some(30)).unwrap().budget, Some(30));
+        assert_eq!(AutoBuy::new(true, Some(20), Some(30)).unwrap().budget, Some(20));
+    }
+
+    #[tokio::test]
+    async fn stops_when_budget_is_exhausted() {
+        let client = global(
+            MockJmClient::new()
+                .with_comic(1, "付费漫画", &[(11, "第1话"), (12, "第2话")])
+                .with_chapter(11, &["00001.webp"], 0)
+                .with_chapter(12, &["00001.webp"], 0)
+                .with_price(11, 30)
+                .with_price(12, 30),
+        )
+        .await;
+        let auto_buy = AutoBuy::new(true, Some(50), Some(100)).unwrap();
+
+        let first = client.get_chapter(11).await.unwrap();
+        let first = auto_buy.chapter(&client, 1, 11, first).await.unwrap();
+        assert!(!first.requires_purchase());
+        assert_eq!(auto_buy.spent(), Some(30));
+
+        let second = client.get_chapter(12).await.unwrap();
+        let result = auto_buy.chapter(&client, 1, 12, second).await;
+        assert!(matches!(result, Err(AppError::PaymentRequired(_))));
+        assert_eq!(auto_buy.spent(), Some(30));
+    }
+
+    #[tokio::test]
+    async fn refunds_when_buy_fails() {
+        let mock = MockJmClient::new().with_comic(2, "付费漫画", &[]).with_price(2, 20).fail_buy();
+        let stats = mock.stats.clone();
+        let client = global(mock).await;
+        let auto_buy = AutoBuy::new(true, Some(50), Some(100)).unwrap();
+
+        let comic = client.get_comic(2).await.unwrap();
+        assert!(auto_buy.comic(&client, 2, comic).await.is_err());
+        assert_eq!(stats.purchases.load(Ordering::SeqCst), 1);
+        assert_eq!(auto_buy.spent(), Some(0));
+    }
+
+    #[tokio::test]
+    async fn refuses_unknown_prices() {
+        let auto_buy = AutoBuy::new(true, Some(50), Some(100)).unwrap();
+        assert!(matches!(auto_buy.price(3, None), Err(AppError::PaymentRequired(_))));
+        assert!(matches!(auto_buy.price(3, Some(-1)), Err(AppError::PaymentRequired(_))));
+        assert_eq!(auto_buy.spent(), Some(0));
+    }
+}
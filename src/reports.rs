@@ -0,0 +1,231 @@
+// 用量报表
+// 按自然日/周/月（北京时间）汇总下载历史中的任务数、页数、字节数、失败数与自动购买花费，
+// 供运营者对照账号风控阈值；支持 JSON 与 CSV（逐漫画明细）输出
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Datelike, Duration, TimeZone, Utc};
+use chrono_tz::Asia::Shanghai;
+use chrono_tz::Tz;
+use jm_downloader_rs::{ApiResult, AppError, R};
+use rocket::http::ContentType;
+use rocket::request::Request;
+use rocket::response::{Responder, Result as RocketResult};
+use rocket::State;
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::okapi::openapi3::Responses;
+use rocket_okapi::openapi;
+use rocket_okapi::response::OpenApiResponderInner;
+use rocket_okapi::util::add_schema_response;
+
+use crate::admin::AdminKey;
+use crate::config::LiveConfig;
+use crate::history::{self, HistoryEntry};
+use crate::purchase::PURCHASE_KIND;
+use crate::models::{ComicUsage, UsageReport};
+
+/// JSON 报表中列出的漫画数
+const TOP_COMICS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Period {
+    Day,
+    Week,
+    Month,
+}
+
+impl Period {
+    fn parse(value: &str) -> ApiResult<Self> {
+        match value {
+            "day" => Ok(Period::Day),
+            "week" => Ok(Period::Week),
+            "month" => Ok(Period::Month),
+            _ => Err(AppError::BadRequest("period 只能为 day、week 或 month".to_string())),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Period::Day => "day",
+            Period::Week => "week",
+            Period::Month => "month",
+        }
+    }
+
+    /// `now` 所在周期的开始时间
+    fn start(self, now: DateTime<Tz>) -> DateTime<Tz> {
+        let today = now.date_naive();
+        let date = match self {
+            Period::Day => today,
+            Period::Week => today - Duration::days(i64::from(today.weekday().num_days_from_monday())),
+            Period::Month => today.with_day(1).unwrap_or(today),
+        };
+        Shanghai
+            .from_local_datetime(&date.and_time(chrono::NaiveTime::MIN))
+            .earliest()
+            .unwrap_or(now)
+    }
+}
+
+/// JSON 报表或 CSV 明细
+pub enum UsageResponse {
+    Json(R<UsageReport>),
+    Csv(String),
+}
+
+impl<'r> Responder<'r, 'static> for UsageResponse {
+    fn respond_to(self, req: &'r Request<'_>) -> RocketResult<'static> {
+        match self {
+            UsageResponse::Json(report) => report.respond_to(req),
+            UsageResponse::Csv(csv) => (ContentType::CSV, csv).respond_to(req),
+        }
+    }
+}
+
+impl OpenApiResponderInner for UsageResponse {
+    fn responses(gen: &mut OpenApiGenerator) -> rocket_okapi::Result<Responses> {
+        let mut responses = Responses::default();
+        add_schema_response(&mut responses, 200, "application/json", gen.json_schema::<R<UsageReport>>())?;
+        add_schema_response(&mut responses, 200, "text/csv", gen.json_schema::<String>())?;
+        Ok(responses)
+    }
+}
+
+/// # 用量报表
+/// 汇总今日（`period=day`，默认）、本周（`week`）或本月（`month`，北京时间）的下载任务数、页数、字节数、失败数与下载最多的漫画；
+/// `format=csv` 时返回逐漫画明细。需要 `X-Admin-Key`。
+#[openapi]
+#[get("/api/reports/usage?<period>&<format>")]
+pub async fn usage(
+    config: &State<LiveConfig>,
+    admin: AdminKey,
+    period: Option<String>,
+    format: Option<String>,
+) -> ApiResult<UsageResponse> {
+    admin.verify(&config.load())?;
+    let period = Period::parse(period.as_deref().unwrap_or("day"))?;
+    let csv = match format.as_deref().unwrap_or("json") {
+        "json" => false,
+        "csv" => true,
+        _ => return Err(AppError::BadRequest("format 只能为 json 或 csv".to_string())),
+    };
+
+    let now = Utc::now().with_timezone(&Shanghai);
+    let from = period.start(now);
+    let entries = tokio::task::spawn_blocking(move || history::load_since(from.timestamp()))
+        .await
+        .map_err(|e| AppError::Internal(format!("读取下载历史任务执行失败: {}", e)))??;
+    let mut report = summarize(&entries);
+    report.period = period.name().to_string();
+    report.from = from.to_rfc3339();
+    report.to = now.to_rfc3339();
+
+    if csv {
+        return Ok(UsageResponse::Csv(to_csv(&report.top_comics)));
+    }
+    report.top_comics.truncate(TOP_COMICS);
+    Ok(UsageResponse::Json(R::success(report)))
+}
+
+/// 汇总记录，`top_comics` 包含全部漫画并按页数降序排列；购买记录只计入花费，不算作任务
+fn summarize(entries: &[HistoryEntry]) -> UsageReport {
+    let mut comics: HashMap<i64, ComicUsage> = HashMap::new();
+    let jobs = || entries.iter().filter(|entry| entry.kind != PURCHASE_KIND);
+    for entry in entries {
+        let comic = comics.entry(entry.comic_id).or_insert_with(|| ComicUsage {
+            comic_id: entry.comic_id,
+            title: None,
+            downloads: 0,
+            failures: 0,
+            pages: 0,
+            bytes: 0,
+            coins: 0,
+        });
+        if entry.title.is_some() {
+            comic.title.clone_from(&entry.title);
+        }
+        comic.coins += entry.coins;
+        if entry.kind == PURCHASE_KIND {
+            continue;
+        }
+        comic.downloads += 1;
+        comic.failures += usize::from(!entry.success);
+        comic.pages += entry.pages;
+        comic.bytes += entry.bytes;
+    }
+    let mut top_comics: Vec<ComicUsage> = comics.into_values().collect();
+    top_comics.sort_by(|a, b| b.pages.cmp(&a.pages).then(a.comic_id.cmp(&b.comic_id)));
+
+    UsageReport {
+        period: String::new(),
+        from: String::new(),
+        to: String::new(),
+        jobs: jobs().count(),
+        failures: jobs().filter(|entry| !entry.success).count(),
+        comics: top_comics.len(),
+        pages: entries.iter().map(|entry| entry.pages).sum(),
+        bytes: entries.iter().map(|entry| entry.bytes).sum(),
+        coins: entries.iter().map(|entry| entry.coins).sum(),
+        top_comics,
+    }
+}
+
+fn to_csv(comics: &[ComicUsage]) -> String {
+    let mut csv = String::from("comic_id,title,downloads,failures,pages,bytes,coins\n");
+    for comic in comics {
+        let title = comic.title.as_deref().unwrap_or_default().replace('"', "\"\"");
+        csv.push_str(&format!(
+            "{},\"{}\",{},{},{},{},{}\n",
+            comic.comic_id, title, comic.downloads, comic.failures, comic.pages, comic.bytes, comic.coins
+        ));
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(comic_id: i64, title: Option<&str>, pages: usize, success: bool) -> HistoryEntry {
+        HistoryEntry {
+            time: 0,
+            kind: "downloadComic".to_string(),
+            comic_id,
+            title: title.map(str::to_string),
+            pages,
+            bytes: pages as u64 * 100,
+            success,
+            seconds: 1,
+            coins: 0,
+        }
+    }
+
+    #[test]
+    fn summarizes_history_by_comic() {
+        let entries = [
+            entry(1, Some("甲"), 10, true),
+            entry(2, None, 0, false),
+            entry(2, Some("乙 \"特别篇\""), 30, true),
+            HistoryEntry { kind: PURCHASE_KIND.to_string(), coins: 20, ..entry(2, None, 0, true) },
+        ];
+        let report = summarize(&entries);
+        assert_eq!((report.jobs, report.failures, report.comics, report.pages, report.bytes), (3, 1, 2, 40, 4000));
+        assert_eq!(report.coins, 20);
+        assert_eq!(report.top_comics[0].comic_id, 2);
+        assert_eq!((report.top_comics[0].downloads, report.top_comics[0].failures), (2, 1));
+        assert_eq!(
+            to_csv(&report.top_comics),
+            "comic_id,title,downloads,failures,pages,bytes,coins\n2,\"乙 \"\"特别篇\"\"\",2,1,30,3000,20\n1,\"甲\",1,0,10,1000,0\n"
+        );
+    }
+
+    #[test]
+    fn periods_start_at_local_midnight() {
+        // 2026-10-14 为周三
+        let now = Shanghai.with_ymd_and_hms(2026, 10, 14, 15, 30, 0).unwrap();
+        assert_eq!(Period::Day.start(now).to_rfc3339(), "2026-10-14T00:00:00+08:00");
+        assert_eq!(Period::Week.start(now).to_rfc3339(), "2026-10-12T00:00:00+08:00");
+        assert_eq!(Period::Month.start(now).to_rfc3339(), "2026-10-01T00:00:00+08:00");
+        assert!(Period::parse("year").is_err());
+    }
+}
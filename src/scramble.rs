@@ -0,0 +1,349 @@
+// 图片打乱规则
+// JM 按章节 ID 区间决定图片的分块数：旧章节固定块数，新章节由 MD5(章节ID + 文件名) 计算；
+// 区间阈值可通过配置调整，个别章节可单独指定规则。拼接后抽样检查块边界的连续性，
+// 发现异常时输出警告，提示 JM 可能更改了打乱算法
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+use image::RgbImage;
+use serde::Deserialize;
+
+/// 默认规则表，与 JM 移动端一致
+pub const DEFAULT_SCRAMBLE_RULES: &str = "0:10,268850:hash10,421926:hash8";
+
+/// 默认从该章节 ID 起不再请求 scramble_id：JM 各章节的 scramble_id 实际都是 220980，
+/// 按哈希分块的章节（默认规则表中自 268850 起）一定大于它
+pub const DEFAULT_SCRAMBLE_ID_SKIP_FROM: i64 = 268850;
+
+/// 单个章节使用的分块规则
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrambleRule {
+    /// 固定块数，0 表示未打乱
+    Fixed(u32),
+    /// 块数为 `(MD5(章节ID + 文件名去扩展名) 末字符 % n) * 2 + 2`
+    Hashed(u32),
+}
+
+impl ScrambleRule {
+    fn block_num(self, chapter_id: i64, filename: &str) -> u32 {
+        match self {
+            ScrambleRule::Fixed(blocks) => blocks,
+            ScrambleRule::Hashed(modulus) => {
+                // 从文件名中移除文件扩展名
+                let filename_without_ext = filename
+                    .rsplit_once('.')
+                    .map(|(name, _)| name)
+                    .unwrap_or(filename);
+                let md5_hash = format!("{:x}", md5::compute(format!("{}{}", chapter_id, filename_without_ext)));
+                let last = md5_hash.chars().last().unwrap() as u32;
+                (last % modulus) * 2 + 2
+            }
+        }
+    }
+}
+
+impl FromStr for ScrambleRule {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        match value.strip_prefix("hash") {
+            Some(modulus) => match modulus.parse::<u32>() {
+                Ok(modulus) if modulus > 0 => Ok(ScrambleRule::Hashed(modulus)),
+                _ => Err(format!("规则 {} 无效，hash 后应为正整数", value)),
+            },
+            None => value
+                .parse::<u32>()
+                .map(ScrambleRule::Fixed)
+                .map_err(|_| format!("规则 {} 无效，应为块数或 hashN", value)),
+        }
+    }
+}
+
+impl fmt::Display for ScrambleRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScrambleRule::Fixed(blocks) => write!(f, "{}", blocks),
+            ScrambleRule::Hashed(modulus) => write!(f, "hash{}", modulus),
+        }
+    }
+}
+
+/// 按章节 ID 区间排列的规则表：`起始章节ID:规则`，逗号分隔
+///
+/// 章节 ID 小于漫画的 scramble_id 时图片未打乱，不受规则表影响
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct ScrambleRules(Vec<(i64, ScrambleRule)>);
+
+impl Default for ScrambleRules {
+    fn default() -> Self {
+        DEFAULT_SCRAMBLE_RULES.parse().expect("默认打乱规则合法")
+    }
+}
+
+impl FromStr for ScrambleRules {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut rules = parse_pairs(value)?;
+        if rules.is_empty() {
+            return Err("至少需要一条规则".to_string());
+        }
+        rules.sort_by_key(|(from, _)| *from);
+        if rules.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+            return Err("起始章节ID重复".to_string());
+        }
+        Ok(Self(rules))
+    }
+}
+
+impl TryFrom<String> for ScrambleRules {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl ScrambleRules {
+    /// 章节适用的规则：起始章节ID不大于该章节的最后一条
+    pub fn rule_for(&self, chapter_id: i64) -> ScrambleRule {
+        self.0
+            .iter()
+            .rev()
+            .find(|(from, _)| *from <= chapter_id)
+            .map(|(_, rule)| *rule)
+            .unwrap_or(ScrambleRule::Fixed(0))
+    }
+}
+
+/// 单独指定规则的章节：`章节ID:规则`，逗号分隔
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct ScrambleOverrides(HashMap<i64, ScrambleRule>);
+
+impl FromStr for ScrambleOverrides {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut overrides = HashMap::new();
+        for (chapter_id, rule) in parse_pairs(value)? {
+            if overrides.insert(chapter_id, rule).is_some() {
+                return Err(format!("章节 {} 重复", chapter_id));
+            }
+        }
+        Ok(Self(overrides))
+    }
+}
+
+impl TryFrom<String> for ScrambleOverrides {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+fn parse_pairs(value: &str) -> Result<Vec<(i64, ScrambleRule)>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            let (chapter_id, rule) = item
+                .split_once(':')
+                .ok_or_else(|| format!("{} 格式错误，应为 章节ID:规则", item))?;
+            let chapter_id = chapter_id
+                .trim()
+                .parse::<i64>()
+                .map_err(|_| format!("{} 中的章节ID无效", item))?;
+            Ok((chapter_id, rule.parse()?))
+        })
+        .collect()
+}
+
+/// 不请求 JM 就能确定分块结果时返回可代替 scramble_id 的值：章节单独指定了规则、适用的规则不打乱，
+/// 或章节 ID 不小于 `skip_from`（大于 0 时）；否则返回 None，需要获取真实的 scramble_id
+pub fn known_scramble_id(
+    rules: &ScrambleRules,
+    overrides: &ScrambleOverrides,
+    skip_from: i64,
+    chapter_id: i64,
+) -> Option<i64> {
+    if overrides.0.contains_key(&chapter_id) || rules.rule_for(chapter_id) == ScrambleRule::Fixed(0) {
+        // 分块数与 scramble_id 无关
+        Some(0)
+    } else if skip_from > 0 && chapter_id >= skip_from {
+        // 不大于章节 ID，按规则表分块
+        Some(skip_from)
+    } else {
+        None
+    }
+}
+
+/// 计算章节中每张图片的分块数，与 `filenames` 顺序一致
+pub fn block_nums(
+    rules: &ScrambleRules,
+    overrides: &ScrambleOverrides,
+    scramble_id: i64,
+    chapter_id: i64,
+    filenames: &[String],
+) -> Vec<u32> {
+    let rule = match overrides.0.get(&chapter_id) {
+        Some(rule) => {
+            debug!("章节 {} 使用单独配置的打乱规则 {}", chapter_id, rule);
+            *rule
+        }
+        None if chapter_id < scramble_id => ScrambleRule::Fixed(0),
+        None => rules.rule_for(chapter_id),
+    };
+    filenames
+        .iter()
+        .map(|filename| rule.block_num(chapter_id, filename))
+        .collect()
+}
+
+/// 每拼接多少张图片抽查一次
+const SEAM_CHECK_INTERVAL: u64 = 16;
+/// 块边界相邻行差异超过块内平均差异的倍数时视为拼接错误
+const SEAM_RATIO_THRESHOLD: f64 = 4.0;
+/// 连续多少次抽查异常时输出警告
+const SEAM_ALERT_STREAK: u32 = 3;
+
+static STITCHED: AtomicU64 = AtomicU64::new(0);
+static SUSPICIOUS_STREAK: AtomicU32 = AtomicU32::new(0);
+
+/// 抽样检查拼接结果：正确还原的图片在块边界处与块内一样连续，
+/// 连续多张图片边界明显断裂时说明规则表已与 JM 的算法不符
+pub fn check_stitched(img: &RgbImage, block_num: u32) {
+    if !STITCHED.fetch_add(1, Ordering::Relaxed).is_multiple_of(SEAM_CHECK_INTERVAL) {
+        return;
+    }
+    let Some(ratio) = seam_ratio(img, block_num) else {
+        return;
+    };
+    if ratio < SEAM_RATIO_THRESHOLD {
+        SUSPICIOUS_STREAK.store(0, Ordering::Relaxed);
+        return;
+    }
+    let streak = SUSPICIOUS_STREAK.fetch_add(1, Ordering::Relaxed) + 1;
+    debug!("拼接自检：块边界差异为块内的 {:.1} 倍（block_num: {}）", ratio, block_num);
+    if streak.is_multiple_of(SEAM_ALERT_STREAK) {
+        error!(
+            "!!! 拼接自检连续 {} 次发现块边界断裂（差异为块内的 {:.1} 倍），JM 可能更改了图片打乱算法，\
+             请检查下载结果并通过 JM_SCRAMBLE_RULES / JM_SCRAMBLE_OVERRIDES 调整规则 !!!",
+            streak, ratio
+        );
+    }
+}
+
+/// 块边界处相邻两行的平均像素差与块内相邻行平均差的比值；图片过小或过于平坦时返回 None
+pub fn seam_ratio(img: &RgbImage, block_num: u32) -> Option<f64> {
+    let (width, height) = img.dimensions();
+    if block_num < 2 || width == 0 || height < block_num * 8 {
+        return None;
+    }
+    // 与 stitch_img 一致：第一块包含余数行，其余块等高
+    let block_height = height / block_num;
+    let remainder = height % block_num;
+    let boundaries: Vec<u32> = (1..block_num).map(|i| remainder + block_height * i).collect();
+
+    let row_diff = |y: u32| -> f64 {
+        let sum: u64 = (0..width)
+            .map(|x| {
+                let a = img.get_pixel(x, y - 1).0;
+                let b = img.get_pixel(x, y).0;
+                (0..3).map(|c| u64::from(a[c].abs_diff(b[c]))).sum::<u64>()
+            })
+            .sum();
+        sum as f64 / f64::from(width)
+    };
+
+    let seam = boundaries.iter().map(|&y| row_diff(y)).sum::<f64>() / boundaries.len() as f64;
+    let interior_rows: Vec<u32> = (1..height).filter(|y| !boundaries.contains(y)).collect();
+    let interior = interior_rows.iter().map(|&y| row_diff(y)).sum::<f64>() / interior_rows.len() as f64;
+    // 纯色或近乎纯色的页面无法判断
+    if seam < 1.0 && interior < 1.0 {
+        return None;
+    }
+    Some(seam / interior.max(1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 调整前硬编码在 jm_client 中的算法
+    fn legacy_block_num(scramble_id: i64, chapter_id: i64, filename: &str) -> u32 {
+        if chapter_id < scramble_id {
+            0
+        } else if chapter_id < 268_850 {
+            10
+        } else {
+            let x = if chapter_id < 421_926 { 10 } else { 8 };
+            ScrambleRule::Hashed(x).block_num(chapter_id, filename)
+        }
+    }
+
+    #[test]
+    fn default_rules_match_legacy_thresholds() {
+        let rules = ScrambleRules::default();
+        let overrides = ScrambleOverrides::default();
+        let filenames: Vec<String> = (1..=20).map(|i| format!("{:05}.webp", i)).collect();
+        for chapter_id in [100, 220_980, 268_849, 268_850, 300_000, 421_925, 421_926, 1_000_000] {
+            let expected: Vec<u32> = filenames
+                .iter()
+                .map(|name| legacy_block_num(220_980, chapter_id, name))
+                .collect();
+            assert_eq!(block_nums(&rules, &overrides, 220_980, chapter_id, &filenames), expected);
+        }
+    }
+
+    #[test]
+    fn overrides_take_precedence() {
+        let rules: ScrambleRules = "0:10, 500000:hash4".parse().unwrap();
+        let overrides: ScrambleOverrides = "600000:0,100:12".parse().unwrap();
+        let names = vec!["00001.webp".to_string()];
+        assert_eq!(block_nums(&rules, &overrides, 1000, 600_000, &names), vec![0]);
+        assert_eq!(block_nums(&rules, &overrides, 1000, 100, &names), vec![12]);
+        assert_eq!(block_nums(&rules, &overrides, 1000, 999, &names), vec![0]);
+        assert_eq!(block_nums(&rules, &overrides, 1000, 1000, &names), vec![10]);
+        assert!(matches!(rules.rule_for(700_000), ScrambleRule::Hashed(4)));
+
+        assert!("".parse::<ScrambleRules>().is_err());
+        assert!("0:10,0:8".parse::<ScrambleRules>().is_err());
+        assert!("0:hash0".parse::<ScrambleRules>().is_err());
+        assert!("abc".parse::<ScrambleOverrides>().is_err());
+    }
+
+    #[test]
+    fn skips_scramble_id_when_rules_decide() {
+        let rules = ScrambleRules::default();
+        let overrides: ScrambleOverrides = "300000:5".parse().unwrap();
+        assert_eq!(known_scramble_id(&rules, &overrides, 268_850, 300_000), Some(0));
+        assert_eq!(known_scramble_id(&rules, &overrides, 268_850, 400_000), Some(268_850));
+        assert_eq!(known_scramble_id(&rules, &overrides, 268_850, 200_000), None);
+        assert_eq!(known_scramble_id(&rules, &overrides, 0, 400_000), None);
+        // 不打乱的规则
+        let rules: ScrambleRules = "0:0".parse().unwrap();
+        assert_eq!(known_scramble_id(&rules, &overrides, 0, 100), Some(0));
+    }
+
+    #[test]
+    fn seam_ratio_detects_broken_boundaries() {
+        // 纵向渐变的图片是连续的；打乱块顺序后块边界出现断裂
+        let smooth = RgbImage::from_fn(32, 200, |_, y| image::Rgb([y as u8, y as u8, 255 - y as u8]));
+        assert!(seam_ratio(&smooth, 10).unwrap() < SEAM_RATIO_THRESHOLD);
+
+        let shuffled = RgbImage::from_fn(32, 200, |_, y| {
+            let v = (9 - y / 20) * 20 + y % 20;
+            image::Rgb([v as u8, v as u8, 255 - v as u8])
+        });
+        assert!(seam_ratio(&shuffled, 10).unwrap() > SEAM_RATIO_THRESHOLD);
+        assert!(seam_ratio(&RgbImage::new(32, 200), 10).is_none());
+    }
+}
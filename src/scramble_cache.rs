@@ -0,0 +1,103 @@
+// scramble_id 缓存
+// 同一章节的 scramble_id 不会变化，获取成功后缓存在内存中；设置 JM_SCRAMBLE_CACHE_FILE 时同时追加到该 JSONL 文件，
+// 重启后从文件恢复，重复下载同一章节不再请求 JM。格式错误的行读取时跳过
+
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+/// 文件中的一行
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    chapter_id: i64,
+    scramble_id: i64,
+}
+
+/// 按章节 ID 缓存的 scramble_id
+#[derive(Default)]
+pub struct ScrambleIdCache {
+    entries: Mutex<HashMap<i64, i64>>,
+    /// 持久化文件，未设置时只缓存在内存中；写入时持 `entries` 的锁，保证记录按行完整追加
+    file: Option<PathBuf>,
+}
+
+impl ScrambleIdCache {
+    /// 打开缓存，`file` 存在时读入其中的记录；读取失败只记录日志
+    pub fn open(file: Option<PathBuf>) -> Self {
+        let mut entries = HashMap::new();
+        if let Some(path) = &file {
+            match std::fs::File::open(path) {
+                Ok(reader) => {
+                    for line in BufReader::new(reader).lines().map_while(std::io::Result::ok) {
+                        if let Ok(entry) = serde_json::from_str::<Entry>(&line) {
+                            entries.insert(entry.chapter_id, entry.scramble_id);
+                        }
+                    }
+                    info!("已从 {} 读取 {} 个章节的 scramble_id", path.display(), entries.len());
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => warn!("读取 scramble_id 缓存 {} 失败: {}", path.display(), e),
+            }
+        }
+        Self {
+            entries: Mutex::new(entries),
+            file,
+        }
+    }
+
+    pub fn get(&self, chapter_id: i64) -> Option<i64> {
+        self.entries.lock().unwrap().get(&chapter_id).copied()
+    }
+
+    /// 记录一个章节的 scramble_id，新值才写入文件
+    pub fn insert(&self, chapter_id: i64, scramble_id: i64) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.insert(chapter_id, scramble_id) == Some(scramble_id) {
+            return;
+        }
+        let Some(path) = &self.file else {
+            return;
+        };
+        let result = serde_json::to_string(&Entry { chapter_id, scramble_id })
+            .map_err(std::io::Error::other)
+            .and_then(|mut line| {
+                line.push('\n');
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)?
+                    .write_all(line.as_bytes())
+            });
+        if let Err(e) = result {
+            warn!("写入 scramble_id 缓存 {} 失败: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn persists_and_reloads_entries() {
+        let path = std::env::temp_dir().join(format!("jm-scramble-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let cache = ScrambleIdCache::open(Some(path.clone()));
+        assert_eq!(cache.get(300_000), None);
+        cache.insert(300_000, 220_980);
+        cache.insert(300_000, 220_980);
+        cache.insert(300_001, 220_980);
+        std::fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(b"broken\n").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 3);
+
+        let reloaded = ScrambleIdCache::open(Some(path.clone()));
+        assert_eq!(reloaded.get(300_000), Some(220_980));
+        assert_eq!(reloaded.get(300_001), Some(220_980));
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,107 @@
+// 服务模式
+// 升级前可先切换为只读模式（查询与搜索照常，新的下载请求返回 10014，进行中的任务继续完成），
+// 待任务列表清空后再切换为维护模式（除健康检查与管理接口外一律返回 10015）。
+// 启动时的模式由 JM_SERVICE_MODE 指定，运行时通过 POST /api/admin/mode 切换，重新加载配置不改变当前模式
+
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use jm_downloader_rs::{ApiResult, AppError};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// 服务模式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceMode {
+    /// 正常服务
+    #[default]
+    Normal,
+    /// 只读：拒绝新的下载，其余接口正常
+    ReadOnly,
+    /// 维护：除健康检查与管理接口外全部拒绝
+    Maintenance,
+}
+
+impl ServiceMode {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::ReadOnly,
+            2 => Self::Maintenance,
+            _ => Self::Normal,
+        }
+    }
+}
+
+impl FromStr for ServiceMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "normal" => Ok(Self::Normal),
+            "read_only" | "read-only" | "readonly" => Ok(Self::ReadOnly),
+            "maintenance" => Ok(Self::Maintenance),
+            _ => Err(format!("{}，应为 normal、read_only 或 maintenance", value)),
+        }
+    }
+}
+
+static MODE: AtomicU8 = AtomicU8::new(ServiceMode::Normal as u8);
+
+/// 当前服务模式
+pub fn current() -> ServiceMode {
+    ServiceMode::from_u8(MODE.load(Ordering::Relaxed))
+}
+
+/// 切换服务模式，返回切换前的模式；启动与管理接口调用
+pub fn set(mode: ServiceMode) -> ServiceMode {
+    ServiceMode::from_u8(MODE.swap(mode as u8, Ordering::Relaxed))
+}
+
+/// 维护模式下返回 [`AppError::Maintenance`]，非管理接口在处理请求前调用
+pub fn ensure_available() -> ApiResult<()> {
+    check_available(current())
+}
+
+/// 只读或维护模式下拒绝新的下载
+pub fn ensure_downloads_allowed() -> ApiResult<()> {
+    check_downloads_allowed(current())
+}
+
+fn check_available(mode: ServiceMode) -> ApiResult<()> {
+    match mode {
+        ServiceMode::Maintenance => Err(AppError::Maintenance("服务维护中，请稍后再试".to_string())),
+        _ => Ok(()),
+    }
+}
+
+fn check_downloads_allowed(mode: ServiceMode) -> ApiResult<()> {
+    check_available(mode)?;
+    match mode {
+        ServiceMode::ReadOnly => Err(AppError::ReadOnly("服务处于只读模式，暂不接受新的下载".to_string())),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_only_rejects_downloads_and_maintenance_rejects_everything() {
+        assert!(check_available(ServiceMode::Normal).is_ok());
+        assert!(check_downloads_allowed(ServiceMode::Normal).is_ok());
+
+        assert!(check_available(ServiceMode::ReadOnly).is_ok());
+        assert_eq!(check_downloads_allowed(ServiceMode::ReadOnly).unwrap_err().code(), "10014");
+
+        assert_eq!(check_available(ServiceMode::Maintenance).unwrap_err().code(), "10015");
+        assert_eq!(check_downloads_allowed(ServiceMode::Maintenance).unwrap_err().code(), "10015");
+
+        assert_eq!("read-only".parse::<ServiceMode>(), Ok(ServiceMode::ReadOnly));
+        assert!("drain".parse::<ServiceMode>().is_err());
+        for mode in [ServiceMode::Normal, ServiceMode::ReadOnly, ServiceMode::Maintenance] {
+            assert_eq!(ServiceMode::from_u8(mode as u8), mode);
+        }
+    }
+}
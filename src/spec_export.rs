@@ -0,0 +1,63 @@
+// 导出 OpenAPI 文档（--export-openapi）
+// 在 `rocket()` 开头检测到该参数时把生成的文档写入文件并退出，不加载配置、不登录 JM，
+// 供下游在 CI 中直接生成客户端，无需启动服务再抓取 /openapi.json
+
+use std::path::{Path, PathBuf};
+
+use jm_downloader_rs::AppError;
+use rocket_okapi::okapi::openapi3::OpenApi;
+
+type Result<T> = std::result::Result<T, AppError>;
+
+const FLAG: &str = "--export-openapi";
+/// 未指定路径时的输出文件
+const DEFAULT_PATH: &str = "openapi.json";
+
+/// 命令行参数为 `--export-openapi <path>` 或 `--export-openapi=<path>` 时返回输出路径，未指定路径时为 `openapi.json`
+pub fn requested() -> Option<PathBuf> {
+    parse(std::env::args().skip(1))
+}
+
+fn parse(args: impl IntoIterator<Item = String>) -> Option<PathBuf> {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == FLAG {
+            let path = args.next().filter(|path| !path.starts_with("--"));
+            return Some(PathBuf::from(path.unwrap_or_else(|| DEFAULT_PATH.to_string())));
+        }
+        if let Some(path) = arg.strip_prefix(FLAG).and_then(|rest| rest.strip_prefix('=')) {
+            return Some(PathBuf::from(path));
+        }
+    }
+    None
+}
+
+/// 把文档以格式化的 JSON 写入 `path`，自动创建上级目录
+pub fn write(path: &Path, spec: &OpenApi) -> Result<()> {
+    let json = serde_json::to_string_pretty(spec)
+        .map_err(|e| AppError::Internal(format!("序列化 OpenAPI 文档失败: {}", e)))?;
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| AppError::Internal(format!("创建目录 {} 失败: {}", dir.display(), e)))?;
+    }
+    std::fs::write(path, json + "\n")
+        .map_err(|e| AppError::Internal(format!("写入 {} 失败: {}", path.display(), e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_export_flag() {
+        assert_eq!(parse(args(&["--export-openapi", "out/spec.json"])), Some(PathBuf::from("out/spec.json")));
+        assert_eq!(parse(args(&["--export-openapi=spec.json"])), Some(PathBuf::from("spec.json")));
+        assert_eq!(parse(args(&["--export-openapi"])), Some(PathBuf::from(DEFAULT_PATH)));
+        assert_eq!(parse(args(&["--export-openapi", "--doctor"])), Some(PathBuf::from(DEFAULT_PATH)));
+        assert_eq!(parse(args(&["--doctor"])), None);
+    }
+}
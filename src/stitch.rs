@@ -0,0 +1,72 @@
+// 图片块拼接
+// JM 把图片按行切成 block_num 块后倒序排列；每块是连续的若干行，因此整块可以直接按字节切片复制，
+// 不必逐像素读写。放在库 crate 中，供二进制、基准测试与集成测试共用
+
+use image::RgbImage;
+
+/// 将图片块拼接回原图，还原 JMComic 应用的打乱效果
+///
+/// 第一块包含余数行，其余块等高；`block_num` 为 0 时原样复制
+pub fn stitch_img(src_img: &RgbImage, block_num: u32) -> RgbImage {
+    if block_num == 0 {
+        return src_img.clone();
+    }
+    let (width, height) = src_img.dimensions();
+    let row_bytes = width as usize * 3;
+    let src = src_img.as_raw();
+    let mut dst = vec![0u8; src.len()];
+    let block_height = height / block_num;
+    let remainder_height = height % block_num;
+
+    for i in 0..block_num {
+        let src_y_start = height - block_height * (i + 1) - remainder_height;
+        let (dst_y_start, rows) = if i == 0 {
+            (0, block_height + remainder_height)
+        } else {
+            (block_height * i + remainder_height, block_height)
+        };
+        let len = rows as usize * row_bytes;
+        let src_offset = src_y_start as usize * row_bytes;
+        let dst_offset = dst_y_start as usize * row_bytes;
+        dst[dst_offset..dst_offset + len].copy_from_slice(&src[src_offset..src_offset + len]);
+    }
+
+    RgbImage::from_raw(width, height, dst).expect("拼接结果与原图尺寸一致")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 原先的逐像素实现，作为对照
+    fn stitch_per_pixel(src_img: &RgbImage, block_num: u32) -> RgbImage {
+        let (width, height) = src_img.dimensions();
+        let mut stitched_img = RgbImage::new(width, height);
+        let remainder_height = height % block_num;
+        for i in 0..block_num {
+            let mut block_height = height / block_num;
+            let src_y_start = height - (block_height * (i + 1)) - remainder_height;
+            let mut dst_y_start = block_height * i;
+            if i == 0 {
+                block_height += remainder_height;
+            } else {
+                dst_y_start += remainder_height;
+            }
+            for y in 0..block_height {
+                for x in 0..width {
+                    stitched_img.put_pixel(x, dst_y_start + y, *src_img.get_pixel(x, src_y_start + y));
+                }
+            }
+        }
+        stitched_img
+    }
+
+    #[test]
+    fn row_copy_matches_per_pixel() {
+        let src = RgbImage::from_fn(7, 53, |x, y| image::Rgb([x as u8, y as u8, (x * y) as u8]));
+        for block_num in [1, 2, 5, 10, 20, 53, 60] {
+            assert_eq!(stitch_img(&src, block_num), stitch_per_pixel(&src, block_num), "block_num={}", block_num);
+        }
+        assert_eq!(stitch_img(&src, 0), src);
+    }
+}
@@ -0,0 +1,27 @@
+// 本地存储：文件保留在下载目录，由 /download 文件服务凭签名链接提供
+
+use jm_downloader_rs::AppError;
+
+use super::{PublishFile, StorageBackend};
+use crate::url_signer::UrlSigner;
+
+#[derive(Clone)]
+pub struct LocalStorage {
+    signer: UrlSigner,
+}
+
+impl LocalStorage {
+    pub fn new(signer: UrlSigner) -> Self {
+        Self { signer }
+    }
+
+    pub fn sign(&self, file: &PublishFile) -> String {
+        self.signer.sign_named(&file.relative_path, &file.name)
+    }
+}
+
+impl StorageBackend for LocalStorage {
+    async fn publish(&self, file: &PublishFile) -> Result<String, AppError> {
+        Ok(self.sign(file))
+    }
+}
@@ -0,0 +1,109 @@
+// 存储后端模块
+// 下载完成的文件先写入本地下载目录，再由存储后端发布为客户端可访问的链接：
+// local 返回本服务 /download 的签名链接，s3 上传到对象存储后返回预签名链接，webdav 上传到网盘后返回文件地址
+
+mod local;
+mod s3;
+mod webdav;
+
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use jm_downloader_rs::AppError;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::config::{Config, StorageKind};
+use crate::image_processor::download_root;
+use crate::url_signer::UrlSigner;
+
+pub use local::LocalStorage;
+pub use s3::S3Storage;
+pub use webdav::WebDavStorage;
+
+type Result<T> = std::result::Result<T, AppError>;
+
+/// 同时上传的文件数上限
+const PUBLISH_CONCURRENCY: usize = 8;
+
+/// 待发布的下载目录中的文件
+#[derive(Debug, Clone)]
+pub struct PublishFile {
+    /// `download/{comic_id}/{chapter_id}/{file}` 形式的相对路径
+    pub relative_path: String,
+    /// 浏览器保存时使用的文件名，如 `标题 - 第1话 - 0001.png`
+    pub name: String,
+    /// 按标题组织目录的后端使用的目录层级，如 `["标题", "第1话"]`
+    pub folder: Vec<String>,
+    /// 在 `folder` 中的文件名，如 `0001.png`
+    pub file_name: String,
+}
+
+impl PublishFile {
+    /// 文件在本地下载目录中的路径
+    pub fn local_path(&self) -> PathBuf {
+        download_root().join(self.relative_path.strip_prefix("download/").unwrap_or(&self.relative_path))
+    }
+}
+
+/// 存储后端
+pub trait StorageBackend {
+    /// 发布下载目录中的文件，返回客户端可访问的链接
+    fn publish(&self, file: &PublishFile) -> impl Future<Output = Result<String>> + Send;
+}
+
+/// 启动时按 `JM_STORAGE` 选定的存储后端，克隆后共享同一份连接
+#[derive(Clone)]
+pub enum Storage {
+    Local(LocalStorage),
+    S3(S3Storage),
+    WebDav(WebDavStorage),
+}
+
+impl Storage {
+    pub fn from_config(config: &Config, signer: &UrlSigner) -> Result<Self> {
+        Ok(match config.storage {
+            StorageKind::Local => Storage::Local(LocalStorage::new(signer.clone())),
+            StorageKind::S3 => Storage::S3(S3Storage::from_config(config, signer.clone())?),
+            StorageKind::WebDav => Storage::WebDav(WebDavStorage::from_config(config)?),
+        })
+    }
+
+    /// 并发发布多个文件，返回的链接与输入顺序一致
+    pub async fn publish_all(&self, files: Vec<PublishFile>) -> Result<Vec<String>> {
+        if let Storage::Local(local) = self {
+            // 本地签名无需 IO，直接按顺序生成
+            return Ok(files.iter().map(|file| local.sign(file)).collect());
+        }
+
+        let semaphore = Arc::new(Semaphore::new(PUBLISH_CONCURRENCY));
+        let mut tasks = JoinSet::new();
+        for (index, file) in files.into_iter().enumerate() {
+            let storage = self.clone();
+            let semaphore = semaphore.clone();
+            tasks.spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                storage.publish(&file).await.map(|url| (index, url))
+            });
+        }
+
+        let mut urls = vec![String::new(); tasks.len()];
+        while let Some(result) = tasks.join_next().await {
+            let (index, url) =
+                result.map_err(|e| AppError::Internal(format!("发布文件任务崩溃: {}", e)))??;
+            urls[index] = url;
+        }
+        Ok(urls)
+    }
+}
+
+impl StorageBackend for Storage {
+    async fn publish(&self, file: &PublishFile) -> Result<String> {
+        match self {
+            Storage::Local(local) => local.publish(file).await,
+            Storage::S3(s3) => s3.publish(file).await,
+            Storage::WebDav(webdav) => webdav.publish(file).await,
+        }
+    }
+}
@@ -0,0 +1,238 @@
+// WebDAV 存储（Nextcloud、Alist 等网盘）
+// 文件按 `{标题}/{章节}` 目录结构上传到 JM_WEBDAV_URL 下，返回网盘中的文件地址（访问需网盘账号）。
+// 远端已有同名文件时，只有内容摘要与本服务上次上传的一致、且远端 ETag 仍是上传后的值才跳过上传；
+// 上传记录只保存在内存中，重启后每个文件会重新上传一次
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use jm_downloader_rs::AppError;
+use reqwest::{Body, Method, StatusCode};
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware, RequestBuilder};
+use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
+
+use super::{PublishFile, StorageBackend};
+use crate::checksums;
+use crate::config::Config;
+use crate::file_server::sanitize_filename;
+use crate::url_signer::pct_encode;
+
+type Result<T> = std::result::Result<T, AppError>;
+
+#[derive(Clone)]
+pub struct WebDavStorage(Arc<WebDavInner>);
+
+struct WebDavInner {
+    /// 网络错误与 5xx/429 自动重试（JM_MAX_RETRIES 次，指数退避）
+    http: ClientWithMiddleware,
+    /// 不经重试中间件的客户端，用于流式上传：流式请求体无法复制重放
+    upload: reqwest::Client,
+    base_url: String,
+    username: Option<String>,
+    password: Option<String>,
+    /// 已确认存在的目录，避免每个文件都重复 MKCOL
+    created_dirs: Mutex<HashSet<String>>,
+    /// 本服务上传过的文件，键为文件地址
+    uploaded: Mutex<HashMap<String, Uploaded>>,
+}
+
+/// 一次上传的记录
+#[derive(Debug, Clone, PartialEq)]
+struct Uploaded {
+    /// 上传内容的 SHA-256
+    sha256: String,
+    /// 上传后远端返回的 ETag
+    etag: Option<String>,
+}
+
+impl WebDavStorage {
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let base_url = config
+            .webdav_url
+            .as_deref()
+            .ok_or_else(|| AppError::Internal("JM_STORAGE 为 webdav 时必须设置 JM_WEBDAV_URL".to_string()))?
+            .trim_end_matches('/')
+            .to_string();
+        let client = reqwest::Client::builder()
+            .build()
+            .map_err(|e| AppError::Internal(format!("创建 WebDAV HTTP 客户端失败: {}", e)))?;
+        let retry_policy = ExponentialBackoff::builder().build_with_max_retries(config.max_retries);
+        let http = ClientBuilder::new(client.clone())
+            .with(RetryTransientMiddleware::new_with_policy(retry_policy))
+            .build();
+        info!("使用 WebDAV 存储: {}", base_url);
+        Ok(Self(Arc::new(WebDavInner {
+            http,
+            upload: client,
+            base_url,
+            username: config.webdav_username.clone(),
+            password: config.webdav_password.clone(),
+            created_dirs: Mutex::new(HashSet::new()),
+            uploaded: Mutex::new(HashMap::new()),
+        })))
+    }
+}
+
+impl StorageBackend for WebDavStorage {
+    async fn publish(&self, file: &PublishFile) -> Result<String> {
+        let inner = &self.0;
+        let local_path = file.local_path();
+        let sha256 = {
+            let local_path = local_path.clone();
+            tokio::task::spawn_blocking(move || checksums::hash_file(&local_path))
+                .await
+                .map_err(|e| AppError::Internal(format!("计算校验和任务执行失败: {}", e)))??
+        };
+
+        let dir = inner.ensure_dir(&file.folder).await?;
+        let url = format!("{}/{}", dir, encode_segment(&file.file_name));
+        // 同一文件可能被多次请求，远端仍是上次上传的同一内容时不再重复上传
+        let uploaded = inner.uploaded.lock().unwrap().get(&url).cloned();
+        let remote_etag = match &uploaded {
+            Some(_) => inner.remote_etag(&url).await?,
+            None => None,
+        };
+        if !is_current(uploaded.as_ref(), &sha256, remote_etag.as_deref()) {
+            let file = tokio::fs::File::open(&local_path).await.map_err(|e| {
+                AppError::Internal(format!("读取文件失败: {}: {}", local_path.display(), e))
+            })?;
+            let size = file
+                .metadata()
+                .await
+                .map_err(|e| AppError::Internal(format!("读取文件失败: {}: {}", local_path.display(), e)))?
+                .len();
+            // 流式上传，合并的大 PDF 不必整个读入内存；部分网盘不接受分块传输，明确给出长度
+            let response = inner
+                .upload_request(&url)
+                .header(reqwest::header::CONTENT_LENGTH, size)
+                .body(Body::wrap_stream(tokio_util::io::ReaderStream::new(file)))
+                .send()
+                .await
+                .map_err(|e| AppError::Internal(format!("上传到 WebDAV 失败: {}: {}", url, e)))?;
+            let status = response.status();
+            if !status.is_success() {
+                return Err(AppError::Internal(format!("上传到 WebDAV 失败: {}: {}", url, status)));
+            }
+            // 部分服务在 PUT 响应中不返回 ETag，再查询一次
+            let etag = match etag(&response) {
+                Some(etag) => Some(etag),
+                None => inner.remote_etag(&url).await?,
+            };
+            inner.uploaded.lock().unwrap().insert(url.clone(), Uploaded { sha256, etag });
+            debug!("已上传到 WebDAV: {} ({} 字节)", url, size);
+        }
+        Ok(url)
+    }
+}
+
+/// 远端文件是否仍是本服务上次上传的同一内容：大小一致不足以说明内容相同（同名的其他产物、重新生成的文件），
+/// 远端不返回 ETag 时无法确认，总是重新上传
+fn is_current(uploaded: Option<&Uploaded>, sha256: &str, remote_etag: Option<&str>) -> bool {
+    uploaded.is_some_and(|uploaded| {
+        uploaded.sha256 == sha256 && remote_etag.is_some() && uploaded.etag.as_deref() == remote_etag
+    })
+}
+
+fn etag(response: &reqwest::Response) -> Option<String> {
+    response
+        .headers()
+        .get(reqwest::header::ETAG)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+impl WebDavInner {
+    fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        let request = self.http.request(method, url);
+        match &self.username {
+            Some(username) => request.basic_auth(username, self.password.as_deref()),
+            None => request,
+        }
+    }
+
+    /// 不经重试中间件的 PUT 请求
+    fn upload_request(&self, url: &str) -> reqwest::RequestBuilder {
+        let request = self.upload.put(url);
+        match &self.username {
+            Some(username) => request.basic_auth(username, self.password.as_deref()),
+            None => request,
+        }
+    }
+
+    /// 逐级创建目录，返回最内层目录的地址
+    async fn ensure_dir(&self, folder: &[String]) -> Result<String> {
+        let mkcol = Method::from_bytes(b"MKCOL").expect("MKCOL 为合法的 HTTP 方法");
+        let mut url = self.base_url.clone();
+        for segment in folder {
+            url = format!("{}/{}", url, encode_segment(segment));
+            if self.created_dirs.lock().unwrap().contains(&url) {
+                continue;
+            }
+            let response = self
+                .request(mkcol.clone(), &url)
+                .send()
+                .await
+                .map_err(|e| AppError::Internal(format!("创建 WebDAV 目录失败: {}: {}", url, e)))?;
+            // 201 为新建，405 为目录已存在
+            match response.status() {
+                status if status.is_success() || status == StatusCode::METHOD_NOT_ALLOWED => {}
+                status => {
+                    return Err(AppError::Internal(format!(
+                        "创建 WebDAV 目录失败: {}: {}",
+                        url, status
+                    )))
+                }
+            }
+            self.created_dirs.lock().unwrap().insert(url.clone());
+        }
+        Ok(url)
+    }
+
+    /// 远端文件的 ETag，文件不存在或服务不返回 ETag 时为 None
+    async fn remote_etag(&self, url: &str) -> Result<Option<String>> {
+        let response = self
+            .request(Method::HEAD, url)
+            .send()
+            .await
+            .map_err(|e| AppError::Internal(format!("查询 WebDAV 文件失败: {}: {}", url, e)))?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => Ok(etag(&response)),
+            status => Err(AppError::Internal(format!("查询 WebDAV 文件失败: {}: {}", url, status))),
+        }
+    }
+}
+
+/// 目录或文件名去除路径分隔符后做 URL 编码
+fn encode_segment(name: &str) -> String {
+    let name = sanitize_filename(name);
+    if name.is_empty() || name == "." || name == ".." {
+        return "_".to_string();
+    }
+    pct_encode(&name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_upload_only_for_unchanged_content() {
+        let uploaded = Uploaded { sha256: "aa".to_string(), etag: Some("\"1\"".to_string()) };
+        assert!(is_current(Some(&uploaded), "aa", Some("\"1\"")));
+        // 未上传过、内容已变、远端被替换或已删除时都重新上传
+        assert!(!is_current(None, "aa", Some("\"1\"")));
+        assert!(!is_current(Some(&uploaded), "bb", Some("\"1\"")));
+        assert!(!is_current(Some(&uploaded), "aa", Some("\"2\"")));
+        assert!(!is_current(Some(&uploaded), "aa", None));
+        let no_etag = Uploaded { etag: None, ..uploaded };
+        assert!(!is_current(Some(&no_etag), "aa", None));
+    }
+
+    #[test]
+    fn encodes_path_segments() {
+        assert_eq!(encode_segment("标题 1"), "%E6%A0%87%E9%A2%98%201");
+        assert_eq!(encode_segment(".."), "_");
+        assert!(!encode_segment("a/b").contains('/'));
+    }
+}
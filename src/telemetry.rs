@@ -0,0 +1,71 @@
+// 链路追踪导出
+// 代码中的 JM API 请求、单张图片下载、图片处理（拼接/编码）与 PDF 合并/压缩/拆分都以 `tracing` span 标注，
+// 以 `--features otel` 编译并设置 JM_OTEL_ENDPOINT 时经 OTLP/HTTP 批量导出到 Jaeger/Tempo 等后端，
+// 便于多实例部署时端到端追踪慢下载。未启用时没有订阅者，span 开销可以忽略；日志仍由 log4rs 输出
+
+use crate::config::Config;
+
+#[cfg(feature = "otel")]
+mod exporter {
+    use std::sync::OnceLock;
+
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry::KeyValue;
+    use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::trace::TracerProvider;
+    use opentelemetry_sdk::{runtime, Resource};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use crate::config::Config;
+
+    static PROVIDER: OnceLock<TracerProvider> = OnceLock::new();
+
+    pub fn init(config: &Config, endpoint: &str) -> Result<(), String> {
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .with_endpoint(endpoint)
+            .build()
+            .map_err(|e| e.to_string())?;
+        let provider = TracerProvider::builder()
+            .with_batch_exporter(exporter, runtime::Tokio)
+            .with_resource(Resource::new(vec![KeyValue::new(
+                "service.name",
+                config.otel_service_name.clone(),
+            )]))
+            .build();
+        let tracer = provider.tracer(env!("CARGO_PKG_NAME"));
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(tracer));
+        tracing::subscriber::set_global_default(subscriber).map_err(|e| e.to_string())?;
+        let _ = PROVIDER.set(provider);
+        Ok(())
+    }
+
+    pub fn shutdown() {
+        if let Some(provider) = PROVIDER.get() {
+            if let Err(e) = provider.shutdown() {
+                warn!("导出剩余链路数据失败: {}", e);
+            }
+        }
+    }
+}
+
+/// 配置了 JM_OTEL_ENDPOINT 时安装 OTLP 导出，需在 tokio 运行时中调用
+pub fn init(config: &Config) {
+    let Some(endpoint) = &config.otel_endpoint else {
+        return;
+    };
+    #[cfg(feature = "otel")]
+    match exporter::init(config, endpoint) {
+        Ok(()) => info!("已启用 OpenTelemetry 链路导出: {}", endpoint),
+        Err(e) => warn!("初始化 OpenTelemetry 链路导出失败，不导出链路: {}", e),
+    }
+    #[cfg(not(feature = "otel"))]
+    warn!("已设置 JM_OTEL_ENDPOINT={}，但程序未以 --features otel 编译，不导出链路", endpoint);
+}
+
+/// 退出前导出尚未发送的 span，会阻塞到导出完成，需在阻塞线程中调用
+pub fn shutdown() {
+    #[cfg(feature = "otel")]
+    exporter::shutdown();
+}
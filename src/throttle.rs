@@ -0,0 +1,81 @@
+// 下载限速模块
+// 全局令牌桶，所有图片下载共享同一速率上限（JM_MAX_DOWNLOAD_MBPS），避免占满小带宽服务器或触发 CDN 风控
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+/// 令牌桶最多积攒的时长，空闲后允许的突发流量为 1 秒的配额
+const BURST: Duration = Duration::from_secs(1);
+
+static LIMITER: Mutex<Bucket> = Mutex::new(Bucket {
+    bytes_per_sec: 0.0,
+    tokens: 0.0,
+    last: None,
+});
+
+struct Bucket {
+    /// 每秒字节数，0 表示不限速
+    bytes_per_sec: f64,
+    /// 当前可用字节数，允许为负（表示已透支，需要等待偿还）
+    tokens: f64,
+    last: Option<Instant>,
+}
+
+impl Bucket {
+    /// 扣除 `bytes` 个令牌，返回需要等待的时长
+    fn take(&mut self, bytes: u64, now: Instant) -> Duration {
+        if self.bytes_per_sec <= 0.0 {
+            return Duration::ZERO;
+        }
+        let capacity = self.bytes_per_sec * BURST.as_secs_f64();
+        let elapsed = self.last.map_or(BURST, |last| now.saturating_duration_since(last));
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.bytes_per_sec).min(capacity);
+        self.last = Some(now);
+        self.tokens -= bytes as f64;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.bytes_per_sec)
+        }
+    }
+}
+
+/// 设置全局下载速率上限（MB/s），0 表示不限速；启动与重新加载配置时调用
+pub fn set_max_download_mbps(mbps: f64) {
+    let mut bucket = LIMITER.lock().unwrap();
+    bucket.bytes_per_sec = mbps * 1_000_000.0;
+    bucket.tokens = 0.0;
+    bucket.last = None;
+}
+
+/// 记录已下载的 `bytes` 字节，超出速率上限时等待到配额恢复
+pub async fn consume(bytes: u64) {
+    let wait = LIMITER.lock().unwrap().take(bytes, Instant::now());
+    if !wait.is_zero() {
+        tokio::time::sleep(wait).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overdraft_waits_proportionally() {
+        let mut bucket = Bucket {
+            bytes_per_sec: 1_000_000.0,
+            tokens: 0.0,
+            last: None,
+        };
+        let now = Instant::now();
+        // 首次请求可用满 1 秒的突发配额
+        assert_eq!(bucket.take(1_000_000, now), Duration::ZERO);
+        // 配额耗尽后再取 0.5 MB 需等待 0.5 秒
+        assert_eq!(bucket.take(500_000, now), Duration::from_millis(500));
+        // 不限速时从不等待
+        bucket.bytes_per_sec = 0.0;
+        assert_eq!(bucket.take(u64::MAX, now), Duration::ZERO);
+    }
+}
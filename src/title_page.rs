@@ -0,0 +1,154 @@
+// PDF 标题页
+// 请求 title_page 为 true 时在合并 PDF 开头插入一页生成的标题页（漫画标题、作者、ID 与下载日期），随后是封面图，
+// 使归档的 PDF 脱离文件名也能识别。文字使用 JM_PDF_FONT 指定的 TrueType 字体绘制，需包含中日文字形；
+// printpdf 会嵌入完整字体，因此生成标题页的 PDF 总会经过 GhostScript，由其只保留用到的字形
+
+use jm_downloader_rs::AppError;
+use printpdf::{Mm, PdfDocumentReference};
+
+use crate::models::GetComicRespData;
+
+type Result<T> = std::result::Result<T, AppError>;
+
+/// 标题页与封面占用的页数
+pub const FRONT_MATTER_PAGES: usize = 2;
+/// 标题页尺寸（A5）
+const PAGE_WIDTH_MM: f32 = 148.0;
+const PAGE_HEIGHT_MM: f32 = 210.0;
+const MARGIN_MM: f32 = 16.0;
+const TITLE_FONT_PT: f32 = 22.0;
+const DETAIL_FONT_PT: f32 = 11.0;
+/// 标题最多占用的行数，超出部分以省略号结尾
+const MAX_TITLE_LINES: usize = 8;
+const MM_PER_PT: f32 = 25.4 / 72.0;
+const LINE_SPACING: f32 = 1.4;
+
+/// 标题页内容与绘制所用字体
+pub struct TitlePage {
+    pub title: String,
+    authors: Vec<String>,
+    comic_id: i64,
+    /// 下载日期（北京时间）
+    date: String,
+    font: Vec<u8>,
+}
+
+impl TitlePage {
+    /// 读取 JM_PDF_FONT 字体，生成漫画的标题页
+    pub async fn new(font_path: &str, comic_id: i64, comic: &GetComicRespData) -> Result<Self> {
+        let font = load_font(font_path).await?;
+        Ok(Self {
+            title: comic.name.clone(),
+            authors: comic.author.clone(),
+            comic_id,
+            date: chrono::Utc::now()
+                .with_timezone(&chrono_tz::Asia::Shanghai)
+                .format("%Y-%m-%d")
+                .to_string(),
+            font,
+        })
+    }
+
+    /// 在文档末尾添加标题页
+    pub fn add_to(&self, doc: &PdfDocumentReference) -> Result<()> {
+        let font = doc
+            .add_external_font(self.font.as_slice())
+            .map_err(|e| AppError::Internal(format!("加载标题页字体失败: {}", e)))?;
+        let (page, layer) = doc.add_page(Mm(PAGE_WIDTH_MM), Mm(PAGE_HEIGHT_MM), "Layer 1");
+        let layer = doc.get_page(page).get_layer(layer);
+
+        let mut y = PAGE_HEIGHT_MM - MARGIN_MM * 2.5;
+        for line in wrap(&self.title, em_per_line(TITLE_FONT_PT), MAX_TITLE_LINES) {
+            layer.use_text(line, TITLE_FONT_PT, Mm(MARGIN_MM), Mm(y), &font);
+            y -= line_height(TITLE_FONT_PT);
+        }
+        y -= line_height(TITLE_FONT_PT);
+
+        let authors = if self.authors.is_empty() { "未知".to_string() } else { self.authors.join("、") };
+        let details = [
+            format!("作者：{}", authors),
+            format!("JM ID：{}", self.comic_id),
+            format!("下载日期：{}", self.date),
+        ];
+        for detail in details {
+            for line in wrap(&detail, em_per_line(DETAIL_FONT_PT), usize::MAX) {
+                layer.use_text(line, DETAIL_FONT_PT, Mm(MARGIN_MM), Mm(y), &font);
+                y -= line_height(DETAIL_FONT_PT);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 读取字体文件并确认是单个 TrueType 字体：printpdf 以 CIDFontType2 嵌入，不支持 CFF 轮廓与字体集合
+pub async fn load_font(path: &str) -> Result<Vec<u8>> {
+    let font = tokio::fs::read(path)
+        .await
+        .map_err(|e| AppError::Internal(format!("读取 PDF 字体 {} 失败: {}", path, e)))?;
+    match font.get(..4) {
+        Some([0, 1, 0, 0]) | Some(b"true") => Ok(font),
+        Some(b"OTTO") => Err(AppError::Internal(format!(
+            "PDF 字体 {} 为 CFF 轮廓的 OpenType 字体，请改用 TrueType（.ttf）字体",
+            path
+        ))),
+        Some(b"ttcf") => Err(AppError::Internal(format!(
+            "PDF 字体 {} 为字体集合（.ttc），请改用单个 TrueType（.ttf）字体",
+            path
+        ))),
+        _ => Err(AppError::Internal(format!("PDF 字体 {} 不是 TrueType 字体", path))),
+    }
+}
+
+/// 一行可容纳的字宽数
+fn em_per_line(font_pt: f32) -> f32 {
+    (PAGE_WIDTH_MM - MARGIN_MM * 2.0) / (font_pt * MM_PER_PT)
+}
+
+fn line_height(font_pt: f32) -> f32 {
+    font_pt * MM_PER_PT * LINE_SPACING
+}
+
+/// 按估算宽度折行：ASCII 字符按 0.55 个字宽、其余（中日韩等全角字符）按 1 个字宽估算，
+/// 不读取字体度量，宁可提前换行；超过 `max_lines` 行时截断并以省略号结尾
+fn wrap(text: &str, max_em: f32, max_lines: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut width = 0.0;
+    for c in text.chars() {
+        let w = if c.is_ascii() { 0.55 } else { 1.0 };
+        if width + w > max_em && !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+            width = 0.0;
+        }
+        if line.is_empty() && c.is_whitespace() {
+            continue;
+        }
+        line.push(c);
+        width += w;
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        if let Some(last) = lines.last_mut() {
+            last.pop();
+            last.push('…');
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_wide_and_narrow_characters() {
+        assert_eq!(wrap("漫画标题测试", 4.0, 8), ["漫画标题", "测试"]);
+        // ASCII 按约半个字宽计算，换行后去掉行首空白
+        assert_eq!(wrap("abcdefg hij", 4.0, 8), ["abcdefg", "hij"]);
+        assert_eq!(wrap("一二三四五六七八九", 2.0, 2), ["一二", "三…"]);
+        assert!(wrap("", 4.0, 8).is_empty());
+    }
+}
@@ -0,0 +1,254 @@
+// .torrent 种子生成
+// 请求 torrent 为 true 时为 downloadComic 产出的文件（单页图片、合并 PDF 及分卷、校验清单）生成 BitTorrent v1 多文件种子，
+// 分块 SHA-1 与 bencode 编码都在本模块中完成，不依赖外部工具。种子名为漫画标题，文件直接位于产物目录中；
+// Tracker 由 JM_TORRENT_TRACKERS 指定，JM_TORRENT_PRIVATE 为 true（默认）时标记为私有种子。
+// 配置了 JM_TORRENT_SEED_COMMAND 时以 `命令 <种子文件> <产物目录>` 在后台调用，由外部 BT 客户端做种
+
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use jm_downloader_rs::AppError;
+use sha1::{Digest, Sha1};
+
+use crate::atomic_file;
+use crate::config::Config;
+use crate::file_server::sanitize_filename;
+
+type Result<T> = std::result::Result<T, AppError>;
+
+/// 产物目录中的种子文件名
+pub const TORRENT_FILE: &str = "comic.torrent";
+/// 分块大小下限与上限
+const MIN_PIECE_LENGTH: u64 = 256 * 1024;
+const MAX_PIECE_LENGTH: u64 = 16 * 1024 * 1024;
+/// 分块数超过该值时加大分块
+const TARGET_PIECES: u64 = 1500;
+
+/// bencode 值；字典以 `BTreeMap` 保存，键按字节序排列
+enum Bencode {
+    Int(i64),
+    Bytes(Vec<u8>),
+    List(Vec<Bencode>),
+    Dict(BTreeMap<&'static str, Bencode>),
+}
+
+impl Bencode {
+    fn str(value: &str) -> Self {
+        Bencode::Bytes(value.as_bytes().to_vec())
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Bencode::Int(value) => out.extend_from_slice(format!("i{}e", value).as_bytes()),
+            Bencode::Bytes(bytes) => {
+                out.extend_from_slice(format!("{}:", bytes.len()).as_bytes());
+                out.extend_from_slice(bytes);
+            }
+            Bencode::List(items) => {
+                out.push(b'l');
+                items.iter().for_each(|item| item.encode(out));
+                out.push(b'e');
+            }
+            Bencode::Dict(entries) => {
+                out.push(b'd');
+                for (key, value) in entries {
+                    Bencode::str(key).encode(out);
+                    value.encode(out);
+                }
+                out.push(b'e');
+            }
+        }
+    }
+}
+
+/// 生成的种子
+#[derive(Debug)]
+pub struct Torrent {
+    /// 种子文件路径
+    pub path: PathBuf,
+    /// info 字典的 SHA-1（十六进制）
+    pub info_hash: String,
+    pub piece_length: u64,
+    pub piece_count: usize,
+    pub total_bytes: u64,
+}
+
+/// 为 `dir` 中的 `files`（文件名，重复的只计一次）生成种子并写入 [`TORRENT_FILE`]
+pub async fn write_torrent(config: &Config, dir: &Path, files: Vec<String>, title: &str) -> Result<Torrent> {
+    let dir = dir.to_path_buf();
+    let name = sanitize_filename(title);
+    let trackers = config.torrent_trackers.clone();
+    let private = config.torrent_private;
+    tokio::task::spawn_blocking(move || {
+        let mut unique: Vec<String> = Vec::with_capacity(files.len());
+        for file in files {
+            if !unique.contains(&file) {
+                unique.push(file);
+            }
+        }
+        let sizes = unique
+            .iter()
+            .map(|file| {
+                let path = dir.join(file);
+                std::fs::metadata(&path)
+                    .map(|meta| meta.len())
+                    .map_err(|e| AppError::Internal(format!("读取 {} 生成种子失败: {}", path.display(), e)))
+            })
+            .collect::<Result<Vec<u64>>>()?;
+        let piece_length = piece_length(sizes.iter().sum());
+        let pieces = hash_pieces(&dir, &unique, piece_length)?;
+
+        let info = info_dict(&name, &unique, &sizes, piece_length, &pieces, private);
+        let mut info_bytes = Vec::new();
+        info.encode(&mut info_bytes);
+        let info_hash = hex::encode(Sha1::digest(&info_bytes));
+
+        let path = dir.join(TORRENT_FILE);
+        atomic_file::write_sync(&path, metainfo(info, &trackers))
+            .map_err(|e| AppError::Internal(format!("写入种子 {} 失败: {}", path.display(), e)))?;
+        Ok(Torrent {
+            path,
+            info_hash,
+            piece_length,
+            piece_count: pieces.len() / 20,
+            total_bytes: sizes.iter().sum(),
+        })
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("生成种子任务执行失败: {}", e)))?
+}
+
+/// 按总大小选择 2 的幂的分块大小，使分块数不超过约 [`TARGET_PIECES`]
+fn piece_length(total_bytes: u64) -> u64 {
+    let mut length = MIN_PIECE_LENGTH;
+    while length < MAX_PIECE_LENGTH && total_bytes.div_ceil(length) > TARGET_PIECES {
+        length *= 2;
+    }
+    length
+}
+
+/// 把文件按顺序首尾相接后逐块计算 SHA-1，分块可以跨越文件边界
+fn hash_pieces(dir: &Path, files: &[String], piece_length: u64) -> Result<Vec<u8>> {
+    let mut pieces = Vec::new();
+    let mut hasher = Sha1::new();
+    let mut filled = 0u64;
+    let mut buf = vec![0u8; 64 * 1024];
+    for file in files {
+        let path = dir.join(file);
+        let read_error = |e: std::io::Error| AppError::Internal(format!("读取 {} 生成种子失败: {}", path.display(), e));
+        let mut reader = std::fs::File::open(&path).map_err(read_error)?;
+        loop {
+            let want = buf.len().min((piece_length - filled) as usize);
+            let n = reader.read(&mut buf[..want]).map_err(read_error)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+            filled += n as u64;
+            if filled == piece_length {
+                pieces.extend_from_slice(&hasher.finalize_reset());
+                filled = 0;
+            }
+        }
+    }
+    if filled > 0 {
+        pieces.extend_from_slice(&hasher.finalize());
+    }
+    Ok(pieces)
+}
+
+fn info_dict(name: &str, files: &[String], sizes: &[u64], piece_length: u64, pieces: &[u8], private: bool) -> Bencode {
+    let files = files
+        .iter()
+        .zip(sizes)
+        .map(|(file, &size)| {
+            Bencode::Dict(BTreeMap::from([
+                ("length", Bencode::Int(size as i64)),
+                ("path", Bencode::List(vec![Bencode::str(file)])),
+            ]))
+        })
+        .collect();
+    let mut info = BTreeMap::from([
+        ("files", Bencode::List(files)),
+        ("name", Bencode::str(name)),
+        ("piece length", Bencode::Int(piece_length as i64)),
+        ("pieces", Bencode::Bytes(pieces.to_vec())),
+    ]);
+    if private {
+        info.insert("private", Bencode::Int(1));
+    }
+    Bencode::Dict(info)
+}
+
+/// 完整的种子文件内容：第一个 Tracker 写入 announce，全部写入 announce-list（每个一层）
+fn metainfo(info: Bencode, trackers: &[String]) -> Vec<u8> {
+    let mut root = BTreeMap::from([
+        ("created by", Bencode::str(concat!("jm-downloader-rs/", env!("CARGO_PKG_VERSION")))),
+        ("creation date", Bencode::Int(chrono::Utc::now().timestamp())),
+        ("info", info),
+    ]);
+    if let Some(first) = trackers.first() {
+        root.insert("announce", Bencode::str(first));
+        let tiers = trackers.iter().map(|tracker| Bencode::List(vec![Bencode::str(tracker)])).collect();
+        root.insert("announce-list", Bencode::List(tiers));
+    }
+    let mut out = Vec::new();
+    Bencode::Dict(root).encode(&mut out);
+    out
+}
+
+/// 配置了 JM_TORRENT_SEED_COMMAND 时在后台调用做种命令，返回是否已调用；命令失败只记录日志
+pub fn spawn_seed_hook(config: &Config, torrent: &Path, dir: &Path) -> bool {
+    let Some(command) = config.torrent_seed_command.clone() else {
+        return false;
+    };
+    let mut cmd = Command::new(&command);
+    cmd.arg(torrent).arg(dir);
+    tokio::task::spawn_blocking(move || match cmd.output() {
+        Ok(output) if output.status.success() => info!("做种命令执行成功: {}", command),
+        Ok(output) => warn!(
+            "做种命令 {} 执行失败（{}）: {}",
+            command,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+        Err(e) => warn!("执行做种命令 {} 失败: {}", command, e),
+    });
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_pieces_across_files_and_encodes_metainfo() {
+        let dir = std::env::temp_dir().join(format!("jm-torrent-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("0001.png"), b"abc").unwrap();
+        std::fs::write(dir.join("merged.pdf"), b"defg").unwrap();
+        let files = vec!["0001.png".to_string(), "merged.pdf".to_string()];
+
+        // 分块跨越文件边界：abcd / efg
+        let pieces = hash_pieces(&dir, &files, 4).unwrap();
+        assert_eq!(pieces[..20], Sha1::digest(b"abcd")[..]);
+        assert_eq!(pieces[20..], Sha1::digest(b"efg")[..]);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let info = info_dict("漫画", &files, &[3, 4], 4, &pieces[..20], true);
+        let mut encoded = Vec::new();
+        info.encode(&mut encoded);
+        let expected_prefix = b"d5:filesld6:lengthi3e4:pathl8:0001.pngeed6:lengthi4e4:pathl10:merged.pdfeee4:name6:";
+        assert!(encoded.starts_with(expected_prefix));
+        assert!(encoded.ends_with(b"7:privatei1ee"));
+
+        let torrent = metainfo(info, &["https://tracker.example/announce".to_string()]);
+        assert!(torrent.starts_with(b"d8:announce32:https://tracker.example/announce13:announce-listll"));
+
+        assert_eq!(piece_length(0), MIN_PIECE_LENGTH);
+        assert_eq!(piece_length(1500 * MIN_PIECE_LENGTH + 1), 2 * MIN_PIECE_LENGTH);
+        assert_eq!(piece_length(u64::MAX), MAX_PIECE_LENGTH);
+    }
+}
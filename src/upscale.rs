@@ -0,0 +1,107 @@
+// 外部放大命令
+// 配置 JM_UPSCALE_CMD 后，每页在拼接（及拆分、裁剪、电子墨水屏处理）之后、保存与合并 PDF 之前
+// 交给该命令放大（如封装了 waifu2x / Real-ESRGAN 的脚本）：以 `<命令> <输入.png> <输出.png>` 调用，
+// 读取输出文件替换页面。同时运行的命令数受 JM_UPSCALE_CONCURRENCY 限制（通常只有一块 GPU），
+// 超过 JM_UPSCALE_TIMEOUT_SECONDS 的命令被终止；命令失败、超时或输出无效时保留未放大的页面，不影响下载
+
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use bytes::Bytes;
+use tokio::process::Command;
+use tokio::sync::Semaphore;
+
+use crate::config::Config;
+
+struct Upscaler {
+    command: String,
+    timeout: Duration,
+    permits: Semaphore,
+}
+
+static UPSCALER: RwLock<Option<Arc<Upscaler>>> = RwLock::new(None);
+/// 临时文件序号，避免并发放大时文件名冲突
+static NEXT_FILE: AtomicU64 = AtomicU64::new(0);
+
+/// 按配置设置放大命令，启动时与重新加载配置时调用；进行中的放大仍使用旧设置
+pub fn configure(config: &Config) {
+    let upscaler = config.upscale_cmd.clone().map(|command| {
+        Arc::new(Upscaler {
+            command,
+            timeout: Duration::from_secs(config.upscale_timeout_seconds),
+            permits: Semaphore::new(config.upscale_concurrency),
+        })
+    });
+    *UPSCALER.write().unwrap() = upscaler;
+}
+
+/// 放大一页 PNG；未配置命令或放大失败时返回 None，由调用方保留原页面
+pub async fn upscale(png: &[u8]) -> Option<Bytes> {
+    let upscaler = UPSCALER.read().unwrap().clone()?;
+    let _permit = upscaler.permits.acquire().await.ok()?;
+    let id = NEXT_FILE.fetch_add(1, Ordering::Relaxed);
+    let dir = std::env::temp_dir();
+    let input = dir.join(format!("jm-upscale-{}-{}.png", std::process::id(), id));
+    let output = input.with_extension("out.png");
+    let result = run(&upscaler, png, &input, &output).await;
+    for path in [&input, &output] {
+        let _ = tokio::fs::remove_file(path).await;
+    }
+    match result {
+        Ok(upscaled) => Some(upscaled),
+        Err(e) => {
+            warn!("放大命令 {} 失败，保留原页面: {}", upscaler.command, e);
+            None
+        }
+    }
+}
+
+async fn run(upscaler: &Upscaler, png: &[u8], input: &Path, output: &Path) -> Result<Bytes, String> {
+    tokio::fs::write(input, png).await.map_err(|e| format!("写入临时文件失败: {}", e))?;
+    let child = Command::new(&upscaler.command)
+        .arg(input)
+        .arg(output)
+        .kill_on_drop(true)
+        .output();
+    let result = tokio::time::timeout(upscaler.timeout, child)
+        .await
+        .map_err(|_| format!("超过 {} 秒未完成", upscaler.timeout.as_secs()))?
+        .map_err(|e| format!("无法执行: {}", e))?;
+    if !result.status.success() {
+        return Err(format!("{}: {}", result.status, String::from_utf8_lossy(&result.stderr).trim()));
+    }
+    let upscaled = tokio::fs::read(output).await.map_err(|e| format!("读取输出文件失败: {}", e))?;
+    // 只接受可识别的图片，避免把空文件或错误信息当作页面
+    image::guess_format(&upscaled).map_err(|_| "输出文件不是图片".to_string())?;
+    Ok(Bytes::from(upscaled))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn falls_back_when_the_command_fails() {
+        let png = jm_downloader_rs::png::encode_png(&image::RgbImage::new(2, 2), Default::default()).unwrap();
+        let upscaler = |command: &str| Upscaler {
+            command: command.to_string(),
+            timeout: Duration::from_secs(5),
+            permits: Semaphore::new(1),
+        };
+        let input = std::env::temp_dir().join(format!("jm-upscale-test-{}.png", std::process::id()));
+        let output = input.with_extension("out.png");
+
+        // cp 把输入原样复制到输出，视为放大成功
+        let copied = run(&upscaler("cp"), &png, &input, &output).await.unwrap();
+        assert_eq!(copied, png);
+        // 命令失败或不存在时返回错误，由调用方保留原页面
+        assert!(run(&upscaler("false"), &png, &input, &output).await.is_err());
+        assert!(run(&upscaler("/nonexistent/upscale"), &png, &input, &output).await.is_err());
+
+        for path in [&input, &output] {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
@@ -0,0 +1,172 @@
+// 下载链接签名模块
+// 下载接口返回的文件路径附带 HMAC-SHA256 签名和过期时间，文件服务接口据此校验，防止他人枚举下载目录
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::config::Config;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 下载链接签名器，克隆后共享同一份有效期设置
+#[derive(Clone)]
+pub struct UrlSigner {
+    key: Arc<Vec<u8>>,
+    /// 链接有效期（秒），可在重新加载配置时修改
+    ttl_seconds: Arc<AtomicU64>,
+}
+
+impl UrlSigner {
+    /// 使用配置中的签名密钥创建；未配置时生成随机密钥（重启后旧链接失效）
+    pub fn from_config(config: &Config) -> Self {
+        let key = match &config.download_signing_key {
+            Some(key) => key.as_bytes().to_vec(),
+            None => {
+                warn!("未配置 JM_DOWNLOAD_SIGNING_KEY，已生成随机签名密钥，服务重启后已签发的下载链接将失效");
+                rand::random::<[u8; 32]>().to_vec()
+            }
+        };
+        Self {
+            key: Arc::new(key),
+            ttl_seconds: Arc::new(AtomicU64::new(config.download_url_ttl)),
+        }
+    }
+
+    /// 修改之后签发的链接的有效期
+    pub fn set_ttl(&self, ttl_seconds: u64) {
+        self.ttl_seconds.store(ttl_seconds, Ordering::Relaxed);
+    }
+
+    /// 当前签发链接的有效期（秒）
+    pub fn ttl_seconds(&self) -> u64 {
+        self.ttl_seconds.load(Ordering::Relaxed)
+    }
+
+    /// 为 `download/...` 形式的相对路径追加过期时间与签名
+    pub fn sign(&self, path: &str) -> String {
+        // 有效期来自配置且没有上限，过大时按永不过期处理
+        let expires = now_secs().saturating_add(self.ttl_seconds());
+        let file_path = path.strip_prefix("download/").unwrap_or(path);
+        format!(
+            "{}?expires={}&sig={}",
+            path,
+            expires,
+            self.signature(file_path, expires)
+        )
+    }
+
+    /// 校验签名与过期时间，`file_path` 为相对下载目录的路径
+    pub fn verify(&self, file_path: &str, expires: u64, sig: &str) -> bool {
+        if expires < now_secs() {
+            return false;
+        }
+        let Ok(sig) = hex::decode(sig) else {
+            return false;
+        };
+        self.mac(file_path, expires).verify_slice(&sig).is_ok()
+    }
+
+    /// 同 [`UrlSigner::sign`]，并附带浏览器保存时使用的文件名
+    pub fn sign_named(&self, path: &str, name: &str) -> String {
+        format!("{}&name={}", self.sign(path), pct_encode(name))
+    }
+
+    fn signature(&self, file_path: &str, expires: u64) -> String {
+        hex::encode(self.mac(file_path, expires).finalize().into_bytes())
+    }
+
+    fn mac(&self, file_path: &str, expires: u64) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC 可接受任意长度密钥");
+        mac.update(file_path.as_bytes());
+        mac.update(b":");
+        mac.update(expires.to_string().as_bytes());
+        mac
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// 百分号编码：仅保留 RFC 3986 非保留字符，可同时用于查询参数和 RFC 5987 文件名
+pub fn pct_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signer() -> UrlSigner {
+        let config: Config = toml::from_str("download_signing_key = \"secret\"\ndownload_url_ttl = 60").unwrap();
+        UrlSigner::from_config(&config)
+    }
+
+    /// 从签名链接中取出过期时间与签名
+    fn query(url: &str) -> (u64, String) {
+        let query = url.split_once('?').unwrap().1;
+        let param = |name: &str| {
+            query
+                .split('&')
+                .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+                .unwrap()
+                .to_string()
+        };
+        (param("expires").parse().unwrap(), param("sig"))
+    }
+
+    #[test]
+    fn verifies_own_links_only() {
+        let signer = signer();
+        let (expires, sig) = query(&signer.sign_named("download/1/2/merged.pdf", "标题.pdf"));
+        assert!(signer.verify("1/2/merged.pdf", expires, &sig));
+
+        // 篡改路径或过期时间
+        assert!(!signer.verify("1/3/merged.pdf", expires, &sig));
+        assert!(!signer.verify("1/2/merged.pdf", expires + 1, &sig));
+        // 签名错误、非十六进制、截断
+        let mut flipped = sig.clone().into_bytes();
+        flipped[0] = if flipped[0] == b'0' { b'1' } else { b'0' };
+        assert!(!signer.verify("1/2/merged.pdf", expires, std::str::from_utf8(&flipped).unwrap()));
+        assert!(!signer.verify("1/2/merged.pdf", expires, "not-hex"));
+        assert!(!signer.verify("1/2/merged.pdf", expires, &sig[..32]));
+        // 其他密钥签发的链接
+        let config: Config = toml::from_str("download_signing_key = \"other\"").unwrap();
+        assert!(!UrlSigner::from_config(&config).verify("1/2/merged.pdf", expires, &sig));
+    }
+
+    #[test]
+    fn rejects_expired_links() {
+        let signer = signer();
+        let expired = now_secs() - 1;
+        let sig = signer.signature("1/2/merged.pdf", expired);
+        assert!(!signer.verify("1/2/merged.pdf", expired, &sig));
+        // 签名本身有效，只因过期被拒绝
+        let valid = now_secs() + 60;
+        assert!(signer.verify("1/2/merged.pdf", valid, &signer.signature("1/2/merged.pdf", valid)));
+    }
+
+    #[test]
+    fn huge_ttl_saturates() {
+        let signer = signer();
+        signer.set_ttl(u64::MAX);
+        let (expires, sig) = query(&signer.sign("download/1/2/merged.pdf"));
+        assert_eq!(expires, u64::MAX);
+        assert!(signer.verify("1/2/merged.pdf", expires, &sig));
+    }
+}
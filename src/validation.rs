@@ -0,0 +1,270 @@
+// 请求参数校验
+// 处理器在访问 JM 或磁盘之前调用 `Validate::validate`，逐字段收集错误后一次性以 BadRequest 返回，
+// 错误信息形如 `chapter_ids[2]: 必须为正整数；expire_seconds: 不能超过 2592000（-1 为不过期）`，
+// 避免非法参数在下载流程深处以内部错误失败
+
+use std::fmt::Display;
+
+use jm_downloader_rs::{ApiResult, AppError};
+
+use crate::config::Config;
+use crate::post_process::PostProcessSteps;
+use crate::models::{
+    CheckLocalRequest, CleanupRequest, CommentRequest, DownloadChapterRequest, DownloadComicRequest, GetComicInfoRequest,
+    PreviewRequest, RawDataRequest, ResolveRequest, SyncChaptersRequest,
+};
+
+/// PDF 密码最大长度：PDF 标准安全处理器只使用密码的前 32 字节
+const MAX_PDF_PASSWORD_LEN: usize = 32;
+/// resolve 接口输入文本的最大长度
+const MAX_RESOLVE_INPUT_LEN: usize = 2048;
+/// 评论内容的最大字符数
+const MAX_COMMENT_CHARS: usize = 500;
+/// PDF 分卷体积上限（MB），换算成字节时不会溢出
+const MAX_PDF_SIZE_MB: u64 = 1024 * 1024;
+
+/// 需要校验的请求
+pub trait Validate {
+    fn check(&self, config: &Config, v: &mut Validator);
+
+    /// 校验请求，存在任何不合法字段时返回包含全部字段错误的 BadRequest
+    fn validate(&self, config: &Config) -> ApiResult<()> {
+        let mut v = Validator::default();
+        self.check(config, &mut v);
+        v.finish()
+    }
+}
+
+/// 逐字段收集错误
+#[derive(Default)]
+pub struct Validator {
+    errors: Vec<String>,
+}
+
+impl Validator {
+    /// `ok` 为 false 时记录 `field` 的错误
+    pub fn check(&mut self, field: &str, ok: bool, message: impl Display) {
+        if !ok {
+            self.errors.push(format!("{}: {}", field, message));
+        }
+    }
+
+    pub fn positive_id(&mut self, field: &str, id: i64) {
+        self.check(field, id > 0, "必须为正整数");
+    }
+
+    /// 不超过 `max` 个且各项为正整数的 ID 列表；`allow_empty` 为 false 时不能为空
+    pub fn id_list(&mut self, field: &str, ids: &[i64], max: usize, allow_empty: bool) {
+        self.check(field, allow_empty || !ids.is_empty(), "不能为空");
+        self.check(field, ids.len() <= max, format!("最多 {} 个，当前 {} 个", max, ids.len()));
+        for (index, &id) in ids.iter().enumerate() {
+            self.positive_id(&format!("{}[{}]", field, index), id);
+        }
+    }
+
+    /// -1 表示不过期，其余须为非负数且不超过 JM_MAX_EXPIRE_SECONDS（为 0 时不限制）
+    pub fn expire_seconds(&mut self, config: &Config, expire_seconds: i64) {
+        self.check("expire_seconds", expire_seconds >= -1, "必须为-1或非负数");
+        let max = config.max_expire_seconds;
+        self.check(
+            "expire_seconds",
+            max == 0 || expire_seconds <= max as i64,
+            format!("不能超过 {}（-1 为不过期）", max),
+        );
+    }
+
+    /// 同时下载的章节数，1 到 JM_MAX_CHAPTERS_PER_REQUEST 之间
+    pub fn chapter_concurrency(&mut self, config: &Config, concurrency: usize) {
+        let limit = config.max_chapters_per_request;
+        self.check("chapter_concurrency", (1..=limit).contains(&concurrency), format!("必须在 1~{} 之间", limit));
+    }
+
+    /// 页面后处理步骤须为已注册的处理器且参数合法
+    pub fn post_process(&mut self, steps: &[String]) {
+        if let Err(e) = PostProcessSteps::parse(steps) {
+            self.check("post_process", false, e);
+        }
+    }
+
+    pub fn finish(self) -> ApiResult<()> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::BadRequest(format!("请求参数不合法：{}", self.errors.join("；"))))
+        }
+    }
+}
+
+/// PDF 加密密码：去掉首尾空白后为空视为不加密，否则须为不超过 32 个字符的可见 ASCII 字符
+fn check_pdf_password(v: &mut Validator, password: Option<&str>) {
+    let Some(password) = password.map(str::trim).filter(|password| !password.is_empty()) else {
+        return;
+    };
+    v.check(
+        "encrypt",
+        password.len() <= MAX_PDF_PASSWORD_LEN,
+        format!("PDF 密码最多 {} 个字符", MAX_PDF_PASSWORD_LEN),
+    );
+    v.check(
+        "encrypt",
+        password.bytes().all(|b| b.is_ascii_graphic()),
+        "PDF 密码只能包含字母、数字与 ASCII 符号，不能包含空格或中文",
+    );
+}
+
+impl Validate for GetComicInfoRequest {
+    fn check(&self, _config: &Config, v: &mut Validator) {
+        v.positive_id("id", self.id);
+    }
+}
+
+impl Validate for RawDataRequest {
+    fn check(&self, _config: &Config, v: &mut Validator) {
+        v.positive_id("id", self.id);
+    }
+}
+
+impl Validate for ResolveRequest {
+    fn check(&self, _config: &Config, v: &mut Validator) {
+        v.check("input", !self.input.trim().is_empty(), "不能为空");
+        v.check(
+            "input",
+            self.input.len() <= MAX_RESOLVE_INPUT_LEN,
+            format!("最多 {} 字节", MAX_RESOLVE_INPUT_LEN),
+        );
+    }
+}
+
+impl Validate for CommentRequest {
+    fn check(&self, _config: &Config, v: &mut Validator) {
+        v.check("comment", !self.comment.trim().is_empty(), "不能为空");
+        v.check(
+            "comment",
+            self.comment.chars().count() <= MAX_COMMENT_CHARS,
+            format!("最多 {} 个字符", MAX_COMMENT_CHARS),
+        );
+        if let Some(reply_to) = self.reply_to {
+            v.positive_id("reply_to", reply_to);
+        }
+    }
+}
+
+impl Validate for PreviewRequest {
+    fn check(&self, _config: &Config, v: &mut Validator) {
+        v.positive_id("comic_id", self.comic_id);
+        if let Some(chapter_id) = self.chapter_id {
+            v.positive_id("chapter_id", chapter_id);
+        }
+    }
+}
+
+impl Validate for CheckLocalRequest {
+    fn check(&self, config: &Config, v: &mut Validator) {
+        v.positive_id("comic_id", self.comic_id);
+        v.id_list("chapter_ids", &self.chapter_ids, config.max_chapters_per_request, true);
+    }
+}
+
+impl Validate for CleanupRequest {
+    fn check(&self, _config: &Config, v: &mut Validator) {
+        if let Some(comic_id) = self.comic_id {
+            v.positive_id("comic_id", comic_id);
+        }
+    }
+}
+
+impl Validate for DownloadChapterRequest {
+    fn check(&self, config: &Config, v: &mut Validator) {
+        v.positive_id("comic_id", self.comic_id);
+        v.id_list("chapter_ids", &self.chapter_ids, config.max_chapters_per_request, false);
+        v.expire_seconds(config, self.expire_seconds);
+        v.chapter_concurrency(config, self.chapter_concurrency);
+        v.post_process(&self.post_process);
+    }
+}
+
+impl Validate for SyncChaptersRequest {
+    fn check(&self, config: &Config, v: &mut Validator) {
+        v.positive_id("comic_id", self.comic_id);
+        if let Some(chapter_id) = self.last_chapter_id {
+            v.positive_id("last_chapter_id", chapter_id);
+        }
+        v.check(
+            "last_sort",
+            self.last_sort.is_none() || self.last_chapter_id.is_none(),
+            "不能与 last_chapter_id 同时设置",
+        );
+        if let Some(max) = self.max_chapters {
+            let limit = config.max_chapters_per_request;
+            v.check("max_chapters", (1..=limit).contains(&max), format!("必须在 1~{} 之间", limit));
+        }
+        v.expire_seconds(config, self.expire_seconds);
+        v.chapter_concurrency(config, self.chapter_concurrency);
+        v.post_process(&self.post_process);
+    }
+}
+
+impl Validate for DownloadComicRequest {
+    fn check(&self, config: &Config, v: &mut Validator) {
+        v.positive_id("comic_id", self.comic_id);
+        v.expire_seconds(config, self.expire_seconds);
+        v.post_process(&self.post_process);
+        if let Some(dpi) = self.pdf_dpi {
+            v.check("pdf_dpi", (36..=600).contains(&dpi), "必须在 36~600 之间");
+        }
+        v.check("pdf_max_pages", self.pdf_max_pages != Some(0), "必须大于0");
+        if let Some(mb) = self.pdf_max_size_mb {
+            v.check(
+                "pdf_max_size_mb",
+                (1..=MAX_PDF_SIZE_MB).contains(&mb),
+                format!("必须在 1~{} 之间", MAX_PDF_SIZE_MB),
+            );
+        }
+        v.check("email_to", self.email_to.is_none() || self.merge, "发送邮件需要同时设置 merge 为 true");
+        v.check("title_page", !self.title_page || self.merge, "生成标题页需要同时设置 merge 为 true");
+        v.check("title_page", !self.title_page || config.pdf_font.is_some(), "服务未配置标题页字体 JM_PDF_FONT");
+        check_pdf_password(v, self.encrypt.as_deref());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comic_request(body: serde_json::Value) -> DownloadComicRequest {
+        serde_json::from_value(body).unwrap()
+    }
+
+    #[test]
+    fn reports_every_invalid_field() {
+        let config: Config = toml::from_str("max_chapters_per_request = 3\nmax_expire_seconds = 3600").unwrap();
+
+        let request: DownloadChapterRequest = serde_json::from_value(serde_json::json!({
+            "comic_id": 0,
+            "chapter_ids": [5, -1, 6, 7],
+            "expire_seconds": 7200,
+            "chapter_concurrency": 0
+        }))
+        .unwrap();
+        let Err(AppError::BadRequest(message)) = request.validate(&config) else {
+            panic!("应校验失败");
+        };
+        assert_eq!(
+            message,
+            "请求参数不合法：comic_id: 必须为正整数；chapter_ids: 最多 3 个，当前 4 个；\
+             chapter_ids[1]: 必须为正整数；expire_seconds: 不能超过 3600（-1 为不过期）；\
+             chapter_concurrency: 必须在 1~3 之间"
+        );
+
+        let valid = comic_request(serde_json::json!({ "comic_id": 1, "expire_seconds": -1, "encrypt": " abc!123 " }));
+        assert!(valid.validate(&config).is_ok());
+        for encrypt in ["密码", "has space", "123456789012345678901234567890123"] {
+            let request = comic_request(serde_json::json!({ "comic_id": 1, "encrypt": encrypt }));
+            assert!(request.validate(&config).is_err(), "{}", encrypt);
+        }
+        for size in [0, MAX_PDF_SIZE_MB + 1, u64::MAX] {
+            let request = comic_request(serde_json::json!({ "comic_id": 1, "pdf_max_size_mb": size }));
+            assert!(request.validate(&config).is_err(), "{}", size);
+        }
+    }
+}
@@ -0,0 +1,241 @@
+// 监视目录批量导入（JM_WATCH_DIR）
+// 每隔 JM_WATCH_INTERVAL_SECONDS 扫描目录中的 `.json` 请求文件，内容与 REST 下载接口的请求体相同：
+// 含 `chapter_ids` 的按 downloadChapter 处理，否则按 downloadComic 处理，与 HTTP 请求一样进入任务队列。
+// 每次扫描只认领任务队列还容得下的文件数，其余留到下次扫描；仍因队列已满被拒绝的文件放回目录等待下次扫描。
+// 认领的文件先移入 `processing/`，结束后加上完成时间后缀移入 `done/` 或 `failed/`（同名请求不会互相覆盖），
+// 并在旁边写入 `<文件名>.result.json`（与 REST 响应相同的 `R<T>`）；
+// 启动时把上次未处理完的 `processing/` 文件放回目录重新处理。写入请求文件时应先用其他扩展名写完再重命名为 `.json`。
+// 服务处于只读或维护模式时暂停扫描，请求文件留在目录中，恢复正常模式后再处理
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use jm_downloader_rs::{ApiResult, AppError, R};
+use serde::Serialize;
+
+use crate::config::LiveConfig;
+use crate::dir_lease::DirLeases;
+use crate::global_client::GlobalJmClient;
+use crate::handlers::{self, InFlightDownloads};
+use crate::jobs::{JobLimits, Jobs};
+use crate::models::{DownloadChapterRequest, DownloadComicRequest};
+use crate::service_mode;
+use crate::storage::Storage;
+
+const PROCESSING: &str = "processing";
+const DONE: &str = "done";
+const FAILED: &str = "failed";
+
+/// 监视目录导入，持有与 Rocket 托管状态相同的共享对象
+pub struct WatchDir {
+    pub dir: PathBuf,
+    pub interval: Duration,
+    pub config: LiveConfig,
+    pub global_client: GlobalJmClient,
+    pub storage: Storage,
+    pub inflight: InFlightDownloads,
+    pub leases: DirLeases,
+    pub jobs: Jobs,
+}
+
+impl WatchDir {
+    /// 在后台定期扫描目录；创建子目录失败时只记录错误，不影响 HTTP 接口
+    pub fn spawn(self) {
+        tokio::spawn(async move {
+            if let Err(e) = prepare(&self.dir).await {
+                error!("监视目录不可用: {}", e);
+                return;
+            }
+            info!("监视目录 {}，每 {} 秒扫描一次请求文件", self.dir.display(), self.interval.as_secs());
+            let watcher = Arc::new(self);
+            let mut ticker = tokio::time::interval(watcher.interval);
+            loop {
+                ticker.tick().await;
+                if service_mode::ensure_downloads_allowed().is_err() {
+                    continue;
+                }
+                let room = watcher.jobs.room(JobLimits::from(&*watcher.config.load()));
+                for path in request_files(&watcher.dir).await.into_iter().take(room) {
+                    let Some(claimed) = claim(&watcher.dir, &path).await else {
+                        continue;
+                    };
+                    let watcher = watcher.clone();
+                    tokio::spawn(async move { watcher.process(claimed).await });
+                }
+            }
+        });
+    }
+
+    /// 处理一个已认领的请求文件并移入 `done/` 或 `failed/`
+    async fn process(&self, path: PathBuf) {
+        let name = path.file_name().unwrap_or_default().to_owned();
+        info!("开始处理监视目录中的请求 {}", name.to_string_lossy());
+        let (succeeded, body) = match self.run(&path).await {
+            Ok(outcome) => outcome,
+            // 与其他来源的任务竞争时队列仍可能已满，放回目录等下次扫描，不算失败
+            Err(AppError::QueueFull(e)) => {
+                info!("任务队列已满，请求 {} 留待下次扫描: {}", name.to_string_lossy(), e);
+                if let Err(e) = tokio::fs::rename(&path, self.dir.join(&name)).await {
+                    error!("放回请求文件 {} 失败: {}", path.display(), e);
+                }
+                return;
+            }
+            Err(e) => (false, to_json(R::<()>::from(e))),
+        };
+        let target = self.dir.join(if succeeded { DONE } else { FAILED });
+        let archived = archived_name(Path::new(&name), chrono::Utc::now());
+        if let Err(e) = tokio::fs::rename(&path, target.join(&archived)).await {
+            error!("移动请求文件 {} 失败: {}", path.display(), e);
+        }
+        let result_path = target.join(format!("{}.result.json", archived));
+        if let Err(e) = tokio::fs::write(&result_path, body + "\n").await {
+            error!("写入处理结果 {} 失败: {}", result_path.display(), e);
+        }
+        if succeeded {
+            info!("监视目录中的请求 {} 处理完成", name.to_string_lossy());
+        } else {
+            warn!("监视目录中的请求 {} 处理失败，结果见 {}", name.to_string_lossy(), result_path.display());
+        }
+    }
+
+    /// 按请求体执行下载，返回是否成功与 `R<T>` 响应
+    async fn run(&self, path: &Path) -> ApiResult<(bool, String)> {
+        let content = tokio::fs::read(path)
+            .await
+            .map_err(|e| AppError::Internal(format!("读取请求文件 {} 失败: {}", path.display(), e)))?;
+        let request: serde_json::Value = serde_json::from_slice(&content)
+            .map_err(|e| AppError::BadRequest(format!("请求文件不是有效的 JSON: {}", e)))?;
+        let config = self.config.load();
+        if is_chapter_request(&request) {
+            let request: DownloadChapterRequest = parse_request(request)?;
+            let outcome = handlers::download_chapters(
+                &config,
+                &self.global_client,
+                &self.storage,
+                &self.inflight,
+                &self.leases,
+                &self.jobs,
+                &request,
+            )
+            .await?;
+            Ok(match outcome.interrupted {
+                Some(e) => (false, to_json(R::partial(e, outcome.data))),
+                None => (true, to_json(R::success(outcome.data))),
+            })
+        } else {
+            let request: DownloadComicRequest = parse_request(request)?;
+            let data = handlers::download_comic_coalesced(
+                &config,
+                &self.global_client,
+                &self.storage,
+                &self.inflight,
+                &self.leases,
+                &self.jobs,
+                request,
+            )
+            .await?;
+            Ok((true, to_json(R::success(data))))
+        }
+    }
+}
+
+/// 含 `chapter_ids` 的请求体按 downloadChapter 处理
+fn is_chapter_request(request: &serde_json::Value) -> bool {
+    request.get("chapter_ids").is_some()
+}
+
+fn parse_request<T: serde::de::DeserializeOwned>(request: serde_json::Value) -> ApiResult<T> {
+    serde_json::from_value(request).map_err(|e| AppError::BadRequest(format!("请求文件字段无效: {}", e)))
+}
+
+/// 与 REST 响应相同的格式化 JSON
+fn to_json<T: Serialize>(body: R<T>) -> String {
+    serde_json::to_string_pretty(&body).unwrap_or_default()
+}
+
+/// 创建子目录，并把上次未处理完的请求放回目录
+async fn prepare(dir: &Path) -> ApiResult<()> {
+    for sub in [PROCESSING, DONE, FAILED] {
+        let path = dir.join(sub);
+        tokio::fs::create_dir_all(&path)
+            .await
+            .map_err(|e| AppError::Internal(format!("创建目录 {} 失败: {}", path.display(), e)))?;
+    }
+    for path in request_files(&dir.join(PROCESSING)).await {
+        if let Some(name) = path.file_name() {
+            if let Err(e) = tokio::fs::rename(&path, dir.join(name)).await {
+                warn!("恢复未处理完的请求 {} 失败: {}", path.display(), e);
+            } else {
+                info!("重新处理上次未完成的请求 {}", name.to_string_lossy());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 目录中按文件名排序的 `.json` 文件（不含子目录）
+async fn request_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+        return files;
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        let is_file = entry.file_type().await.is_ok_and(|kind| kind.is_file());
+        if is_file && path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    files
+}
+
+/// 移入 `done/`/`failed/` 时的文件名：在扩展名前加上完成时间（毫秒），如 `a.json` -> `a.20240102-030405123.json`
+fn archived_name(name: &Path, finished_at: chrono::DateTime<chrono::Utc>) -> String {
+    let stem = name.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = name.extension().unwrap_or_default().to_string_lossy();
+    format!("{}.{}.{}", stem, finished_at.format("%Y%m%d-%H%M%S%3f"), extension)
+}
+
+/// 把请求文件移入 `processing/`，避免下次扫描重复提交；失败时（如已被其他进程取走）跳过
+async fn claim(dir: &Path, path: &Path) -> Option<PathBuf> {
+    let claimed = dir.join(PROCESSING).join(path.file_name()?);
+    tokio::fs::rename(path, &claimed).await.ok()?;
+    Some(claimed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn recovers_and_claims_request_files() {
+        let dir = std::env::temp_dir().join(format!("jm-watch-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join(PROCESSING)).unwrap();
+        std::fs::write(dir.join("b.json"), r#"{"comic_id": 1}"#).unwrap();
+        std::fs::write(dir.join("a.tmp"), "").unwrap();
+        std::fs::write(dir.join(PROCESSING).join("a.json"), r#"{"comic_id": 1, "chapter_ids": [2]}"#).unwrap();
+
+        prepare(&dir).await.unwrap();
+        assert!(dir.join(DONE).is_dir() && dir.join(FAILED).is_dir());
+        let files = request_files(&dir).await;
+        assert_eq!(files, vec![dir.join("a.json"), dir.join("b.json")]);
+
+        let claimed = claim(&dir, &files[0]).await.unwrap();
+        assert_eq!(claimed, dir.join(PROCESSING).join("a.json"));
+        assert!(claim(&dir, &files[0]).await.is_none());
+        let request: serde_json::Value = serde_json::from_slice(&std::fs::read(&claimed).unwrap()).unwrap();
+        assert!(is_chapter_request(&request));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn archives_under_unique_names() {
+        let finished_at = chrono::DateTime::from_timestamp_millis(1_704_164_645_123).unwrap();
+        assert_eq!(archived_name(Path::new("a.json"), finished_at), "a.20240102-030405123.json");
+        let later = finished_at + chrono::Duration::milliseconds(1);
+        assert_ne!(archived_name(Path::new("a.json"), finished_at), archived_name(Path::new("a.json"), later));
+    }
+}
@@ -0,0 +1,364 @@
+// JMComic 网页端客户端
+// 当移动端 API 不可用（接口变更、加密方式变更）时，通过解析网页 HTML 获取同样的数据
+
+use std::sync::Arc;
+
+use jm_downloader_rs::AppError;
+use reqwest::cookie::Jar;
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
+use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
+
+use crate::jm_api::JmApi;
+use crate::jm_client::JmRetryStrategy;
+use crate::models::{GetChapterRespData, GetComicRespData, SeriesRespData};
+
+type Result<T> = std::result::Result<T, AppError>;
+
+const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/128.0.0.0 Safari/537.36";
+const DEFAULT_SCRAMBLE_ID: i64 = 220_980;
+
+pub struct WebJmClient {
+    client: ClientWithMiddleware,
+    web_domain: String,
+}
+
+impl WebJmClient {
+    pub fn new(web_domain: String, max_retries: u32) -> Self {
+        let cookie_jar = Arc::new(Jar::default());
+        let reqwest_client = reqwest::Client::builder()
+            .cookie_provider(cookie_jar)
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .unwrap();
+        let retry_policy = ExponentialBackoff::builder().build_with_max_retries(max_retries);
+        let client = ClientBuilder::new(reqwest_client)
+            .with(RetryTransientMiddleware::new_with_policy_and_strategy(
+                retry_policy,
+                JmRetryStrategy,
+            ))
+            .build();
+
+        Self { client, web_domain }
+    }
+
+    /// 请求网页并返回 HTML 文本
+    async fn fetch_html(&self, path: &str, what: &str) -> Result<(reqwest::StatusCode, String)> {
+        let url = format!("https://{}{}", self.web_domain, path);
+        let http_resp = self
+            .client
+            .get(&url)
+            .header("user-agent", USER_AGENT)
+            .send()
+            .await
+            .map_err(|e| AppError::Internal(format!("获取{}网页失败: {}", what, e)))?;
+
+        let status = http_resp.status();
+        let body = http_resp
+            .text()
+            .await
+            .map_err(|e| AppError::Internal(format!("读取{}网页失败: {}", what, e)))?;
+        Ok((status, body))
+    }
+}
+
+impl JmApi for WebJmClient {
+    async fn login(&self, username: &str, password: &str) -> Result<()> {
+        let url = format!("https://{}/login", self.web_domain);
+        let form = [
+            ("username", username),
+            ("password", password),
+            ("id_remember", "on"),
+            ("login_remember", "on"),
+            ("submit_login", ""),
+        ];
+        let http_resp = self
+            .client
+            .post(&url)
+            .header("user-agent", USER_AGENT)
+            .form(&form)
+            .send()
+            .await
+            .map_err(|e| AppError::Internal(format!("网页登录请求失败: {}", e)))?;
+
+        let status = http_resp.status();
+        if !status.is_success() && !status.is_redirection() {
+            return Err(AppError::Internal(format!(
+                "Web login failed with status {}",
+                status
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn get_comic(&self, aid: i64) -> Result<GetComicRespData> {
+        let (status, html) = self.fetch_html(&format!("/album/{}/", aid), "漫画").await?;
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Err(AppError::NotFound(format!("漫画 {} 未找到", aid)));
+        }
+        if status != reqwest::StatusCode::OK {
+            return Err(AppError::Internal(format!(
+                "Get web album failed with status {}",
+                status
+            )));
+        }
+
+        parse_album_html(aid, &html)
+    }
+
+    async fn get_chapter(&self, id: i64) -> Result<GetChapterRespData> {
+        let (status, html) = self.fetch_html(&format!("/photo/{}/", id), "章节").await?;
+        if status != reqwest::StatusCode::OK {
+            return Err(AppError::Internal(format!(
+                "Get web photo failed with status {}",
+                status
+            )));
+        }
+
+        let images = parse_photo_images(id, &html);
+        if images.is_empty() {
+            return Err(AppError::Internal(format!("网页中未解析到章节 {} 的图片", id)));
+        }
+
+        Ok(GetChapterRespData {
+            images,
+            ..Default::default()
+        })
+    }
+
+    async fn get_scramble_id(&self, id: i64) -> Result<i64> {
+        let (status, html) = self.fetch_html(&format!("/photo/{}/", id), "章节").await?;
+        if status != reqwest::StatusCode::OK {
+            return Err(AppError::Internal(format!(
+                "Get web scramble_id failed with status {}",
+                status
+            )));
+        }
+
+        Ok(parse_scramble_id(&html).unwrap_or(DEFAULT_SCRAMBLE_ID))
+    }
+}
+
+/// 从漫画页 HTML 中解析漫画信息
+fn parse_album_html(aid: i64, html: &str) -> Result<GetComicRespData> {
+    let name = between(html, "id=\"book-name\">", "</h1>")
+        .map(strip_tags)
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| AppError::NotFound(format!("漫画 {} 未找到", aid)))?;
+
+    let author = parse_tag_list(html, "author");
+
+    let description = between(html, "敘述：", "</h2>")
+        .or_else(|| between(html, "叙述：", "</h2>"))
+        .map(strip_tags)
+        .unwrap_or_default();
+
+    let likes = between(html, &format!("id=\"albim_likes_{}\">", aid), "</span>")
+        .map(strip_tags)
+        .unwrap_or_default();
+
+    Ok(GetComicRespData {
+        name,
+        series: parse_episodes(html),
+        total_views: String::new(),
+        likes,
+        author,
+        description,
+        tags: parse_tag_list(html, "tags"),
+        works: parse_tag_list(html, "works"),
+        actors: parse_tag_list(html, "actor"),
+        add_time: parse_item_date(html, "datePublished"),
+        update_time: parse_item_date(html, "dateModified"),
+        // 网页端未登录，无法得知收藏与购买状态
+        is_favorite: false,
+        price: None,
+        purchased: false,
+    })
+}
+
+/// 解析 `data-type="..."` 标记下的链接文本列表（作者、标签、作品、登场人物）
+fn parse_tag_list(html: &str, data_type: &str) -> Vec<String> {
+    between(html, &format!("data-type=\"{}\">", data_type), "</span>")
+        .map(|block| {
+            block
+                .split("</a>")
+                .map(strip_tags)
+                .filter(|name| !name.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 解析 `itemprop="..." content="YYYY-MM-DD"` 形式的日期，按东八区零点转为 Unix 秒
+fn parse_item_date(html: &str, itemprop: &str) -> Option<i64> {
+    let date = between(html, &format!("itemprop=\"{}\" content=\"", itemprop), "\"")?;
+    let date = chrono::NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").ok()?;
+    date.and_hms_opt(0, 0, 0)?
+        .and_local_timezone(chrono_tz::Asia::Shanghai)
+        .single()
+        .map(|time| time.timestamp())
+}
+
+/// 解析章节列表（单章节漫画返回空列表，与移动端 API 行为一致）
+fn parse_episodes(html: &str) -> Vec<SeriesRespData> {
+    let Some(episode_block) = between(html, "<div class=\"episode\">", "</ul>") else {
+        return Vec::new();
+    };
+
+    episode_block
+        .split("data-album=\"")
+        .skip(1)
+        .filter_map(|segment| {
+            let (id, rest) = segment.split_once('"')?;
+            id.parse::<i64>().ok()?;
+            let text = rest.split_once('>').map(|(_, text)| text).unwrap_or(rest);
+            let text = text.split("</a>").next().unwrap_or(text);
+            Some(SeriesRespData {
+                id: id.to_string(),
+                name: strip_tags(text),
+            })
+        })
+        .collect()
+}
+
+/// 解析章节页中的图片文件名
+fn parse_photo_images(id: i64, html: &str) -> Vec<String> {
+    let marker = format!("/media/photos/{}/", id);
+    html.split("data-original=\"")
+        .skip(1)
+        .filter_map(|segment| segment.split('"').next())
+        .filter(|url| url.contains(&marker))
+        .filter_map(|url| url.rsplit('/').next())
+        .map(|filename| filename.split('?').next().unwrap_or(filename).to_string())
+        .collect()
+}
+
+fn parse_scramble_id(html: &str) -> Option<i64> {
+    html.split("var scramble_id = ")
+        .nth(1)
+        .and_then(|s| s.split(';').next())
+        .and_then(|s| s.trim().parse::<i64>().ok())
+}
+
+fn between<'a>(text: &'a str, start: &str, end: &str) -> Option<&'a str> {
+    let (_, rest) = text.split_once(start)?;
+    rest.split_once(end).map(|(inner, _)| inner)
+}
+
+/// 去除 HTML 标签、解码实体并压缩空白
+fn strip_tags(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for ch in text.chars() {
+        match ch {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => plain.push(ch),
+            _ => {}
+        }
+    }
+    decode_entities(&plain).split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// 解码 HTML 实体（常见命名实体与 `&#39;`、`&#x27;` 形式的数字实体），无法识别的保持原样
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| Some((decode_entity(&rest[1..end + 1])?, end + 2)));
+        match entity {
+            Some((ch, len)) => {
+                decoded.push(ch);
+                rest = &rest[len..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    let code = match name {
+        "amp" => return Some('&'),
+        "lt" => return Some('<'),
+        "gt" => return Some('>'),
+        "quot" => return Some('"'),
+        "apos" => return Some('\''),
+        "nbsp" => return Some(' '),
+        _ => {
+            let number = name.strip_prefix('#')?;
+            match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            }
+        }
+    };
+    char::from_u32(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 截取自网页端漫画页的结构，保留解析用到的标记
+    const ALBUM_FIXTURE: &str = r#"
+        <h1 class="book-name" id="book-name">Tom &amp; Jerry&#39;s  <span>日常</span></h1>
+        <span itemprop="author" data-type="author"><a href="/search?q=A">作者A</a><a href="/search?q=B">B&amp;C</a></span>
+        <span data-type="tags"><a>全彩</a> <a>&#x6F22;化</a></span>
+        <span data-type="works"></span>
+        <h2 class="p-t-5 p-b-5">叙述：第一行&lt;简介&gt;&nbsp;结尾</h2>
+        <span id="albim_likes_350234">1.2K</span>
+        <meta itemprop="datePublished" content="2024-01-02">
+        <div class="episode"><ul>
+          <a href="/photo/350234" data-album="350234"><li>第1話 &quot;開始&quot;</li></a>
+          <a href="/photo/350235" data-album="350235"><li>第2話</li></a>
+        </ul></div>
+    "#;
+
+    #[test]
+    fn parses_album_page() {
+        let comic = parse_album_html(350234, ALBUM_FIXTURE).unwrap();
+        assert_eq!(comic.name, "Tom & Jerry's 日常");
+        assert_eq!(comic.author, ["作者A", "B&C"]);
+        assert_eq!(comic.tags, ["全彩", "漢化"]);
+        assert!(comic.works.is_empty());
+        assert_eq!(comic.description, "第一行<简介> 结尾");
+        assert_eq!(comic.likes, "1.2K");
+        assert_eq!(comic.add_time, Some(1704124800));
+        assert_eq!(comic.update_time, None);
+        let episodes: Vec<_> = comic.series.iter().map(|ep| (ep.id.as_str(), ep.name.as_str())).collect();
+        assert_eq!(episodes, [("350234", "第1話 \"開始\""), ("350235", "第2話")]);
+
+        let missing = parse_album_html(1, "<html><h1 id=\"book-name\"></h1></html>");
+        assert!(matches!(missing, Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn parses_photo_page() {
+        let html = r#"
+            <img data-original="https://cdn.example/media/photos/350234/00001.webp?v=1&amp;t=2">
+            <img data-original="https://cdn.example/media/photos/350234/00002.webp">
+            <img data-original="https://cdn.example/media/photos/999/ad.jpg">
+            <script>var scramble_id = 220980;</script>
+        "#;
+        assert_eq!(parse_photo_images(350234, html), ["00001.webp", "00002.webp"]);
+        assert_eq!(parse_scramble_id(html), Some(220980));
+        assert_eq!(parse_scramble_id("<html></html>"), None);
+    }
+
+    #[test]
+    fn decodes_entities() {
+        assert_eq!(decode_entities("a &amp; b &#39;c&#x27; &lt;d&gt;"), "a & b 'c' <d>");
+        // 无法识别的实体与裸露的 & 保持原样
+        assert_eq!(decode_entities("R&D &unknown; &#xZZ; &"), "R&D &unknown; &#xZZ; &");
+    }
+}
@@ -0,0 +1,124 @@
+// 拼接正确性测试
+// 每一行像素都编码了自己的行号，拼接后按行号即可判断行的去向；
+// 固定用例写明期望的行顺序，其余用例与按块倒序的独立描述对照，并验证拼接是行的双射
+
+use image::{Rgb, RgbImage};
+use jm_downloader_rs::stitch::stitch_img;
+
+/// 每个像素为 (行号低位, 行号高位, 列号)
+fn row_tagged(width: u32, height: u32) -> RgbImage {
+    RgbImage::from_fn(width, height, |x, y| Rgb([y as u8, (y >> 8) as u8, x as u8]))
+}
+
+/// 图片每一行对应的原始行号；同一行内的像素必须一致且列号不变
+fn row_ids(img: &RgbImage) -> Vec<u32> {
+    (0..img.height())
+        .map(|y| {
+            let id = |x: u32| {
+                let Rgb([lo, hi, col]) = *img.get_pixel(x, y);
+                assert_eq!(u32::from(col), x & 0xff, "第 {} 行第 {} 列错位", y, x);
+                u32::from(lo) | u32::from(hi) << 8
+            };
+            let first = id(0);
+            assert!((1..img.width()).all(|x| id(x) == first), "第 {} 行混入了其他行的像素", y);
+            first
+        })
+        .collect()
+}
+
+/// 按 `order` 重排行：结果第 i 行取自 `img` 第 order[i] 行
+fn reorder_rows(img: &RgbImage, order: &[u32]) -> RgbImage {
+    RgbImage::from_fn(img.width(), img.height(), |x, y| *img.get_pixel(x, order[y as usize]))
+}
+
+/// 独立于实现的期望行顺序：打乱图自上而下分为 block_num 块，前面各块等高、最后一块包含余数行，拼接即按块倒序排列
+fn expected_order(height: u32, block_num: u32) -> Vec<u32> {
+    if block_num == 0 {
+        return (0..height).collect();
+    }
+    let block_height = height / block_num;
+    let mut blocks: Vec<Vec<u32>> = (0..block_num)
+        .map(|i| {
+            let start = block_height * i;
+            let end = if i + 1 == block_num { height } else { start + block_height };
+            (start..end).collect()
+        })
+        .collect();
+    blocks.reverse();
+    blocks.concat()
+}
+
+/// 手工推算的用例：(高度, block_num, 拼接后每行来自打乱图的第几行)
+const FIXTURES: &[(u32, u32, &[u32])] = &[
+    (6, 0, &[0, 1, 2, 3, 4, 5]),
+    (6, 1, &[0, 1, 2, 3, 4, 5]),
+    (8, 2, &[4, 5, 6, 7, 0, 1, 2, 3]),
+    (7, 7, &[6, 5, 4, 3, 2, 1, 0]),
+    // 余数行随最后一块一起移到顶部
+    (10, 3, &[6, 7, 8, 9, 3, 4, 5, 0, 1, 2]),
+    (12, 5, &[8, 9, 10, 11, 6, 7, 4, 5, 2, 3, 0, 1]),
+    (11, 10, &[9, 10, 8, 7, 6, 5, 4, 3, 2, 1, 0]),
+    // 高度小于 block_num 时各块高度为 0，整图不变
+    (5, 8, &[0, 1, 2, 3, 4]),
+];
+
+#[test]
+fn fixtures_match_expected_row_order() {
+    for &(height, block_num, order) in FIXTURES {
+        let scrambled = row_tagged(4, height);
+        let stitched = stitch_img(&scrambled, block_num);
+        assert_eq!(row_ids(&stitched), order, "height={}, block_num={}", height, block_num);
+        assert_eq!(order, expected_order(height, block_num), "用例本身与期望顺序不一致");
+    }
+}
+
+#[test]
+fn restores_scrambled_pages() {
+    // 0 与 JM 使用的 2..=20 块，高度覆盖整除、余 1、余 block_num-1 与小于 block_num 的情况
+    for block_num in std::iter::once(0).chain(2..=20) {
+        let n = block_num.max(1);
+        let heights = [n * 5, n * 5 + 1, n * 6 - 1, n - 1, 3000 + n / 2];
+        for height in heights {
+            let original = row_tagged(7, height);
+            let order = expected_order(height, block_num);
+            // 打乱是拼接的逆操作：原图第 i 行位于打乱图的第 order[i] 行
+            let mut inverse = vec![0; order.len()];
+            for (dst, &src) in order.iter().enumerate() {
+                inverse[src as usize] = dst as u32;
+            }
+            let scrambled = reorder_rows(&original, &inverse);
+            assert_eq!(
+                stitch_img(&scrambled, block_num),
+                original,
+                "height={}, block_num={}",
+                height,
+                block_num
+            );
+        }
+    }
+}
+
+#[test]
+fn stitch_is_a_row_bijection() {
+    for width in [1, 3, 16] {
+        for height in 0..=70 {
+            for block_num in 0..=24 {
+                let stitched = stitch_img(&row_tagged(width, height), block_num);
+                assert_eq!(stitched.dimensions(), (width, height));
+                // row_ids 同时检查每一行是否完整地来自同一源行
+                let ids = row_ids(&stitched);
+                let mut sorted = ids.clone();
+                sorted.sort_unstable();
+                assert_eq!(
+                    sorted,
+                    (0..height).collect::<Vec<_>>(),
+                    "width={}, height={}, block_num={} 时有行丢失或重复",
+                    width,
+                    height,
+                    block_num
+                );
+                assert_eq!(ids, expected_order(height, block_num));
+            }
+        }
+    }
+}